@@ -0,0 +1,2 @@
+//! Shared library with the C interface of the solver, see `sudoku::ffi` for the functions and their status codes.
+pub use sudoku::ffi::*;
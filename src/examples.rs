@@ -0,0 +1,43 @@
+//! Puzzles embedded in the crate, for documentation, tests and the `--demo` option of the command line application.
+use crate::solvers::backtracking::Sudoku;
+
+/// Puzzle solved by the logical techniques alone.
+pub const EASY: &str =
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+/// Puzzle which takes the backtracking search a few hundred placements.
+pub const HARD: &str =
+    "8.....136.3.....4.5...69....8..9..1....5....3..5.7.49.6..9.......4...76.1.2......";
+
+/// Puzzle without repeated values in which the last cell of the first row cannot be filled.
+pub const UNSOLVABLE: &str =
+    "12345678.........9...............................................................";
+
+/// The easy puzzle with a wrong 2 in r2c9, which only shows once 12 cells are forced.
+pub const WRONG_CLUE: &str =
+    "53..7....6..195..2.98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+/// Names and puzzles of the examples.
+pub const ALL: [(&str, &str); 3] = [("easy", EASY), ("hard", HARD), ("unsolvable", UNSOLVABLE)];
+
+/// Returns the example puzzle of the name, None if there is no such example.
+pub fn example(name: &str) -> Option<Sudoku> {
+    ALL.iter()
+        .find(|&&(example, _)| example == name)
+        .map(|&(_, line)| line.parse().expect("the examples are valid puzzles"))
+}
+
+#[test]
+fn examples_load() {
+    for &(name, line) in ALL.iter() {
+        let sudoku = example(name).unwrap();
+        assert_eq!(line, sudoku.to_line());
+        assert!(sudoku.is_consistent());
+    }
+    assert_eq!(None, example("medium"));
+    let mut easy = example("easy").unwrap();
+    easy.solve_logical();
+    assert!(easy.solved());
+    assert_eq!(Ok(1), example("hard").unwrap().count_solutions(2));
+    assert_eq!(Ok(0), example("unsolvable").unwrap().count_solutions(1));
+}
@@ -0,0 +1,154 @@
+//! C interface of the solver, enabled with the `ffi` feature.
+//!
+//! Sudokus are passed as buffers of 81 bytes in row-major order where `0` is an empty cell. Functions return one of
+//! the status codes below, never unwind into the caller: a panic is caught and reported as `SUDOKU_INTERNAL_ERROR`.
+//! A C++ caller declares them as:
+//!
+//! ```c
+//! extern "C" int32_t sudoku_solve(const uint8_t *input, uint8_t *output);
+//! extern "C" int32_t sudoku_count_solutions(const uint8_t *input, uint32_t limit);
+//! ```
+use crate::solvers::backtracking::{Sudoku, SIZE};
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+/// The sudoku is solved.
+pub const SUDOKU_SOLVED: i32 = 0;
+/// The clues do not repeat a value, yet the sudoku has no solution.
+pub const SUDOKU_UNSOLVABLE: i32 = 1;
+/// A cell holds a value above 9 or clues repeat a value in a row, column or square.
+pub const SUDOKU_INVALID: i32 = -1;
+/// A buffer pointer is null.
+pub const SUDOKU_NULL_POINTER: i32 = -2;
+/// The solver panicked.
+pub const SUDOKU_INTERNAL_ERROR: i32 = -3;
+
+/// Solves the 9x9 sudoku in `input` and writes the solution to `output`, both buffers of 81 bytes.
+///
+/// Returns `SUDOKU_SOLVED` once the solution is written, otherwise the status code of the error and `output` is left
+/// untouched.
+///
+/// # Safety
+///
+/// `input` must be valid for reading and `output` for writing 81 bytes, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_solve(input: *const u8, output: *mut u8) -> i32 {
+    if input.is_null() || output.is_null() {
+        return SUDOKU_NULL_POINTER;
+    }
+    let input = slice::from_raw_parts(input, SIZE);
+    let output = slice::from_raw_parts_mut(output, SIZE);
+    catch_panic(|| {
+        let mut s = match read_sudoku(input) {
+            Some(s) => s,
+            None => return SUDOKU_INVALID,
+        };
+        match s.solve() {
+            Ok(()) => {
+                output.copy_from_slice(s.as_slice());
+                SUDOKU_SOLVED
+            }
+            Err(_) => SUDOKU_UNSOLVABLE,
+        }
+    })
+}
+
+/// Returns the number of solutions of the 9x9 sudoku in the 81-byte `input` buffer, counting stops once `limit`
+/// solutions are found. Use a `limit` of 2 to check whether the solution is unique.
+///
+/// Returns a negative status code on errors, a sudoku with repeated clues is `SUDOKU_INVALID` rather than 0.
+///
+/// # Safety
+///
+/// `input` must be valid for reading 81 bytes, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_count_solutions(input: *const u8, limit: u32) -> i32 {
+    if input.is_null() {
+        return SUDOKU_NULL_POINTER;
+    }
+    let input = slice::from_raw_parts(input, SIZE);
+    catch_panic(|| match read_sudoku(input) {
+        // 9x9 grids are searched without a node limit, the count is always finished.
+        Some(s) => match s.count_solutions((limit as usize).min(i32::MAX as usize)) {
+            Ok(count) => count as i32,
+            Err(_) => SUDOKU_INTERNAL_ERROR,
+        },
+        None => SUDOKU_INVALID,
+    })
+}
+
+/// Returns the sudoku of the buffer, None if a value is above 9 or clues repeat a value.
+fn read_sudoku(input: &[u8]) -> Option<Sudoku> {
+    if input.iter().any(|&value| value > 9) {
+        return None;
+    }
+    let s = Sudoku::new(input.iter().copied())?;
+    match s.is_consistent() {
+        true => Some(s),
+        false => None,
+    }
+}
+
+/// Runs the function, turning a panic into `SUDOKU_INTERNAL_ERROR`.
+fn catch_panic(f: impl FnOnce() -> i32) -> i32 {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(SUDOKU_INTERNAL_ERROR)
+}
+
+#[cfg(test)]
+use crate::solvers::backtracking::solvable_field_helper;
+
+#[test]
+fn solve_through_ffi() {
+    let input = solvable_field_helper().as_slice().to_vec();
+    let mut output = [0u8; SIZE];
+    assert_eq!(SUDOKU_SOLVED, unsafe {
+        sudoku_solve(input.as_ptr(), output.as_mut_ptr())
+    });
+    let mut expected = solvable_field_helper();
+    expected.solve().unwrap();
+    assert_eq!(expected.as_slice(), &output[..]);
+    assert_eq!(1, unsafe { sudoku_count_solutions(input.as_ptr(), 2) });
+    let empty = [0u8; SIZE];
+    assert_eq!(5, unsafe { sudoku_count_solutions(empty.as_ptr(), 5) });
+}
+
+#[test]
+fn ffi_errors() {
+    let mut output = [0u8; SIZE];
+    let mut input = solvable_field_helper().as_slice().to_vec();
+    assert_eq!(SUDOKU_NULL_POINTER, unsafe {
+        sudoku_solve(std::ptr::null(), output.as_mut_ptr())
+    });
+    assert_eq!(SUDOKU_NULL_POINTER, unsafe {
+        sudoku_solve(input.as_ptr(), std::ptr::null_mut())
+    });
+    assert_eq!(SUDOKU_NULL_POINTER, unsafe {
+        sudoku_count_solutions(std::ptr::null(), 2)
+    });
+    input[2] = 10;
+    assert_eq!(SUDOKU_INVALID, unsafe {
+        sudoku_solve(input.as_ptr(), output.as_mut_ptr())
+    });
+    // The first row already holds 5 in its first cell.
+    input[2] = 5;
+    assert_eq!(SUDOKU_INVALID, unsafe {
+        sudoku_count_solutions(input.as_ptr(), 2)
+    });
+    assert_eq!([0u8; SIZE], output);
+    assert_eq!(SUDOKU_INTERNAL_ERROR, catch_panic(|| panic!("boom")));
+}
+
+#[test]
+fn unsolvable_through_ffi() {
+    // 1-8 fill the first row, so 9 is the only value left for its last cell, yet the last column already holds it.
+    let mut input = [0u8; SIZE];
+    for (i, cell) in input[..8].iter_mut().enumerate() {
+        *cell = i as u8 + 1;
+    }
+    input[SIZE - 1] = 9;
+    let mut output = [0u8; SIZE];
+    assert_eq!(SUDOKU_UNSOLVABLE, unsafe {
+        sudoku_solve(input.as_ptr(), output.as_mut_ptr())
+    });
+    assert_eq!(0, unsafe { sudoku_count_solutions(input.as_ptr(), 2) });
+}
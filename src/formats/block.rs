@@ -0,0 +1,141 @@
+//! Puzzles typed by hand as nine lines of nine cells, e.g. `5 3 . . 7 . . . .` or `53..7....`.
+//!
+//! Cells may be separated by whitespace, commas or `|`, empty cells are `.` or `0` mixed in any way. Unlike `FromStr`,
+//! which skips characters it doesn't know, every character must be a cell or a separator, so a typo is reported with
+//! its line and column instead of shifting the cells after it.
+use super::is_ignored_line;
+use crate::prelude::*;
+use crate::solvers::backtracking::{Sudoku, SudokuError, EMPTY, SIDE};
+
+/// Returns `true` if the text holds nine lines, apart from blank and comment lines, and none is a border of a grid.
+///
+/// Rows of the pretty grid start with `|` and its borders are drawn with `-`, `=`, `+` or box characters, so that
+/// layout is never taken for a block.
+pub fn is_block(text: &str) -> bool {
+    let lines: Vec<&str> = rows(text).map(|(_, line)| line).collect();
+    lines.len() == SIDE
+        && lines.iter().all(|line| {
+            !line.trim_start().starts_with('|')
+                && !line.contains(|c: char| matches!(c, '-' | '=' | '+') || !c.is_ascii())
+        })
+}
+
+/// Parses a block of nine lines of nine cells.
+///
+/// Fails with the (1-based) line of the text when a line doesn't hold nine cells or has a character which is neither
+/// a cell nor a separator, the (1-based) column of the character is in the message.
+pub fn parse_block(text: &str) -> Result<Sudoku, SudokuError> {
+    let mut field = Vec::with_capacity(SIDE * SIDE);
+    let mut count = 0;
+    for (num, line) in rows(text) {
+        let invalid = |msg: String| SudokuError::ParseLine { line: num, msg };
+        let cells = parse_row(line).map_err(invalid)?;
+        if cells.len() != SIDE {
+            return Err(invalid(format!(
+                "expected {} cells, found {}",
+                SIDE,
+                cells.len()
+            )));
+        }
+        field.extend(cells);
+        count += 1;
+    }
+    if count != SIDE {
+        return Err(SudokuError::Parse(format!(
+            "expected {} lines, found {}",
+            SIDE, count
+        )));
+    }
+    Ok(Sudoku::from_field(field))
+}
+
+/// Returns the lines of the text with their (1-based) numbers, skipping blank and comment lines.
+fn rows(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(num, line)| (num + 1, line))
+        .filter(|(_, line)| !is_ignored_line(line))
+}
+
+/// Returns the values of the cells of the line, or the message about its first character which is not a cell.
+fn parse_row(line: &str) -> Result<Vec<u8>, String> {
+    let mut cells = Vec::with_capacity(SIDE);
+    for (col, c) in line.chars().enumerate() {
+        match c {
+            '.' | '0' => cells.push(EMPTY),
+            '1'..='9' => cells.push(c as u8 - b'0'),
+            ',' | '|' => {}
+            c if c.is_whitespace() => {}
+            c => {
+                return Err(format!(
+                    "column {}: `{}` is not a cell, expected a digit or `.`",
+                    col + 1,
+                    c
+                ))
+            }
+        }
+    }
+    Ok(cells)
+}
+
+#[cfg(test)]
+use crate::solvers::backtracking::solvable_field_helper;
+
+#[test]
+fn accepted_spellings() {
+    let puzzle = solvable_field_helper();
+    let blocks = [
+        "53..7....\n6..195...\n.98....6.\n8...6...3\n4..8.3..1\n7...2...6\n.6....28.\n...419..5\n....8..79\n",
+        include_str!("../../tasks/1.sudoku"),
+        "5 3 . . 7 . . . .\n6 0 0 1 9 5 . . .\n. 9 8 0 0 0 0 6 .\n8 . . . 6 . . . 3\n4 . . 8 . 3 . . 1\n\
+         7 . . . 2 . . . 6\n. 6 . . . . 2 8 .\n. . . 4 1 9 . . 5\n. . . . 8 . . 7 9",
+        "# spaces and pipes\n53.|.7.|...\n600|195|000\n.98|...|.6.\n8..|.6.|..3\n4..|8.3|..1\n\n7..|.2.|..6\n\
+         .6.|...|28.\n...|419|..5\n...|.8.|.79\r\n",
+        "  53. .7. ...\n\t6.. 195 ...\n.98 ... .6.\n8.. .6. ..3\n4.. 8.3 ..1\n7.. .2. ..6\n.6. ... 28.\n\
+         ... 419 ..5\n... .8. .79   ",
+    ];
+    for block in blocks.iter() {
+        assert!(is_block(block), "{}", block);
+        assert_eq!(puzzle, parse_block(block).unwrap(), "{}", block);
+    }
+}
+
+#[test]
+fn rejected_blocks() {
+    let line = |num: usize| match num {
+        3 => "5 3 . . 7 . . .".to_string(),
+        5 => "5 3 . . 7 . _ . .".to_string(),
+        _ => ". . . . . . . . .".to_string(),
+    };
+    let block = |lines: &[usize]| -> String {
+        lines
+            .iter()
+            .map(|&num| format!("{}\n", line(num)))
+            .collect()
+    };
+    assert_eq!(
+        Err(SudokuError::ParseLine {
+            line: 3,
+            msg: "expected 9 cells, found 8".to_string()
+        }),
+        parse_block(&block(&[1, 2, 3, 4, 6, 7, 8, 9, 10]))
+    );
+    assert_eq!(
+        "Line 5: column 13: `_` is not a cell, expected a digit or `.`",
+        parse_block(&block(&[1, 2, 4, 4, 5, 6, 7, 8, 9]))
+            .unwrap_err()
+            .to_string()
+    );
+    assert_eq!(
+        "Cannot parse sudoku: expected 9 lines, found 8",
+        parse_block(&block(&[1, 2, 4, 6, 7, 8, 9, 10]))
+            .unwrap_err()
+            .to_string()
+    );
+    // Other layouts are not blocks.
+    let puzzle = solvable_field_helper();
+    assert!(!is_block(&puzzle.to_line()));
+    assert!(!is_block(&puzzle.to_string()));
+    assert!(!is_block(&puzzle.to_unicode_string()));
+    assert!(!is_block(&block(&[1, 2, 4, 6, 7, 8, 9, 10])));
+}
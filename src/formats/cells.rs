@@ -0,0 +1,153 @@
+//! Sparse lists of clues like `r1c3=4, r2c7=9`, handy for puzzles with few clues.
+//!
+//! Every clue is a `rXcY=V` token with the 1-based row and column and the value, case doesn't matter. Tokens are
+//! separated by whitespace or commas, blank and comment lines are skipped. Only 9x9 sudokus are supported.
+use super::is_ignored_line;
+use crate::prelude::*;
+use crate::solvers::backtracking::{Sudoku, SudokuError, SIDE};
+use crate::solvers::geometry::Pos;
+
+/// Returns `true` if the text holds clues in the cell list format rather than a grid.
+pub fn is_cell_list(text: &str) -> bool {
+    tokens(text)
+        .next()
+        .is_some_and(|token| token.contains('=') && token.starts_with(['r', 'R']))
+}
+
+/// Parses the clues of a sudoku written as `rXcY=V` tokens.
+///
+/// Fails with the offending token when it is malformed, when its cell is outside the grid or its value out of range,
+/// and when a cell is assigned twice.
+pub fn parse_cell_list(text: &str) -> Result<Sudoku, SudokuError> {
+    let mut clues: Vec<(Pos, u8, &str)> = Vec::new();
+    for token in tokens(text) {
+        let (row, col, value) = parse_token(token)?;
+        let invalid = |msg: String| SudokuError::Parse(format!("`{}`: {}", token, msg));
+        if !(1..=SIDE).contains(&row) || !(1..=SIDE).contains(&col) {
+            return Err(invalid(format!(
+                "cell outside of the {}x{} grid",
+                SIDE, SIDE
+            )));
+        }
+        if !(1..=SIDE).contains(&value) {
+            return Err(invalid(format!("value must be between 1 and {}", SIDE)));
+        }
+        let cell = Pos::new(row - 1, col - 1);
+        if let Some(&(_, _, earlier)) = clues.iter().find(|&&(other, _, _)| other == cell) {
+            return Err(invalid(format!(
+                "{} is already assigned by `{}`",
+                cell, earlier
+            )));
+        }
+        clues.push((cell, value as u8, token));
+    }
+    clues
+        .iter()
+        .fold(Sudoku::builder(), |builder, &(cell, value, _)| {
+            builder.set(cell.row, cell.col, value)
+        })
+        .build()
+}
+
+/// Returns the givens of the sudoku as a cell list, row by row, e.g. `r1c1=5, r1c2=3`; cells filled later are left
+/// out.
+pub fn to_cell_list(sudoku: &Sudoku) -> String {
+    let side = sudoku.side();
+    let clues: Vec<String> = (0..side * side)
+        .map(|pos| Pos::new(pos / side, pos % side))
+        .filter(|cell| sudoku.is_given(cell.row, cell.col))
+        .filter_map(|cell| {
+            sudoku
+                .value(cell.row, cell.col)
+                .map(|value| format!("{}={}", cell, value))
+        })
+        .collect();
+    clues.join(", ")
+}
+
+/// Returns the tokens of the text, skipping blank and comment lines.
+fn tokens(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .filter(|line| !is_ignored_line(line))
+        .flat_map(|line| line.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|token| !token.is_empty())
+}
+
+/// Returns the 1-based row and column and the value of a `rXcY=V` token.
+fn parse_token(token: &str) -> Result<(usize, usize, usize), SudokuError> {
+    let malformed = || SudokuError::Parse(format!("`{}`: expected a clue like r1c3=4", token));
+    let lower = token.to_ascii_lowercase();
+    let (cell, value) = lower.split_once('=').ok_or_else(malformed)?;
+    let (row, col) = cell
+        .strip_prefix('r')
+        .and_then(|cell| cell.split_once('c'))
+        .ok_or_else(malformed)?;
+    let number = |text: &str| match text.chars().all(|c| c.is_ascii_digit()) {
+        true => text.parse::<usize>().map_err(|_| malformed()),
+        false => Err(malformed()),
+    };
+    Ok((number(row)?, number(col)?, number(value)?))
+}
+
+#[test]
+fn round_trip() {
+    let puzzle = crate::solvers::backtracking::solvable_field_helper();
+    let list = to_cell_list(&puzzle);
+    assert!(list.starts_with("r1c1=5, r1c2=3, r1c5=7, r2c1=6, "));
+    assert!(list.ends_with(", r9c5=8, r9c8=7, r9c9=9"));
+    assert!(is_cell_list(&list));
+    assert_eq!(puzzle, parse_cell_list(&list).unwrap());
+    // Values found by the solver are not clues.
+    let mut solved = puzzle.clone();
+    solved.solve().unwrap();
+    assert_eq!(list, to_cell_list(&solved));
+}
+
+#[test]
+fn parse_loose_lists() {
+    let s = parse_cell_list("# sparse\nR1C3=4,r2c7=9  r9C9=1,\n").unwrap();
+    assert_eq!(3, s.clue_count());
+    assert_eq!(Some(4), s.value(0, 2));
+    assert_eq!(Some(9), s.value(1, 6));
+    assert_eq!(Some(1), s.value(8, 8));
+    assert_eq!(Sudoku::empty(), parse_cell_list("").unwrap());
+    assert!(!is_cell_list(&"1".repeat(81)));
+}
+
+#[test]
+fn parse_errors() {
+    let message = |text: &str| parse_cell_list(text).unwrap_err().to_string();
+    assert_eq!(
+        "Cannot parse sudoku: `r0c5=3`: cell outside of the 9x9 grid",
+        message("r1c1=1 r0c5=3")
+    );
+    assert_eq!(
+        "Cannot parse sudoku: `r1c10=3`: cell outside of the 9x9 grid",
+        message("r1c10=3")
+    );
+    assert_eq!(
+        "Cannot parse sudoku: `r2c2=0`: value must be between 1 and 9",
+        message("r2c2=0")
+    );
+    assert_eq!(
+        "Cannot parse sudoku: `r2c2=300`: value must be between 1 and 9",
+        message("r2c2=300")
+    );
+    assert_eq!(
+        "Cannot parse sudoku: `R4C4=7`: r4c4 is already assigned by `r4c4=5`",
+        message("r4c4=5, R4C4=7")
+    );
+    for token in ["r1c1", "r1c1=", "c1r1=5", "r1c=5", "r1c1=x", "r+1c1=5"].iter() {
+        assert_eq!(
+            format!(
+                "Cannot parse sudoku: `{}`: expected a clue like r1c3=4",
+                token
+            ),
+            message(token)
+        );
+    }
+    assert!(matches!(
+        parse_cell_list("r1c1=5 r1c9=5"),
+        Err(SudokuError::ConflictingClues(_))
+    ));
+}
@@ -0,0 +1,169 @@
+//! Metadata of the puzzles of a collection, read from the comment lines right above a puzzle:
+//!
+//! ```text
+//! # id: 42
+//! # source: nyt
+//! # tags: daily, hard
+//! 53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79
+//! ```
+//!
+//! Keys are case insensitive, other keys and comments are skipped. A blank line drops the metadata read before it, so
+//! it only applies to the puzzle right below it.
+use super::is_ignored_line;
+use crate::prelude::*;
+use crate::solvers::backtracking::Sudoku;
+
+/// Fields of the catalog a puzzle comes from, so results can be joined back to it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metadata {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub id: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub source: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "Vec::is_empty", default)
+    )]
+    pub tags: Vec<String>,
+}
+
+impl Metadata {
+    /// Returns `true` if no field is set.
+    pub fn is_empty(&self) -> bool {
+        self.id.is_none() && self.source.is_none() && self.tags.is_empty()
+    }
+
+    /// Reads the field of a comment line like `# source: nyt`, returns `false` for other lines.
+    ///
+    /// Tags are separated by commas and add up over several lines.
+    pub fn read_comment(&mut self, line: &str) -> bool {
+        let (key, value) = match line
+            .trim()
+            .strip_prefix('#')
+            .and_then(|comment| comment.split_once(':'))
+        {
+            Some((key, value)) => (key.trim().to_lowercase(), value.trim()),
+            None => return false,
+        };
+        match key.as_str() {
+            "id" => self.id = Some(value.to_string()),
+            "source" => self.source = Some(value.to_string()),
+            "tags" => self.tags.extend(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string),
+            ),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Returns the comment lines `read_comment` reads back, like `# id: 42`, for the fields which are set.
+    pub fn to_comments(&self) -> Vec<String> {
+        let mut comments = Vec::new();
+        if let Some(id) = &self.id {
+            comments.push(format!("# id: {}", id));
+        }
+        if let Some(source) = &self.source {
+            comments.push(format!("# source: {}", source));
+        }
+        if !self.tags.is_empty() {
+            comments.push(format!("# tags: {}", self.tags.join(", ")));
+        }
+        comments
+    }
+
+    /// Returns the metadata read so far for the puzzle on the next line, None if there is none, and starts over.
+    pub(crate) fn take(&mut self) -> Option<Metadata> {
+        match self.is_empty() {
+            true => None,
+            false => Some(core::mem::take(self)),
+        }
+    }
+}
+
+/// Collects the metadata of the puzzles of a collection while its lines are read one after another.
+#[derive(Debug, Default)]
+pub(crate) struct MetadataReader {
+    pending: Metadata,
+}
+
+impl MetadataReader {
+    /// Reads a blank or comment line, see `is_ignored_line`.
+    pub(crate) fn skip(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            self.pending = Metadata::default();
+        } else {
+            self.pending.read_comment(line);
+        }
+    }
+
+    /// Attaches the metadata read above the line of the puzzle to it.
+    pub(crate) fn attach(&mut self, sudoku: Sudoku) -> Sudoku {
+        match self.pending.take() {
+            Some(metadata) => sudoku.with_metadata(metadata),
+            None => sudoku,
+        }
+    }
+}
+
+/// Returns the metadata of the comment lines at the start of a single puzzle, None if they hold none.
+pub fn leading_metadata(text: &str) -> Option<Metadata> {
+    let mut reader = MetadataReader::default();
+    for line in text.lines().take_while(|line| is_ignored_line(line)) {
+        reader.skip(line);
+    }
+    reader.pending.take()
+}
+
+impl Sudoku {
+    /// Returns the sudoku with the metadata of the catalog it comes from, which solving keeps.
+    pub fn with_metadata(mut self, metadata: Metadata) -> Sudoku {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Returns the metadata of the sudoku, None for sudokus without any.
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+}
+
+#[test]
+fn read_comment_lines() {
+    let mut metadata = Metadata::default();
+    assert!(metadata.read_comment("# id: 42"));
+    assert!(metadata.read_comment("#Source:nyt "));
+    assert!(metadata.read_comment("# tags: daily, hard,"));
+    assert!(metadata.read_comment("# TAGS: x-wing"));
+    assert!(!metadata.read_comment("# date: 2019-05-01"));
+    assert!(!metadata.read_comment("# Puzzle 3"));
+    assert!(!metadata.read_comment("// id: 7"));
+    assert_eq!(
+        Metadata {
+            id: Some("42".to_string()),
+            source: Some("nyt".to_string()),
+            tags: vec![
+                "daily".to_string(),
+                "hard".to_string(),
+                "x-wing".to_string()
+            ],
+        },
+        metadata
+    );
+    let text = "# id: 42\n# source: nyt\n# tags: daily, hard, x-wing\n5..";
+    assert_eq!(format!("{}\n5..", metadata.to_comments().join("\n")), text);
+    assert_eq!(Some(metadata), leading_metadata(text));
+    // Only the comments after the last blank line apply.
+    assert_eq!(
+        Some(Metadata {
+            source: Some("nyt".to_string()),
+            ..Metadata::default()
+        }),
+        leading_metadata("# id: 42\n\n# source: nyt\n5..")
+    );
+    assert_eq!(None, leading_metadata("# A puzzle\n5..\n# id: 42"));
+}
@@ -0,0 +1,347 @@
+//! Reading sudokus from files in various formats, and writing them.
+use crate::prelude::*;
+use crate::solvers::backtracking::{Sudoku, SudokuError, SIZE};
+use metadata::MetadataReader;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io::BufRead;
+
+pub mod block;
+pub mod cells;
+pub mod metadata;
+#[cfg(feature = "std")]
+pub mod output;
+#[cfg(feature = "std")]
+pub mod sdm;
+#[cfg(feature = "std")]
+pub mod ss;
+
+/// Returns `true` for lines which hold no puzzle data: blank lines and comments starting with `#` or `//`.
+pub fn is_ignored_line(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#') || line.starts_with("//")
+}
+
+/// Parses a single sudoku in the layout it is written in: clues like `r1c3=4`, see `cells`, nine lines of nine cells,
+/// see `block`, or a single line, a pretty grid and every other layout of `FromStr`.
+pub fn detect_and_parse(text: &str) -> Result<Sudoku, SudokuError> {
+    if cells::is_cell_list(text) {
+        cells::parse_cell_list(text)
+    } else if block::is_block(text) {
+        block::parse_block(text)
+    } else {
+        text.parse()
+    }
+}
+
+/// Parses a batch of sudokus written one per line.
+///
+/// Blank and comment lines are skipped. Each line is parsed independently and the first malformed one fails the whole batch with
+/// its (1-based) line number.
+pub fn parse_sudoku_batch(data: &str) -> Result<Vec<Sudoku>, SudokuError> {
+    parse_sudoku_lines(data).map(|(_, res)| res).collect()
+}
+
+/// Parses a batch of sudokus written one per line, a malformed line doesn't stop the rest from being parsed.
+///
+/// Returns the (1-based) line number with the result of every line which isn't blank or a comment. Puzzles get the
+/// metadata of the comment lines above them, see `metadata`.
+pub fn parse_sudoku_lines(
+    data: &str,
+) -> impl Iterator<Item = (usize, Result<Sudoku, SudokuError>)> + '_ {
+    let mut metadata = MetadataReader::default();
+    data.lines().enumerate().filter_map(move |(num, line)| {
+        if is_ignored_line(line) {
+            metadata.skip(line);
+            return None;
+        }
+        let res = line
+            .parse()
+            .map(|sudoku| metadata.attach(sudoku))
+            .map_err(|err: SudokuError| SudokuError::ParseLine {
+                line: num + 1,
+                msg: err.to_string(),
+            });
+        Some((num + 1, res))
+    })
+}
+
+/// Loads a batch of sudokus written one per line from the file, see `parse_sudoku_batch`.
+#[cfg(feature = "std")]
+pub fn load_sudoku_batch(path: &str) -> Result<Vec<Sudoku>, SudokuError> {
+    let data = fs::read_to_string(path).map_err(|err| SudokuError::Io(err.to_string()))?;
+    parse_sudoku_batch(&data)
+}
+
+/// Reads sudokus written one per line lazily, a line is read only once the previous puzzle has been taken.
+///
+/// Returns every puzzle with its (1-based) line number, blank and comment lines are skipped, puzzles get the metadata
+/// of the comment lines above them. Collections too large to be held in memory are parsed this way, the iterator
+/// should be dropped after the first error.
+#[cfg(feature = "std")]
+pub fn stream_puzzles(
+    reader: impl BufRead,
+) -> impl Iterator<Item = Result<(usize, Sudoku), SudokuError>> {
+    let mut metadata = MetadataReader::default();
+    reader
+        .lines()
+        .enumerate()
+        .filter_map(move |(num, line)| match line {
+            Err(err) => Some(Err(SudokuError::Io(err.to_string()))),
+            Ok(ref line) if is_ignored_line(line) => {
+                metadata.skip(line);
+                None
+            }
+            Ok(line) => Some(
+                line.parse()
+                    .map(|sudoku| (num + 1, metadata.attach(sudoku)))
+                    .map_err(|err: SudokuError| SudokuError::ParseLine {
+                        line: num + 1,
+                        msg: err.to_string(),
+                    }),
+            ),
+        })
+}
+
+/// Splits data holding 9x9 grids written back to back, without line breaks between them, into its puzzles.
+///
+/// Returns None unless the data holds nothing but digits and `.` (apart from whitespace and comment lines) and the
+/// cells of more than one grid, a multiple of 81.
+pub fn split_concatenated(data: &str) -> Option<Result<Vec<Sudoku>, SudokuError>> {
+    let cells: String = data
+        .lines()
+        .filter(|line| !is_ignored_line(line))
+        .flat_map(|line| line.chars())
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let is_cell = |c: char| c.is_ascii_digit() || c == '.';
+    if cells.len() <= SIZE || cells.len() % SIZE != 0 || !cells.chars().all(is_cell) {
+        return None;
+    }
+    info!(
+        "Found {} puzzles of {} cells written back to back",
+        cells.len() / SIZE,
+        SIZE
+    );
+    Some(
+        cells
+            .as_bytes()
+            .chunks(SIZE)
+            .map(|grid| core::str::from_utf8(grid).unwrap().parse())
+            .collect(),
+    )
+}
+
+/// Returns `true` if the data looks like a batch: several lines where the first one holds a whole sudoku.
+pub fn is_batch(data: &str) -> bool {
+    let mut lines = data.lines().filter(|line| !is_ignored_line(line));
+    match (lines.next(), lines.next()) {
+        (Some(first), Some(_)) => first.parse::<Sudoku>().is_ok(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+const BATCH: &str = concat!(
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79\n",
+    "\n",
+    "12345678.........9...............................................................\n",
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..7\n",
+);
+
+#[test]
+fn detect_layouts() {
+    let puzzle = crate::solvers::backtracking::solvable_field_helper();
+    let layouts = [
+        puzzle.to_line(),
+        puzzle.to_string(),
+        cells::to_cell_list(&puzzle),
+        include_str!("../../tasks/1.sudoku").to_string(),
+    ];
+    for text in layouts.iter() {
+        assert_eq!(puzzle, detect_and_parse(text).unwrap(), "{}", text);
+    }
+    // A typo in a block is reported rather than skipped.
+    let typo = include_str!("../../tasks/1.sudoku").replacen("7,", "7;", 1);
+    assert!(typo.parse::<Sudoku>().is_ok());
+    assert_eq!(
+        Err(SudokuError::ParseLine {
+            line: 1,
+            msg: "column 22: `;` is not a cell, expected a digit or `.`".to_string()
+        }),
+        detect_and_parse(&typo)
+    );
+}
+
+#[test]
+fn parse_batch() {
+    let data: String = BATCH.lines().take(3).map(|l| format!("{}\n", l)).collect();
+    let mut sudokus = parse_sudoku_batch(&data).unwrap();
+    assert_eq!(2, sudokus.len());
+    assert!(sudokus[0].solve().is_ok());
+    assert!(sudokus[1].solve().is_err());
+}
+
+#[test]
+fn parse_lines_independently() {
+    let results: Vec<_> = parse_sudoku_lines(BATCH).collect();
+    assert_eq!(
+        vec![1, 3, 4],
+        results.iter().map(|(line, _)| *line).collect::<Vec<_>>()
+    );
+    assert!(results[0].1.is_ok() && results[1].1.is_ok());
+    assert!(matches!(
+        results[2].1,
+        Err(SudokuError::ParseLine { line: 4, .. })
+    ));
+}
+
+#[test]
+fn parse_batch_with_malformed_line() {
+    match parse_sudoku_batch(BATCH) {
+        Err(SudokuError::ParseLine { line, .. }) => assert_eq!(4, line),
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn load_missing_batch() {
+    assert!(matches!(
+        load_sudoku_batch("tasks/missing.sudoku"),
+        Err(SudokuError::Io(_))
+    ));
+}
+
+#[test]
+fn detect_batch() {
+    assert!(is_batch(BATCH));
+    assert!(!is_batch(&BATCH[..82]));
+    assert!(!is_batch(include_str!("../../tasks/1.sudoku")));
+}
+
+#[test]
+fn parse_batch_with_comments() {
+    let plain: String = BATCH.lines().take(3).map(|l| format!("{}\n", l)).collect();
+    let commented = format!("# Collection 1, 2 puzzles\n{}// the end\n", plain);
+    assert_eq!(
+        parse_sudoku_batch(&plain).unwrap(),
+        parse_sudoku_batch(&commented).unwrap()
+    );
+    assert!(is_batch(&commented));
+    // Line numbers are counted in the original data.
+    let data = format!("# Collection 1\n{}", BATCH);
+    match parse_sudoku_batch(&data) {
+        Err(SudokuError::ParseLine { line, .. }) => assert_eq!(5, line),
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn split_grids_written_back_to_back() {
+    let first = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+    let second = first.replace('.', "0");
+    let data = format!("# three puzzles\n{}{}\n{}\n", first, second, ".".repeat(81));
+    let sudokus = split_concatenated(&data).unwrap().unwrap();
+    assert_eq!(3, sudokus.len());
+    assert_eq!(sudokus[0], sudokus[1]);
+    assert_eq!(first, sudokus[1].to_line());
+    assert_eq!(Sudoku::empty(), sudokus[2]);
+    assert!(split_concatenated(first).is_none());
+    assert!(split_concatenated(&format!("{}{}7", first, first)).is_none());
+    assert!(split_concatenated(&format!("{}|{}", first, &first[1..])).is_none());
+}
+
+/// Reader of a long batch which generates its lines on demand and counts them.
+#[cfg(all(feature = "std", test))]
+struct GeneratedBatch {
+    lines: usize,
+    generated: std::rc::Rc<core::cell::Cell<usize>>,
+    pending: Vec<u8>,
+}
+
+#[cfg(all(feature = "std", test))]
+impl std::io::Read for GeneratedBatch {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pending.is_empty() && self.generated.get() < self.lines {
+            let num = self.generated.get();
+            self.generated.set(num + 1);
+            self.pending = match num % 100 {
+                0 => "# comment\n".into(),
+                _ => format!("{}{}\n", num % 9 + 1, ".".repeat(80)).into_bytes(),
+            };
+        }
+        let len = buf.len().min(self.pending.len());
+        buf[..len].copy_from_slice(&self.pending[..len]);
+        self.pending.drain(..len);
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn stream_long_batch() {
+    let generated = std::rc::Rc::new(core::cell::Cell::new(0));
+    let reader = std::io::BufReader::with_capacity(
+        256,
+        GeneratedBatch {
+            lines: 10_000,
+            generated: generated.clone(),
+            pending: Vec::new(),
+        },
+    );
+    let mut puzzles = stream_puzzles(reader);
+    let (line, first) = puzzles.next().unwrap().unwrap();
+    assert_eq!(2, line);
+    assert_eq!(2, first.as_slice()[0]);
+    // Only the lines up to the first puzzle have been read.
+    assert!(generated.get() <= 2);
+    let rest: Vec<(usize, Sudoku)> = puzzles.map(Result::unwrap).collect();
+    assert_eq!(10_000, generated.get());
+    assert_eq!(9_899, rest.len());
+    assert_eq!(10_000, rest.last().unwrap().0);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn stream_malformed_line() {
+    let data = format!("{}\n\n53..7\n", ".".repeat(81));
+    let results: Vec<_> = stream_puzzles(std::io::Cursor::new(data)).collect();
+    assert_eq!(2, results.len());
+    assert_eq!(1, results[0].as_ref().unwrap().0);
+    assert!(matches!(
+        results[1],
+        Err(SudokuError::ParseLine { line: 3, .. })
+    ));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn stream_puzzles_with_metadata() {
+    let catalog = fs::read_to_string("tasks/catalog.txt").unwrap();
+    let sudokus: Vec<(usize, Sudoku)> = stream_puzzles(catalog.as_bytes())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let metadata = sudokus[0].1.metadata().unwrap();
+    assert_eq!(
+        (Some("42"), Some("nyt"), vec!["daily", "easy"]),
+        (
+            metadata.id.as_deref(),
+            metadata.source.as_deref(),
+            metadata.tags.iter().map(String::as_str).collect()
+        )
+    );
+    // The fields of a puzzle do not carry over to the next one.
+    let metadata = sudokus[1].1.metadata().unwrap();
+    assert_eq!(
+        (Some("43"), None),
+        (metadata.id.as_deref(), metadata.source.as_deref())
+    );
+    let lines: Vec<_> = parse_sudoku_lines(&catalog).collect();
+    assert_eq!(Some(metadata), lines[1].1.as_ref().unwrap().metadata());
+    // Plain puzzles have none, and a blank line drops the fields above it.
+    assert!(parse_sudoku_lines(BATCH).all(|(_, res)| res.map_or(true, |s| s.metadata().is_none())));
+    let separated = format!("# id: 42\n\n{}", BATCH.lines().next().unwrap());
+    assert_eq!(None, parse_sudoku_batch(&separated).unwrap()[0].metadata());
+}
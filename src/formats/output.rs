@@ -0,0 +1,165 @@
+//! Writing a sudoku in any output format of the command line application with a single call.
+use crate::render::{OutputOptions, SvgOptions};
+use crate::solvers::backtracking::Sudoku;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Format of `Sudoku::write_to` and `Sudoku::save`, named like the values of the `--format` option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    /// A single line with `.` for empty cells, see `Sudoku::to_line`.
+    Line,
+    /// The ASCII grid of `Display`.
+    Pretty,
+    /// The ASCII grid with column letters and row numbers, see `Sudoku::to_labeled_string`.
+    Labeled,
+    /// The grid drawn with Unicode box characters, see `Sudoku::to_unicode_string`.
+    Unicode,
+    /// The grid with the candidates of the empty cells, see `Sudoku::to_marked_string`.
+    Marks,
+    /// A line with `0` for empty cells, see `sdm::write_sdm`.
+    Sdm,
+    /// Rows of three squares separated by `|`, see `ss::write_ss`.
+    Ss,
+    /// The givens as clues like `r1c1=5, r1c2=3`, see `cells::to_cell_list`.
+    Cells,
+    /// The array of cells of the serialization, `0` for empty ones.
+    #[cfg(feature = "serde")]
+    Json,
+    Svg,
+    Html,
+    Latex,
+}
+
+impl OutputFormat {
+    /// Returns the format of the name, e.g. `sdm`, None for unknown names.
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        Some(match name {
+            "line" => OutputFormat::Line,
+            "pretty" => OutputFormat::Pretty,
+            "labeled" => OutputFormat::Labeled,
+            "unicode" => OutputFormat::Unicode,
+            "marks" => OutputFormat::Marks,
+            "sdm" => OutputFormat::Sdm,
+            "ss" => OutputFormat::Ss,
+            "cells" => OutputFormat::Cells,
+            #[cfg(feature = "serde")]
+            "json" => OutputFormat::Json,
+            "svg" => OutputFormat::Svg,
+            "html" => OutputFormat::Html,
+            "latex" => OutputFormat::Latex,
+            _ => return None,
+        })
+    }
+}
+
+impl Sudoku {
+    /// Writes the sudoku in the format, the output ends with a line break.
+    pub fn write_to(&self, w: &mut impl Write, format: OutputFormat) -> io::Result<()> {
+        self.write_to_with(w, format, &OutputOptions::default())
+    }
+
+    /// Writes the sudoku like `write_to`, the line, pretty, `.sdm` and `.ss` formats follow the options.
+    pub fn write_to_with(
+        &self,
+        w: &mut impl Write,
+        format: OutputFormat,
+        opts: &OutputOptions,
+    ) -> io::Result<()> {
+        let text = match format {
+            OutputFormat::Line => self.to_line_with(opts),
+            OutputFormat::Pretty => self.to_string_with(opts),
+            OutputFormat::Labeled => self.to_labeled_string(),
+            OutputFormat::Unicode => self.to_unicode_string(),
+            OutputFormat::Marks => self.to_marked_string(),
+            OutputFormat::Sdm => return super::sdm::write_sdm_with(w, Some(self), opts),
+            OutputFormat::Ss => return super::ss::write_ss_with(w, self, opts),
+            OutputFormat::Cells => super::cells::to_cell_list(self),
+            #[cfg(feature = "serde")]
+            OutputFormat::Json => serde_json::to_string(self)?,
+            OutputFormat::Svg => self.to_svg(&SvgOptions::default()),
+            OutputFormat::Html => self.to_html(),
+            OutputFormat::Latex => self.to_latex(),
+        };
+        match text.ends_with('\n') {
+            true => w.write_all(text.as_bytes()),
+            false => writeln!(w, "{}", text),
+        }
+    }
+
+    /// Writes the sudoku in the format to the file at the path, replacing the file if it exists.
+    ///
+    /// The format is always the given one, the extension of the path is not looked at.
+    pub fn save(&self, path: &Path, format: OutputFormat) -> io::Result<()> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        self.write_to(&mut file, format)?;
+        file.flush()
+    }
+}
+
+#[cfg(test)]
+use crate::solvers::backtracking::solvable_field_helper;
+
+#[test]
+fn write_every_format() {
+    let s = solvable_field_helper();
+    let written = |format| {
+        let mut out = Vec::new();
+        s.write_to(&mut out, format).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let starts = [
+        (OutputFormat::Line, "53..7....6..195..."),
+        (OutputFormat::Pretty, "====="),
+        (OutputFormat::Labeled, "    A   B   C"),
+        (OutputFormat::Unicode, "┏━━━"),
+        (OutputFormat::Marks, "┏━━━"),
+        (OutputFormat::Sdm, "530070000600195000"),
+        (OutputFormat::Ss, "53.|.7.|...\n"),
+        (OutputFormat::Cells, "r1c1=5, r1c2=3, "),
+        (OutputFormat::Svg, "<svg "),
+        (OutputFormat::Html, "<table "),
+        (OutputFormat::Latex, "% Sudoku grid"),
+    ];
+    for &(format, start) in starts.iter() {
+        let out = written(format);
+        assert!(out.starts_with(start), "{:?}: {}", format, out);
+        assert!(
+            out.ends_with('\n') && !out.ends_with("\n\n"),
+            "{:?}",
+            format
+        );
+    }
+    #[cfg(feature = "serde")]
+    assert!(written(OutputFormat::Json).starts_with("[5,3,0,0,7,"));
+    assert_eq!(Some(OutputFormat::Ss), OutputFormat::from_name("ss"));
+    let opts = OutputOptions {
+        empty_char: Some('0'),
+        ..OutputOptions::default()
+    };
+    let mut out = Vec::new();
+    s.write_to_with(&mut out, OutputFormat::Line, &opts)
+        .unwrap();
+    assert_eq!(
+        format!("{}\n", s.to_line_with(&opts)),
+        String::from_utf8(out).unwrap()
+    );
+    assert_eq!(None, OutputFormat::from_name("png"));
+}
+
+#[test]
+fn save_to_file() {
+    let path = std::env::temp_dir().join(format!("sudoku_{}_save.sdm", std::process::id()));
+    let s = solvable_field_helper();
+    s.save(&path, OutputFormat::Line).unwrap();
+    // The extension doesn't pick the format.
+    assert_eq!(
+        format!("{}\n", s.to_line()),
+        fs::read_to_string(&path).unwrap()
+    );
+    fs::remove_file(&path).unwrap();
+    assert!(s
+        .save(Path::new("/nonexistent/dir/s.txt"), OutputFormat::Line)
+        .is_err());
+}
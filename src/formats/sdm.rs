@@ -0,0 +1,136 @@
+//! The `.sdm` multi-puzzle format: one puzzle of 81 characters per line with `0` (or `.`) for empty cells.
+//!
+//! The first line may be a header, it is recognized by characters other than digits and dots and skipped.
+use crate::render::OutputOptions;
+use crate::solvers::backtracking::{value_char, Sudoku, SudokuError, EMPTY, SIZE};
+use std::io::{self, BufRead, Write};
+
+/// Returns `true` if the character is a cell of an `.sdm` line.
+fn is_cell(c: char) -> bool {
+    c.is_ascii_digit() || c == '.'
+}
+
+/// Reads all puzzles of an `.sdm` collection.
+///
+/// Blank lines are skipped, every other line must hold exactly 81 cells after trimming, otherwise an error with the
+/// (1-based) line number is returned.
+pub fn read_sdm(reader: impl BufRead) -> Result<Vec<Sudoku>, SudokuError> {
+    let mut sudokus = Vec::new();
+    for (num, line) in reader.lines().enumerate() {
+        let line = line.map_err(|err| SudokuError::Io(err.to_string()))?;
+        let line = line.trim();
+        if line.is_empty() || (num == 0 && !line.chars().all(is_cell)) {
+            continue;
+        }
+        let invalid = |msg: String| SudokuError::ParseLine { line: num + 1, msg };
+        if let Some(c) = line.chars().find(|&c| !is_cell(c)) {
+            return Err(invalid(format!("unexpected character '{}'", c)));
+        }
+        if line.len() != SIZE {
+            return Err(invalid(format!(
+                "expected {} cells, found {}",
+                SIZE,
+                line.len()
+            )));
+        }
+        sudokus.push(
+            line.parse()
+                .map_err(|err: SudokuError| invalid(err.to_string()))?,
+        );
+    }
+    Ok(sudokus)
+}
+
+/// Writes the sudokus as an `.sdm` collection, one per line with `0` for empty cells.
+pub fn write_sdm<'a>(
+    writer: &mut impl Write,
+    sudokus: impl IntoIterator<Item = &'a Sudoku>,
+) -> io::Result<()> {
+    write_sdm_with(writer, sudokus, &OutputOptions::default())
+}
+
+/// Writes the sudokus as an `.sdm` collection with the empty cells drawn as the options say.
+pub fn write_sdm_with<'a>(
+    writer: &mut impl Write,
+    sudokus: impl IntoIterator<Item = &'a Sudoku>,
+    opts: &OutputOptions,
+) -> io::Result<()> {
+    let empty = opts.empty('0');
+    for sudoku in sudokus {
+        let line: String = sudoku
+            .field
+            .iter()
+            .map(|&cell| match cell {
+                EMPTY => empty,
+                v => value_char(v),
+            })
+            .collect();
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+const SAMPLE: &str = concat!(
+    "[sample collection]\r\n",
+    "530070000600195000098000060800060003400803001700020006060000280000419005000080079\r\n",
+    "\r\n",
+    "000000000000000000000000000000000000000000000000000000000000000000000000000000001\r\n",
+);
+
+#[test]
+fn read_sample() {
+    let sudokus = read_sdm(SAMPLE.as_bytes()).unwrap();
+    assert_eq!(2, sudokus.len());
+    assert_eq!(
+        crate::solvers::backtracking::solvable_field_helper(),
+        sudokus[0]
+    );
+}
+
+#[test]
+fn round_trip() {
+    let sudokus = read_sdm(SAMPLE.as_bytes()).unwrap();
+    let mut out = Vec::new();
+    write_sdm(&mut out, &sudokus).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert_eq!(
+        SAMPLE
+            .lines()
+            .skip(1)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>(),
+        text.lines().collect::<Vec<_>>()
+    );
+    assert_eq!(sudokus, read_sdm(text.as_bytes()).unwrap());
+    let mut dotted = Vec::new();
+    let opts = OutputOptions {
+        empty_char: Some('.'),
+        ..OutputOptions::default()
+    };
+    write_sdm_with(&mut dotted, &sudokus, &opts).unwrap();
+    let dotted = String::from_utf8(dotted).unwrap();
+    assert_eq!(text.replace('0', "."), dotted);
+    assert_eq!(sudokus, read_sdm(dotted.as_bytes()).unwrap());
+}
+
+#[test]
+fn read_short_line() {
+    let data = format!("{}\n{}\n", "0".repeat(81), "0".repeat(80));
+    assert_eq!(
+        Err(SudokuError::ParseLine {
+            line: 2,
+            msg: "expected 81 cells, found 80".to_string()
+        }),
+        read_sdm(data.as_bytes())
+    );
+}
+
+#[test]
+fn read_long_line() {
+    let data = format!("{}\n", "0".repeat(82));
+    assert!(matches!(
+        read_sdm(data.as_bytes()),
+        Err(SudokuError::ParseLine { line: 1, .. })
+    ));
+}
@@ -0,0 +1,187 @@
+//! The Simple Sudoku `.ss` grid format.
+//!
+//! A grid is written as nine rows of `53.|.7.|...` with `|` between squares and `---+---+---` lines between bands.
+//! Both `.` and `0` are accepted for empty cells, `.` is written.
+use crate::render::OutputOptions;
+use crate::solvers::backtracking::{value_char, Sudoku, SudokuError, EMPTY, SIDE, SQUARE_SIDE};
+use std::io::{self, BufRead, Write};
+
+/// Line separating bands of the grid.
+const SEPARATOR: &str = "---+---+---";
+
+/// Reads a single grid in the `.ss` format.
+///
+/// Trailing whitespace and blank lines after the grid are ignored. When a line doesn't match the expected structure
+/// an error with its (1-based) line number is returned.
+pub fn read_ss(reader: impl BufRead) -> Result<Sudoku, SudokuError> {
+    let mut field = Vec::new();
+    let mut lines = 0;
+    for (num, line) in reader.lines().enumerate() {
+        let line = line.map_err(|err| SudokuError::Io(err.to_string()))?;
+        let line = line.trim_end();
+        let invalid = |msg: &str| SudokuError::ParseLine {
+            line: num + 1,
+            msg: msg.to_string(),
+        };
+        if lines == SIDE + 2 {
+            if !line.is_empty() {
+                return Err(invalid("unexpected line after the grid"));
+            }
+            continue;
+        }
+        lines += 1;
+        if lines % (SQUARE_SIDE + 1) == 0 {
+            if line != SEPARATOR {
+                return Err(invalid("expected a `---+---+---` separator"));
+            }
+            continue;
+        }
+        let chars: Vec<char> = line.chars().collect();
+        if chars.len() != SEPARATOR.len() || chars[3] != '|' || chars[7] != '|' {
+            return Err(invalid("expected a row of three squares separated by `|`"));
+        }
+        for (i, &c) in chars.iter().enumerate().filter(|(i, _)| i % 4 != 3) {
+            field.push(match c {
+                '.' => EMPTY,
+                c if c.is_ascii_digit() => c as u8 - b'0',
+                _ => {
+                    return Err(invalid(&format!(
+                        "unexpected character '{}' in column {}",
+                        c,
+                        i + 1
+                    )))
+                }
+            });
+        }
+    }
+    if lines != SIDE + 2 {
+        return Err(SudokuError::Parse(format!(
+            "expected {} lines, found {}",
+            SIDE + 2,
+            lines
+        )));
+    }
+    Ok(Sudoku::new(field.into_iter()).unwrap())
+}
+
+/// Writes the sudoku in the `.ss` format.
+///
+/// Sudokus of other sizes are written in the same way, with squares of their size.
+pub fn write_ss(writer: &mut impl Write, sudoku: &Sudoku) -> io::Result<()> {
+    write_ss_with(writer, sudoku, &OutputOptions::default())
+}
+
+/// Writes the sudoku in the `.ss` format with the empty cells drawn as the options say.
+pub fn write_ss_with(
+    writer: &mut impl Write,
+    sudoku: &Sudoku,
+    opts: &OutputOptions,
+) -> io::Result<()> {
+    let empty = opts.empty('.');
+    let geometry = sudoku.geometry();
+    let (side, box_rows, box_cols) = (geometry.side(), geometry.box_rows(), geometry.box_cols());
+    let separator = vec!["-".repeat(box_cols); box_rows].join("+");
+    for row in 0..side {
+        if row != 0 && row % box_rows == 0 {
+            writeln!(writer, "{}", separator)?;
+        }
+        let line: Vec<String> = sudoku.field[row * side..(row + 1) * side]
+            .chunks(box_cols)
+            .map(|square| {
+                square
+                    .iter()
+                    .map(|&cell| match cell {
+                        EMPTY => empty,
+                        v => value_char(v),
+                    })
+                    .collect()
+            })
+            .collect();
+        writeln!(writer, "{}", line.join("|"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+const SAMPLE: &str = concat!(
+    "53.|.7.|...\n",
+    "6..|195|...  \n",
+    ".98|...|.6.\n",
+    "---+---+---\n",
+    "8..|.6.|..3\n",
+    "4..|8.3|..1\n",
+    "700|.2.|..6\n",
+    "---+---+---\n",
+    ".6.|...|28.\n",
+    "...|419|..5\n",
+    "...|.8.|.79\n",
+    "\n",
+);
+
+#[test]
+fn read_solve_and_write() {
+    let mut s = read_ss(SAMPLE.as_bytes()).unwrap();
+    assert_eq!(crate::solvers::backtracking::solvable_field_helper(), s);
+    s.solve().unwrap();
+    let mut out = Vec::new();
+    write_ss(&mut out, &s).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.starts_with("534|678|912\n672|195|348\n198|342|567\n---+---+---\n"));
+    assert_eq!(s, read_ss(text.as_bytes()).unwrap());
+}
+
+#[test]
+fn read_missing_separator() {
+    let data = SAMPLE.replacen(SEPARATOR, "-----------", 1);
+    assert_eq!(
+        Err(SudokuError::ParseLine {
+            line: 4,
+            msg: "expected a `---+---+---` separator".to_string()
+        }),
+        read_ss(data.as_bytes())
+    );
+}
+
+#[test]
+fn read_malformed_row() {
+    let data = SAMPLE.replace("4..|8.3|..1", "4..8.3..1");
+    assert!(matches!(
+        read_ss(data.as_bytes()),
+        Err(SudokuError::ParseLine { line: 6, .. })
+    ));
+    let data = SAMPLE.replace("4..|8.3|..1", "4..|8x3|..1");
+    assert!(matches!(
+        read_ss(data.as_bytes()),
+        Err(SudokuError::ParseLine { line: 6, .. })
+    ));
+}
+
+#[test]
+fn read_truncated_grid() {
+    let data: String = SAMPLE.lines().take(5).map(|l| format!("{}\n", l)).collect();
+    assert!(matches!(
+        read_ss(data.as_bytes()),
+        Err(SudokuError::Parse(_))
+    ));
+}
+
+#[test]
+fn write_4x4() {
+    let s = Sudoku::parse_with_box_size("1.....2..3.....4", 2).unwrap();
+    let mut out = Vec::new();
+    write_ss(&mut out, &s).unwrap();
+    assert_eq!(
+        "1.|..\n..|2.\n--+--\n.3|..\n..|.4\n",
+        String::from_utf8(out).unwrap()
+    );
+    let mut out = Vec::new();
+    let opts = OutputOptions {
+        empty_char: Some('0'),
+        ..OutputOptions::default()
+    };
+    write_ss_with(&mut out, &s, &opts).unwrap();
+    assert_eq!(
+        "10|00\n00|20\n--+--\n03|00\n00|04\n",
+        String::from_utf8(out).unwrap()
+    );
+}
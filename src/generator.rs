@@ -0,0 +1,249 @@
+use crate::prelude::*;
+use crate::solvers::backtracking::{Sudoku, EMPTY};
+use crate::solvers::logical::Difficulty;
+use crate::solvers::value_order::{splitmix64, SolveOptions};
+#[cfg(feature = "std")]
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+#[cfg(feature = "std")]
+use rand::SeedableRng;
+use rand::{Rng, RngCore};
+
+/// Placements the uniqueness check of a cleared cell may make when the search of the grid is limited, see
+/// `NodeLimit::Default`, so a 16x16 puzzle is generated in seconds instead of minutes.
+pub const GENERATE_NODES: u64 = 1000;
+
+/// Generates a random puzzle with a unique solution.
+///
+/// A random solved grid is built first, then its cells are cleared in random order as long as the solution stays
+/// unique, so the puzzle has no clue which could be removed.
+pub fn generate(rng: &mut impl Rng) -> Sudoku {
+    generate_with_options(rng, &SolveOptions::random(None))
+}
+
+/// Generates a puzzle like `generate`, the solved grid is found by a search with the options.
+///
+/// A `Random` order without a seed gets one from `rng`, so the same `rng` gives the same puzzle. The `Ascending` order
+/// always starts from the same grid, only the clues kept vary.
+pub fn generate_with_options(rng: &mut impl Rng, opts: &SolveOptions) -> Sudoku {
+    generate_from(Sudoku::empty(), rng, opts)
+}
+
+/// Generates a random puzzle with squares of `box_size` rows and columns, see `Sudoku::with_box_size`.
+///
+/// Returns None if the box size is not supported.
+pub fn generate_with_box_size(rng: &mut impl Rng, box_size: usize) -> Option<Sudoku> {
+    generate_with_box_shape(rng, box_size, box_size)
+}
+
+/// Generates a random puzzle with boxes of `box_rows` rows and `box_cols` columns, see `Sudoku::with_box_shape`.
+///
+/// Clues of grids larger than 9x9 whose removal takes more than `GENERATE_NODES` placements to check are kept.
+///
+/// Returns None if the box shape is not supported.
+pub fn generate_with_box_shape(
+    rng: &mut impl Rng,
+    box_rows: usize,
+    box_cols: usize,
+) -> Option<Sudoku> {
+    generate_with_box_shape_and_options(rng, box_rows, box_cols, &SolveOptions::random(None))
+}
+
+/// Generates a puzzle like `generate_with_box_shape`, the solved grid is found by a search with the options.
+///
+/// `NodeLimit::Unlimited` lifts the limit of the uniqueness checks too, the puzzle has no clue which could be removed.
+pub fn generate_with_box_shape_and_options(
+    rng: &mut impl Rng,
+    box_rows: usize,
+    box_cols: usize,
+    opts: &SolveOptions,
+) -> Option<Sudoku> {
+    Sudoku::with_box_shape(box_rows, box_cols).map(|empty| generate_from(empty, rng, opts))
+}
+
+/// Returns a generator of random numbers seeded with the seed, or with a seed drawn from the system entropy when it is
+/// None, together with the seed used.
+///
+/// The same seed gives the same puzzles, so a run can be reproduced from the seed it reports.
+#[cfg(feature = "std")]
+pub fn seeded_rng(seed: Option<u64>) -> (StdRng, u64) {
+    let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+    (StdRng::seed_from_u64(seed), seed)
+}
+
+/// Puzzles `generate_daily` generates at most before it settles for one of another difficulty.
+pub const DAILY_ATTEMPTS: usize = 20;
+
+/// Generates the puzzle of the day: the same date and difficulty give the same puzzle on every run and platform.
+///
+/// The seed is the 64-bit FNV-1a hash of the UTF-8 bytes of the date as given, e.g. `2024-06-01`, which seeds the
+/// SplitMix64 generator. Puzzles are drawn from it until one is rated `difficulty`. A puzzle which is too hard gets
+/// clues of its solution back, in the order the generator shuffles them, until it is no longer too hard. After
+/// `DAILY_ATTEMPTS` puzzles the last one is returned whatever its difficulty.
+pub fn generate_daily(date: &str, difficulty: Difficulty) -> Sudoku {
+    let mut rng = SplitMix64(fnv1a(date.as_bytes()));
+    let mut puzzle = Sudoku::empty();
+    for _ in 0..DAILY_ATTEMPTS {
+        puzzle = ease_to(generate(&mut rng), difficulty, &mut rng);
+        if puzzle.rate().ok() == Some(difficulty) {
+            break;
+        }
+    }
+    puzzle
+}
+
+/// Returns the 64-bit FNV-1a hash of the bytes, stable unlike the hashers of the standard library.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+/// Adds clues of the solution to the puzzle in random order until it is rated `difficulty` or easier.
+fn ease_to(puzzle: Sudoku, difficulty: Difficulty, rng: &mut impl Rng) -> Sudoku {
+    let mut solution = puzzle.clone();
+    solution.solve().expect("generated puzzles have a solution");
+    let mut field = puzzle.field.clone();
+    let mut empty: Vec<usize> = (0..field.len())
+        .filter(|&pos| field[pos] == EMPTY)
+        .collect();
+    empty.shuffle(rng);
+    let mut eased = puzzle;
+    for pos in empty {
+        if eased.rate().is_ok_and(|rated| rated <= difficulty) {
+            break;
+        }
+        field[pos] = solution.field[pos];
+        eased = eased.with_field(field.clone());
+    }
+    eased
+}
+
+/// SplitMix64 generator of `generate_daily`, whose numbers never change with the version of `rand` like `StdRng` may.
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl RngCore for SplitMix64 {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        splitmix64(&mut self.0)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Solves the empty sudoku with the options and clears its cells while the solution stays unique.
+///
+/// The puzzle keeps the default options of the empty sudoku, the ones of the generator are not its business.
+fn generate_from(empty: Sudoku, rng: &mut impl Rng, opts: &SolveOptions) -> Sudoku {
+    let opts = SolveOptions {
+        seed: Some(opts.seed.unwrap_or_else(|| rng.gen())),
+        ..*opts
+    };
+    let mut full = empty.clone().with_options(&opts);
+    full.solve().expect("an empty grid has a solution");
+    let mut field = full.field.clone();
+    let mut positions: Vec<usize> = (0..field.len()).collect();
+    positions.shuffle(rng);
+    // A check which gives up keeps the clue, the puzzle stays unique but may have clues it could do without.
+    let max_nodes = opts
+        .node_limit
+        .max_nodes(empty.geometry())
+        .map_or(u64::MAX, |max| max.min(GENERATE_NODES));
+    for pos in positions {
+        let val = field[pos];
+        field[pos] = EMPTY;
+        let count = empty
+            .with_field(field.clone())
+            .count_solutions_within(2, max_nodes);
+        if count != Some(1) {
+            field[pos] = val;
+        }
+    }
+    empty.with_field(field)
+}
+
+#[test]
+fn generated_puzzle_has_unique_solution() {
+    let s = generate(&mut rand::thread_rng());
+    assert!(!s.solved());
+    assert_eq!(Ok(1), s.count_solutions(2));
+}
+
+#[test]
+fn generate_4x4() {
+    let s = generate_with_box_size(&mut rand::thread_rng(), 2).unwrap();
+    assert_eq!(4, s.side());
+    assert!(!s.solved());
+    assert_eq!(Ok(1), s.count_solutions(2));
+    assert!(generate_with_box_size(&mut rand::thread_rng(), 5).is_none());
+}
+
+#[test]
+fn generate_6x6() {
+    let s = generate_with_box_shape(&mut rand::thread_rng(), 2, 3).unwrap();
+    assert_eq!((6, 2, 3), (s.side(), s.box_rows(), s.box_cols()));
+    assert_eq!(Ok(1), s.count_solutions(2));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn seeded_generation() {
+    let (mut rng, seed) = seeded_rng(Some(42));
+    assert_eq!(42, seed);
+    let first = generate(&mut rng);
+    assert_eq!(first, generate(&mut seeded_rng(Some(42)).0));
+    assert_ne!(first, generate(&mut seeded_rng(Some(43)).0));
+    let (mut rng, seed) = seeded_rng(None);
+    assert_eq!(generate(&mut rng), generate(&mut seeded_rng(Some(seed)).0));
+}
+
+#[test]
+fn generate_with_value_orders() {
+    use crate::solvers::value_order::ValueOrder;
+    let ascending = SolveOptions::default();
+    let s = generate_with_options(&mut rand::thread_rng(), &ascending);
+    assert_eq!(Ok(1), s.count_solutions(2));
+    assert_eq!(ValueOrder::Ascending, s.value_order());
+    let mut solution = s.clone();
+    solution.solve().unwrap();
+    // The ascending search fills an empty grid with the same solution every time.
+    let mut first = Sudoku::empty();
+    first.solve().unwrap();
+    assert_eq!(first.as_slice(), solution.as_slice());
+    let opts = SolveOptions::random(Some(7));
+    let s = generate_with_box_shape_and_options(&mut rand::thread_rng(), 2, 3, &opts).unwrap();
+    assert_eq!(Ok(1), s.count_solutions(2));
+}
+
+#[test]
+fn daily_puzzle() {
+    let hard = generate_daily("2024-06-01", Difficulty::Hard);
+    // Pinned so that a change of the seed, the generator or the rater shows up.
+    assert_eq!(
+        ".9.2..6.5....3.....8.9....1.7.16.2.82.....15............8..1..44......8.....7.3.9",
+        hard.to_line()
+    );
+    assert_eq!(Ok(Difficulty::Hard), hard.rate());
+    assert_eq!(Ok(1), hard.count_solutions(2));
+    assert_eq!(hard, generate_daily("2024-06-01", Difficulty::Hard));
+    assert_ne!(hard, generate_daily("2024-06-02", Difficulty::Hard));
+    for difficulty in [Difficulty::Easy, Difficulty::Medium].iter() {
+        let s = generate_daily("2024-06-01", *difficulty);
+        assert_eq!(Ok(*difficulty), s.rate());
+        assert_eq!(Ok(1), s.count_solutions(2));
+    }
+    assert_eq!(0xAF63_DC4C_8601_EC8C, fnv1a(b"a"));
+}
@@ -0,0 +1,518 @@
+use crate::{describe_hint, input_format, load_sudoku_from, render, Failure, Variant};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use sudoku::solvers::backtracking::Sudoku;
+
+/// Help printed by the `help` command.
+const COMMANDS_HELP: &str = "Commands (rows and columns are numbered from 1):
+    set R C V       put value V into the cell
+    clear R C       empty the cell
+    reset           empty every cell which is not a given
+    unlock R C      make the given an ordinary cell to correct the puzzle
+    lock R C        make the filled cell a given of the puzzle
+    candidates R C  list values allowed in the cell
+    note R C V      add V to your notes of the empty cell
+    unnote R C V    remove V from your notes of the cell
+    hint            suggest the value of one cell
+    solve           fill all empty cells
+    undo            revert the last change
+    load PATH       load a new puzzle
+    save PATH       save the puzzle, the format is chosen by the extension
+    savec PATH      save your notes of every cell, as JSON if PATH ends with .json
+    loadc PATH      replace your notes with the ones saved by savec
+    help            print this help
+    quit            exit";
+
+/// State of an interactive session: the edited sudoku and its previous versions for `undo`.
+pub struct Session {
+    sudoku: Sudoku,
+    history: Vec<Sudoku>,
+}
+
+/// What the interpreter prints in response to a command.
+#[derive(Debug, PartialEq)]
+enum Response {
+    /// The sudoku is changed, the message is printed with the new grid.
+    Changed(String),
+    /// The message is printed, the sudoku stays the same.
+    Message(String),
+    Quit,
+}
+
+impl Session {
+    pub fn new(sudoku: Sudoku) -> Session {
+        Session {
+            sudoku,
+            history: Vec::new(),
+        }
+    }
+
+    /// Reads commands from `input` until `quit` or the end of input, writing responses to `out`.
+    pub fn run(&mut self, input: impl BufRead, out: &mut impl Write) -> Result<(), Failure> {
+        let write_failure = |err: io::Error| Failure::Io(format!("Cannot write output: {}", err));
+        writeln!(
+            out,
+            "{}\n{}",
+            render(&self.sudoku, "pretty", false),
+            COMMANDS_HELP
+        )
+        .map_err(write_failure)?;
+        for line in input.lines() {
+            let line = line.map_err(|err| Failure::Io(format!("Cannot read command: {}", err)))?;
+            let text = match self.execute(&line) {
+                Response::Quit => return Ok(()),
+                Response::Message(msg) => msg,
+                Response::Changed(msg) => {
+                    format!("{}\n{}", msg, render(&self.sudoku, "pretty", false))
+                }
+            };
+            writeln!(out, "{}", text).map_err(write_failure)?;
+        }
+        Ok(())
+    }
+
+    /// Executes a single command.
+    fn execute(&mut self, line: &str) -> Response {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (*command, args),
+            None => return Response::Message(String::new()),
+        };
+        let res = match (command, args.len()) {
+            ("set", 3) => self.set(args),
+            ("clear", 2) => self.clear(args),
+            ("reset", 0) => self.reset(),
+            ("unlock", 2) => self.lock(args, false),
+            ("lock", 2) => self.lock(args, true),
+            ("candidates", 2) => self.candidates(args),
+            ("note", 3) => self.note(args, true),
+            ("unnote", 3) => self.note(args, false),
+            ("hint", 0) => self.hint(),
+            ("solve", 0) => self.solve(),
+            ("undo", 0) => self.undo(),
+            ("load", 1) => self.load(args[0]),
+            ("save", 1) => self.save(args[0]),
+            ("savec", 1) => self.save_notes(args[0]),
+            ("loadc", 1) => self.load_notes(args[0]),
+            ("help", 0) => Ok(Response::Message(COMMANDS_HELP.to_string())),
+            ("quit", 0) => Ok(Response::Quit),
+            _ => Err(format!(
+                "Unknown command `{}`, type `help` for the list of commands",
+                line.trim()
+            )),
+        };
+        res.unwrap_or_else(|msg| Response::Message(format!("Error: {}", msg)))
+    }
+
+    /// Replaces the sudoku with the changed one, keeping the current version for `undo`.
+    fn change(&mut self, sudoku: Sudoku, msg: String) -> Response {
+        let previous = std::mem::replace(&mut self.sudoku, sudoku);
+        self.history.push(previous);
+        Response::Changed(msg)
+    }
+
+    fn set(&mut self, args: &[&str]) -> Result<Response, String> {
+        let (row, col) = self.parse_cell(args)?;
+        let value = parse_number(args[2], "value", self.sudoku.side())?;
+        let mut sudoku = self.sudoku.clone();
+        sudoku.set(row, col, value as u8).map_err(|err| {
+            format!(
+                "Cannot put {} into r{}c{}: {}",
+                value,
+                row + 1,
+                col + 1,
+                err.msg
+            )
+        })?;
+        Ok(self.change(
+            sudoku,
+            format!("Put {} into r{}c{}", value, row + 1, col + 1),
+        ))
+    }
+
+    fn clear(&mut self, args: &[&str]) -> Result<Response, String> {
+        let (row, col) = self.parse_cell(args)?;
+        let mut sudoku = self.sudoku.clone();
+        sudoku
+            .clear(row, col)
+            .map_err(|err| format!("Cannot clear r{}c{}: {}", row + 1, col + 1, err.msg))?;
+        Ok(self.change(sudoku, format!("Cleared r{}c{}", row + 1, col + 1)))
+    }
+
+    fn reset(&mut self) -> Result<Response, String> {
+        let mut sudoku = self.sudoku.clone();
+        sudoku.reset();
+        Ok(self.change(sudoku, "Reset to the givens".to_string()))
+    }
+
+    /// Makes the cell a given of the puzzle, or an ordinary cell when `lock` is false.
+    fn lock(&mut self, args: &[&str], lock: bool) -> Result<Response, String> {
+        let (row, col) = self.parse_cell(args)?;
+        let mut sudoku = self.sudoku.clone();
+        let msg = match lock {
+            true if sudoku.value(row, col).is_none() => {
+                return Err(format!(
+                    "Cannot lock r{}c{}: the cell is empty",
+                    row + 1,
+                    col + 1
+                ))
+            }
+            true => {
+                sudoku.lock(row, col);
+                format!("r{}c{} is a given", row + 1, col + 1)
+            }
+            false => {
+                sudoku.unlock(row, col);
+                format!("r{}c{} can be changed", row + 1, col + 1)
+            }
+        };
+        self.change(sudoku, String::new());
+        Ok(Response::Message(msg))
+    }
+
+    fn candidates(&self, args: &[&str]) -> Result<Response, String> {
+        let (row, col) = self.parse_cell(args)?;
+        if let Some(value) = self.sudoku.value(row, col) {
+            return Ok(Response::Message(format!(
+                "r{}c{} is {}",
+                row + 1,
+                col + 1,
+                value
+            )));
+        }
+        let candidates: Vec<String> = self
+            .sudoku
+            .candidates(row, col)
+            .iter()
+            .map(|val| val.to_string())
+            .collect();
+        Ok(Response::Message(match candidates.is_empty() {
+            true => format!("No value is allowed in r{}c{}", row + 1, col + 1),
+            false => format!(
+                "Candidates of r{}c{}: {}",
+                row + 1,
+                col + 1,
+                candidates.join(" ")
+            ),
+        }))
+    }
+
+    /// Adds the value to the notes of the cell, or removes it when `add` is false.
+    fn note(&mut self, args: &[&str], add: bool) -> Result<Response, String> {
+        let (row, col) = self.parse_cell(args)?;
+        let value = parse_number(args[2], "value", self.sudoku.side())? as u8;
+        let mut sudoku = self.sudoku.clone();
+        let res = match add {
+            true => sudoku.add_note(row, col, value),
+            false => sudoku.remove_note(row, col, value),
+        };
+        res.map_err(|err| {
+            format!(
+                "Cannot note {} in r{}c{}: {}",
+                value,
+                row + 1,
+                col + 1,
+                err.msg
+            )
+        })?;
+        let notes: Vec<String> = sudoku.notes(row, col).map(|val| val.to_string()).collect();
+        let msg = match notes.is_empty() {
+            true => format!("No notes in r{}c{}", row + 1, col + 1),
+            false => format!("Notes of r{}c{}: {}", row + 1, col + 1, notes.join(" ")),
+        };
+        self.change(sudoku, String::new());
+        Ok(Response::Message(msg))
+    }
+
+    fn hint(&self) -> Result<Response, String> {
+        match self.sudoku.hint() {
+            Ok(Some(hint)) => Ok(Response::Message(describe_hint(&hint))),
+            Ok(None) => Ok(Response::Message(
+                "Sudoku is solved, there is nothing to hint".to_string(),
+            )),
+            Err(_) => Err("sudoku cannot be solved".to_string()),
+        }
+    }
+
+    fn solve(&mut self) -> Result<Response, String> {
+        let mut sudoku = self.sudoku.clone();
+        sudoku
+            .solve()
+            .map_err(|_| "sudoku cannot be solved".to_string())?;
+        Ok(self.change(sudoku, "Solved!".to_string()))
+    }
+
+    fn undo(&mut self) -> Result<Response, String> {
+        match self.history.pop() {
+            Some(previous) => {
+                self.sudoku = previous;
+                Ok(Response::Changed("Undone".to_string()))
+            }
+            None => Err("nothing to undo".to_string()),
+        }
+    }
+
+    fn load(&mut self, path: &str) -> Result<Response, String> {
+        let variant = Variant {
+            rules: self.sudoku.rules(),
+            ..Variant::default()
+        };
+        let sudoku =
+            load_sudoku_from(path, "auto", None, &variant).map_err(|failure| match failure {
+                Failure::InvalidInput(msg) | Failure::Io(msg) | Failure::Interrupted(msg) => msg,
+                _ => "sudoku cannot be solved".to_string(),
+            })?;
+        let sudoku = sudoku.with_node_limit(self.sudoku.node_limit());
+        // Keep the regions of a jigsaw sudoku when a puzzle of the same size is loaded.
+        let sudoku = match sudoku.side() == self.sudoku.side() {
+            true => sudoku
+                .with_regions(self.sudoku.regions().clone())
+                .expect("regions of a sudoku of the same size cover the grid"),
+            false => sudoku,
+        };
+        Ok(self.change(sudoku, format!("Loaded {}", path)))
+    }
+
+    fn save(&self, path: &str) -> Result<Response, String> {
+        let rendered = render(&self.sudoku, input_format(path, "auto"), false);
+        fs::write(path, rendered + "\n").map_err(|err| format!("Cannot save {}: {}", path, err))?;
+        Ok(Response::Message(format!("Saved {}", path)))
+    }
+
+    fn save_notes(&self, path: &str) -> Result<Response, String> {
+        let notes = match path.ends_with(".json") {
+            true => self.sudoku.export_candidates_json(),
+            false => self.sudoku.export_candidates(),
+        };
+        fs::write(path, notes + "\n").map_err(|err| format!("Cannot save {}: {}", path, err))?;
+        Ok(Response::Message(format!("Saved notes to {}", path)))
+    }
+
+    fn load_notes(&mut self, path: &str) -> Result<Response, String> {
+        let text =
+            fs::read_to_string(path).map_err(|err| format!("Cannot read {}: {}", path, err))?;
+        let mut sudoku = self.sudoku.clone();
+        sudoku
+            .import_candidates(&text)
+            .map_err(|err| format!("Cannot load notes from {}: {}", path, err))?;
+        self.change(sudoku, String::new());
+        Ok(Response::Message(format!("Loaded notes from {}", path)))
+    }
+
+    /// Parses 1-based row and column of a cell of the grid into 0-based indices.
+    fn parse_cell(&self, args: &[&str]) -> Result<(usize, usize), String> {
+        let side = self.sudoku.side();
+        Ok((
+            parse_number(args[0], "row", side)? - 1,
+            parse_number(args[1], "column", side)? - 1,
+        ))
+    }
+}
+
+/// Parses a number from 1 to `max`.
+fn parse_number(arg: &str, name: &str, max: usize) -> Result<usize, String> {
+    match arg.parse() {
+        Ok(number) if (1..=max).contains(&number) => Ok(number),
+        _ => Err(format!(
+            "{} must be a number from 1 to {}, got `{}`",
+            name, max, arg
+        )),
+    }
+}
+
+#[cfg(test)]
+fn run_script(sudoku: Sudoku, script: &str) -> String {
+    let mut out = Vec::new();
+    Session::new(sudoku)
+        .run(io::Cursor::new(script), &mut out)
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn set_undo_and_quit() {
+    let mut session = Session::new(Sudoku::empty());
+    assert_eq!(
+        Response::Changed("Put 5 into r1c2".to_string()),
+        session.execute("set 1 2 5")
+    );
+    assert_eq!(Some(5), session.sudoku.value(0, 1));
+    assert_eq!(
+        Response::Message("Error: Cannot put 5 into r1c9: 5 is already in row 1".to_string()),
+        session.execute("set 1 9 5")
+    );
+    assert_eq!(
+        Response::Message("Candidates of r1c1: 1 2 3 4 6 7 8 9".to_string()),
+        session.execute("candidates 1 1")
+    );
+    assert_eq!(
+        Response::Changed("Undone".to_string()),
+        session.execute("undo")
+    );
+    assert_eq!(None, session.sudoku.value(0, 1));
+    assert_eq!(
+        Response::Message("Error: nothing to undo".to_string()),
+        session.execute("undo")
+    );
+    assert_eq!(Response::Quit, session.execute("quit"));
+}
+
+#[test]
+fn notes_commands() {
+    let mut session = Session::new(Sudoku::empty());
+    assert_eq!(
+        Response::Message("Notes of r1c1: 4".to_string()),
+        session.execute("note 1 1 4")
+    );
+    assert_eq!(
+        Response::Message("Notes of r1c1: 2 4".to_string()),
+        session.execute("note 1 1 2")
+    );
+    session.execute("set 1 1 4");
+    assert_eq!(0, session.sudoku.notes(0, 0).count());
+    assert_eq!(
+        Response::Message("Error: Cannot note 3 in r1c1: the cell is filled".to_string()),
+        session.execute("note 1 1 3")
+    );
+    // Undoing the placement brings the notes back.
+    session.execute("undo");
+    assert_eq!(vec![2, 4], session.sudoku.notes(0, 0).collect::<Vec<_>>());
+    assert_eq!(
+        Response::Message("Notes of r1c1: 2".to_string()),
+        session.execute("unnote 1 1 4")
+    );
+    session.execute("undo");
+    assert_eq!(vec![2, 4], session.sudoku.notes(0, 0).collect::<Vec<_>>());
+}
+
+#[test]
+fn invalid_commands() {
+    let mut session = Session::new(Sudoku::empty());
+    assert_eq!(
+        Response::Message("Error: row must be a number from 1 to 9, got `0`".to_string()),
+        session.execute("set 0 1 1")
+    );
+    assert_eq!(
+        Response::Message(
+            "Error: Unknown command `set 1`, type `help` for the list of commands".to_string()
+        ),
+        session.execute("set 1")
+    );
+    // Rows, columns and values go up to the side of the grid.
+    let mut session = Session::new(Sudoku::with_box_size(2).unwrap());
+    assert_eq!(
+        Response::Message("Error: row must be a number from 1 to 4, got `5`".to_string()),
+        session.execute("set 5 5 1")
+    );
+    assert_eq!(
+        Response::Message("Error: value must be a number from 1 to 4, got `5`".to_string()),
+        session.execute("note 1 1 5")
+    );
+}
+
+#[test]
+fn scripted_session() {
+    let sudoku: Sudoku =
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"
+            .parse()
+            .unwrap();
+    let out = run_script(sudoku, "clear 1 1\nhint\nsolve\nquit\nset 1 1 1\n");
+    assert!(out.contains("Error: Cannot clear r1c1: the cell is a given\n"));
+    assert!(out.contains(
+        "Row 5, column 5 (E5): 5 (naked single) - 5 is the only remaining candidate of r5c5\n"
+    ));
+    assert!(out.contains("Solved!\n"));
+    // Commands after `quit` are not executed.
+    assert!(!out.contains("r1c1: 1"));
+}
+
+#[test]
+fn givens_are_kept() {
+    let sudoku: Sudoku =
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79"
+            .parse()
+            .unwrap();
+    let mut session = Session::new(sudoku.clone());
+    assert_eq!(
+        Response::Message("Error: Cannot put 2 into r1c1: the cell is a given".to_string()),
+        session.execute("set 1 1 2")
+    );
+    session.execute("set 1 3 4");
+    assert_eq!(
+        Response::Changed("Reset to the givens".to_string()),
+        session.execute("reset")
+    );
+    assert_eq!(sudoku, session.sudoku);
+    assert_eq!(
+        Response::Message("r1c1 can be changed".to_string()),
+        session.execute("unlock 1 1")
+    );
+    assert_eq!(
+        Response::Changed("Put 2 into r1c1".to_string()),
+        session.execute("set 1 1 2")
+    );
+    assert_eq!(
+        Response::Message("r1c1 is a given".to_string()),
+        session.execute("lock 1 1")
+    );
+    assert!(session.sudoku.is_given(0, 0));
+    assert_eq!(
+        Response::Message("Error: Cannot lock r1c3: the cell is empty".to_string()),
+        session.execute("lock 1 3")
+    );
+    // Undo goes back through the edits of the givens too.
+    session.execute("undo");
+    assert!(!session.sudoku.is_given(0, 0));
+}
+
+#[test]
+fn save_and_load() {
+    let path = std::env::temp_dir().join(format!("sudoku_{}_session.ss", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut session = Session::new(Sudoku::empty());
+    session.execute("set 9 9 7");
+    assert_eq!(
+        Response::Message(format!("Saved {}", path)),
+        session.execute(&format!("save {}", path))
+    );
+    let mut other = Session::new(Sudoku::empty());
+    other.execute(&format!("load {}", path));
+    assert_eq!(Some(7), other.sudoku.value(8, 8));
+}
+
+#[test]
+fn save_and_load_notes() {
+    let dir = std::env::temp_dir();
+    let text = dir.join(format!("sudoku_{}_notes.txt", std::process::id()));
+    let json = dir.join(format!("sudoku_{}_notes.json", std::process::id()));
+    let (text, json) = (text.to_str().unwrap(), json.to_str().unwrap());
+    let mut session = Session::new(Sudoku::empty());
+    session.execute("note 1 1 4");
+    session.execute("note 1 1 2");
+    for path in [text, json].iter() {
+        assert_eq!(
+            Response::Message(format!("Saved notes to {}", path)),
+            session.execute(&format!("savec {}", path))
+        );
+    }
+    assert!(fs::read_to_string(json).unwrap().starts_with("[[2,4],[],"));
+    let mut other = Session::new(Sudoku::empty());
+    for path in [text, json].iter() {
+        assert_eq!(
+            Response::Message(format!("Loaded notes from {}", path)),
+            other.execute(&format!("loadc {}", path))
+        );
+        assert_eq!(vec![2, 4], other.sudoku.notes(0, 0).collect::<Vec<_>>());
+        other.execute("undo");
+        assert_eq!(0, other.sudoku.notes(0, 0).count());
+    }
+    // Notes of a value already in the row are refused.
+    other.execute("set 1 5 4");
+    assert_eq!(
+        Response::Message(format!(
+            "Error: Cannot load notes from {}: Invalid notes: r1c1: 4 is ruled out by the filled cells",
+            text
+        )),
+        other.execute(&format!("loadc {}", text))
+    );
+}
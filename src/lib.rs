@@ -0,0 +1,26 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+#[macro_use]
+extern crate log;
+
+pub mod examples;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod formats;
+pub mod generator;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod render;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod solvers;
+
+/// Items of the standard prelude which `core` lacks, modules building without `std` import them from here.
+mod prelude {
+    pub(crate) use alloc::boxed::Box;
+    pub(crate) use alloc::format;
+    pub(crate) use alloc::string::{String, ToString};
+    pub(crate) use alloc::vec;
+    pub(crate) use alloc::vec::Vec;
+}
@@ -1,75 +1,6105 @@
 #[macro_use]
 extern crate log;
 extern crate clap;
+extern crate ctrlc;
 extern crate env_logger;
+extern crate serde;
+extern crate serde_json;
+extern crate sudoku;
 
-mod solvers;
+mod interactive;
 
-use clap::{App, Arg};
-use solvers::backtracking::Sudoku;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::fs;
+use std::io::{self, BufRead, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use sudoku::examples;
+use sudoku::formats::metadata::{self, Metadata};
+use sudoku::formats::output::OutputFormat;
+use sudoku::formats::{self, cells, sdm, ss};
+use sudoku::generator;
+use sudoku::render::{OutputOptions, SvgOptions};
+use sudoku::solvers::backtracking::{
+    SolveStatus, Sudoku, SudokuError, Summary, Unsolvable, MIN_UNIQUE_CLUES,
+};
+use sudoku::solvers::cache::SolveCache;
+use sudoku::solvers::cages;
+use sudoku::solvers::classify::{PuzzleClass, CLASSIFY_CAP};
+use sudoku::solvers::corpus::{self, CorpusStats};
+use sudoku::solvers::cross_check::cross_check;
+use sudoku::solvers::diagnosis::Diagnosis;
+use sudoku::solvers::filter::PuzzleFilter;
+use sudoku::solvers::geometry::{Geometry, Pos};
+use sudoku::solvers::limit::NodeLimit;
+#[cfg(test)]
+use sudoku::solvers::logical::HintSource;
+use sudoku::solvers::logical::{Difficulty, Hint, Technique};
+use sudoku::solvers::observer::{Progress, SolveEvent, SolveObserver, SolveStats};
+use sudoku::solvers::parity;
+use sudoku::solvers::partial::PartialError;
+use sudoku::solvers::regions::Regions;
+use sudoku::solvers::repair::Repair;
+#[cfg(test)]
+use sudoku::solvers::rules::Unit;
+use sudoku::solvers::rules::{self, CellDiff, Rules};
+use sudoku::solvers::state::SolverState;
+use sudoku::solvers::stress::{self, StressReport};
+use sudoku::solvers::symmetry::SymmetryKind;
+use sudoku::solvers::thermometers;
+use sudoku::solvers::transform::CanonicalKey;
+use sudoku::solvers::value_order::{SolveOptions, ValueOrder};
 
-fn main() {
+fn main() -> ExitCode {
     env_logger::init();
-    let mut s = match load_sudoku_from_file(&get_sudoku_path()) {
-        Ok(path) => path,
-        Err(err) => {
-            error!("Cannot load sudoku from file: {}", err.msg);
-            return;
+    let args = with_default_subcommand(std::env::args().collect());
+    match run(&app().get_matches_from(args)) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(failure) => {
+            if let Failure::InvalidInput(msg) | Failure::Io(msg) | Failure::Interrupted(msg) =
+                &failure
+            {
+                error!("{}", msg);
+            }
+            ExitCode::from(exit_code(&failure))
+        }
+    }
+}
+
+/// Reason why the application did not succeed, it defines the exit code.
+#[derive(Debug)]
+enum Failure {
+    /// The puzzle is valid, but has no solution.
+    Unsolvable,
+    /// The input is malformed or doesn't describe a valid puzzle.
+    InvalidInput(String),
+    /// The input or the output cannot be read or written.
+    Io(String),
+    /// Solving was stopped with Ctrl-C.
+    Interrupted(String),
+    /// The compared grids are different, or the solvers disagree with `--check-solvers` or `stress`.
+    Differs,
+}
+
+/// Help section describing exit codes of the application.
+const EXIT_CODES_HELP: &str = "EXIT CODES:
+    0    Sudoku is solved
+    1    Sudoku is valid, but cannot be solved; with --count-solutions, some sudoku
+         doesn't have exactly one solution; with compare, the grids differ; with
+         --check-solvers or stress, the solvers disagree
+    2    Input is invalid or malformed
+    3    Input cannot be read or output cannot be written
+    130  Solving was interrupted with Ctrl-C, the search is saved to the --checkpoint file";
+
+/// Maps the failure to the exit code of the application, see `EXIT_CODES_HELP`.
+fn exit_code(failure: &Failure) -> u8 {
+    match failure {
+        Failure::Unsolvable | Failure::Differs => 1,
+        Failure::InvalidInput(_) => 2,
+        Failure::Io(_) => 3,
+        Failure::Interrupted(_) => 130,
+    }
+}
+
+/// Builds the failure for an error which occurred while loading sudokus from `path`.
+fn load_failure(path: &str, err: SudokuError) -> Failure {
+    match err {
+        SudokuError::Io(msg) => Failure::Io(format!("Cannot read {}: {}", source_name(path), msg)),
+        err => Failure::InvalidInput(format!(
+            "Cannot load sudoku from {}: {}",
+            source_name(path),
+            err
+        )),
+    }
+}
+
+fn run(matches: &ArgMatches) -> Result<(), Failure> {
+    match matches.subcommand() {
+        ("solve", Some(matches)) => run_solve(matches),
+        ("verify", Some(matches)) => run_verify(matches),
+        ("generate", Some(matches)) => run_generate(matches),
+        ("rate", Some(matches)) => run_rate(matches),
+        ("hint", Some(matches)) => run_hint(matches),
+        ("interactive", Some(matches)) => run_interactive(matches),
+        ("compare", Some(matches)) => run_compare(matches),
+        ("dedupe", Some(matches)) => run_dedupe(matches),
+        ("filter", Some(matches)) => run_filter(matches),
+        ("convert", Some(matches)) => run_convert(matches),
+        ("stats", Some(matches)) => run_stats(matches),
+        ("stress", Some(matches)) => run_stress(matches),
+        _ => unreachable!("subcommand is required"),
+    }
+}
+
+/// Name of the input in messages when the task is given inline with `--grid`.
+const GRID_INPUT: &str = "--grid";
+
+/// Name of the input in messages when the task is an example puzzle picked with `--demo`.
+const DEMO_INPUT: &str = "--demo";
+
+/// Returns the path given with `--sudoku-path`, `GRID_INPUT` for `--grid`, `DEMO_INPUT` for `--demo`, or `-` for
+/// stdin when it is piped.
+fn input_path<'a>(matches: &'a ArgMatches) -> Result<&'a str, Failure> {
+    let inline = match (matches.is_present("grid"), matches.is_present("demo")) {
+        (true, _) => Some(GRID_INPUT),
+        (false, true) => Some(DEMO_INPUT),
+        (false, false) => None,
+    };
+    resolve_input(
+        matches.value_of("sudoku_path"),
+        inline,
+        !io::stdin().is_terminal(),
+    )
+}
+
+/// Picks the input of the task from the `--sudoku-path` argument and the `inline` one, `--grid` or `--demo`, see
+/// `input_path`.
+fn resolve_input<'a>(
+    path: Option<&'a str>,
+    inline: Option<&'a str>,
+    piped: bool,
+) -> Result<&'a str, Failure> {
+    match (path, inline) {
+        (Some(_), Some(inline)) => Err(Failure::InvalidInput(format!(
+            "Provide the task with either --sudoku-path or {}, not both",
+            inline
+        ))),
+        (Some(path), None) => Ok(path),
+        (None, Some(inline)) => Ok(inline),
+        (None, None) if piped => Ok("-"),
+        (None, None) => Err(Failure::InvalidInput(
+            "Provide the task with --sudoku-path or --grid, or pipe it to stdin; try --demo for an example"
+                .to_string(),
+        )),
+    }
+}
+
+/// Returns the task given inline with `--grid`, its parts joined when the shell split it at whitespace.
+///
+/// The clues of a cell list like `r1c3=4 r2c7=9` stay separated.
+fn grid_arg(matches: &ArgMatches) -> Option<String> {
+    let parts: Vec<&str> = matches
+        .values_of("grid")?
+        .flat_map(str::split_whitespace)
+        .collect();
+    match parts.iter().any(|part| part.contains('=')) {
+        true => Some(parts.join(" ")),
+        false => Some(parts.concat()),
+    }
+}
+
+/// Returns the example puzzle picked with `--demo`, `easy` when no name is given, and tells which one on stderr.
+fn demo_arg(matches: &ArgMatches) -> Option<String> {
+    if !matches.is_present("demo") {
+        return None;
+    }
+    let name = matches.value_of("demo").unwrap_or("easy");
+    let demo = examples::example(name).expect("--demo only allows the names of the examples");
+    eprintln!("Demo puzzle: {}", name);
+    Some(demo.to_line())
+}
+
+/// Returns the task given inline with `--grid` or `--demo`, None when it is read from a file or stdin.
+fn inline_task(matches: &ArgMatches, path: &str) -> Option<String> {
+    match path {
+        GRID_INPUT => grid_arg(matches),
+        DEMO_INPUT => demo_arg(matches),
+        _ => None,
+    }
+}
+
+/// Reads the task of a subcommand from the input at `path`, see `input_path`.
+fn read_task(matches: &ArgMatches, path: &str) -> Result<String, Failure> {
+    match inline_task(matches, path) {
+        Some(task) => Ok(task),
+        None => read_input(path, io::stdin()),
+    }
+}
+
+/// Loads a single sudoku given with the input arguments of a subcommand.
+///
+/// Warns about puzzles with too few clues.
+fn load_sudoku(matches: &ArgMatches) -> Result<Sudoku, Failure> {
+    let path = input_path(matches)?;
+    let variant = selected_variant(matches)?;
+    let data = read_task(matches, path)?;
+    let input = input_format(path, matches.value_of("input_format").unwrap());
+    let s = parse_sudoku(&data, input, box_shape(matches), &variant)
+        .map_err(|err| load_failure(path, err))?
+        .with_node_limit(node_limit(matches));
+    warn_about_few_clues(&s);
+    Ok(s)
+}
+
+/// Loads a single sudoku from the file at `path`, or from stdin when the path is `-`.
+///
+/// The size of the grid is detected unless the (rows, columns) `box_shape` is given, see `parse_sudoku`.
+fn load_sudoku_from(
+    path: &str,
+    requested_format: &str,
+    box_shape: Option<(usize, usize)>,
+    variant: &Variant,
+) -> Result<Sudoku, Failure> {
+    let data = read_input(path, io::stdin())?;
+    let input = input_format(path, requested_format);
+    parse_sudoku(&data, input, box_shape, variant).map_err(|err| load_failure(path, err))
+}
+
+/// Returns the (rows, columns) of a box selected with `--size` or `--box-size`, None when the size is detected from
+/// the input.
+fn box_shape(matches: &ArgMatches) -> Option<(usize, usize)> {
+    if let Some(shape) = matches.value_of("box_size") {
+        return parse_box_shape(shape).ok();
+    }
+    matches.value_of("size").map(|size| match size {
+        "4" => (2, 2),
+        "16" => (4, 4),
+        _ => (3, 3),
+    })
+}
+
+/// Rules, regions and cages of the loaded sudokus, selected with `--variant`, `--regions` and `--cages`.
+#[derive(Debug, Clone, Default)]
+struct Variant {
+    rules: Rules,
+    /// Layout of the regions of a jigsaw sudoku, see `Regions::parse`.
+    regions: Option<String>,
+    /// Cages of a killer sudoku, see `cages::parse_cages`.
+    cages: Option<String>,
+    /// Thermometers, see `thermometers::parse_thermometers`.
+    thermometers: Option<String>,
+    /// Marks of an even/odd sudoku, see `parity::parse_parity`.
+    parity: Option<String>,
+}
+
+impl Variant {
+    /// Returns `true` if the variant has the classic rules and no regions, cages, thermometers or parity marks.
+    fn is_classic(&self) -> bool {
+        self.rules == Rules::default()
+            && self.regions.is_none()
+            && self.cages.is_none()
+            && self.thermometers.is_none()
+            && self.parity.is_none()
+    }
+
+    /// Returns the sudoku following the rules and, when they are given, the regions, the cages, the thermometers and the
+    /// parity marks of the variant.
+    fn apply(&self, sudoku: Sudoku) -> Result<Sudoku, SudokuError> {
+        let mut sudoku = sudoku.with_rules(self.rules);
+        if let Some(ref layout) = self.regions {
+            let regions = Regions::parse(layout, sudoku.geometry())?;
+            sudoku = sudoku.with_regions(regions)?;
+        }
+        if let Some(ref cages) = self.cages {
+            let cages = cages::parse_cages(cages, sudoku.geometry())?;
+            sudoku = sudoku.with_cages(cages)?;
+        }
+        if let Some(ref thermometers) = self.thermometers {
+            for thermometer in thermometers::parse_thermometers(thermometers, sudoku.geometry())? {
+                sudoku = sudoku.with_constraint(thermometer);
+            }
+        }
+        if let Some(ref marks) = self.parity {
+            sudoku = sudoku.with_parity(parity::parse_parity(marks)?)?;
+        }
+        Ok(sudoku)
+    }
+}
+
+/// Returns the rules of the variant selected with `--variant`.
+fn variant_rules(matches: &ArgMatches) -> Rules {
+    Rules {
+        diagonals: matches.value_of("variant") == Some("x"),
+        windows: matches.value_of("variant") == Some("hyper"),
+        anti_knight: matches.value_of("variant") == Some("anti-knight"),
+        non_consecutive: matches.value_of("variant") == Some("non-consecutive"),
+    }
+}
+
+/// Returns the variant selected with `--variant`, `--regions`, `--cages`, `--thermometers` and `--parity`, all but the
+/// first are read from files.
+fn selected_variant(matches: &ArgMatches) -> Result<Variant, Failure> {
+    let read = |arg| {
+        matches
+            .value_of(arg)
+            .map(|path| {
+                fs::read_to_string(path)
+                    .map_err(|err| Failure::Io(format!("Cannot read {}: {}", path, err)))
+            })
+            .transpose()
+    };
+    Ok(Variant {
+        rules: variant_rules(matches),
+        regions: read("regions")?,
+        cages: read("cages")?,
+        thermometers: read("thermometers")?,
+        parity: read("parity")?,
+    })
+}
+
+/// Parses the `--box-size` value like `2x3` into (rows, columns) of a box.
+fn parse_box_shape(shape: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("expected ROWSxCOLUMNS of a box like 2x3, got `{}`", shape);
+    let (rows, cols) = shape.split_once('x').ok_or_else(invalid)?;
+    let (rows, cols) = match (rows.parse(), cols.parse()) {
+        (Ok(rows), Ok(cols)) => (rows, cols),
+        _ => return Err(invalid()),
+    };
+    match Sudoku::with_box_shape(rows, cols) {
+        Some(_) => Ok((rows, cols)),
+        None => Err(format!(
+            "boxes must have at least 2 rows and columns and at most 16 cells, got `{}`",
+            shape
+        )),
+    }
+}
+
+/// Solves the sudoku, or a batch of sudokus, and prints the solution.
+fn run_solve(matches: &ArgMatches) -> Result<(), Failure> {
+    let quiet = matches.is_present("quiet");
+    let format = match matches.occurrences_of("format") {
+        0 if quiet => "line",
+        _ => matches.value_of("format").unwrap(),
+    };
+    if matches.is_present("classify") && format != "json" {
+        return Err(Failure::InvalidInput(
+            "--classify needs --format json".to_string(),
+        ));
+    }
+    if let Some(partial) = matches.value_of("partial") {
+        return run_solve_partial(matches, partial, format);
+    }
+    if format == "json" && !matches.is_present("count_solutions") {
+        return run_solve_json(matches);
+    }
+    let path = input_path(matches)?;
+    let input = input_format(path, matches.value_of("input_format").unwrap());
+    let box_shape = box_shape(matches);
+    let mut reader: Box<dyn BufRead> = match inline_task(matches, path) {
+        Some(task) => Box::new(io::Cursor::new(task)),
+        None => open_input(path)?,
+    };
+    let head = read_head(&mut reader).map_err(|err| read_failure(path, err))?;
+    let whole = matches.is_present("count_solutions");
+    if !whole && input == "line" && is_line_batch(&head, box_shape) {
+        let reader = io::Cursor::new(head).chain(reader);
+        return solve_stream(matches, path, format, reader);
+    }
+    let data = read_all(path, io::Cursor::new(head).chain(reader))?;
+    let variant = selected_variant(matches)?;
+    let time = matches.is_present("time");
+    let progress = matches.is_present("progress");
+    if matches.is_present("count_solutions") {
+        let mut output = matches.value_of("output").map(open_output).transpose()?;
+        let max = matches
+            .value_of("max")
+            .map_or(DEFAULT_MAX_SOLUTIONS, |max| max.parse().unwrap());
+        let sudokus = parse_puzzles(&data, input, box_shape, &variant)
+            .map_err(|err| load_failure(path, err))?;
+        return match output {
+            Some(ref mut file) => count_solutions(&sudokus, max, file),
+            None => count_solutions(&sudokus, max, &mut io::stdout()),
+        };
+    }
+    if let Some(res) = solve_parsed_batch(matches, path, &data, format, &variant) {
+        return res;
+    }
+    let opts = solve_options(matches);
+    let mut output = matches.value_of("output").map(open_output).transpose()?;
+    let mut s = parse_sudoku(&data, input, box_shape, &variant)
+        .map_err(|err| load_failure(path, err))?
+        .with_options(&opts);
+    let checkpoint = matches.value_of("checkpoint");
+    if let Some(path) = checkpoint {
+        s = resume_from_checkpoint(s, path)?;
+        if let Err(err) = ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::Relaxed)) {
+            warn!("Ctrl-C stops the solver without a checkpoint: {}", err);
+        }
+    }
+    warn_about_few_clues(&s);
+    let color = use_color(io::stdout().is_terminal(), matches.is_present("no_color"));
+    let opts = output_options(matches);
+    for line in task_report(&s, format, color, quiet, &opts) {
+        println!("{}", line);
+    }
+    if matches.is_present("explain") {
+        for line in explanation_report(&s) {
+            println!("{}", line);
+        }
+    }
+    if matches.is_present("check_solvers") {
+        match cross_check(&s) {
+            Ok(_) if !quiet => println!("All solvers agree"),
+            Ok(_) => {}
+            Err(mismatch) => {
+                eprintln!("Solvers disagree: {}", mismatch);
+                return Err(Failure::Differs);
+            }
+        }
+    }
+    let start = Instant::now();
+    let mut deepest = None;
+    let solved = match (animate_delay(matches), checkpoint) {
+        (None, Some(path)) => solve_with_checkpoint(&mut s, path, &INTERRUPTED)?,
+        (Some(delay), _) => s.solve_with_observer(&mut Animation::new(
+            io::stdout(),
+            &s,
+            delay,
+            use_color(io::stdout().is_terminal(), matches.is_present("no_color")),
+        )),
+        (None, None) => {
+            let (solved, observer) = solve_observed(&mut s, progress);
+            deepest = Some(observer.deepest());
+            solved
         }
     };
-    println!("Solving sudoku");
-    println!("{}", s);
-    match s.solve() {
+    let elapsed = start.elapsed();
+    for line in solution_report(
+        &s,
+        solved.as_ref().err(),
+        format,
+        color,
+        &opts,
+        quiet,
+        output.is_some(),
+    ) {
+        println!("{}", line);
+    }
+    if let (Err(err), false) = (&solved, quiet) {
+        for line in best_effort_report(&s, err, deepest.as_ref(), format, color, &opts) {
+            println!("{}", line);
+        }
+    }
+    let res = match solved {
         Ok(_) => {
-            println!("Solved!");
-            println!("{}", s);
+            if let Some(ref mut file) = output {
+                write_solution(file, &s, format, &opts)?;
+            }
+            Ok(())
+        }
+        Err(err) => {
+            if quiet {
+                eprintln!("{}", unsolvable_message(&s, &err));
+            }
+            Err(Failure::Unsolvable)
         }
-        Err(_) => println!("Cannot solve sudoku"),
+    };
+    if time {
+        eprintln!("Solve time: {}", format_duration(elapsed));
     }
+    res
 }
 
-#[derive(Debug)]
-struct LoadingError {
-    msg: String,
+/// Solves the sudokus of the data when it holds a batch, None when it holds a single sudoku.
+///
+/// Puzzles of sdm files and of grids written back to back are numbered instead of their lines.
+fn solve_parsed_batch(
+    matches: &ArgMatches,
+    path: &str,
+    data: &str,
+    format: &str,
+    variant: &Variant,
+) -> Option<Result<(), Failure>> {
+    let input = input_format(path, matches.value_of("input_format").unwrap());
+    let sudokus = match parse_batch(data, input, box_shape(matches), variant)? {
+        Ok(sudokus) => sudokus,
+        Err(err) => return Some(Err(load_failure(path, err))),
+    };
+    let (mut output, done) = match batch_output(matches) {
+        Ok(output) => output,
+        Err(failure) => return Some(Err(failure)),
+    };
+    let opts = solve_options(matches);
+    let mut unique = matches
+        .is_present("dedupe")
+        .then(|| Unique::for_variant(variant));
+    let sudokus = sudokus
+        .into_iter()
+        .enumerate()
+        .map(|(i, s)| (i + 1, s))
+        .filter(|(line, s)| {
+            unique
+                .as_mut()
+                .map_or(true, |unique| unique.is_new(*line, s))
+        })
+        .skip(done)
+        .map(|(line, s)| Ok((line, s.with_options(&opts))));
+    let batch = BatchOptions {
+        time: matches.is_present("time"),
+        summary: !matches.is_present("no_summary") && !matches.is_present("quiet"),
+        progress: matches.is_present("progress"),
+        cache: cache_capacity(matches),
+        output: output_options(matches),
+    };
+    Some(match output {
+        Some(ref mut file) => solve_batch(sudokus, format, &batch, file),
+        None => solve_batch(sudokus, format, &batch, &mut io::stdout()),
+    })
 }
 
-fn load_sudoku_from_file(file_path: &str) -> Result<Sudoku, LoadingError> {
-    let data = match fs::read_to_string(file_path) {
-        Ok(s) => s,
+/// Solves the task from the partial solution in the file of `--partial`, which keeps its clues and adds entries.
+///
+/// A wrong entry, or a task without a solution, is reported like an unsolvable sudoku, other errors of the partial
+/// solution are invalid input.
+fn run_solve_partial(
+    matches: &ArgMatches,
+    partial_path: &str,
+    format: &str,
+) -> Result<(), Failure> {
+    if format == "json" {
+        return Err(Failure::InvalidInput(
+            "--partial cannot be used with --format json".to_string(),
+        ));
+    }
+    let path = input_path(matches)?;
+    let data = read_task(matches, path)?;
+    let variant = selected_variant(matches)?;
+    let requested = matches.value_of("input_format").unwrap();
+    let box_shape = box_shape(matches);
+    let s = parse_sudoku(&data, input_format(path, requested), box_shape, &variant)
+        .map_err(|err| load_failure(path, err))?
+        .with_options(&solve_options(matches));
+    let partial_data = read_input(partial_path, io::stdin())?;
+    let partial = parse_sudoku(
+        &partial_data,
+        input_format(partial_path, requested),
+        box_shape,
+        &variant,
+    )
+    .map_err(|err| load_failure(partial_path, err))?;
+    let quiet = matches.is_present("quiet");
+    let message = match s.solve_from(&partial) {
+        Ok(solved) => {
+            let mut output = matches.value_of("output").map(open_output).transpose()?;
+            let color = use_color(io::stdout().is_terminal(), matches.is_present("no_color"));
+            let opts = output_options(matches);
+            for line in
+                solution_report(&solved, None, format, color, &opts, quiet, output.is_some())
+            {
+                println!("{}", line);
+            }
+            if let Some(ref mut file) = output {
+                write_solution(file, &solved, format, &opts)?;
+            }
+            return Ok(());
+        }
+        Err(PartialError::Unsolvable(err)) => unsolvable_message(&s, &err),
+        Err(err @ PartialError::WrongEntry { .. }) => {
+            format!("Cannot solve from {}: {}", source_name(partial_path), err)
+        }
         Err(err) => {
-            return Err({
-                LoadingError {
-                    msg: err.to_string(),
-                }
-            })
+            return Err(Failure::InvalidInput(format!(
+                "Cannot solve from {}: {}",
+                source_name(partial_path),
+                err
+            )))
         }
     };
-    let clean_data: Vec<u8> = data
-        .chars()
-        .filter(|c| c.to_digit(10).is_some())
-        .map(|c| c.to_digit(10).unwrap() as u8)
+    match quiet {
+        true => eprintln!("{}", message),
+        false => println!("{}", message),
+    }
+    Err(Failure::Unsolvable)
+}
+
+/// Returns the numbered steps of solving the sudoku without guessing, with the reason behind each filled cell.
+///
+/// Nothing is explained for inconsistent sudokus, the solver reports them.
+fn explanation_report(s: &Sudoku) -> Vec<String> {
+    if !s.is_consistent() {
+        return vec![];
+    }
+    let mut logical = s.clone();
+    let mut lines: Vec<String> = logical
+        .solve_logical()
+        .iter()
+        .enumerate()
+        .map(|(i, explanation)| format!("{}. {}", i + 1, explanation))
         .collect();
-    match Sudoku::new(clean_data.into_iter()) {
-        Some(sudoku) => Ok(sudoku),
-        None => Err(LoadingError {
-            msg: "Cannot create sudoku from data".to_string(),
-        }),
+    if !logical.solved() {
+        lines.push(format!(
+            "No technique applies to the remaining {} cells, they are found with search",
+            logical.empty_count()
+        ));
     }
+    lines
 }
 
-fn get_sudoku_path() -> String {
-    let matches = App::new("Sudoku solver")
-        .version("0.1.0")
-        .author("Yuriy Senko <yura.senko@gmail.com>")
-        .arg(
-            Arg::with_name("sudoku_path")
-                .short("s")
-                .long("--sudoku-path")
-                .takes_value(true)
-                .required(true)
-                .help("File with the task"),
-        )
-        .get_matches();
-    matches.value_of("sudoku_path").unwrap().to_string()
+/// Line printed before the solution. Solver's own output can be read back: only the solution after it is parsed.
+const SOLVED_BANNER: &str = "\nSolved!\n";
+
+/// Returns lines printed to stdout before solving the sudoku, nothing in quiet mode.
+fn task_report(
+    task: &Sudoku,
+    format: &str,
+    color: bool,
+    quiet: bool,
+    opts: &OutputOptions,
+) -> Vec<String> {
+    match (quiet, task.solved()) {
+        (true, _) => vec![],
+        (false, true) => vec!["Sudoku is already solved".to_string()],
+        (false, false) => vec![
+            format!("Solving sudoku with {}", task.summary()),
+            render_with(task, format, color, opts),
+        ],
+    }
+}
+
+/// Returns lines printed to stdout after solving the sudoku `s`, which is the solution unless there is an `error`.
+///
+/// The solution itself is left out when it is written to a file. In quiet mode nothing but the solution is printed.
+fn solution_report(
+    s: &Sudoku,
+    error: Option<&Unsolvable>,
+    format: &str,
+    color: bool,
+    opts: &OutputOptions,
+    quiet: bool,
+    to_file: bool,
+) -> Vec<String> {
+    let mut lines = vec![];
+    match error {
+        None => {
+            if !quiet {
+                lines.push(SOLVED_BANNER.trim().to_string());
+            }
+            if !to_file {
+                lines.push(render_with(s, format, color, opts));
+            }
+        }
+        Some(err) if !quiet => lines.push(unsolvable_message(s, err)),
+        Some(_) => {}
+    }
+    lines
+}
+
+/// Returns the progress possible on a sudoku the search did not solve: the cells forced without guessing up to the
+/// first contradiction, then the deepest grid of a search which stopped at its limit.
+///
+/// The forced cells are left out when there are none, the grid would be the task again. Only the plain search knows
+/// its deepest grid, `--checkpoint` and `--animate` leave it out.
+fn best_effort_report(
+    s: &Sudoku,
+    err: &Unsolvable,
+    deepest: Option<&Sudoku>,
+    format: &str,
+    color: bool,
+    opts: &OutputOptions,
+) -> Vec<String> {
+    let mut task = s.clone();
+    task.reset();
+    let empty = task.empty_count();
+    let (best, contradiction) = s.best_effort();
+    let determined = empty - best.empty_count();
+    let mut lines = vec![match contradiction {
+        Some(contradiction) => format!(
+            "Without guessing {} of {} empty cells are determined before {}",
+            determined, empty, contradiction.diagnosis
+        ),
+        None => format!(
+            "Without guessing {} of {} empty cells are determined",
+            determined, empty
+        ),
+    }];
+    if determined > 0 {
+        lines.push(render_with(&best, format, color, opts));
+    }
+    if let (Diagnosis::LimitReached { .. }, Some(deepest)) = (&err.diagnosis, deepest) {
+        lines.push(format!(
+            "The deepest search filled {} of {} empty cells",
+            empty - deepest.empty_count(),
+            empty
+        ));
+        lines.push(render_with(deepest, format, color, opts));
+    }
+    lines
+}
+
+/// Returns the message printed when the sudoku cannot be solved, with the reason found by the solver.
+fn unsolvable_message(s: &Sudoku, err: &Unsolvable) -> String {
+    let message = format!("Cannot solve sudoku with {} clues: {}", s.clue_count(), err);
+    match err.diagnosis {
+        Diagnosis::LimitReached { .. } => format!("{}, pass --no-limit to search on", message),
+        _ => message,
+    }
+}
+
+/// Returns `true` for a 9x9 sudoku with the classic rules, boxes, no cages, parity marks or other constraints.
+fn is_classic(s: &Sudoku) -> bool {
+    s.geometry() == Geometry::CLASSIC
+        && s.rules() == Rules::default()
+        && *s.regions() == Regions::boxes(Geometry::CLASSIC)
+        && s.cages().is_empty()
+        && s.constraints().is_empty()
+        && s.parity_marks().is_empty()
+}
+
+/// Returns `false` for an unsolved classic puzzle with too few clues to have a unique solution, variants need fewer
+/// clues. The searches which only tell a unique solution apart are skipped for such a puzzle.
+fn may_be_unique(s: &Sudoku) -> bool {
+    !is_classic(s) || s.clue_count() >= MIN_UNIQUE_CLUES || s.solved()
+}
+
+/// Returns a warning for a puzzle which cannot have a unique solution, see `may_be_unique`.
+fn few_clues_warning(s: &Sudoku) -> Option<String> {
+    match s.clue_count() {
+        clues if !may_be_unique(s) => Some(format!(
+            "Sudoku has only {} clues, at least {} are needed for a unique solution. \
+             The puzzle may be truncated, it may have many solutions or take long to solve, uniqueness is not checked",
+            clues, MIN_UNIQUE_CLUES
+        )),
+        _ => None,
+    }
+}
+
+/// Prints the warning about too few clues to stderr, see `few_clues_warning`.
+fn warn_about_few_clues(s: &Sudoku) {
+    if let Some(warning) = few_clues_warning(s) {
+        eprintln!("Warning: {}", warning);
+    }
+}
+
+/// Placements of the search between checks whether a progress line is due.
+const PROGRESS_CHECK_EVERY: u64 = 100_000;
+
+/// Minimal time between two progress lines.
+const PROGRESS_PERIOD: Duration = Duration::from_secs(1);
+
+/// Solves the sudoku, reporting progress of a long search every second.
+///
+/// Progress lines go to stderr with `progress`, otherwise they are logged at the info level. Returns the counters of
+/// the whole search too.
+fn solve_with_progress(s: &mut Sudoku, progress: bool) -> (Result<(), Unsolvable>, SolveStats) {
+    let (res, observer) = solve_observed(s, progress);
+    (res, observer.stats)
+}
+
+/// Solves the sudoku like `solve_with_progress`, returns the observer of the search, e.g. for its deepest grid.
+fn solve_observed(s: &mut Sudoku, progress: bool) -> (Result<(), Unsolvable>, ProgressReport) {
+    let mut observer = ProgressReport::new(s, progress);
+    let res = s.solve_with_observer(&mut observer);
+    (res, observer)
+}
+
+/// Observer of `solve_with_progress` which keeps the counters of the latest step and the values the search placed.
+///
+/// The grid is only rebuilt from the values when a progress line is due, so following the search stays cheap.
+struct ProgressReport {
+    task: Sudoku,
+    /// Values placed by the search in every cell, 0 for cells it left empty.
+    placed: Vec<u8>,
+    /// Values placed when the search had placed the most values at once, and their number.
+    deepest: Vec<u8>,
+    deepest_depth: usize,
+    stats: SolveStats,
+    /// Time of the previous progress line.
+    last: Instant,
+    progress: bool,
+}
+
+impl ProgressReport {
+    /// Returns the observer of the search of the task, printing progress lines to stderr with `progress`.
+    fn new(task: &Sudoku, progress: bool) -> ProgressReport {
+        ProgressReport {
+            task: task.clone(),
+            placed: vec![0; task.as_slice().len()],
+            deepest: vec![0; task.as_slice().len()],
+            deepest_depth: 0,
+            stats: SolveStats::default(),
+            last: Instant::now(),
+            progress,
+        }
+    }
+
+    /// Returns the grid as the search left it.
+    fn grid(&self) -> Sudoku {
+        self.grid_with(&self.placed)
+    }
+
+    /// Returns the grid with the most values the search placed at once, which follow the rules like every grid of
+    /// the search.
+    fn deepest(&self) -> Sudoku {
+        self.grid_with(&self.deepest)
+    }
+
+    /// Returns the task with the values placed by the search.
+    fn grid_with(&self, placed: &[u8]) -> Sudoku {
+        let mut grid = self.task.clone();
+        for (pos, &value) in placed.iter().enumerate().filter(|(_, &value)| value != 0) {
+            let (row, col) = grid.geometry().cell(pos);
+            let _ = grid.set(row, col, value);
+        }
+        grid
+    }
+}
+
+impl SolveObserver for ProgressReport {
+    fn on_event(&mut self, event: SolveEvent, stats: &SolveStats) {
+        self.stats = *stats;
+        match event {
+            SolveEvent::Placed { pos, value } => {
+                self.placed[pos] = value;
+                if stats.depth > self.deepest_depth {
+                    self.deepest_depth = stats.depth;
+                    self.deepest.clone_from(&self.placed);
+                }
+            }
+            SolveEvent::RolledBack { pos, .. } => {
+                self.placed[pos] = 0;
+                return;
+            }
+        }
+        if stats.placements % PROGRESS_CHECK_EVERY != 0 || self.last.elapsed() < PROGRESS_PERIOD {
+            return;
+        }
+        self.last = Instant::now();
+        let line = progress_line(stats, self.grid().search_space_estimate());
+        match self.progress {
+            true => eprintln!("{}", line),
+            false => info!("{}", line),
+        }
+    }
+}
+
+/// Set by Ctrl-C when solving with `--checkpoint`.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Returns the sudoku continuing the search saved to the checkpoint file, the sudoku itself if there is no file.
+fn resume_from_checkpoint(s: Sudoku, path: &str) -> Result<Sudoku, Failure> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(s),
+        Err(err) => {
+            return Err(Failure::Io(format!(
+                "Cannot read checkpoint {}: {}",
+                path, err
+            )))
+        }
+    };
+    serde_json::from_str::<SolverState>(&json)
+        .map_err(|err| SudokuError::InvalidState(err.to_string()))
+        .and_then(|state| s.with_state(state))
+        .map_err(|err| Failure::InvalidInput(format!("Cannot resume from {}: {}", path, err)))
+}
+
+/// Solves the sudoku until `interrupted` is set, then saves the search to the checkpoint file to continue it later.
+///
+/// The checkpoint file is removed once the search finishes.
+fn solve_with_checkpoint(
+    s: &mut Sudoku,
+    path: &str,
+    interrupted: &AtomicBool,
+) -> Result<Result<(), Unsolvable>, Failure> {
+    match s.solve_resumable(|_| interrupted.load(Ordering::Relaxed)) {
+        Ok(SolveStatus::Stopped) => {
+            fs::write(path, serde_json::to_string(&s.save_state()).unwrap())
+                .map_err(|err| Failure::Io(format!("Cannot write checkpoint {}: {}", path, err)))?;
+            Err(Failure::Interrupted(format!(
+                "Interrupted, run again with --checkpoint {} to continue",
+                path
+            )))
+        }
+        solved => {
+            if let Err(err) = fs::remove_file(path) {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("Cannot remove checkpoint {}: {}", path, err);
+                }
+            }
+            Ok(solved.map(|_| ()))
+        }
+    }
+}
+
+/// Delay between frames of `--animate` when none is given.
+const DEFAULT_ANIMATION_DELAY: Duration = Duration::from_millis(50);
+
+/// Returns the delay between frames when the search should be animated with `--animate`.
+fn animate_delay(matches: &ArgMatches) -> Option<Duration> {
+    match matches.value_of("animate") {
+        Some(ms) => Some(Duration::from_millis(ms.parse().unwrap())),
+        None if matches.is_present("animate") => Some(DEFAULT_ANIMATION_DELAY),
+        None => None,
+    }
+}
+
+/// Observer of `--animate` which prints the grid after every placement and rollback of the search.
+///
+/// The terminal is never switched to the alternate screen nor is the cursor hidden, so stopping the search with
+/// Ctrl-C leaves it as usable as it was.
+struct Animation<W: Write> {
+    out: W,
+    /// The grid as the search left it, updated with every step.
+    grid: Sudoku,
+    delay: Duration,
+    /// Frames replace each other in place, otherwise they are printed one after another without escape sequences.
+    in_place: bool,
+    /// Lines printed by the previous frame.
+    printed: usize,
+}
+
+impl<W: Write> Animation<W> {
+    fn new(out: W, task: &Sudoku, delay: Duration, in_place: bool) -> Animation<W> {
+        Animation {
+            out,
+            grid: task.clone(),
+            delay,
+            in_place,
+            printed: 0,
+        }
+    }
+}
+
+impl<W: Write> SolveObserver for Animation<W> {
+    fn on_event(&mut self, event: SolveEvent, _stats: &SolveStats) {
+        let (pos, caption) = match event {
+            SolveEvent::Placed { pos, value } => {
+                let (row, col) = self.grid.geometry().cell(pos);
+                let _ = self.grid.set(row, col, value);
+                (
+                    pos,
+                    format!("{} placed in {}", value, rules::cell_name(row, col)),
+                )
+            }
+            SolveEvent::RolledBack { pos, value } => {
+                let (row, col) = self.grid.geometry().cell(pos);
+                let _ = self.grid.clear(row, col);
+                (
+                    pos,
+                    format!("{} taken back from {}", value, rules::cell_name(row, col)),
+                )
+            }
+        };
+        let frame = animation_frame(&self.grid, pos, &caption, self.in_place, self.printed);
+        self.printed = frame.lines().count();
+        // The animation only shows the search, a closed stdout must not stop it.
+        let _ = self
+            .out
+            .write_all(frame.as_bytes())
+            .and_then(|_| self.out.flush());
+        std::thread::sleep(self.delay);
+    }
+}
+
+/// Returns the frame of `--animate` showing the grid after the step which changed the cell at `pos`, described by the
+/// caption.
+///
+/// In place the cursor first moves up over the `printed` lines of the previous frame and the changed cell is
+/// highlighted, otherwise the frame is plain text following the previous one.
+fn animation_frame(
+    grid: &Sudoku,
+    pos: usize,
+    caption: &str,
+    in_place: bool,
+    printed: usize,
+) -> String {
+    match (in_place, printed) {
+        (false, _) => format!("{}\n{}", caption, grid),
+        // The caption line is cleared first, as the previous one may be longer.
+        (true, 0) => format!("\x1b[2K{}\n{}", caption, grid.to_animation_frame(pos)),
+        (true, _) => format!(
+            "\x1b[{}A\x1b[2K{}\n{}",
+            printed,
+            caption,
+            grid.to_animation_frame(pos)
+        ),
+    }
+}
+
+/// Returns the progress line of the search with its counters and `Sudoku::search_space_estimate` of the grid.
+fn progress_line(stats: &SolveStats, search_space: f64) -> String {
+    format!(
+        "Still solving: {} placements, {} backtracks, {} cells filled by the search, at most 10^{:.1} grids left",
+        stats.placements, stats.backtracks, stats.depth, search_space
+    )
+}
+
+/// Formats the duration with a unit matching its magnitude: microseconds, milliseconds or seconds.
+fn format_duration(duration: Duration) -> String {
+    match duration.as_micros() {
+        micros if micros < 1000 => format!("{}µs", micros),
+        micros if micros < 1_000_000 => format!("{:.3}ms", micros as f64 / 1000.0),
+        _ => format!("{:.3}s", duration.as_secs_f64()),
+    }
+}
+
+/// Checks that the sudoku, or every sudoku of a batch, follows the rules without printing solutions.
+fn run_verify(matches: &ArgMatches) -> Result<(), Failure> {
+    let path = input_path(matches)?;
+    let data = read_task(matches, path)?;
+    let input = input_format(path, matches.value_of("input_format").unwrap());
+    let box_shape = box_shape(matches);
+    let variant = selected_variant(matches)?;
+    let suggest_fix = matches.is_present("suggest_fix");
+    let show_ambiguity = matches.is_present("show_ambiguity");
+    let check_unique = matches.is_present("check_unique") || suggest_fix || show_ambiguity;
+    let original = matches
+        .value_of("against")
+        .map(|original| load_sudoku_from(original, "auto", box_shape, &variant))
+        .transpose()?;
+    if let Some(sudokus) = parse_batch(&data, input, box_shape, &variant) {
+        if original.is_some() {
+            return Err(Failure::InvalidInput(
+                "--against cannot be used with a batch of puzzles".to_string(),
+            ));
+        }
+        if matches.is_present("cell") {
+            return Err(Failure::InvalidInput(
+                "--cell cannot be used with a batch of puzzles".to_string(),
+            ));
+        }
+        if suggest_fix {
+            return Err(Failure::InvalidInput(
+                "--suggest-fix cannot be used with a batch of puzzles".to_string(),
+            ));
+        }
+        if show_ambiguity {
+            return Err(Failure::InvalidInput(
+                "--show-ambiguity cannot be used with a batch of puzzles".to_string(),
+            ));
+        }
+        let sudokus = sudokus.map_err(|err| load_failure(path, err))?;
+        let results: Vec<_> = sudokus
+            .into_iter()
+            .map(|s| validate(&s.with_node_limit(node_limit(matches)), None, check_unique))
+            .collect();
+        for (i, res) in results.iter().enumerate() {
+            println!("Puzzle {} is {}", i + 1, validation_report(res));
+        }
+        let valid = results.iter().filter(|res| res.is_ok()).count();
+        println!("{} of {} puzzles are valid", valid, results.len());
+        return combined_validation(&results);
+    }
+    let s = parse_sudoku(&data, input, box_shape, &variant)
+        .map_err(|err| load_failure(path, err))?
+        .with_node_limit(node_limit(matches));
+    warn_about_few_clues(&s);
+    println!("Sudoku has {}", s.summary());
+    println!("Units: {}", s.unit_fill());
+    println!("Digits: {}", describe_digits(&s));
+    let (res, class) = validate_classified(&s, original.as_ref(), check_unique);
+    println!("Sudoku is {}", validation_report(&res));
+    if suggest_fix && (!s.is_consistent() || matches!(res, Err(Failure::Unsolvable))) {
+        println!("{}", describe_repairs(&s.suggest_repairs()));
+    }
+    if show_ambiguity && res.is_err() {
+        let class = class.unwrap_or_else(|| s.classify());
+        for line in ambiguity_report(&class).unwrap_or_default() {
+            println!("{}", line);
+        }
+    }
+    if let Some(cell) = selected_cell(matches, &s)? {
+        let checked = check_cell(&s, original.as_ref(), cell);
+        match &checked {
+            Ok(report) | Err(report) => println!("{}", report),
+        }
+        if res.is_ok() && checked.is_err() {
+            return Err(Failure::InvalidInput(format!(
+                "Cell {} is wrong",
+                cell.label()
+            )));
+        }
+    }
+    match res {
+        Err(Failure::InvalidInput(_)) => {
+            Err(Failure::InvalidInput("Sudoku is invalid".to_string()))
+        }
+        res => res.map(|_| ()),
+    }
+}
+
+/// Returns the cell selected with `--cell` by its label like `E5`, see `Pos::from_label`.
+fn selected_cell(matches: &ArgMatches, s: &Sudoku) -> Result<Option<Pos>, Failure> {
+    let label = match matches.value_of("cell") {
+        Some(label) => label,
+        None => return Ok(None),
+    };
+    match Pos::from_label(label, s.side()) {
+        Some(cell) => Ok(Some(cell)),
+        None => Err(Failure::InvalidInput(format!(
+            "{} is not a cell of the {}x{} grid, name cells like E5",
+            label,
+            s.side(),
+            s.side()
+        ))),
+    }
+}
+
+/// Checks the value of a single cell: it must follow the rules and, when the `original` puzzle has a unique
+/// solution, be the value of that solution. The solution itself is never revealed.
+///
+/// Returns the report of the cell, as the error when the value is wrong.
+fn check_cell(s: &Sudoku, original: Option<&Sudoku>, cell: Pos) -> Result<String, String> {
+    let label = cell.label();
+    let value = match s.get(cell) {
+        Some(value) => value,
+        None => return Ok(format!("Cell {} is empty", label)),
+    };
+    let conflict = s
+        .conflicts()
+        .into_iter()
+        .find(|conflict| conflict.cells.contains(&(cell.row, cell.col)));
+    if let Some(conflict) = conflict {
+        return Err(format!(
+            "Cell {} holds {}, which breaks the rules: {}",
+            label, value, conflict
+        ));
+    }
+    let solution = original
+        .filter(|original| may_be_unique(original))
+        .and_then(|original| match original.classify_up_to(2) {
+            PuzzleClass::Unique(solution) => Some(solution),
+            _ => None,
+        });
+    match solution.map(|solution| solution.get(cell) == Some(value)) {
+        Some(true) => Ok(format!(
+            "Cell {} holds {}, which matches the solution",
+            label, value
+        )),
+        Some(false) => Err(format!(
+            "Cell {} holds {}, which is not the value of the solution",
+            label, value
+        )),
+        None => Ok(format!(
+            "Cell {} holds {}, which follows the rules",
+            label, value
+        )),
+    }
+}
+
+/// Checks the clues of the sudoku and, with `check_unique`, that it has exactly one solution.
+///
+/// When the `original` puzzle is given, the sudoku must keep all of its clues. Returns a short description of the
+/// sudoku when it is valid.
+fn validate(s: &Sudoku, original: Option<&Sudoku>, check_unique: bool) -> Result<String, Failure> {
+    validate_classified(s, original, check_unique).0
+}
+
+/// Like `validate`, but also returns the classification of the sudoku when the uniqueness check needed one, so that
+/// the caller does not have to search for the solutions again.
+fn validate_classified(
+    s: &Sudoku,
+    original: Option<&Sudoku>,
+    check_unique: bool,
+) -> (Result<String, Failure>, Option<PuzzleClass>) {
+    if let Some(res) = validate_clues(s, original, check_unique) {
+        return (res, None);
+    }
+    let class = s.classify();
+    (uniqueness_report(s, &class), Some(class))
+}
+
+/// The checks of `validate` which don't need a classification, `None` when the sudoku must be classified.
+fn validate_clues(
+    s: &Sudoku,
+    original: Option<&Sudoku>,
+    check_unique: bool,
+) -> Option<Result<String, Failure>> {
+    let conflicts = s.conflicts();
+    if !conflicts.is_empty() {
+        let conflicts: Vec<String> = conflicts.iter().map(|c| c.to_string()).collect();
+        return Some(Err(Failure::InvalidInput(format!(
+            "incorrect: {}",
+            conflicts.join("; ")
+        ))));
+    }
+    let violations: Vec<String> = s.violations().iter().map(|v| v.to_string()).collect();
+    if !violations.is_empty() {
+        return Some(Err(Failure::InvalidInput(format!(
+            "incorrect: {}",
+            violations.join("; ")
+        ))));
+    }
+    let wrong_sums: Vec<String> = s.wrong_sums().iter().map(|w| w.to_string()).collect();
+    if !wrong_sums.is_empty() {
+        return Some(Err(Failure::InvalidInput(format!(
+            "incorrect: {}",
+            wrong_sums.join("; ")
+        ))));
+    }
+    let altered = original.map_or_else(Vec::new, |original| s.altered_clues(original));
+    if !altered.is_empty() {
+        let cells: Vec<String> = altered
+            .iter()
+            .map(|&(row, col)| rules::cell_name(row, col))
+            .collect();
+        return Some(Err(Failure::InvalidInput(format!(
+            "incorrect: clues of the original puzzle are changed at {}",
+            cells.join(", ")
+        ))));
+    }
+    if s.solved() {
+        return Some(Ok("correct".to_string()));
+    }
+    let empty = s.empty_count();
+    if !check_unique {
+        return Some(Ok(format!("consistent, {} cells are empty", empty)));
+    }
+    if !may_be_unique(s) {
+        let mut solution = s.clone();
+        return Some(match solution.solve() {
+            Ok(()) => Err(Failure::InvalidInput(format!(
+                "ambiguous, it has more than one solution with {} clues, e.g. {}",
+                s.clue_count(),
+                solution.to_line()
+            ))),
+            Err(err) if matches!(err.diagnosis, Diagnosis::LimitReached { .. }) => {
+                Err(Failure::InvalidInput(format!(
+                    "consistent, {} cells are empty, whether it has a solution is unknown: the search reached its \
+                     limit, pass --no-limit to search on",
+                    empty
+                )))
+            }
+            Err(_) => Err(Failure::Unsolvable),
+        });
+    }
+    None
+}
+
+/// Describes the uniqueness of the solution of a consistent sudoku from its `class`.
+fn uniqueness_report(s: &Sudoku, class: &PuzzleClass) -> Result<String, Failure> {
+    let empty = s.empty_count();
+    match class {
+        PuzzleClass::None => Err(Failure::Unsolvable),
+        PuzzleClass::Unique(_) => Ok(format!(
+            "consistent, {} cells are empty, the solution is unique",
+            empty
+        )),
+        PuzzleClass::Unknown { .. } => Err(Failure::InvalidInput(format!(
+            "consistent, {} cells are empty, whether the solution is unique is unknown: the search reached its \
+             limit, pass --no-limit to search on",
+            empty
+        ))),
+        PuzzleClass::Multiple { found, sample, .. } => Err(Failure::InvalidInput(format!(
+            "ambiguous, it has {}{} solutions with {} clues, e.g. {}",
+            found,
+            if *found == CLASSIFY_CAP {
+                " or more"
+            } else {
+                ""
+            },
+            s.clue_count(),
+            sample.to_line()
+        ))),
+    }
+}
+
+/// Returns lines with two solutions of an ambiguous puzzle and the cells where they differ, None for other puzzles.
+fn ambiguity_report(class: &PuzzleClass) -> Option<Vec<String>> {
+    let (sample, second) = match class {
+        PuzzleClass::Multiple { sample, second, .. } => (sample, second),
+        _ => return None,
+    };
+    let mut lines = vec![
+        "First solution:".to_string(),
+        sample.to_string(),
+        "Second solution:".to_string(),
+        second.to_string(),
+    ];
+    lines.extend(comparison_report(&class.ambiguity()?));
+    Some(lines)
+}
+
+/// Describes the result of `validate`.
+fn validation_report(res: &Result<String, Failure>) -> &str {
+    match res {
+        Ok(report)
+        | Err(Failure::InvalidInput(report))
+        | Err(Failure::Io(report))
+        | Err(Failure::Interrupted(report)) => report,
+        Err(_) => "consistent, but it has no solution",
+    }
+}
+
+/// Picks the failure which defines the exit code for validation of a batch: invalid puzzles go first.
+fn combined_validation(results: &[Result<String, Failure>]) -> Result<(), Failure> {
+    let invalid = results
+        .iter()
+        .filter(|res| matches!(res, Err(Failure::InvalidInput(_))))
+        .count();
+    if invalid > 0 {
+        return Err(Failure::InvalidInput(format!(
+            "{} of {} puzzles are invalid",
+            invalid,
+            results.len()
+        )));
+    }
+    match results.iter().any(|res| res.is_err()) {
+        true => Err(Failure::Unsolvable),
+        false => Ok(()),
+    }
+}
+
+/// Prints a new random puzzle.
+///
+/// Without `--seed` the seed drawn for the puzzle is printed to stderr, so it can be generated again.
+fn run_generate(matches: &ArgMatches) -> Result<(), Failure> {
+    let s = match matches.value_of("daily") {
+        Some(date) => {
+            let difficulty = matches
+                .value_of("difficulty")
+                .and_then(Difficulty::from_name)
+                .unwrap_or(Difficulty::Medium);
+            generator::generate_daily(date, difficulty)
+        }
+        None => {
+            let (s, seed) = generate(matches);
+            if !matches.is_present("seed") {
+                eprintln!("Seed: {}", seed);
+            }
+            s
+        }
+    };
+    let color = use_color(io::stdout().is_terminal(), matches.is_present("no_color"));
+    let format = matches.value_of("format").unwrap();
+    println!(
+        "{}",
+        render_with(&s, format, color, &output_options(matches))
+    );
+    Ok(())
+}
+
+/// Generates the puzzle of the `generate` arguments, returns it with the seed of its random numbers.
+fn generate(matches: &ArgMatches) -> (Sudoku, u64) {
+    let seed = matches.value_of("seed").map(|seed| seed.parse().unwrap());
+    let (mut rng, seed) = generator::seeded_rng(seed);
+    let opts = SolveOptions {
+        node_limit: node_limit(matches),
+        ..SolveOptions::random(None)
+    };
+    let s = match box_shape(matches) {
+        Some((rows, cols)) => {
+            generator::generate_with_box_shape_and_options(&mut rng, rows, cols, &opts)
+                .expect("--size and --box-size only allow supported sizes")
+        }
+        None => generator::generate_with_options(&mut rng, &opts),
+    };
+    (s, seed)
+}
+
+/// Prints how hard the sudoku is.
+fn run_rate(matches: &ArgMatches) -> Result<(), Failure> {
+    if matches.is_present("batch") {
+        let path = input_path(matches)?;
+        let data = read_task(matches, path)?;
+        let variant = selected_variant(matches)?;
+        let limit = node_limit(matches);
+        return match matches.value_of("output").map(open_output).transpose()? {
+            Some(ref mut file) => grade_batch(&data, &variant, limit, file),
+            None => grade_batch(&data, &variant, limit, &mut io::stdout()),
+        };
+    }
+    let s = load_sudoku(matches)?;
+    match s.rate() {
+        Ok(difficulty) => {
+            let class = s.classify();
+            println!("Puzzle has {}", class);
+            if let PuzzleClass::Multiple { sample, .. } = &class {
+                println!("Sample solution: {}", sample.to_line());
+            }
+            println!("Difficulty: {}", difficulty);
+            println!("Symmetry: {}", describe_symmetry(&s.clue_symmetry()));
+            Ok(())
+        }
+        Err(err) => {
+            println!("{}", unsolvable_message(&s, &err));
+            Err(Failure::Unsolvable)
+        }
+    }
+}
+
+/// Returns one suggested fix per line, or tells that no single clue removal helps.
+fn describe_repairs(repairs: &[Repair]) -> String {
+    match repairs.is_empty() {
+        true => "No single clue removal makes the puzzle solvable".to_string(),
+        false => repairs
+            .iter()
+            .map(|repair| format!("To fix it, {}", repair))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Returns the symmetries of the clues separated by commas, `none` without any.
+fn describe_symmetry(symmetry: &[SymmetryKind]) -> String {
+    match symmetry.is_empty() {
+        true => "none".to_string(),
+        false => symmetry
+            .iter()
+            .map(SymmetryKind::to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+/// Returns how many cells hold every value of the sudoku, like `1: 3, 2: 2`; values above 9 are summed up.
+fn describe_digits(s: &Sudoku) -> String {
+    let histogram = s.digit_histogram();
+    let mut counts: Vec<String> = (1..=s.side().min(9))
+        .map(|digit| format!("{}: {}", digit, histogram[digit]))
+        .collect();
+    if s.side() > 9 {
+        counts.push(format!("above 9: {}", s.digit_overflow()));
+    }
+    counts.join(", ")
+}
+
+/// Header of the CSV written by `rate --batch`.
+const GRADES_HEADER: &str = "line,puzzle,clues,unique,difficulty,effort,time_ms,error";
+
+/// Columns added to `GRADES_HEADER` when puzzles of the batch have metadata, see `formats::metadata`.
+const METADATA_HEADER: &str = "id,source,tags";
+
+/// Writes a CSV row grading every puzzle of the batch, one puzzle per line, see `GRADES_HEADER`.
+///
+/// Malformed lines get a row with the error, the other puzzles are still graded. Fails after all rows are written if
+/// some line is malformed. The columns of `METADATA_HEADER` follow when a comment line of the batch holds metadata.
+fn grade_batch(
+    data: &str,
+    variant: &Variant,
+    limit: NodeLimit,
+    out: &mut impl Write,
+) -> Result<(), Failure> {
+    let write_failure = |err: io::Error| Failure::Io(format!("Cannot write grades: {}", err));
+    let with_metadata = data
+        .lines()
+        .any(|line| Metadata::default().read_comment(line));
+    match with_metadata {
+        true => writeln!(out, "{},{}", GRADES_HEADER, METADATA_HEADER),
+        false => writeln!(out, "{}", GRADES_HEADER),
+    }
+    .map_err(write_failure)?;
+    let mut malformed = 0;
+    for (line, res) in formats::parse_sudoku_lines(data) {
+        let mut metadata = None;
+        let mut row = match res.and_then(|s| variant.apply(s)) {
+            Ok(s) => {
+                let s = s.with_node_limit(limit);
+                metadata = s.metadata().cloned();
+                grade(&s)
+            }
+            Err(err) => {
+                malformed += 1;
+                let puzzle = data.lines().nth(line - 1).unwrap_or_default().trim();
+                vec![
+                    csv_field(puzzle),
+                    String::new(),
+                    "unknown".to_string(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    csv_field(&err.to_string()),
+                ]
+            }
+        };
+        if with_metadata {
+            row.extend(metadata_columns(metadata.unwrap_or_default()));
+        }
+        writeln!(out, "{},{}", line, row.join(",")).map_err(write_failure)?;
+    }
+    match malformed {
+        0 => Ok(()),
+        n => Err(Failure::InvalidInput(format!(
+            "Cannot parse {} of the puzzles",
+            n
+        ))),
+    }
+}
+
+/// Returns the columns of `GRADES_HEADER` after the line number for the puzzle.
+///
+/// The effort is the number of values the search places and takes back, the time is the time of that search.
+fn grade(s: &Sudoku) -> Vec<String> {
+    let unique = match may_be_unique(s) {
+        true => match s.classify_up_to(2) {
+            PuzzleClass::Unique(_) => "yes",
+            PuzzleClass::Unknown { .. } => "unknown",
+            PuzzleClass::None | PuzzleClass::Multiple { .. } => "no",
+        },
+        false => "no",
+    };
+    let searched = s.rate().and_then(|difficulty| {
+        let mut stats = SolveStats::default();
+        let mut observer = Progress::new(1, |s: &SolveStats| stats = *s);
+        let mut solution = s.clone();
+        let start = Instant::now();
+        solution.solve_with_observer(&mut observer)?;
+        Ok((difficulty, stats, start.elapsed()))
+    });
+    // The rating counts the solutions with another search than the solver's, only one of them may reach the limit.
+    let (difficulty, effort, time) = match searched {
+        Ok((difficulty, stats, time)) => (
+            difficulty.to_string(),
+            (stats.placements + stats.backtracks).to_string(),
+            format!("{:.3}", time.as_secs_f64() * 1000.0),
+        ),
+        Err(err) if matches!(err.diagnosis, Diagnosis::LimitReached { .. }) => {
+            ("unknown".to_string(), "unknown".to_string(), String::new())
+        }
+        Err(_) => ("unsolvable".to_string(), String::new(), String::new()),
+    };
+    vec![
+        s.to_line(),
+        s.clue_count().to_string(),
+        unique.to_string(),
+        difficulty,
+        effort,
+        time,
+        String::new(),
+    ]
+}
+
+/// Returns the columns of `METADATA_HEADER`, the tags are separated by semicolons.
+fn metadata_columns(metadata: Metadata) -> Vec<String> {
+    vec![
+        csv_field(&metadata.id.unwrap_or_default()),
+        csv_field(&metadata.source.unwrap_or_default()),
+        csv_field(&metadata.tags.join(";")),
+    ]
+}
+
+/// Quotes the CSV field if it holds a comma, a quote or a line break.
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+/// Prints the cells which differ in the two grids, the grids are equal when nothing differs.
+fn run_compare(matches: &ArgMatches) -> Result<(), Failure> {
+    let variant = Variant::default();
+    let first = load_sudoku_from(matches.value_of("first").unwrap(), "auto", None, &variant)?;
+    let second = load_sudoku_from(matches.value_of("second").unwrap(), "auto", None, &variant)?;
+    if first.side() != second.side() {
+        return Err(Failure::InvalidInput(format!(
+            "Cannot compare a {0}x{0} grid with a {1}x{1} grid",
+            first.side(),
+            second.side()
+        )));
+    }
+    let diff = first.diff(&second);
+    for line in comparison_report(&diff) {
+        println!("{}", line);
+    }
+    match diff.is_empty() {
+        true => Ok(()),
+        false => Err(Failure::Differs),
+    }
+}
+
+/// Returns lines listing the differences of two grids as a table of cells and their values in both grids.
+fn comparison_report(diff: &[CellDiff]) -> Vec<String> {
+    if diff.is_empty() {
+        return vec!["Grids are identical".to_string()];
+    }
+    let cells = match diff.len() {
+        1 => "1 cell differs:".to_string(),
+        n => format!("{} cells differ:", n),
+    };
+    let mut lines = vec![cells];
+    lines.extend(diff.iter().map(|d| d.to_string()));
+    lines
+}
+
+/// Prints the value of one empty cell and, with `--apply`, the sudoku with this cell filled.
+fn run_hint(matches: &ArgMatches) -> Result<(), Failure> {
+    let mut s = load_sudoku(matches)?;
+    let cell = selected_cell(matches, &s)?;
+    let max = matches.value_of("max_technique").map(|name| {
+        Technique::from_name(name).expect("--max-technique only allows the names of techniques")
+    });
+    let found = match (cell, max) {
+        (None, None) => s.hint(),
+        (Some(cell), None) => s.hint_at(cell),
+        (None, Some(max)) => s.hint_up_to(max),
+        (Some(cell), Some(max)) => s.hint_at_up_to(cell, max),
+    };
+    let hint = match found {
+        Ok(Some(hint)) => hint,
+        Ok(None) => {
+            match cell {
+                Some(cell) if s.value(cell.row, cell.col).is_some() => {
+                    println!("Cell {} is already filled", cell.label())
+                }
+                None if s.empty_count() == 0 => {
+                    println!("Sudoku is solved, there is nothing to hint")
+                }
+                _ => println!("No hint available at this level"),
+            }
+            return Ok(());
+        }
+        Err(err) => {
+            println!("{}", unsolvable_message(&s, &err));
+            return Err(Failure::Unsolvable);
+        }
+    };
+    println!("{}", describe_hint(&hint));
+    if let Some(rank) = fill_rank(&s, hint.cell.row, hint.cell.col) {
+        println!("{}", rank);
+    }
+    if matches.is_present("apply") {
+        s.apply_hint(&hint);
+        println!("{}", s.to_line());
+    }
+    Ok(())
+}
+
+/// Describes the hint and the reason behind it with 1-based coordinates and the label of the cell.
+fn describe_hint(hint: &Hint) -> String {
+    format!(
+        "Row {}, column {} ({}): {} ({}) - {}",
+        hint.cell.row + 1,
+        hint.cell.col + 1,
+        hint.cell.label(),
+        hint.value,
+        hint.source,
+        hint.reason()
+    )
+}
+
+/// Tells when the solver fills the cell, like `It is the 12th of 51 cells the solver determines`, see
+/// `Sudoku::fill_order`; None if the puzzle has no solution.
+fn fill_rank(s: &Sudoku, row: usize, col: usize) -> Option<String> {
+    let mut solved = s.clone();
+    solved.solve().ok()?;
+    let order = solved.fill_order();
+    let rank = order.iter().position(|&(r, c, _)| (r, c) == (row, col))? + 1;
+    Some(format!(
+        "It is the {} of {} cells the solver determines",
+        ordinal(rank),
+        order.len()
+    ))
+}
+
+/// Returns the number with its English ordinal suffix, e.g. `1st`, `12th` or `23rd`.
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+/// Lets the user edit the sudoku with commands typed into the terminal.
+fn run_interactive(matches: &ArgMatches) -> Result<(), Failure> {
+    let box_shape = box_shape(matches);
+    let variant = selected_variant(matches)?;
+    let sudoku = match ["sudoku_path", "grid", "demo"]
+        .iter()
+        .any(|&arg| matches.is_present(arg))
+    {
+        true => load_sudoku(matches)?,
+        false => {
+            let empty = box_shape.map_or_else(Sudoku::empty, |(rows, cols)| {
+                Sudoku::with_box_shape(rows, cols)
+                    .expect("--size and --box-size only allow supported sizes")
+            });
+            variant
+                .apply(empty)
+                .map_err(|err| Failure::InvalidInput(err.to_string()))?
+        }
+    };
+    interactive::Session::new(sudoku.with_node_limit(node_limit(matches)))
+        .run(io::stdin().lock(), &mut io::stdout())
+}
+
+/// Opens the file given with `--output`, creating or truncating it.
+fn open_output(path: &str) -> Result<fs::File, Failure> {
+    fs::File::create(path)
+        .map_err(|err| Failure::Io(format!("Cannot create output file {}: {}", path, err)))
+}
+
+/// Opens the `--output` file of a batch, returns it with the number of puzzles to skip.
+///
+/// With `--resume` the solutions already in the file are kept and the batch continues after them, otherwise the file
+/// is truncated and nothing is skipped.
+fn batch_output(matches: &ArgMatches) -> Result<(Option<fs::File>, usize), Failure> {
+    match matches.value_of("output") {
+        Some(path) if matches.is_present("resume") => {
+            let (file, done) = open_resumed_output(path)?;
+            if done > 0 {
+                eprintln!("Skipping {} puzzles already solved in {}", done, path);
+            }
+            Ok((Some(file), done))
+        }
+        Some(path) => Ok((Some(open_output(path)?), 0)),
+        None => Ok((None, 0)),
+    }
+}
+
+/// Opens the output file to append to it, returns it with the number of complete lines it holds.
+///
+/// A last line without a line break was cut short by an interrupted run, it is removed, like the summary line of a
+/// JSON batch. A missing file is created.
+fn open_resumed_output(path: &str) -> Result<(fs::File, usize), Failure> {
+    let failure =
+        |err: io::Error| Failure::Io(format!("Cannot resume output file {}: {}", path, err));
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(failure)?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content).map_err(failure)?;
+    let mut complete = content
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |last| last + 1);
+    // The summary of a JSON batch closes a finished run, it is written again at the end.
+    let last_line = content[..complete.saturating_sub(1)]
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |last| last + 1);
+    if content[last_line..complete].starts_with(b"{\"summary\"") {
+        complete = last_line;
+    }
+    file.set_len(complete as u64).map_err(failure)?;
+    file.seek(SeekFrom::End(0)).map_err(failure)?;
+    let lines = content[..complete]
+        .iter()
+        .filter(|&&byte| byte == b'\n')
+        .count();
+    Ok((file, lines))
+}
+
+/// Writes the rendered solution to the output file.
+fn write_output(file: &mut fs::File, rendered: &str) -> Result<(), Failure> {
+    writeln!(file, "{}", rendered)
+        .map_err(|err| Failure::Io(format!("Cannot write solution: {}", err)))
+}
+
+/// Writes the solution to the output file in the format of `--format` with the options of `--empty-char`.
+fn write_solution(
+    file: &mut fs::File,
+    s: &Sudoku,
+    format: &str,
+    opts: &OutputOptions,
+) -> Result<(), Failure> {
+    let format = OutputFormat::from_name(format).expect("--format only allows output formats");
+    s.write_to_with(file, format, opts)
+        .map_err(|err| Failure::Io(format!("Cannot write solution: {}", err)))
+}
+
+/// Result of a solve in the JSON output format.
+#[derive(Debug, Serialize)]
+struct JsonOutput {
+    puzzle: Option<Sudoku>,
+    /// Counts of the cells of the puzzle.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<Summary>,
+    /// Whether the puzzle has a unique solution, none or several.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    classification: Option<JsonClassification>,
+    /// Symmetries of the positions of the clues of the puzzle.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symmetry: Option<Vec<SymmetryKind>>,
+    solution: Option<Sudoku>,
+    /// Fields of the catalog the puzzle comes from, see `formats::metadata`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<Metadata>,
+    solved: bool,
+    duration_ms: f64,
+    /// Counters of the search, none when the sudoku is not searched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<SolveStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<JsonError>,
+}
+
+impl JsonOutput {
+    /// Returns the output of a sudoku which cannot be solved because of the error.
+    fn failed(puzzle: Option<Sudoku>, error: JsonError) -> JsonOutput {
+        JsonOutput {
+            summary: puzzle.as_ref().map(Sudoku::summary),
+            classification: None,
+            symmetry: puzzle.as_ref().map(Sudoku::clue_symmetry),
+            metadata: puzzle.as_ref().and_then(|s| s.metadata().cloned()),
+            puzzle,
+            solution: None,
+            solved: false,
+            duration_ms: 0.0,
+            stats: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Classification of the puzzle in the JSON output, see `Sudoku::classify`.
+#[derive(Debug, Serialize)]
+struct JsonClassification {
+    /// `unique`, `none` or `multiple`.
+    class: &'static str,
+    /// Solutions found before the search stopped at `CLASSIFY_CAP`.
+    found: usize,
+    /// First solution of a puzzle with several ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample: Option<Sudoku>,
+}
+
+impl JsonClassification {
+    fn of(puzzle: &Sudoku) -> JsonClassification {
+        let class = puzzle.classify();
+        JsonClassification {
+            class: class.name(),
+            found: class.found(),
+            sample: match class {
+                PuzzleClass::Multiple { sample, .. } => Some(sample),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Failure in the JSON output format, every kind has a `code` which stays the same across releases.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum JsonError {
+    /// The sudoku cannot be loaded, serialized with the code and the fields of the error, e.g. the `line`.
+    Sudoku(SudokuError),
+    /// Clues break the rules.
+    Conflicts {
+        code: &'static str,
+        message: String,
+        conflicts: Vec<JsonConflict>,
+    },
+    /// The sudoku has no solution or the search stopped at its limit, with the progress possible without guessing.
+    Unsolvable {
+        code: &'static str,
+        message: String,
+        best_effort: Box<JsonBestEffort>,
+    },
+    /// Any other failure, its code names the class of the failure which also defines the exit code.
+    Failure { code: &'static str, message: String },
+}
+
+/// Progress on an unsolvable sudoku of the JSON output, see `best_effort_report`.
+#[derive(Debug, Serialize)]
+struct JsonBestEffort {
+    /// Empty cells of the task filled without guessing, out of `empty`.
+    determined: usize,
+    empty: usize,
+    /// The contradiction the forced cells run into.
+    contradiction: Option<String>,
+    /// The task with the forced cells.
+    grid: Sudoku,
+    /// The grid with the most values the search placed at once, only when it stopped at its limit.
+    deepest: Option<Sudoku>,
+}
+
+/// Value repeated in a unit or a broken constraint of the JSON output.
+#[derive(Debug, Serialize)]
+struct JsonConflict {
+    message: String,
+    /// (row, column) of the cells, 1-based.
+    cells: Vec<(usize, usize)>,
+}
+
+impl JsonError {
+    fn from_failure(failure: &Failure) -> JsonError {
+        let (code, message) = match failure {
+            Failure::InvalidInput(msg) => ("invalid_input", msg.clone()),
+            Failure::Io(msg) => ("io", msg.clone()),
+            Failure::Interrupted(msg) => ("interrupted", msg.clone()),
+            _ => ("unsolvable", "Sudoku cannot be solved".to_string()),
+        };
+        JsonError::Failure { code, message }
+    }
+
+    /// Returns the error listing the conflicts of the clues, None if the clues follow the rules.
+    fn from_conflicts(s: &Sudoku) -> Option<JsonError> {
+        let one_based =
+            |cells: &[(usize, usize)]| cells.iter().map(|&(row, col)| (row + 1, col + 1)).collect();
+        let conflicts: Vec<JsonConflict> = s
+            .conflicts()
+            .iter()
+            .map(|c| JsonConflict {
+                message: c.to_string(),
+                cells: one_based(&c.cells),
+            })
+            .chain(s.violations().iter().map(|v| JsonConflict {
+                message: v.to_string(),
+                cells: one_based(&v.cells),
+            }))
+            .collect();
+        if conflicts.is_empty() {
+            return None;
+        }
+        let messages: Vec<&str> = conflicts.iter().map(|c| c.message.as_str()).collect();
+        let err = SudokuError::ConflictingClues(messages.join("; "));
+        Some(JsonError::Conflicts {
+            code: err.code(),
+            message: err.to_string(),
+            conflicts,
+        })
+    }
+}
+
+/// Solves the sudoku for `--format json`: the outcome, failures included, is a single JSON document written to stdout
+/// or to the `--output` file, while the exit code still tells the class of the failure.
+///
+/// A batch is solved like in the other formats, with a JSON line per puzzle.
+fn run_solve_json(matches: &ArgMatches) -> Result<(), Failure> {
+    for (arg, flag) in [
+        ("animate", "--animate"),
+        ("checkpoint", "--checkpoint"),
+        ("explain", "--explain"),
+        ("check_solvers", "--check-solvers"),
+    ] {
+        if matches.is_present(arg) {
+            return Err(Failure::InvalidInput(format!(
+                "{} cannot be used with --format json",
+                flag
+            )));
+        }
+    }
+    let task = read_task_for_json(matches);
+    if let Ok((path, data, variant)) = &task {
+        if let Some(res) = solve_parsed_batch(matches, path, data, "json", variant) {
+            return res;
+        }
+    }
+    let (output, res, file) = match matches.value_of("output").map(open_output).transpose() {
+        Ok(file) => {
+            let opts = solve_options(matches);
+            let loaded = task
+                .and_then(|(path, data, variant)| load_for_json(matches, path, &data, &variant))
+                .map(|s| s.with_options(&opts));
+            let (output, res) = solve_to_json(loaded, matches.is_present("classify"));
+            (output, res, file)
+        }
+        Err(failure) => (
+            JsonOutput::failed(None, JsonError::from_failure(&failure)),
+            Err(failure),
+            None,
+        ),
+    };
+    let json = to_json(&output, matches.is_present("pretty"));
+    match file {
+        Some(mut file) => write_output(&mut file, &json)?,
+        None => println!("{}", json),
+    }
+    res
+}
+
+/// Reads the task and the variant given with the arguments for the JSON output, a failure comes with its JSON error.
+fn read_task_for_json<'a>(
+    matches: &'a ArgMatches,
+) -> Result<(&'a str, String, Variant), (JsonError, Failure)> {
+    let failed = |failure: Failure| (JsonError::from_failure(&failure), failure);
+    let path = input_path(matches).map_err(failed)?;
+    let data = read_task(matches, path).map_err(failed)?;
+    let variant = selected_variant(matches).map_err(failed)?;
+    Ok((path, data, variant))
+}
+
+/// Loads the single sudoku of the task for the JSON output, a failure comes with its JSON error.
+///
+/// Clues which break the rules fail here too, so the JSON error lists the conflicts.
+fn load_for_json(
+    matches: &ArgMatches,
+    path: &str,
+    data: &str,
+    variant: &Variant,
+) -> Result<Sudoku, (JsonError, Failure)> {
+    let input = input_format(path, matches.value_of("input_format").unwrap());
+    let s = parse_sudoku(data, input, box_shape(matches), variant)
+        .map_err(|err| (JsonError::Sudoku(err.clone()), load_failure(path, err)))?;
+    match JsonError::from_conflicts(&s) {
+        Some(error) => {
+            let failure = Failure::InvalidInput(format!(
+                "Cannot solve {}: clues break the rules",
+                source_name(path)
+            ));
+            Err((error, failure))
+        }
+        None => Ok(s),
+    }
+}
+
+/// Solves the loaded sudoku, collecting the outcome for the JSON output, with the classification of the puzzle with
+/// `classify`.
+///
+/// Also returns the failure, if any, so the exit code matches the JSON output.
+fn solve_to_json(
+    loaded: Result<Sudoku, (JsonError, Failure)>,
+    classify: bool,
+) -> (JsonOutput, Result<(), Failure>) {
+    let mut s = match loaded {
+        Ok(s) => s,
+        Err((error, failure)) => return (JsonOutput::failed(None, error), Err(failure)),
+    };
+    let puzzle = s.clone();
+    let classification = classify.then(|| JsonClassification::of(&puzzle));
+    let start = Instant::now();
+    let (res, observer) = solve_observed(&mut s, false);
+    let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+    match res {
+        Ok(_) => (
+            JsonOutput {
+                summary: Some(puzzle.summary()),
+                classification,
+                symmetry: Some(puzzle.clue_symmetry()),
+                metadata: puzzle.metadata().cloned(),
+                puzzle: Some(puzzle),
+                solution: Some(s),
+                solved: true,
+                duration_ms,
+                stats: Some(observer.stats),
+                error: None,
+            },
+            Ok(()),
+        ),
+        Err(err) => {
+            let mut task = puzzle.clone();
+            task.reset();
+            let (grid, contradiction) = puzzle.best_effort();
+            let best_effort = Box::new(JsonBestEffort {
+                determined: task.empty_count() - grid.empty_count(),
+                empty: task.empty_count(),
+                contradiction: contradiction.map(|c| c.diagnosis.to_string()),
+                grid,
+                deepest: matches!(err.diagnosis, Diagnosis::LimitReached { .. })
+                    .then(|| observer.deepest()),
+            });
+            let error = JsonError::Unsolvable {
+                code: "unsolvable",
+                message: unsolvable_message(&s, &err),
+                best_effort,
+            };
+            let output = JsonOutput {
+                classification,
+                duration_ms,
+                stats: Some(observer.stats),
+                ..JsonOutput::failed(Some(puzzle), error)
+            };
+            (output, Err(Failure::Unsolvable))
+        }
+    }
+}
+
+fn to_json(output: &JsonOutput, pretty: bool) -> String {
+    match pretty {
+        true => serde_json::to_string_pretty(output).unwrap(),
+        false => serde_json::to_string(output).unwrap(),
+    }
+}
+
+/// Returns how the input at `path` is called in messages.
+fn source_name(path: &str) -> String {
+    match path {
+        "-" => "stdin".to_string(),
+        GRID_INPUT => "the --grid argument".to_string(),
+        DEMO_INPUT => "the --demo puzzle".to_string(),
+        path => format!("file {}", path),
+    }
+}
+
+/// Reads the whole input: the file at `path`, or `stdin` when the path is `-`.
+fn read_input(path: &str, stdin: impl Read) -> Result<String, Failure> {
+    match path {
+        "-" => read_all(path, stdin),
+        path => read_all(
+            path,
+            fs::File::open(path).map_err(|err| read_failure(path, err))?,
+        ),
+    }
+}
+
+/// Opens the file at `path` for reading line by line, or `stdin` when the path is `-`.
+fn open_input(path: &str) -> Result<Box<dyn BufRead>, Failure> {
+    match path {
+        "-" => Ok(Box::new(io::stdin().lock())),
+        path => fs::File::open(path)
+            .map(|file| Box::new(io::BufReader::new(file)) as Box<dyn BufRead>)
+            .map_err(|err| read_failure(path, err)),
+    }
+}
+
+/// Reads the lines of the input up to its second line with data, enough to tell a batch apart with `is_line_batch`.
+fn read_head(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut head = String::new();
+    let mut data_lines = 0;
+    while data_lines < 2 {
+        let start = head.len();
+        if reader.read_line(&mut head)? == 0 {
+            break;
+        }
+        if !formats::is_ignored_line(&head[start..]) {
+            data_lines += 1;
+        }
+    }
+    Ok(head)
+}
+
+/// Returns `true` if the head of the input starts a batch of one sudoku per line, see `formats::stream_puzzles`.
+fn is_line_batch(head: &str, box_shape: Option<(usize, usize)>) -> bool {
+    box_shape.map_or(true, |shape| shape == (3, 3) || shape == (4, 4)) && formats::is_batch(head)
+}
+
+fn read_failure(path: &str, err: io::Error) -> Failure {
+    Failure::Io(format!("Cannot read {}: {}", source_name(path), err))
+}
+
+/// Reads the rest of the input, which must hold more than whitespace.
+fn read_all(path: &str, mut reader: impl Read) -> Result<String, Failure> {
+    let mut data = String::new();
+    match reader.read_to_string(&mut data) {
+        Err(err) => Err(read_failure(path, err)),
+        Ok(_) if data.trim().is_empty() => Err(Failure::InvalidInput(format!(
+            "Cannot read {}: input is empty",
+            source_name(path)
+        ))),
+        Ok(_) => Ok(data),
+    }
+}
+
+/// Parses a single sudoku written in the given input format.
+///
+/// The output of the solver is accepted too, then the solution printed after `SOLVED_BANNER` is parsed. Only 9x9 and
+/// 16x16 sudokus are detected, other sizes need the (rows, columns) `box_shape`. The sudoku follows the rules and the
+/// regions of the `variant`, and has the metadata of the comment lines it starts with.
+fn parse_sudoku(
+    data: &str,
+    input_format: &str,
+    box_shape: Option<(usize, usize)>,
+    variant: &Variant,
+) -> Result<Sudoku, SudokuError> {
+    let metadata = metadata::leading_metadata(data);
+    let data = match data.rsplit_once(SOLVED_BANNER) {
+        Some((_, solution)) => solution,
+        None => data,
+    };
+    let marked = parity::split_markers(data);
+    let data = marked.as_ref().map_or(data, |(grid, _)| grid.as_str());
+    let sudoku = match (input_format, box_shape) {
+        ("ss", None) | ("ss", Some((3, 3))) => ss::read_ss(data.as_bytes()),
+        ("ss", Some(_)) => Err(SudokuError::Parse(
+            "only 9x9 sudokus can be read in the ss format".to_string(),
+        )),
+        ("cells", _) => cells::parse_cell_list(data),
+        ("line", None) => formats::detect_and_parse(data),
+        (_, Some((rows, cols))) => Sudoku::parse_with_box_shape(data, rows, cols),
+        _ => data.parse(),
+    };
+    let sudoku = match marked {
+        Some((_, marks)) => sudoku.and_then(|s| variant.apply(s)?.with_parity(marks)),
+        None => sudoku.and_then(|s| variant.apply(s)),
+    };
+    match metadata {
+        Some(metadata) => sudoku.map(|s| s.with_metadata(metadata)),
+        None => sudoku,
+    }
+}
+
+/// Parses the data as a batch of sudokus, returns `None` if it holds a single sudoku.
+///
+/// Batches are only detected for sudokus of the detected sizes, with other `box_shape` the data is a single sudoku.
+fn parse_batch(
+    data: &str,
+    input_format: &str,
+    box_shape: Option<(usize, usize)>,
+    variant: &Variant,
+) -> Option<Result<Vec<Sudoku>, SudokuError>> {
+    let sudokus = match input_format {
+        "sdm" => sdm::read_sdm(data.as_bytes()),
+        "ss" | "cells" => return None,
+        _ if cells::is_cell_list(data) => return None,
+        _ if parity::split_markers(data).is_some() => return None,
+        _ if box_shape.is_some_and(|shape| shape != (3, 3) && shape != (4, 4)) => return None,
+        _ if formats::is_batch(data) => formats::parse_sudoku_batch(data),
+        _ if box_shape.map_or(true, |shape| shape == (3, 3)) => formats::split_concatenated(data)?,
+        _ => return None,
+    };
+    Some(sudokus.and_then(|sudokus| sudokus.into_iter().map(|s| variant.apply(s)).collect()))
+}
+
+/// Parses the data as a batch of sudokus, or as a single one when it is not a batch, see `parse_batch`.
+fn parse_puzzles(
+    data: &str,
+    input_format: &str,
+    box_shape: Option<(usize, usize)>,
+    variant: &Variant,
+) -> Result<Vec<Sudoku>, SudokuError> {
+    match parse_batch(data, input_format, box_shape, variant) {
+        Some(sudokus) => sudokus,
+        None => parse_sudoku(data, input_format, box_shape, variant).map(|s| vec![s]),
+    }
+}
+
+/// Renders the sudoku in the format selected with `--format`.
+///
+/// `color` highlights givens and solver-filled cells of the pretty grid.
+fn render(sudoku: &Sudoku, format: &str, color: bool) -> String {
+    render_with(sudoku, format, color, &OutputOptions::default())
+}
+
+/// Renders the sudoku like `render`, the text formats draw empty cells as selected with `--empty-char`.
+///
+/// With `--mark-filled` the pretty grid puts the cells filled by the solver in brackets instead of coloring them.
+fn render_with(sudoku: &Sudoku, format: &str, color: bool, opts: &OutputOptions) -> String {
+    match format {
+        "line" => sudoku.to_line_with(opts),
+        "sdm" => {
+            let mut out = Vec::new();
+            sdm::write_sdm_with(&mut out, Some(sudoku), opts).unwrap();
+            String::from_utf8(out).unwrap().trim_end().to_string()
+        }
+        "ss" => {
+            let mut out = Vec::new();
+            ss::write_ss_with(&mut out, sudoku, opts).unwrap();
+            String::from_utf8(out).unwrap()
+        }
+        "unicode" => sudoku.to_unicode_string(),
+        "labeled" => sudoku.to_labeled_string(),
+        "cells" => cells::to_cell_list(sudoku),
+        "svg" => sudoku.to_svg(&SvgOptions::default()),
+        "html" => sudoku.to_html(),
+        "latex" => sudoku.to_latex(),
+        "marks" => sudoku.to_marked_string(),
+        _ if color && !opts.mark_filled => sudoku.to_colored_string_with(opts),
+        _ => sudoku.to_string_with(opts),
+    }
+}
+
+/// Returns the options of the search selected with `--value-order`, `--seed` and `--no-limit`.
+///
+/// Without `--no-limit` the search of grids larger than 9x9 stops at the default limit, see `NodeLimit::Default`.
+/// Without `--seed` the random order draws a seed and prints it to stderr, so the search can be repeated.
+fn solve_options(matches: &ArgMatches) -> SolveOptions {
+    let mut opts = SolveOptions {
+        value_order: match matches.value_of("value_order") {
+            Some("least-constraining") => ValueOrder::LeastConstraining,
+            Some("random") => ValueOrder::Random,
+            _ => ValueOrder::Ascending,
+        },
+        seed: matches.value_of("seed").map(|seed| seed.parse().unwrap()),
+        node_limit: node_limit(matches),
+    };
+    if opts.value_order == ValueOrder::Random && opts.seed.is_none() {
+        let seed = opts.seed();
+        eprintln!("Seed: {}", seed);
+        opts.seed = Some(seed);
+    }
+    opts
+}
+
+/// Returns the limit of the search, `NodeLimit::Unlimited` with `--no-limit`.
+fn node_limit(matches: &ArgMatches) -> NodeLimit {
+    match matches.is_present("no_limit") {
+        true => NodeLimit::Unlimited,
+        false => NodeLimit::Default,
+    }
+}
+
+/// Returns the output options selected with `--empty-char`.
+fn output_options(matches: &ArgMatches) -> OutputOptions {
+    OutputOptions::default()
+        .with_empty_char(
+            matches
+                .value_of("empty_char")
+                .and_then(|empty| empty.chars().next()),
+        )
+        .with_mark_filled(matches.is_present("mark_filled"))
+}
+
+/// Accepts an ISO date like `2024-06-01`, the days of a month are not checked.
+fn validate_date(date: String) -> Result<(), String> {
+    let parts: Vec<&str> = date.split('-').collect();
+    let number = |part: &str, len: usize, max: u32| {
+        part.len() == len
+            && part.chars().all(|c| c.is_ascii_digit())
+            && (1..=max).contains(&part.parse().unwrap())
+    };
+    match parts.as_slice() {
+        [year, month, day]
+            if number(year, 4, 9999) && number(month, 2, 12) && number(day, 2, 31) =>
+        {
+            Ok(())
+        }
+        _ => Err(format!("expected a date like 2024-06-01, got `{}`", date)),
+    }
+}
+
+/// Accepts a single character for empty cells which cannot be mistaken for a value.
+fn validate_empty_char(empty: String) -> Result<(), String> {
+    let mut chars = empty.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if ('1'..='9').contains(&c) || ('A'..='G').contains(&c) => Err(format!(
+            "`{}` stands for a value, empty cells need another character",
+            c
+        )),
+        (Some(_), None) => Ok(()),
+        _ => Err(format!("expected a single character, got `{}`", empty)),
+    }
+}
+
+/// Returns `true` if output should be colored: only for terminals, unless disabled with `--no-color`.
+fn use_color(is_terminal: bool, no_color: bool) -> bool {
+    is_terminal && !no_color
+}
+
+/// Solves a batch of one sudoku per line as it is read: each puzzle is solved and its solution written before the
+/// next line is read, so collections of any size can be solved.
+fn solve_stream(
+    matches: &ArgMatches,
+    path: &str,
+    format: &str,
+    reader: impl BufRead,
+) -> Result<(), Failure> {
+    let variant = selected_variant(matches)?;
+    let opts = solve_options(matches);
+    let (mut output, done) = batch_output(matches)?;
+    let batch = BatchOptions {
+        time: matches.is_present("time"),
+        summary: !matches.is_present("no_summary") && !matches.is_present("quiet"),
+        progress: matches.is_present("progress"),
+        cache: cache_capacity(matches),
+        output: output_options(matches),
+    };
+    let mut unique = matches
+        .is_present("dedupe")
+        .then(|| Unique::for_variant(&variant));
+    let sudokus = formats::stream_puzzles(reader)
+        .filter(|res| match (res, unique.as_mut()) {
+            (Ok((line, sudoku)), Some(unique)) => unique.is_new(*line, sudoku),
+            _ => true,
+        })
+        .skip(done)
+        .map(|res| {
+            res.and_then(|(line, sudoku)| Ok((line, variant.apply(sudoku)?.with_options(&opts))))
+                .map_err(|err| load_failure(path, err))
+        });
+    match output {
+        Some(ref mut file) => solve_batch(sudokus, format, &batch, file),
+        None => solve_batch(sudokus, format, &batch, &mut io::stdout()),
+    }
+}
+
+/// How `solve_batch` solves a batch and what it reports.
+#[derive(Debug, Clone, Copy, Default)]
+struct BatchOptions {
+    /// Print the solve time of every puzzle and the total time to stderr.
+    time: bool,
+    /// Print the `BatchReport` to stderr at the end.
+    summary: bool,
+    /// Passed to `solve_with_progress`.
+    progress: bool,
+    /// Capacity of the `SolveCache` answering repeated puzzles, no cache without it.
+    cache: Option<usize>,
+    /// How the solutions are written, see `render_with`.
+    output: OutputOptions,
+}
+
+/// Returns the capacity of the cache of solutions requested with `--cache`.
+fn cache_capacity(matches: &ArgMatches) -> Option<usize> {
+    matches
+        .value_of("cache")
+        .map(|capacity| capacity.parse().unwrap())
+}
+
+/// Solves every puzzle of the batch, given with its line in the input, and writes one solution line per puzzle.
+///
+/// Puzzles which cannot be solved are written as `unsolvable` lines and make the whole batch fail. A puzzle which
+/// cannot be loaded stops the batch after the solutions of the puzzles before it have been written.
+/// See `BatchOptions` for the reports of the batch.
+fn solve_batch(
+    sudokus: impl IntoIterator<Item = Result<(usize, Sudoku), Failure>>,
+    format: &str,
+    batch: &BatchOptions,
+    out: &mut impl Write,
+) -> Result<(), Failure> {
+    let mut report = BatchReport::default();
+    let res = solve_puzzles(sudokus, format, batch, out, &mut report);
+    if batch.time {
+        eprintln!("Total: {}", format_duration(report.total_time));
+    }
+    match (batch.summary, format) {
+        (true, "json") => {
+            let summary = serde_json::json!({ "summary": JsonBatchReport::of(&report) });
+            writeln!(out, "{}", summary)
+                .map_err(|err| Failure::Io(format!("Cannot write solutions: {}", err)))?;
+        }
+        (true, _) => {
+            for line in report.lines() {
+                eprintln!("{}", line);
+            }
+        }
+        (false, _) => {}
+    }
+    res
+}
+
+/// Solves and writes the puzzles of `solve_batch`, counting them in the report; a puzzle answered by the cache
+/// takes no backtracks.
+fn solve_puzzles(
+    sudokus: impl IntoIterator<Item = Result<(usize, Sudoku), Failure>>,
+    format: &str,
+    batch: &BatchOptions,
+    out: &mut impl Write,
+    report: &mut BatchReport,
+) -> Result<(), Failure> {
+    let progress = batch.progress;
+    let mut cache = batch.cache.map(SolveCache::new);
+    let format = match format {
+        "sdm" => "sdm",
+        "json" => "json",
+        _ => "line",
+    };
+    let mut res = Ok(());
+    for (i, sudoku) in sudokus.into_iter().enumerate() {
+        let (line, mut sudoku) = match sudoku {
+            Ok(sudoku) => sudoku,
+            Err(failure) => {
+                report.parse_errors += 1;
+                res = Err(failure);
+                break;
+            }
+        };
+        let start = Instant::now();
+        let (solved, stats) = match cache.as_mut() {
+            Some(cache) => {
+                let mut stats = SolveStats::default();
+                let solved = cache.solve_with(&mut sudoku, |s| {
+                    let (solved, solve_stats) = solve_with_progress(s, progress);
+                    stats = solve_stats;
+                    solved
+                });
+                (solved, stats)
+            }
+            None => solve_with_progress(&mut sudoku, progress),
+        };
+        let elapsed = start.elapsed();
+        let id = sudoku
+            .metadata()
+            .and_then(|metadata| metadata.id.as_deref());
+        report.record(line, id, solved.is_ok(), elapsed, stats.backtracks);
+        if batch.time {
+            eprintln!("Puzzle {}: {}", i + 1, format_duration(elapsed));
+        }
+        let line = match solved {
+            Ok(_) if format == "json" => serde_json::to_string(&sudoku).unwrap(),
+            Ok(_) => render_with(&sudoku, format, false, &batch.output),
+            Err(err) => {
+                eprintln!("Cannot solve the sudoku on line {}: {}", line, err);
+                res = Err(Failure::Unsolvable);
+                match format {
+                    "json" => "null".to_string(),
+                    _ => "unsolvable".to_string(),
+                }
+            }
+        };
+        report.cache_hits = cache.as_ref().map(SolveCache::hits);
+        writeln!(out, "{}", line)
+            .map_err(|err| Failure::Io(format!("Cannot write solutions: {}", err)))?;
+    }
+    res
+}
+
+/// Counts of a batch run, printed to stderr once the batch is solved.
+///
+/// Reports of parts of a batch solved apart, e.g. by different threads, add up with `merge`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct BatchReport {
+    solved: usize,
+    unsolvable: usize,
+    /// Puzzles which cannot be loaded, the first one stops the batch.
+    parse_errors: usize,
+    total_time: Duration,
+    /// Line of the puzzle which took the longest to solve in the input, with its solve time.
+    slowest: Option<(usize, Duration)>,
+    /// Id of the slowest puzzle in its metadata, see `formats::metadata`.
+    slowest_id: Option<String>,
+    backtracks: u64,
+    /// Puzzles answered by the `SolveCache`, None without a cache.
+    cache_hits: Option<usize>,
+}
+
+impl BatchReport {
+    /// Counts the puzzle at the line of the input, with the id of its metadata, solved or not, which took `elapsed`
+    /// and `backtracks` steps back.
+    fn record(
+        &mut self,
+        line: usize,
+        id: Option<&str>,
+        solved: bool,
+        elapsed: Duration,
+        backtracks: u64,
+    ) {
+        self.merge(&BatchReport {
+            solved: solved as usize,
+            unsolvable: !solved as usize,
+            total_time: elapsed,
+            slowest: Some((line, elapsed)),
+            slowest_id: id.map(str::to_string),
+            backtracks,
+            ..BatchReport::default()
+        });
+    }
+
+    /// Adds the counts of the other part of the batch, the slowest puzzle of the earlier part wins a tie.
+    fn merge(&mut self, other: &BatchReport) {
+        self.solved += other.solved;
+        self.unsolvable += other.unsolvable;
+        self.parse_errors += other.parse_errors;
+        self.total_time += other.total_time;
+        self.backtracks += other.backtracks;
+        if let Some(hits) = other.cache_hits {
+            self.cache_hits = Some(self.cache_hits.unwrap_or(0) + hits);
+        }
+        if let Some((line, elapsed)) = other.slowest {
+            if self.slowest.map_or(true, |(_, slowest)| elapsed > slowest) {
+                self.slowest = Some((line, elapsed));
+                self.slowest_id = other.slowest_id.clone();
+            }
+        }
+    }
+
+    /// Returns the number of puzzles of the batch, loaded or not.
+    fn puzzles(&self) -> usize {
+        self.solved + self.unsolvable + self.parse_errors
+    }
+
+    /// Returns the lines of the summary, the average time is taken over the puzzles which were loaded.
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Puzzles: {}, solved: {}, unsolvable: {}, parse errors: {}",
+            self.puzzles(),
+            self.solved,
+            self.unsolvable,
+            self.parse_errors
+        )];
+        let loaded = (self.solved + self.unsolvable) as u32;
+        if let Some((line, slowest)) = self.slowest {
+            lines.push(format!(
+                "Solve time: {} in total, {} on average, {} for the slowest puzzle on line {}{}",
+                format_duration(self.total_time),
+                format_duration(self.total_time / loaded),
+                format_duration(slowest),
+                line,
+                self.slowest_id
+                    .as_ref()
+                    .map_or_else(String::new, |id| format!(", id {}", id))
+            ));
+        }
+        lines.push(format!("Backtracks: {}", self.backtracks));
+        if let Some(hits) = self.cache_hits {
+            lines.push(format!("Cache hits: {}", hits));
+        }
+        lines
+    }
+}
+
+/// Summary of a batch in the JSON output format, see `BatchReport`.
+#[derive(Debug, Serialize)]
+struct JsonBatchReport {
+    puzzles: usize,
+    solved: usize,
+    unsolvable: usize,
+    parse_errors: usize,
+    total_ms: f64,
+    /// Average solve time of the puzzles which were loaded, none without them.
+    average_ms: Option<f64>,
+    /// Line of the slowest puzzle in the input.
+    slowest_line: Option<usize>,
+    slowest_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slowest_id: Option<String>,
+    backtracks: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_hits: Option<usize>,
+}
+
+impl JsonBatchReport {
+    fn of(report: &BatchReport) -> JsonBatchReport {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let loaded = report.solved + report.unsolvable;
+        JsonBatchReport {
+            puzzles: report.puzzles(),
+            solved: report.solved,
+            unsolvable: report.unsolvable,
+            parse_errors: report.parse_errors,
+            total_ms: ms(report.total_time),
+            average_ms: (loaded > 0).then(|| ms(report.total_time) / loaded as f64),
+            slowest_line: report.slowest.map(|(line, _)| line),
+            slowest_ms: report.slowest.map(|(_, slowest)| ms(slowest)),
+            slowest_id: report.slowest_id.clone(),
+            backtracks: report.backtracks,
+            cache_hits: report.cache_hits,
+        }
+    }
+}
+
+/// Number of solutions after which `--count-solutions` stops counting unless `--max` is given.
+const DEFAULT_MAX_SOLUTIONS: usize = 1000;
+
+/// Writes the number of solutions, up to `max`, of every sudoku on its own line.
+///
+/// Fails unless every sudoku has exactly one solution. A count stopped at the node limit is written as `unknown`
+/// with the solutions found until then, e.g. `unknown, the search stopped at its limit with 1 solution found, pass
+/// --no-limit to search on`.
+fn count_solutions(sudokus: &[Sudoku], max: usize, out: &mut impl Write) -> Result<(), Failure> {
+    let mut res = Ok(());
+    for sudoku in sudokus {
+        let (count, unique) = match sudoku.count_solutions(max) {
+            Ok(count) => (count.to_string(), count == 1),
+            Err(err) => (
+                format!("unknown, {}, pass --no-limit to search on", err),
+                false,
+            ),
+        };
+        if !unique {
+            res = Err(Failure::Unsolvable);
+        }
+        writeln!(out, "{}", count)
+            .map_err(|err| Failure::Io(format!("Cannot write solution counts: {}", err)))?;
+    }
+    res
+}
+
+/// Returns the format of the input file: the requested one, or the one matching the file extension for `auto`.
+fn input_format<'a>(path: &str, requested: &'a str) -> &'a str {
+    match requested {
+        "auto" if path.ends_with(".sdm") => "sdm",
+        "auto" if path.ends_with(".ss") => "ss",
+        "auto" if path.ends_with(".cells") => "cells",
+        "auto" => "line",
+        requested => requested,
+    }
+}
+
+/// Writes the puzzles of a batch without duplicates to stdout or to the `--output` file.
+fn run_dedupe(matches: &ArgMatches) -> Result<(), Failure> {
+    let path = input_path(matches)?;
+    let data = read_task(matches, path)?;
+    match matches.value_of("output").map(open_output).transpose()? {
+        Some(mut file) => dedupe(&data, path, &mut file),
+        None => dedupe(&data, path, &mut io::stdout()),
+    }
+}
+
+/// Writes every puzzle (one per line) of the data unless it is equivalent to one written before.
+fn dedupe(data: &str, path: &str, out: &mut impl Write) -> Result<(), Failure> {
+    let mut unique = Unique::default();
+    let mut dropped = 0;
+    for (line, sudoku) in formats::parse_sudoku_lines(data) {
+        let sudoku = sudoku.map_err(|err| load_failure(path, err))?;
+        match unique.is_new(line, &sudoku) {
+            true => writeln!(out, "{}", sudoku.to_line())
+                .map_err(|err| Failure::Io(format!("Cannot write puzzles: {}", err)))?,
+            false => dropped += 1,
+        }
+    }
+    info!("Dropped {} duplicate puzzles", dropped);
+    Ok(())
+}
+
+/// Puzzles seen so far in a batch, to skip the ones equivalent to an earlier puzzle, see `Sudoku::canonical_form`.
+///
+/// Only the keys are kept, with the line of the first puzzle of each. The canonical form relabels digits and swaps
+/// bands and stacks, which most variants do not allow, so puzzles of a variant are only skipped when they are exact
+/// copies.
+#[derive(Default)]
+struct Unique {
+    seen: HashMap<PuzzleKey, usize>,
+    exact: bool,
+}
+
+/// Key of a puzzle in `Unique`.
+#[derive(PartialEq, Eq, Hash)]
+enum PuzzleKey {
+    Canonical(CanonicalKey),
+    Exact(String),
+}
+
+impl Unique {
+    /// Returns the puzzles seen of a batch of the variant.
+    fn for_variant(variant: &Variant) -> Unique {
+        Unique {
+            seen: HashMap::new(),
+            exact: !variant.is_classic(),
+        }
+    }
+
+    /// Returns `true` unless the puzzle on the line is equivalent to one seen before, skipped lines are logged.
+    fn is_new(&mut self, line: usize, sudoku: &Sudoku) -> bool {
+        let key = match self.exact {
+            true => PuzzleKey::Exact(sudoku.to_line()),
+            false => PuzzleKey::Canonical(sudoku.canonical_form()),
+        };
+        match self.seen.entry(key) {
+            Entry::Occupied(first) => {
+                info!(
+                    "Skipping line {}, the puzzle of line {} again",
+                    line,
+                    first.get()
+                );
+                false
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(line);
+                true
+            }
+        }
+    }
+}
+
+/// Writes the puzzles of a file with one puzzle per line which match the predicates of the command line, then prints
+/// how many matched.
+///
+/// The input is read a line at a time, so collections of any size can be filtered.
+fn run_filter(matches: &ArgMatches) -> Result<(), Failure> {
+    let path = input_path(matches)?;
+    let variant = selected_variant(matches)?;
+    let filter = puzzle_filter(matches);
+    let limit = node_limit(matches);
+    let reader: Box<dyn BufRead> = match inline_task(matches, path) {
+        Some(task) => Box::new(io::Cursor::new(task)),
+        None => open_input(path)?,
+    };
+    let output = matches.value_of("output");
+    let sdm = output.is_some_and(|path| path.ends_with(".sdm"));
+    let report = match output.map(open_output).transpose()? {
+        Some(mut file) => filter_puzzles(reader, path, &variant, limit, &filter, sdm, &mut file)?,
+        None => filter_puzzles(
+            reader,
+            path,
+            &variant,
+            limit,
+            &filter,
+            sdm,
+            &mut io::stdout(),
+        )?,
+    };
+    eprintln!("{} of {} puzzles match", report.lines.len(), report.total);
+    Ok(())
+}
+
+/// Solves every puzzle of a file with one puzzle per line with the techniques first and prints how often each of them
+/// fired, how many puzzles needed guessing and percentiles of the placements, backtracks and solve times.
+fn run_stats(matches: &ArgMatches) -> Result<(), Failure> {
+    let path = input_path(matches)?;
+    let variant = selected_variant(matches)?;
+    let reader: Box<dyn BufRead> = match inline_task(matches, path) {
+        Some(task) => Box::new(io::Cursor::new(task)),
+        None => open_input(path)?,
+    };
+    let stats = corpus_stats(reader, &variant, node_limit(matches))?;
+    let lines = match matches.is_present("csv") {
+        true => stats_csv(&stats),
+        false => stats_table(&stats),
+    };
+    let write = |out: &mut dyn Write| lines.iter().try_for_each(|line| writeln!(out, "{}", line));
+    match matches.value_of("output").map(open_output).transpose()? {
+        Some(mut file) => write(&mut file),
+        None => write(&mut io::stdout()),
+    }
+    .map_err(|err| Failure::Io(format!("Cannot write statistics: {}", err)))
+}
+
+/// Checks that the solvers agree on random puzzles, prints the puzzles they disagree on and fails like
+/// `--check-solvers` if there are any.
+///
+/// Without `--seed` a seed is drawn and printed to stderr, so a failing run can be repeated.
+fn run_stress(matches: &ArgMatches) -> Result<(), Failure> {
+    let iterations = matches.value_of("iterations").unwrap().parse().unwrap();
+    let seed = matches.value_of("seed").map(|seed| seed.parse().unwrap());
+    let (_, seed) = generator::seeded_rng(seed);
+    if !matches.is_present("seed") {
+        eprintln!("Seed: {}", seed);
+    }
+    let report = stress::stress(iterations, seed);
+    for line in stress_report(&report) {
+        println!("{}", line);
+    }
+    match report.is_ok() {
+        true => Ok(()),
+        false => Err(Failure::Differs),
+    }
+}
+
+/// Returns the puzzles of the stress test the solvers disagree on, then the counts of the puzzles.
+fn stress_report(report: &StressReport) -> Vec<String> {
+    let mut lines: Vec<String> = report
+        .inconsistencies
+        .iter()
+        .map(|i| i.to_string())
+        .collect();
+    lines.push(format!(
+        "{} puzzles, {} with a unique solution, {} skipped after {} placements, {} inconsistencies",
+        report.iterations,
+        report.unique,
+        report.skipped,
+        stress::STRESS_NODES,
+        report.inconsistencies.len()
+    ));
+    lines
+}
+
+/// Writes the puzzles of the input, a single one or a batch, in the format of `--format` without solving them.
+fn run_convert(matches: &ArgMatches) -> Result<(), Failure> {
+    let path = input_path(matches)?;
+    let data = read_task(matches, path)?;
+    let input = input_format(path, matches.value_of("input_format").unwrap());
+    let box_shape = box_shape(matches);
+    let variant = selected_variant(matches)?;
+    let sudokus =
+        parse_puzzles(&data, input, box_shape, &variant).map_err(|err| load_failure(path, err))?;
+    let converted = convert(&sudokus, matches.value_of("format").unwrap())?;
+    match matches.value_of("output").map(open_output).transpose()? {
+        Some(mut file) => write!(file, "{}", converted),
+        None => write!(io::stdout(), "{}", converted),
+    }
+    .map_err(|err| Failure::Io(format!("Cannot write puzzles: {}", err)))
+}
+
+/// Returns the puzzles written in the format, a line per puzzle for `line` and `sdm`, and the grid for `ss` and
+/// `pretty`, which hold a single puzzle.
+///
+/// The `line` and `pretty` formats keep the metadata of the puzzles as comment lines above them. The `sdm` and `ss`
+/// formats only hold 9x9 grids.
+fn convert(sudokus: &[Sudoku], format: &str) -> Result<String, Failure> {
+    if sudokus.len() > 1 && (format == "ss" || format == "pretty") {
+        return Err(Failure::InvalidInput(format!(
+            "Cannot convert {} puzzles to {}: the format holds a single puzzle",
+            sudokus.len(),
+            format
+        )));
+    }
+    let mut lines = Vec::new();
+    for (i, s) in sudokus.iter().enumerate() {
+        if s.geometry() != Geometry::CLASSIC && (format == "sdm" || format == "ss") {
+            return Err(Failure::InvalidInput(format!(
+                "Cannot convert puzzle {} to {}: the format holds 9x9 grids only, the puzzle is {}x{}",
+                i + 1,
+                format,
+                s.side(),
+                s.side()
+            )));
+        }
+        if format == "line" || format == "pretty" {
+            lines.extend(s.metadata().map(Metadata::to_comments).unwrap_or_default());
+        }
+        lines.push(render(s, format, false));
+    }
+    Ok(lines
+        .iter()
+        .map(|line| format!("{}\n", line.trim_end()))
+        .collect())
+}
+
+/// Solves every puzzle (one per line) of the input which follows the variant with `Sudoku::solve_with_techniques`,
+/// the search stopping at `limit`.
+///
+/// Puzzles which cannot be read are counted and skipped, only a failure to read the input stops the count.
+fn corpus_stats(
+    reader: impl BufRead,
+    variant: &Variant,
+    limit: NodeLimit,
+) -> Result<CorpusStats, Failure> {
+    let mut stats = CorpusStats::default();
+    for res in formats::stream_puzzles(reader) {
+        let mut sudoku = match res.and_then(|(_, sudoku)| variant.apply(sudoku)) {
+            Ok(sudoku) => sudoku.with_node_limit(limit),
+            Err(SudokuError::Io(err)) => {
+                return Err(Failure::Io(format!("Cannot read puzzles: {}", err)))
+            }
+            Err(err) => {
+                warn!("Skipping a puzzle: {}", err);
+                stats.parse_errors += 1;
+                continue;
+            }
+        };
+        let start = Instant::now();
+        let solve = sudoku.solve_with_techniques();
+        stats.record(&solve, start.elapsed());
+    }
+    Ok(stats)
+}
+
+/// Percentiles of the distributions printed by `stats`.
+const STATS_PERCENTILES: [u8; 3] = [50, 90, 99];
+
+/// Returns the `STATS_PERCENTILES` of the values formatted with `format`, `-` without values.
+fn percentiles<T: Copy + Ord>(values: &[T], format: impl Fn(T) -> String) -> Vec<String> {
+    STATS_PERCENTILES
+        .iter()
+        .map(|&percent| {
+            corpus::percentile(values, percent).map_or_else(|| "-".to_string(), &format)
+        })
+        .collect()
+}
+
+/// Returns the lines of the table printed by `stats`.
+fn stats_table(stats: &CorpusStats) -> Vec<String> {
+    let read = stats.solved + stats.unsolvable;
+    let mut lines = vec![
+        format!(
+            "Puzzles: {}, solved: {}, unsolvable: {}, parse errors: {}",
+            stats.puzzles(),
+            stats.solved,
+            stats.unsolvable,
+            stats.parse_errors
+        ),
+        format!(
+            "Needed guessing: {} of {} puzzles ({:.1}%)",
+            stats.guessed,
+            read,
+            stats.guessed as f64 * 100.0 / read.max(1) as f64
+        ),
+        String::new(),
+        format!("{:<16}{:>12}", "Technique", "Deductions"),
+    ];
+    lines.extend(Technique::ALL.iter().map(|&technique| {
+        format!(
+            "{:<16}{:>12}",
+            technique.to_string(),
+            stats.deductions_of(technique)
+        )
+    }));
+    lines.push(String::new());
+    lines.push(format!("{:<16}{:>12}{:>12}{:>12}", "", "p50", "p90", "p99"));
+    let rows = [
+        (
+            "Placements",
+            percentiles(&stats.placements, |n| n.to_string()),
+        ),
+        (
+            "Backtracks",
+            percentiles(&stats.backtracks, |n| n.to_string()),
+        ),
+        ("Time", percentiles(&stats.times, format_duration)),
+    ];
+    lines.extend(rows.iter().map(|(name, values)| {
+        format!(
+            "{:<16}{:>12}{:>12}{:>12}",
+            name, values[0], values[1], values[2]
+        )
+    }));
+    lines
+}
+
+/// Returns the lines of the CSV written by `stats --csv`, one `metric,value` row per number of the table.
+fn stats_csv(stats: &CorpusStats) -> Vec<String> {
+    let mut lines = vec![
+        "metric,value".to_string(),
+        format!("puzzles,{}", stats.puzzles()),
+        format!("solved,{}", stats.solved),
+        format!("unsolvable,{}", stats.unsolvable),
+        format!("parse_errors,{}", stats.parse_errors),
+        format!("guessed,{}", stats.guessed),
+    ];
+    lines.extend(Technique::ALL.iter().map(|&technique| {
+        format!(
+            "{},{}",
+            technique.to_string().replace(' ', "_"),
+            stats.deductions_of(technique)
+        )
+    }));
+    let ms = |time: Duration| format!("{:.3}", time.as_secs_f64() * 1000.0);
+    let rows = [
+        (
+            "placements",
+            percentiles(&stats.placements, |n| n.to_string()),
+        ),
+        (
+            "backtracks",
+            percentiles(&stats.backtracks, |n| n.to_string()),
+        ),
+        ("time_ms", percentiles(&stats.times, ms)),
+    ];
+    for (name, values) in rows.iter() {
+        for (percent, value) in STATS_PERCENTILES.iter().zip(values.iter()) {
+            lines.push(format!("{}_p{},{}", name, percent, value));
+        }
+    }
+    lines
+}
+
+/// Returns the filter with the predicates given to the `filter` subcommand.
+fn puzzle_filter(matches: &ArgMatches) -> PuzzleFilter {
+    let number = |arg| matches.value_of(arg).map(|n: &str| n.parse().unwrap());
+    PuzzleFilter {
+        min_clues: number("min_clues"),
+        max_clues: number("max_clues"),
+        valid_only: matches.is_present("valid_only"),
+        unique_only: matches.is_present("unique_only"),
+        difficulty: matches
+            .value_of("difficulty")
+            .and_then(Difficulty::from_name),
+    }
+}
+
+/// Puzzles selected by `filter_puzzles`.
+#[derive(Debug, PartialEq, Eq)]
+struct FilterReport {
+    /// Lines of the matching puzzles in the input.
+    lines: Vec<usize>,
+    /// Number of puzzles read.
+    total: usize,
+}
+
+/// Writes every puzzle (one per line) of the input which follows the variant and matches the filter, in the `.sdm`
+/// layout with `sdm` and as a line otherwise. The searches of the filter stop at `limit`.
+///
+/// Stops at the first puzzle which cannot be read.
+fn filter_puzzles(
+    reader: impl BufRead,
+    path: &str,
+    variant: &Variant,
+    limit: NodeLimit,
+    filter: &PuzzleFilter,
+    sdm: bool,
+    out: &mut impl Write,
+) -> Result<FilterReport, Failure> {
+    let write_failure = |err: io::Error| Failure::Io(format!("Cannot write puzzles: {}", err));
+    let mut report = FilterReport {
+        lines: Vec::new(),
+        total: 0,
+    };
+    for res in formats::stream_puzzles(reader) {
+        let (line, sudoku) = res
+            .and_then(|(line, sudoku)| Ok((line, variant.apply(sudoku)?.with_node_limit(limit))))
+            .map_err(|err| load_failure(path, err))?;
+        report.total += 1;
+        if !filter.matches(&sudoku) {
+            continue;
+        }
+        match sdm {
+            true => sdm::write_sdm(out, Some(&sudoku)),
+            false => writeln!(out, "{}", sudoku.to_line()),
+        }
+        .map_err(write_failure)?;
+        report.lines.push(line);
+    }
+    Ok(report)
+}
+
+/// Names of the subcommands, see `app`.
+const SUBCOMMANDS: [&str; 13] = [
+    "solve",
+    "verify",
+    "generate",
+    "rate",
+    "hint",
+    "interactive",
+    "compare",
+    "dedupe",
+    "filter",
+    "convert",
+    "stats",
+    "stress",
+    "help",
+];
+
+/// Inserts the `solve` subcommand into the command line when no subcommand is given.
+///
+/// Keeps `sudoku -s PATH` working as it did before the subcommands were introduced.
+fn with_default_subcommand(mut args: Vec<String>) -> Vec<String> {
+    let explicit = args.get(1).is_some_and(|arg| {
+        SUBCOMMANDS.contains(&arg.as_str())
+            || ["-h", "--help", "-V", "--version"].contains(&arg.as_str())
+    });
+    if !explicit {
+        args.insert(1.min(args.len()), "solve".to_string());
+    }
+    args
+}
+
+/// Arguments of subcommands which load a single task.
+fn input_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("sudoku_path")
+            .short("s")
+            .long("--sudoku-path")
+            .takes_value(true)
+            .help("File with the task. Use `-` or omit the option to read the task from stdin"),
+        Arg::with_name("grid")
+            .long("grid")
+            .takes_value(true)
+            .multiple(true)
+            .conflicts_with("sudoku_path")
+            .help(
+                "Task given inline as a line of cells, `.` or `0` for empty ones, e.g. `53..7....6..195...`, or as a \
+                 list of clues like `r1c3=4,r2c7=9`",
+            ),
+        Arg::with_name("demo")
+            .long("demo")
+            .takes_value(true)
+            .min_values(0)
+            .possible_values(&["easy", "hard", "unsolvable"])
+            .conflicts_with_all(&["sudoku_path", "grid"])
+            .value_name("NAME")
+            .help("Task is an example puzzle embedded in the application, `easy` unless another one is named"),
+        Arg::with_name("input_format")
+            .long("input-format")
+            .takes_value(true)
+            .possible_values(&["auto", "line", "sdm", "ss", "cells"])
+            .default_value("auto")
+            .help(
+                "Format of the task file, `auto` detects it from the file extension and contents",
+            ),
+        size_arg(),
+        box_size_arg(),
+        variant_arg(),
+        Arg::with_name("regions")
+            .long("regions")
+            .takes_value(true)
+            .help("File with a jigsaw layout: a region label 1-9 then A-G (or letters from A alone) for every cell"),
+        Arg::with_name("cages")
+            .long("cages")
+            .takes_value(true)
+            .help("File with killer cages, a cage per line: its sum and cells like `15 r1c1 r1c2`"),
+        Arg::with_name("thermometers")
+            .long("thermometers")
+            .takes_value(true)
+            .help("File with thermometers, one per line: its cells from the bulb like `r1c1 r2c2 r3c3`"),
+        Arg::with_name("parity")
+            .long("parity")
+            .takes_value(true)
+            .help("File with even/odd marks: `e`, `o` or `.` for every cell, marks in the task replace them"),
+    ]
+}
+
+fn size_arg() -> Arg<'static, 'static> {
+    Arg::with_name("size")
+        .long("size")
+        .takes_value(true)
+        .possible_values(&["4", "9", "16"])
+        .help("Number of rows of the grid, 9x9 and 16x16 grids are detected when omitted")
+}
+
+fn box_size_arg() -> Arg<'static, 'static> {
+    Arg::with_name("box_size")
+        .long("box-size")
+        .takes_value(true)
+        .conflicts_with("size")
+        .validator(|shape| parse_box_shape(&shape).map(|_| ()))
+        .help("Rows and columns of a box like 2x3 for grids with rectangular boxes")
+}
+
+fn variant_arg() -> Arg<'static, 'static> {
+    Arg::with_name("variant")
+        .long("variant")
+        .takes_value(true)
+        .possible_values(&["classic", "x", "hyper", "anti-knight", "non-consecutive"])
+        .default_value("classic")
+        .help(
+            "Rules of the puzzle, `x` also requires both main diagonals and `hyper` (Windoku) four extra 3x3 \
+             windows to hold every value once, `anti-knight` forbids equal values a knight's move apart \
+             and `non-consecutive` consecutive values in neighbouring cells",
+        )
+}
+
+fn cell_arg(help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name("cell")
+        .long("cell")
+        .takes_value(true)
+        .value_name("CELL")
+        .help(help)
+}
+
+fn format_arg(formats: &'static [&'static str]) -> Arg<'static, 'static> {
+    Arg::with_name("format")
+        .long("format")
+        .takes_value(true)
+        .possible_values(formats)
+        .default_value("pretty")
+        .help("Format used to print sudokus")
+}
+
+fn empty_char_arg() -> Arg<'static, 'static> {
+    Arg::with_name("empty_char")
+        .long("empty-char")
+        .takes_value(true)
+        .validator(validate_empty_char)
+        .help("Character of empty cells in the line, pretty, sdm and ss formats, e.g. `0`, `.` or ` `")
+}
+
+fn no_limit_arg() -> Arg<'static, 'static> {
+    Arg::with_name("no_limit").long("no-limit").help(
+        "Search grids larger than 9x9 until they are solved, however long it takes, instead of stopping after 1000 \
+         placements per cell",
+    )
+}
+
+fn seed_arg(help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name("seed")
+        .long("seed")
+        .takes_value(true)
+        .validator(|seed| {
+            seed.parse::<u64>()
+                .map(|_| ())
+                .map_err(|_| format!("expected a number, got `{}`", seed))
+        })
+        .help(help)
+}
+
+fn no_color_arg() -> Arg<'static, 'static> {
+    Arg::with_name("no_color")
+        .long("no-color")
+        .help("Do not highlight givens and solved cells in the output")
+}
+
+fn app() -> App<'static, 'static> {
+    App::new("Sudoku solver")
+        .version("0.1.0")
+        .author("Yuriy Senko <yura.senko@gmail.com>")
+        .after_help(EXIT_CODES_HELP)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("solve")
+                .about("Solves the task, `solve` is assumed when no subcommand is given")
+                .args(&input_args())
+                .arg(format_arg(&[
+                    "pretty", "line", "unicode", "json", "sdm", "ss", "svg", "html", "latex", "marks",
+                    "labeled",
+                ]))
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help("Write only the solution to the file instead of stdout"),
+                )
+                .arg(
+                    Arg::with_name("resume")
+                        .long("resume")
+                        .requires("output")
+                        .help(
+                            "Continue an interrupted batch: keep the solutions in the --output file and skip as many \
+                             puzzles as it has complete lines",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("pretty")
+                        .long("pretty")
+                        .help("Indent the JSON output"),
+                )
+                .arg(empty_char_arg())
+                .arg(Arg::with_name("classify").long("classify").help(
+                    "Add to the JSON output whether the puzzle has a unique solution, none or several, which takes \
+                     another search",
+                ))
+                .arg(
+                    Arg::with_name("mark_filled")
+                        .long("mark-filled")
+                        .help(
+                            "Put the cells filled by the solver in brackets like `[4]` in the pretty grid, for \
+                             terminals without color",
+                        ),
+                )
+                .arg(no_color_arg())
+                .arg(Arg::with_name("quiet").short("q").long("quiet").help(
+                    "Print nothing but the solution, as a single line unless --format is given. \
+                             Errors are printed to stderr",
+                ))
+                .arg(
+                    Arg::with_name("progress")
+                        .long("progress")
+                        .help("Print the progress of long solves to stderr every second"),
+                )
+                .arg(
+                    Arg::with_name("animate")
+                        .long("animate")
+                        .takes_value(true)
+                        .min_values(0)
+                        .max_values(1)
+                        .value_name("MS")
+                        .conflicts_with_all(&["count_solutions", "dedupe", "progress", "quiet"])
+                        .validator(|ms| match ms.parse::<u64>() {
+                            Ok(_) => Ok(()),
+                            _ => Err("must be a number of milliseconds".to_string()),
+                        })
+                        .help(
+                            "Show the grid after every step of the search, waiting MS milliseconds \
+                             [default: 50] between steps. Steps replace each other in a terminal",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("checkpoint")
+                        .long("checkpoint")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .conflicts_with_all(&["animate", "count_solutions", "dedupe", "progress"])
+                        .help(
+                            "Save the search to the file when Ctrl-C stops it, \
+                             and continue the search saved there if the file exists",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("partial")
+                        .long("partial")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .conflicts_with_all(&[
+                            "animate",
+                            "checkpoint",
+                            "check_solvers",
+                            "count_solutions",
+                            "dedupe",
+                            "explain",
+                            "progress",
+                            "resume",
+                        ])
+                        .help(
+                            "Finish the partial solution in the file, which keeps the clues of the task and adds \
+                             your entries, and name the earliest wrong entry when they leave no solution",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("value_order")
+                        .long("value-order")
+                        .takes_value(true)
+                        .possible_values(&["ascending", "least-constraining", "random"])
+                        .default_value("ascending")
+                        .help(
+                            "Order in which the search tries the values of a cell, `least-constraining` tries the \
+                             value taking the fewest options from other cells first",
+                        ),
+                )
+                .arg(seed_arg("Seed of the random value order, the same seed repeats the search"))
+                .arg(no_limit_arg())
+                .arg(
+                    Arg::with_name("check_solvers")
+                        .long("check-solvers")
+                        .help("Check that every solver implementation finds the same solution"),
+                )
+                .arg(
+                    Arg::with_name("explain")
+                        .long("explain")
+                        .help("Print the numbered steps of solving without guessing and the reason for each"),
+                )
+                .arg(
+                    Arg::with_name("time").long("time").help(
+                        "Print the time spent solving to stderr, the JSON output always has it",
+                    ),
+                )
+                .arg(Arg::with_name("no_summary").long("no-summary").help(
+                    "Don't print the counts of solved and unsolvable puzzles, the solve time and the backtracks \
+                     of a batch to stderr, nor the summary line of the JSON output",
+                ))
+                .arg(
+                    Arg::with_name("count_solutions")
+                        .long("count-solutions")
+                        .help("Print the number of solutions instead of a solution"),
+                )
+                .arg(
+                    Arg::with_name("max")
+                        .long("max")
+                        .takes_value(true)
+                        .requires("count_solutions")
+                        .validator(|max| match max.parse::<usize>() {
+                            Ok(max) if max > 0 => Ok(()),
+                            _ => Err("must be a positive number".to_string()),
+                        })
+                        .help("Stop counting solutions once this many are found [default: 1000]"),
+                )
+                .arg(Arg::with_name("dedupe").long("dedupe").help(
+                    "Skip puzzles of a batch which are the same as an earlier one up to symmetry and relabeling",
+                ))
+                .arg(
+                    Arg::with_name("cache")
+                        .long("cache")
+                        .takes_value(true)
+                        .value_name("CAPACITY")
+                        .validator(|capacity| match capacity.parse::<usize>() {
+                            Ok(capacity) if capacity > 0 => Ok(()),
+                            _ => Err("must be a positive number".to_string()),
+                        })
+                        .help(
+                            "Answer puzzles repeated in a batch from the solutions of the last CAPACITY \
+                             puzzles, the hits are counted in the summary",
+                        ),
+                )
+                .after_help("The file may contain one task per line to solve a batch."),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about(
+                    "Checks that a complete or partially filled grid follows the rules, \
+                     the solution is never printed",
+                )
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("against")
+                        .long("against")
+                        .takes_value(true)
+                        .help("File with the original puzzle, its clues must not be changed"),
+                )
+                .arg(
+                    Arg::with_name("check_unique")
+                        .long("check-unique")
+                        .help("Also check that the puzzle has exactly one solution"),
+                )
+                .arg(
+                    Arg::with_name("suggest_fix")
+                        .long("suggest-fix")
+                        .help(
+                            "Suggest a clue to remove when the puzzle has no solution, implies \
+                             --check-unique",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("show_ambiguity")
+                        .long("show-ambiguity")
+                        .help(
+                            "Print two solutions of an ambiguous puzzle and the cells where they differ, \
+                             implies --check-unique",
+                        ),
+                )
+                .arg(cell_arg(
+                    "Also check the value of the cell, named like E5, against the solution of the --against \
+                     puzzle",
+                ))
+                .arg(no_limit_arg())
+                .after_help("The file may contain one task per line to check a batch."),
+        )
+        .subcommand(
+            SubCommand::with_name("generate")
+                .about("Prints a new random puzzle with a unique solution")
+                .arg(format_arg(&[
+                    "pretty", "line", "unicode", "sdm", "ss", "svg", "html", "latex", "marks",
+                    "labeled", "cells",
+                ]))
+                .arg(empty_char_arg())
+                .arg(no_color_arg())
+                .arg(size_arg())
+                .arg(box_size_arg())
+                .arg(seed_arg("Seed of the random numbers, the same seed generates the same puzzle"))
+                .arg(no_limit_arg())
+                .arg(
+                    Arg::with_name("daily")
+                        .long("daily")
+                        .takes_value(true)
+                        .value_name("DATE")
+                        .validator(validate_date)
+                        .conflicts_with_all(&["seed", "size", "box_size"])
+                        .help("Generate the 9x9 puzzle of the day, the same for the same date like 2024-06-01"),
+                )
+                .arg(
+                    Arg::with_name("difficulty")
+                        .long("difficulty")
+                        .takes_value(true)
+                        .possible_values(&["easy", "medium", "hard"])
+                        .requires("daily")
+                        .help("Difficulty of `rate` the puzzle of the day has, medium by default"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("rate")
+                .about("Rates how hard the task is to solve without guessing")
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("batch")
+                        .long("batch")
+                        .help("Grade a file with one puzzle per line and print the grades as CSV"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .requires("batch")
+                        .help("Write the CSV grades to the file instead of stdout"),
+                )
+                .arg(no_limit_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("hint")
+                .about("Prints the value of one empty cell, found without guessing when possible")
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("apply")
+                        .long("apply")
+                        .help("Also print the task with the hinted cell filled as a single line"),
+                )
+                .arg(cell_arg("Hint the value of this cell, named like E5, rather than of any empty cell"))
+                .arg(
+                    Arg::with_name("max_technique")
+                        .long("max-technique")
+                        .takes_value(true)
+                        .possible_values(&Technique::ALL.map(Technique::name))
+                        .help(
+                            "Hint only with the techniques up to this one, from the easiest, rather than taking the \
+                             value from the solution when they do not apply",
+                        ),
+                )
+                .arg(no_limit_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("interactive")
+                .about("Edits the task, or an empty grid, with commands typed into the terminal")
+                .args(&input_args())
+                .arg(no_limit_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("compare")
+                .about("Prints the cells which differ in two grids, e.g. a solution and your own")
+                .arg(
+                    Arg::with_name("first")
+                        .required(true)
+                        .help("File with the first grid"),
+                )
+                .arg(
+                    Arg::with_name("second")
+                        .required(true)
+                        .help("File with the second grid"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dedupe")
+                .about(
+                    "Prints the puzzles of a file with one puzzle per line, leaving out the ones which are the \
+                     same as an earlier one up to symmetry and relabeling",
+                )
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help("Write the puzzles to the file instead of stdout"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("filter")
+                .about(
+                    "Prints the puzzles of a file with one puzzle per line which match all the given predicates, \
+                     and how many matched",
+                )
+                .args(&input_args())
+                .arg(clue_count_arg("min_clues", "min-clues", "Only puzzles with at least N clues"))
+                .arg(clue_count_arg("max_clues", "max-clues", "Only puzzles with at most N clues"))
+                .arg(
+                    Arg::with_name("difficulty")
+                        .long("difficulty")
+                        .takes_value(true)
+                        .possible_values(&["easy", "medium", "hard"])
+                        .help("Only puzzles of the difficulty of `rate`, needs a search for every puzzle"),
+                )
+                .arg(
+                    Arg::with_name("unique_only")
+                        .long("unique-only")
+                        .help("Only puzzles with a unique solution, needs a search for every puzzle"),
+                )
+                .arg(
+                    Arg::with_name("valid_only")
+                        .long("valid-only")
+                        .help("Only puzzles whose clues follow the rules, including the ones of the variant"),
+                )
+                .arg(no_limit_arg())
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help("Write the puzzles to the file instead of stdout, as an .sdm collection for .sdm files"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("convert")
+                .about("Writes the task, a single puzzle or a batch, in another format without solving it")
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .required(true)
+                        .possible_values(&["line", "sdm", "ss", "pretty"])
+                        .help(
+                            "Format to write, `ss` and `pretty` hold a single puzzle, `sdm` and `ss` 9x9 grids only; \
+                             `line` and `pretty` keep metadata comments like `# id: 42`",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help("Write the puzzles to the file instead of stdout"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about(
+                    "Solves the puzzles of a file with one puzzle per line with the techniques first and prints how \
+                     often each technique fired, how many puzzles needed guessing and percentiles of the placements, \
+                     backtracks and solve times",
+                )
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("csv")
+                        .long("csv")
+                        .help("Print the numbers as CSV rows of a metric and its value instead of a table"),
+                )
+                .arg(no_limit_arg())
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help("Write the statistics to the file instead of stdout"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("stress")
+                .about(
+                    "Checks that counting the solutions, solving and classifying agree on random puzzles with \
+                     random numbers of clues, and prints the puzzles they disagree on",
+                )
+                .arg(
+                    Arg::with_name("iterations")
+                        .long("iterations")
+                        .takes_value(true)
+                        .default_value("1000")
+                        .validator(|iterations| match iterations.parse::<u32>() {
+                            Ok(_) => Ok(()),
+                            _ => Err("must be a number".to_string()),
+                        })
+                        .help("Number of random puzzles to check"),
+                )
+                .arg(seed_arg("Seed of the random puzzles, the same seed checks the same puzzles")),
+        )
+}
+
+/// Returns the option `--long` of the `filter` subcommand taking a number of clues.
+fn clue_count_arg(
+    name: &'static str,
+    long: &'static str,
+    help: &'static str,
+) -> Arg<'static, 'static> {
+    Arg::with_name(name)
+        .long(long)
+        .takes_value(true)
+        .value_name("N")
+        .validator(|clues| {
+            clues
+                .parse::<usize>()
+                .map(|_| ())
+                .map_err(|_| format!("expected a number, got `{}`", clues))
+        })
+        .help(help)
+}
+
+#[test]
+fn no_color_when_piped() {
+    assert!(!use_color(false, false));
+    assert!(!use_color(true, true));
+    assert!(use_color(true, false));
+    let s: Sudoku = "1".repeat(81).parse().unwrap();
+    assert!(!render(&s, "pretty", use_color(false, false)).contains('\x1b'));
+}
+
+#[test]
+fn render_images() {
+    let s: Sudoku = "1".repeat(81).parse().unwrap();
+    let svg = render(&s, "svg", false);
+    assert!(svg.starts_with("<svg "));
+    assert_eq!(81, svg.matches("<text ").count());
+    let html = render(&s, "html", false);
+    assert!(html.starts_with("<table "));
+    assert_eq!(81, html.matches("<td ").count());
+    assert!(render(&s, "latex", false).contains("\\begin{tikzpicture}"));
+    assert!(render(&Sudoku::empty(), "marks", false).contains("┃ 1 2 3 │ 1 2 3 │"));
+}
+
+/// Puzzle where the last cell of the first row cannot be filled.
+#[cfg(test)]
+const UNSOLVABLE: &str = concat!(
+    "12345678.........9",
+    "...............................................................",
+);
+
+/// Writes `data` to a file in the temporary directory and returns the path to it.
+#[cfg(test)]
+fn write_temp_file(name: &str, data: &str) -> String {
+    let path = std::env::temp_dir().join(format!("sudoku_{}_{}", std::process::id(), name));
+    fs::write(&path, data).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+/// Loads the sudoku from the file like the command line application does.
+#[cfg(test)]
+fn load(path: &str) -> Result<Sudoku, Failure> {
+    let data = read_input(path, io::empty())?;
+    parse_sudoku(&data, "line", None, &Variant::default()).map_err(|err| load_failure(path, err))
+}
+
+/// Returns the JSON output of `solve --format json` with the arguments, and the failure defining the exit code.
+#[cfg(test)]
+fn solve_json_args(args: &[&str]) -> (serde_json::Value, Result<(), Failure>) {
+    let mut all = vec!["sudoku", "--format", "json"];
+    all.extend_from_slice(args);
+    let matches = parse_args(&all).unwrap();
+    let solve = matches.subcommand_matches("solve").unwrap();
+    let loaded = read_task_for_json(solve)
+        .and_then(|(path, data, variant)| load_for_json(solve, path, &data, &variant));
+    let (output, res) = solve_to_json(loaded, solve.is_present("classify"));
+    (serde_json::from_str(&to_json(&output, false)).unwrap(), res)
+}
+
+#[test]
+fn json_output() {
+    let (json, res) = solve_json_args(&["-s", "tasks/1.sudoku"]);
+    assert!(res.is_ok());
+    assert_eq!(json["solved"], true);
+    assert_eq!(json["puzzle"][0], 5);
+    assert_eq!(json["puzzle"][2], 0);
+    assert_eq!(json["summary"]["clues"], 30);
+    assert_eq!(json["summary"]["empty"], 51);
+    assert_eq!(json["summary"]["consistent"], true);
+    assert_eq!(json["symmetry"], serde_json::json!(["rotational"]));
+    assert!(json.get("classification").is_none());
+    assert_eq!(json["solution"][2], 4);
+    assert!(json["duration_ms"].is_number());
+    assert!(json["stats"]["placements"].as_u64().unwrap() >= 51);
+    assert_eq!(json["stats"]["depth"], 51);
+    assert!(json.get("error").is_none());
+    assert!(json.get("metadata").is_none());
+}
+
+#[test]
+fn metadata_in_json_output() {
+    let (json, res) = solve_json_args(&["-s", "tasks/nyt.sudoku"]);
+    assert!(res.is_ok());
+    assert_eq!(
+        serde_json::json!({"id": "42", "source": "nyt"}),
+        json["metadata"]
+    );
+    assert_eq!(json["solution"][2], 4);
+    // Solving keeps the metadata.
+    let mut s = load("tasks/nyt.sudoku").unwrap();
+    s.solve().unwrap();
+    assert_eq!(Some("42"), s.metadata().and_then(|m| m.id.as_deref()));
+}
+
+#[test]
+fn metadata_in_grades() {
+    let data = fs::read_to_string("tasks/catalog.txt").unwrap();
+    let mut out = Vec::new();
+    grade_batch(&data, &Variant::default(), NodeLimit::Default, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(format!("{},{}", GRADES_HEADER, METADATA_HEADER), lines[0]);
+    assert!(lines[1].ends_with(",,42,nyt,daily;easy"), "{}", lines[1]);
+    assert!(lines[2].ends_with(",,43,,"), "{}", lines[2]);
+    // Batches without metadata keep their columns.
+    let mut out = Vec::new();
+    grade_batch(
+        examples::EASY,
+        &Variant::default(),
+        NodeLimit::Default,
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        GRADES_HEADER,
+        String::from_utf8(out).unwrap().lines().next().unwrap()
+    );
+}
+
+#[test]
+fn technique_stats_of_corpus() {
+    let corpus = fs::read_to_string("tasks/corpus.txt").unwrap();
+    let stats = corpus_stats(corpus.as_bytes(), &Variant::default(), NodeLimit::Default).unwrap();
+    assert_eq!(
+        (4, 2, 1, 1, 2),
+        (
+            stats.puzzles(),
+            stats.solved,
+            stats.unsolvable,
+            stats.parse_errors,
+            stats.guessed
+        )
+    );
+    assert!(stats.deductions_of(Technique::NakedSingle) >= 51);
+    // The easy example is solved by 51 naked singles, without a single guess.
+    let easy = format!("{}\n{}\n", examples::EASY, examples::EASY);
+    let stats = corpus_stats(easy.as_bytes(), &Variant::default(), NodeLimit::Default).unwrap();
+    let table = stats_table(&stats);
+    assert_eq!(
+        vec![
+            "Puzzles: 2, solved: 2, unsolvable: 0, parse errors: 0",
+            "Needed guessing: 0 of 2 puzzles (0.0%)",
+            "",
+            "Technique         Deductions",
+            "naked single             102",
+            "hidden single              0",
+            "",
+            "                         p50         p90         p99",
+            "Placements                51          51          51",
+            "Backtracks                 0           0           0",
+        ],
+        table[..10].to_vec()
+    );
+    assert!(table[10].starts_with("Time"));
+    let csv = stats_csv(&stats);
+    assert_eq!("metric,value", csv[0]);
+    assert!(csv.contains(&"naked_single,102".to_string()));
+    assert!(csv.contains(&"placements_p90,51".to_string()));
+    assert!(csv.contains(&"backtracks_p99,0".to_string()));
+    let empty = stats_table(&CorpusStats::default());
+    assert!(
+        empty[8].ends_with("-           -           -"),
+        "{}",
+        empty[8]
+    );
+}
+
+#[test]
+fn symmetry_of_rated_puzzles() {
+    let task = load("tasks/1.sudoku").unwrap();
+    assert_eq!("180° rotational", describe_symmetry(&task.clue_symmetry()));
+    assert_eq!(
+        "none",
+        describe_symmetry(&examples::example("hard").unwrap().clue_symmetry())
+    );
+    assert_eq!(
+        "horizontal mirror, vertical mirror",
+        describe_symmetry(&[SymmetryKind::Horizontal, SymmetryKind::Vertical])
+    );
+}
+
+#[test]
+fn json_output_with_error() {
+    let path = write_temp_file("json_output_with_error", UNSOLVABLE);
+    let (json, res) = solve_json_args(&["-s", &path]);
+    assert!(matches!(res, Err(Failure::Unsolvable)));
+    assert_eq!(json["solved"], false);
+    assert!(json["solution"].is_null());
+    assert_eq!(json["puzzle"][0], 1);
+    assert_eq!(json["error"]["code"], "unsolvable");
+    assert_eq!(
+        json["error"]["message"],
+        "Cannot solve sudoku with 9 clues: r1c9 has no candidates"
+    );
+    assert_eq!(json["error"]["best_effort"]["determined"], 0);
+    assert_eq!(
+        json["error"]["best_effort"]["contradiction"],
+        "r1c9 has no candidates"
+    );
+    let path = write_temp_file("json_output_with_wrong_clue", examples::WRONG_CLUE);
+    let (json, _) = solve_json_args(&["-s", &path]);
+    let best_effort = &json["error"]["best_effort"];
+    assert_eq!(
+        (12, 50),
+        (
+            best_effort["determined"].as_u64().unwrap(),
+            best_effort["empty"].as_u64().unwrap()
+        )
+    );
+    assert_eq!(81, best_effort["grid"].as_array().unwrap().len());
+    assert!(best_effort["deepest"].is_null());
+
+    let (json, res) = solve_json_args(&["-s", "tasks/missing.sudoku"]);
+    assert!(matches!(res, Err(Failure::Io(_))));
+    assert!(json["puzzle"].is_null());
+    assert_eq!(json["error"]["code"], "io");
+    assert!(json["error"]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Cannot read file tasks/missing.sudoku"));
+}
+
+#[test]
+fn json_output_of_ambiguous_puzzle() {
+    let path = write_temp_file("json_output_of_ambiguous_puzzle", AMBIGUOUS);
+    let (json, res) = solve_json_args(&["-s", &path, "--classify"]);
+    assert!(res.is_ok());
+    assert_eq!(json["classification"]["class"], "multiple");
+    assert_eq!(json["classification"]["found"], 2);
+    assert_eq!(json["classification"]["sample"][0], 5);
+    let unsolvable = write_temp_file("json_unsolvable_class", UNSOLVABLE);
+    let (json, _) = solve_json_args(&["-s", &unsolvable, "--classify"]);
+    assert_eq!(json["classification"]["class"], "none");
+    assert_eq!(json["classification"]["found"], 0);
+    let (json, _) = solve_json_args(&["-s", "tasks/1.sudoku", "--classify"]);
+    assert_eq!(json["classification"]["class"], "unique");
+    assert_eq!(json["classification"]["found"], 1);
+    assert!(json["classification"].get("sample").is_none());
+    // Other formats have no classification.
+    let matches = parse_args(&["sudoku", "-s", "tasks/1.sudoku", "--classify"]).unwrap();
+    match run(&matches) {
+        Err(Failure::InvalidInput(msg)) => assert_eq!("--classify needs --format json", msg),
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn json_load_errors() {
+    let path = write_temp_file("json_load_errors", "123");
+    let (json, res) = solve_json_args(&["-s", &path]);
+    assert!(matches!(res, Err(Failure::InvalidInput(_))));
+    assert_eq!(
+        serde_json::json!({
+            "code": "parse",
+            "message": "Cannot parse sudoku: expected 81 cells, found 3"
+        }),
+        json["error"]
+    );
+
+    let cages = write_temp_file("json_load_errors.cages", "3 r1c1 r1c2\nr1c3 4\n");
+    let (json, res) = solve_json_args(&["-s", "tasks/empty.sudoku", "--cages", &cages]);
+    assert!(matches!(res, Err(Failure::InvalidInput(_))));
+    assert_eq!(json["error"]["code"], "parse_line");
+    assert_eq!(json["error"]["line"], 2);
+}
+
+#[test]
+fn json_batch() {
+    // The puzzles of a batch are solved one per line as in the other formats.
+    let batch = write_temp_file(
+        "json_batch",
+        &format!("{}\n{}\n", examples::EASY, examples::HARD),
+    );
+    let output = write_temp_file("json_batch_solutions", "");
+    let matches = parse_args(&["sudoku", "-s", &batch, "--format", "json", "-o", &output]).unwrap();
+    run(&matches).unwrap();
+    let solutions = fs::read_to_string(&output).unwrap();
+    let lines: Vec<&str> = solutions.lines().collect();
+    assert_eq!(3, lines.len());
+    for line in lines[..2].iter() {
+        assert!(serde_json::from_str::<Sudoku>(line).unwrap().solved());
+    }
+    let summary: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+    assert_eq!(2, summary["summary"]["puzzles"]);
+    assert_eq!(2, summary["summary"]["solved"]);
+    assert!(summary["summary"]["slowest_ms"].is_number());
+    // A resumed batch keeps the solutions and writes the summary again.
+    let matches = parse_args(&[
+        "sudoku", "-s", &batch, "--format", "json", "-o", &output, "--resume",
+    ])
+    .unwrap();
+    run(&matches).unwrap();
+    let resumed = fs::read_to_string(&output).unwrap();
+    assert_eq!(lines[..2], resumed.lines().collect::<Vec<_>>()[..2]);
+    assert_eq!(3, resumed.lines().count());
+    // -q leaves the summary out.
+    let matches = parse_args(&[
+        "sudoku", "-s", &batch, "--format", "json", "-o", &output, "-q",
+    ])
+    .unwrap();
+    run(&matches).unwrap();
+    assert_eq!(2, fs::read_to_string(&output).unwrap().lines().count());
+}
+
+#[test]
+fn json_ignored_flags() {
+    for flag in ["--animate", "--explain", "--check-solvers"] {
+        let matches =
+            parse_args(&["sudoku", "-s", "tasks/1.sudoku", "--format", "json", flag]).unwrap();
+        match run(&matches) {
+            Err(Failure::InvalidInput(msg)) => {
+                assert_eq!(format!("{} cannot be used with --format json", flag), msg)
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+}
+
+#[test]
+fn json_conflicting_clues() {
+    let path = write_temp_file("json_conflicting_clues", &format!("55{}", ".".repeat(79)));
+    let (json, res) = solve_json_args(&["-s", &path]);
+    assert!(matches!(res, Err(Failure::InvalidInput(_))));
+    let error = &json["error"];
+    assert_eq!(error["code"], "conflicting_clues");
+    assert!(error["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Conflicting clues: 5 is repeated in row 1"));
+    assert_eq!(
+        serde_json::json!([[1, 1], [1, 2]]),
+        error["conflicts"][0]["cells"]
+    );
+    // The row and the square both repeat the value.
+    assert_eq!(2, error["conflicts"].as_array().unwrap().len());
+}
+
+#[test]
+fn json_failure_codes() {
+    let json = |failure: Failure| serde_json::to_value(JsonError::from_failure(&failure)).unwrap();
+    assert_eq!(
+        serde_json::json!({"code": "io", "message": "Cannot create output file"}),
+        json(Failure::Io("Cannot create output file".to_string()))
+    );
+    assert_eq!("unsolvable", json(Failure::Unsolvable)["code"]);
+    assert_eq!(
+        "invalid_input",
+        json(Failure::InvalidInput(String::new()))["code"]
+    );
+    assert_eq!(
+        "interrupted",
+        json(Failure::Interrupted(String::new()))["code"]
+    );
+}
+
+#[test]
+fn detect_input_format() {
+    assert_eq!("sdm", input_format("puzzles.sdm", "auto"));
+    assert_eq!("line", input_format("puzzles.txt", "auto"));
+    assert_eq!("sdm", input_format("puzzles.txt", "sdm"));
+    assert_eq!("ss", input_format("puzzle.ss", "auto"));
+    assert_eq!("cells", input_format("sparse.cells", "auto"));
+}
+
+#[test]
+fn read_stdin() {
+    let stdin = io::Cursor::new(include_str!("../tasks/1.sudoku"));
+    let data = read_input("-", stdin).unwrap();
+    assert!(parse_sudoku(&data, "line", None, &Variant::default()).is_ok());
+}
+
+#[test]
+fn read_empty_stdin() {
+    match read_input("-", io::Cursor::new(" \n")) {
+        Err(Failure::InvalidInput(msg)) => assert_eq!("Cannot read stdin: input is empty", msg),
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn read_batch_from_stdin() {
+    let stdin = io::Cursor::new(format!("{}\n{}\n", UNSOLVABLE, UNSOLVABLE));
+    let data = read_input("-", stdin).unwrap();
+    assert_eq!(
+        2,
+        parse_batch(&data, "line", None, &Variant::default())
+            .unwrap()
+            .unwrap()
+            .len()
+    );
+}
+
+#[test]
+fn stream_batch_with_malformed_line() {
+    let path = write_temp_file("stream_batch_with_malformed_line", "");
+    let puzzle = load("tasks/1.sudoku").unwrap().to_line();
+    let mut stdin = io::Cursor::new(format!(
+        "# batch\n{}\n\n{}\n53..7\n{}\n",
+        puzzle, UNSOLVABLE, puzzle
+    ));
+    let head = read_head(&mut stdin).unwrap();
+    assert_eq!(format!("# batch\n{}\n\n{}\n", puzzle, UNSOLVABLE), head);
+    assert!(is_line_batch(&head, None));
+    assert!(!is_line_batch(&head, Some((2, 3))));
+    assert!(!is_line_batch(&head[..20], None));
+    let matches = parse_args(&["sudoku", "solve", "-o", &path]).unwrap();
+    let matches = matches.subcommand_matches("solve").unwrap();
+    match solve_stream(matches, "-", "line", io::Cursor::new(head).chain(stdin)) {
+        Err(Failure::InvalidInput(msg)) => assert!(msg.contains("Line 5"), "{}", msg),
+        res => panic!("Unexpected result {:?}", res),
+    }
+    // The puzzles before the malformed line are solved all the same.
+    let content = fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(2, lines.len());
+    assert!(lines[0].starts_with("534678912"));
+    assert_eq!("unsolvable", lines[1]);
+}
+
+#[test]
+fn solve_grids_written_back_to_back() {
+    let puzzles: Vec<Sudoku> = ["tasks/1.sudoku", "tasks/2.sudoku", "tasks/very_hard.sudoku"]
+        .iter()
+        .map(|path| load(path).unwrap())
+        .collect();
+    let data: String = puzzles
+        .iter()
+        .map(|s| s.to_line().replace('.', "0"))
+        .collect();
+    assert_eq!(243, data.len());
+    let sudokus = parse_batch(&data, "line", None, &Variant::default())
+        .unwrap()
+        .unwrap();
+    assert_eq!(puzzles, sudokus);
+    let mut out = Vec::new();
+    solve_batch(
+        sudokus.into_iter().enumerate().map(Ok),
+        "pretty",
+        &BatchOptions::default(),
+        &mut out,
+    )
+    .unwrap();
+    let solutions: Vec<String> = puzzles
+        .into_iter()
+        .map(|mut s| {
+            s.solve().unwrap();
+            s.to_line()
+        })
+        .collect();
+    assert_eq!(
+        solutions,
+        String::from_utf8(out).unwrap().lines().collect::<Vec<_>>()
+    );
+    let data = format!("{}1234567", data);
+    assert!(parse_batch(&data, "line", None, &Variant::default()).is_none());
+    match parse_sudoku(&data, "line", None, &Variant::default()) {
+        Err(err) => assert_eq!(
+            "Cannot parse sudoku: expected 81 cells, found 250",
+            err.to_string()
+        ),
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn write_single_solution_to_file() {
+    let path = write_temp_file("write_single_solution_to_file", "stale content");
+    let mut file = open_output(&path).unwrap();
+    let mut s = load("tasks/1.sudoku").unwrap();
+    s.solve().unwrap();
+    write_solution(&mut file, &s, "line", &OutputOptions::default()).unwrap();
+    assert_eq!(
+        format!("{}\n", s.to_line()),
+        fs::read_to_string(&path).unwrap()
+    );
+    let mut file = open_output(&path).unwrap();
+    write_solution(&mut file, &s, "ss", &OutputOptions::default()).unwrap();
+    assert_eq!(render(&s, "ss", false), fs::read_to_string(&path).unwrap());
+    // The character of empty cells follows --empty-char, also in the file.
+    let matches = parse_args(&["sudoku", "-s", "tasks/1.sudoku", "--empty-char", "0"]).unwrap();
+    let opts = output_options(matches.subcommand_matches("solve").unwrap());
+    let task = load("tasks/1.sudoku").unwrap();
+    let mut file = open_output(&path).unwrap();
+    write_solution(&mut file, &task, "line", &opts).unwrap();
+    assert_eq!(
+        format!("{}\n", task.to_line().replace('.', "0")),
+        fs::read_to_string(&path).unwrap()
+    );
+}
+
+#[test]
+fn write_batch_solutions_to_file() {
+    let path = write_temp_file("write_batch_solutions_to_file", "");
+    let mut file = open_output(&path).unwrap();
+    let sudokus = vec![load("tasks/1.sudoku").unwrap(), UNSOLVABLE.parse().unwrap()];
+    assert!(matches!(
+        solve_batch(
+            sudokus.into_iter().enumerate().map(Ok),
+            "pretty",
+            &BatchOptions::default(),
+            &mut file
+        ),
+        Err(Failure::Unsolvable)
+    ));
+    let content = fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(2, lines.len());
+    assert!(lines[0].starts_with("534678912"));
+    assert_eq!("unsolvable", lines[1]);
+}
+
+#[test]
+fn batch_with_cache() {
+    let easy = load("tasks/1.sudoku").unwrap();
+    let hard = load("tasks/very_hard.sudoku").unwrap();
+    let unsolvable: Sudoku = UNSOLVABLE.parse().unwrap();
+    let batch = [&easy, &hard, &easy, &unsolvable, &easy, &unsolvable, &hard];
+    let solve = |cache: Option<usize>| {
+        let sudokus = batch.iter().map(|&s| s.clone()).enumerate().map(Ok);
+        let mut out = Vec::new();
+        let mut report = BatchReport::default();
+        let opts = BatchOptions {
+            cache,
+            ..BatchOptions::default()
+        };
+        let res = solve_puzzles(sudokus, "line", &opts, &mut out, &mut report);
+        assert!(matches!(res, Err(Failure::Unsolvable)));
+        (String::from_utf8(out).unwrap(), report)
+    };
+    let (uncached, report) = solve(None);
+    assert_eq!(None, report.cache_hits);
+    let (cached, report) = solve(Some(8));
+    assert_eq!(uncached, cached);
+    assert_eq!(Some(4), report.cache_hits);
+    assert_eq!((5, 2), (report.solved, report.unsolvable));
+    assert_eq!(
+        Some("Cache hits: 4"),
+        report.lines().last().map(String::as_str)
+    );
+    // A single entry is replaced by every other puzzle, only the easy one repeated right away would hit.
+    let (_, report) = solve(Some(1));
+    assert_eq!(Some(0), report.cache_hits);
+    let matches = parse_args(&["sudoku", "--cache", "0", "-s", "tasks/1.sudoku"]);
+    assert!(matches.is_err());
+}
+
+#[test]
+fn batch_report() {
+    let ms = Duration::from_millis;
+    let puzzles = [
+        (1, true, 5, 10),
+        (2, false, 2, 40),
+        (4, true, 9, 0),
+        (5, true, 9, 3),
+    ];
+    let mut whole = BatchReport::default();
+    for &(line, solved, elapsed, backtracks) in puzzles.iter() {
+        whole.record(line, None, solved, ms(elapsed), backtracks);
+    }
+    whole.parse_errors += 1;
+    assert_eq!(
+        BatchReport {
+            solved: 3,
+            unsolvable: 1,
+            parse_errors: 1,
+            total_time: ms(25),
+            slowest: Some((4, ms(9))),
+            slowest_id: None,
+            backtracks: 53,
+            cache_hits: None,
+        },
+        whole
+    );
+    // Parts solved apart add up to the report of the whole batch, whatever the split.
+    for split in 0..puzzles.len() + 1 {
+        let (first, second) = puzzles.split_at(split);
+        let mut parts = [BatchReport::default(), BatchReport::default()];
+        for (part, puzzles) in parts.iter_mut().zip([first, second].iter()) {
+            for &(line, solved, elapsed, backtracks) in puzzles.iter() {
+                part.record(line, None, solved, ms(elapsed), backtracks);
+            }
+        }
+        parts[1].parse_errors += 1;
+        let mut merged = BatchReport::default();
+        merged.merge(&parts[0]);
+        merged.merge(&parts[1]);
+        assert_eq!(whole, merged);
+    }
+    assert_eq!(
+        vec![
+            "Puzzles: 5, solved: 3, unsolvable: 1, parse errors: 1",
+            "Solve time: 25.000ms in total, 6.250ms on average, 9.000ms for the slowest puzzle on line 4",
+            "Backtracks: 53",
+        ],
+        whole.lines()
+    );
+    let mut named = BatchReport::default();
+    named.record(3, Some("42"), true, ms(5), 0);
+    named.record(7, None, true, ms(2), 0);
+    assert!(named.lines()[1].ends_with("for the slowest puzzle on line 3, id 42"));
+    let mut failed = BatchReport::default();
+    failed.parse_errors += 1;
+    assert_eq!(
+        vec![
+            "Puzzles: 1, solved: 0, unsolvable: 0, parse errors: 1",
+            "Backtracks: 0"
+        ],
+        failed.lines()
+    );
+}
+
+#[test]
+fn resume_interrupted_batch() {
+    // The easy example with its digits shifted by 0 to 4, so every puzzle has another solution.
+    let puzzles: Vec<Sudoku> = (0..5)
+        .map(|shift| {
+            let line: String = examples::EASY
+                .chars()
+                .map(|c| match c.to_digit(10) {
+                    Some(d) => char::from_digit((d - 1 + shift) % 9 + 1, 10).unwrap(),
+                    None => c,
+                })
+                .collect();
+            line.parse().unwrap()
+        })
+        .collect();
+    let solutions: Vec<String> = puzzles
+        .iter()
+        .map(|s| {
+            let mut s = s.clone();
+            s.solve().unwrap();
+            s.to_line()
+        })
+        .collect();
+    let input = write_temp_file(
+        "resume_input",
+        &puzzles
+            .iter()
+            .map(|s| s.to_line() + "\n")
+            .collect::<String>(),
+    );
+    // The interrupted run wrote three solutions and a part of the fourth one.
+    let partial = format!("{}\n{}", solutions[..3].join("\n"), &solutions[3][..20]);
+    let output = write_temp_file("resume_output", &partial);
+    let solve = |resume: bool| {
+        let mut args = vec!["sudoku", "-s", &input, "-o", &output, "--no-summary"];
+        if resume {
+            args.push("--resume");
+        }
+        run(&parse_args(&args).unwrap()).unwrap();
+        fs::read_to_string(&output).unwrap()
+    };
+    assert_eq!(format!("{}\n", solutions.join("\n")), solve(true));
+    // Nothing is left to solve.
+    assert_eq!(format!("{}\n", solutions.join("\n")), solve(true));
+    fs::write(&output, &partial).unwrap();
+    assert_eq!(format!("{}\n", solutions.join("\n")), solve(false));
+    let (_, done) = open_resumed_output(&write_temp_file("resume_empty", "")).unwrap();
+    assert_eq!(0, done);
+    assert!(parse_args(&["sudoku", "-s", &input, "--resume"]).is_err());
+}
+
+#[test]
+fn dedupe_batch() {
+    let puzzle: Sudoku = examples::EASY.parse().unwrap();
+    let copies = [
+        puzzle.to_line(),
+        "# the same puzzle again, then rotated".to_string(),
+        puzzle.to_line(),
+        puzzle.rotate().unwrap().to_line(),
+    ]
+    .join("\n");
+    let mut out = Vec::new();
+    dedupe(&copies, "-", &mut out).unwrap();
+    assert_eq!(
+        format!("{}\n", puzzle.to_line()),
+        String::from_utf8(out).unwrap()
+    );
+    let distinct = [examples::EASY, examples::HARD, examples::UNSOLVABLE].join("\n");
+    let mut out = Vec::new();
+    dedupe(&distinct, "-", &mut out).unwrap();
+    assert_eq!(format!("{}\n", distinct), String::from_utf8(out).unwrap());
+    // Solving the batch skips the copies too.
+    let input = write_temp_file("dedupe_batch", &copies);
+    let output = write_temp_file("dedupe_batch_solutions", "");
+    let matches = parse_args(&["sudoku", "-s", &input, "-o", &output, "--dedupe"]).unwrap();
+    run(&matches).unwrap();
+    let mut solution = puzzle.clone();
+    solution.solve().unwrap();
+    assert_eq!(
+        format!("{}\n", solution.to_line()),
+        fs::read_to_string(&output).unwrap()
+    );
+    let matches = parse_args(&["sudoku", "dedupe", "-s", &input, "-o", &output]).unwrap();
+    run(&matches).unwrap();
+    assert_eq!(
+        format!("{}\n", puzzle.to_line()),
+        fs::read_to_string(&output).unwrap()
+    );
+    // The canonical form swaps bands, which breaks the diagonals, only exact copies are skipped.
+    let x = Variant {
+        rules: Rules {
+            diagonals: true,
+            ..Rules::default()
+        },
+        ..Variant::default()
+    };
+    let mut unique = Unique::for_variant(&x);
+    assert!(unique.is_new(1, &puzzle));
+    assert!(unique.is_new(2, &puzzle.swap_bands(0, 1).unwrap()));
+    assert!(!unique.is_new(3, &puzzle));
+}
+
+#[test]
+fn open_output_in_missing_directory() {
+    match open_output("/nonexistent/dir/solution.txt") {
+        Err(Failure::Io(msg)) => {
+            assert!(msg.starts_with("Cannot create output file /nonexistent/dir/solution.txt: "))
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn exit_codes() {
+    assert_eq!(1, exit_code(&Failure::Unsolvable));
+    assert_eq!(2, exit_code(&Failure::InvalidInput(String::new())));
+    assert_eq!(3, exit_code(&Failure::Io(String::new())));
+    assert_eq!(130, exit_code(&Failure::Interrupted(String::new())));
+}
+
+#[test]
+fn load_failures() {
+    assert!(matches!(
+        load_failure("-", SudokuError::Parse(String::new())),
+        Failure::InvalidInput(_)
+    ));
+    assert!(matches!(
+        load_failure("-", SudokuError::Io(String::new())),
+        Failure::Io(_)
+    ));
+    assert!(matches!(load("tasks/missing.sudoku"), Err(Failure::Io(_))));
+}
+
+/// Parses the command line like the application does.
+#[cfg(test)]
+fn parse_args(args: &[&str]) -> Result<ArgMatches<'static>, clap::Error> {
+    let args = args.iter().map(|arg| arg.to_string()).collect();
+    app().get_matches_from_safe(with_default_subcommand(args))
+}
+
+#[test]
+fn bare_sudoku_path_means_solve() {
+    let matches = parse_args(&["sudoku", "-s", "tasks/1.sudoku"]).unwrap();
+    let (name, solve) = matches.subcommand();
+    assert_eq!("solve", name);
+    assert_eq!(
+        Some("tasks/1.sudoku"),
+        solve.unwrap().value_of("sudoku_path")
+    );
+    assert_eq!(
+        "solve",
+        parse_args(&["sudoku"]).unwrap().subcommand_name().unwrap()
+    );
+}
+
+#[test]
+fn resolve_input_arguments() {
+    assert_eq!(
+        "a.sudoku",
+        resolve_input(Some("a.sudoku"), None, false).unwrap()
+    );
+    assert_eq!(
+        "a.sudoku",
+        resolve_input(Some("a.sudoku"), None, true).unwrap()
+    );
+    assert_eq!(
+        GRID_INPUT,
+        resolve_input(None, Some(GRID_INPUT), false).unwrap()
+    );
+    assert_eq!(
+        GRID_INPUT,
+        resolve_input(None, Some(GRID_INPUT), true).unwrap()
+    );
+    assert_eq!("-", resolve_input(None, None, true).unwrap());
+    assert_eq!(
+        DEMO_INPUT,
+        resolve_input(None, Some(DEMO_INPUT), true).unwrap()
+    );
+    for &piped in [false, true].iter() {
+        assert!(matches!(
+            resolve_input(Some("a.sudoku"), Some(GRID_INPUT), piped),
+            Err(Failure::InvalidInput(_))
+        ));
+    }
+    match resolve_input(None, None, false) {
+        Err(Failure::InvalidInput(msg)) => assert_eq!(
+            "Provide the task with --sudoku-path or --grid, or pipe it to stdin; try --demo for an example",
+            msg
+        ),
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn grid_argument() {
+    let line = load("tasks/1.sudoku").unwrap().to_line();
+    let matches = parse_args(&["sudoku", "--grid", &line]).unwrap();
+    let solve = matches.subcommand_matches("solve").unwrap();
+    assert_eq!(GRID_INPUT, input_path(solve).unwrap());
+    assert_eq!(line, read_task(solve, GRID_INPUT).unwrap());
+    assert_eq!(load("tasks/1.sudoku").unwrap(), load_sudoku(solve).unwrap());
+    // A grid split by the shell is joined again.
+    let (first, second) = line.split_at(40);
+    let spaced = format!("{} {}", &first[..20], &first[20..]);
+    let matches = parse_args(&["sudoku", "rate", "--grid", &spaced, second]).unwrap();
+    let rate = matches.subcommand_matches("rate").unwrap();
+    assert_eq!(line, read_task(rate, GRID_INPUT).unwrap());
+    let err = parse_args(&["sudoku", "--grid", &line, "-s", "tasks/1.sudoku"]).unwrap_err();
+    assert_eq!(clap::ErrorKind::ArgumentConflict, err.kind);
+    match load_sudoku(
+        parse_args(&["sudoku", "--grid", "53..7"])
+            .unwrap()
+            .subcommand_matches("solve")
+            .unwrap(),
+    ) {
+        Err(Failure::InvalidInput(msg)) => {
+            assert!(msg.starts_with("Cannot load sudoku from the --grid argument: "))
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn grid_of_cell_clues() {
+    let matches = parse_args(&["sudoku", "--grid", "r1c3=4,", "R2C7=9", "r9c9=1"]).unwrap();
+    let solve = matches.subcommand_matches("solve").unwrap();
+    assert_eq!(
+        "r1c3=4, R2C7=9 r9c9=1",
+        read_task(solve, GRID_INPUT).unwrap()
+    );
+    let s = load_sudoku(solve).unwrap();
+    assert_eq!(3, s.clue_count());
+    assert_eq!(Some(9), s.value(1, 6));
+    assert_eq!("r1c3=4, r2c7=9, r9c9=1", render(&s, "cells", false));
+    // The list holds the givens only, it cannot show a solution.
+    assert!(parse_args(&["sudoku", "--grid", "r1c3=4", "--format", "cells"]).is_err());
+    let matches = parse_args(&["sudoku", "--grid", "r1c3=4", "r0c5=3"]).unwrap();
+    match load_sudoku(matches.subcommand_matches("solve").unwrap()) {
+        Err(Failure::InvalidInput(msg)) => {
+            assert!(
+                msg.ends_with("`r0c5=3`: cell outside of the 9x9 grid"),
+                "{}",
+                msg
+            )
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn generate_with_seed() {
+    let generated = |args: &[&str]| {
+        let matches = parse_args(args).unwrap();
+        let (s, seed) = generate(matches.subcommand_matches("generate").unwrap());
+        (render(&s, "line", false), seed)
+    };
+    let (first, seed) = generated(&["sudoku", "generate", "--seed", "42"]);
+    assert_eq!(42, seed);
+    assert_eq!(first, generated(&["sudoku", "generate", "--seed", "42"]).0);
+    assert_ne!(first, generated(&["sudoku", "generate", "--seed", "7"]).0);
+    let (small, _) = generated(&["sudoku", "generate", "--size", "4", "--seed", "42"]);
+    assert_eq!(16, small.len());
+    let (random, seed) = generated(&["sudoku", "generate"]);
+    let seed = seed.to_string();
+    assert_eq!(
+        random,
+        generated(&["sudoku", "generate", "--seed", &seed]).0
+    );
+    assert!(parse_args(&["sudoku", "generate", "--seed", "-1"]).is_err());
+}
+
+#[test]
+fn mark_filled_option() {
+    let matches = parse_args(&["sudoku", "-s", "tasks/1.sudoku", "--mark-filled"]).unwrap();
+    let solve = matches.subcommand_matches("solve").unwrap();
+    let opts = output_options(solve);
+    assert!(opts.mark_filled);
+    let task = load_sudoku(solve).unwrap();
+    let mut solution = task.clone();
+    solution.solve().unwrap();
+    let lines = solution_report(&solution, None, "pretty", true, &opts, false, false);
+    assert_eq!(solution.to_string_marked(), lines[1]);
+    assert!(lines[1].contains("| 5 | 3 |[4]|"), "{}", lines[1]);
+    // The other formats are not affected.
+    assert_eq!(
+        solution.to_line(),
+        render_with(&solution, "line", false, &opts)
+    );
+    assert_eq!(task.to_string(), render_with(&task, "pretty", false, &opts));
+}
+
+#[test]
+fn empty_char_option() {
+    let task = load("tasks/1.sudoku").unwrap();
+    let line = task.to_line();
+    for &empty in ["0", ".", " ", "_"].iter() {
+        let matches =
+            parse_args(&["sudoku", "-s", "tasks/1.sudoku", "--empty-char", empty]).unwrap();
+        let opts = output_options(matches.subcommand_matches("solve").unwrap());
+        assert_eq!(
+            line.replace('.', empty),
+            render_with(&task, "line", false, &opts)
+        );
+        let grid = render_with(&task, "pretty", false, &opts);
+        assert_eq!(task.to_string().lines().count(), grid.lines().count());
+        assert!(grid.contains(&format!("| 5 | 3 | {} |", empty)), "{}", grid);
+        assert_eq!(
+            line.replace('.', "0").replace('0', empty),
+            render_with(&task, "sdm", false, &opts)
+        );
+    }
+    let defaults = OutputOptions::default();
+    assert_eq!(line, render_with(&task, "line", false, &defaults));
+    assert_eq!(
+        task.to_string(),
+        render_with(&task, "pretty", false, &defaults)
+    );
+    for &wrong in ["", "00", "..", "5", "9", "A"].iter() {
+        let res = parse_args(&["sudoku", "generate", "--empty-char", wrong]);
+        assert_eq!(clap::ErrorKind::ValueValidation, res.unwrap_err().kind);
+    }
+    assert!(parse_args(&["sudoku", "generate", "--empty-char", "0"]).is_ok());
+}
+
+#[test]
+fn value_order_args() {
+    let order = |args: &[&str]| {
+        let matches = parse_args(args).unwrap();
+        solve_options(matches.subcommand_matches("solve").unwrap())
+    };
+    assert_eq!(SolveOptions::default(), order(&["sudoku"]));
+    assert_eq!(
+        SolveOptions {
+            value_order: ValueOrder::LeastConstraining,
+            seed: Some(3),
+            node_limit: NodeLimit::Default,
+        },
+        order(&[
+            "sudoku",
+            "--value-order",
+            "least-constraining",
+            "--seed",
+            "3"
+        ])
+    );
+    assert_eq!(
+        SolveOptions::random(Some(42)),
+        order(&["sudoku", "--value-order", "random", "--seed", "42"])
+    );
+    assert!(parse_args(&["sudoku", "--value-order", "descending"]).is_err());
+    assert_eq!(
+        SolveOptions::default().max_nodes(None),
+        order(&["sudoku", "--no-limit"])
+    );
+    let mut expected = load("tasks/1.sudoku").unwrap();
+    expected.solve().unwrap();
+    for name in ["ascending", "least-constraining", "random"].iter() {
+        let mut s = load("tasks/1.sudoku").unwrap();
+        s.solve_with_options(&order(&["sudoku", "--value-order", name]))
+            .unwrap();
+        assert_eq!(expected, s);
+    }
+}
+
+#[test]
+fn solve_args() {
+    let matches = parse_args(&[
+        "sudoku", "solve", "--format", "json", "--pretty", "-o", "out.txt",
+    ])
+    .unwrap();
+    let solve = matches.subcommand_matches("solve").unwrap();
+    assert_eq!(None, solve.value_of("sudoku_path"));
+    assert_eq!(Some("json"), solve.value_of("format"));
+    assert_eq!(Some("auto"), solve.value_of("input_format"));
+    assert_eq!(Some("out.txt"), solve.value_of("output"));
+    assert!(solve.is_present("pretty"));
+    assert!(parse_args(&["sudoku", "solve", "--format", "xml"]).is_err());
+}
+
+#[test]
+fn verify_rate_and_hint_args() {
+    for name in ["verify", "rate", "hint"].iter() {
+        let matches = parse_args(&["sudoku", name, "-s", "tasks/1.sudoku"]).unwrap();
+        let sub = matches.subcommand_matches(name).unwrap();
+        assert_eq!(Some("tasks/1.sudoku"), sub.value_of("sudoku_path"));
+        assert_eq!(Some("auto"), sub.value_of("input_format"));
+        // Only `solve` writes solutions.
+        assert!(parse_args(&["sudoku", name, "--format", "line"]).is_err());
+    }
+    // Every level of hints is a technique of the rater.
+    for technique in Technique::ALL.iter() {
+        let name = technique.name();
+        assert_eq!(technique.to_string().replace(' ', "-"), name);
+        assert_eq!(Some(*technique), Technique::from_name(name));
+        assert!(parse_args(&["sudoku", "hint", "--max-technique", name]).is_ok());
+    }
+    assert!(parse_args(&["sudoku", "hint", "--max-technique", "x-wing"]).is_err());
+}
+
+#[test]
+fn generate_args() {
+    let matches = parse_args(&["sudoku", "generate", "--format", "line"]).unwrap();
+    let generate = matches.subcommand_matches("generate").unwrap();
+    assert_eq!(Some("line"), generate.value_of("format"));
+    assert!(parse_args(&["sudoku", "generate", "--format", "json"]).is_err());
+    assert!(parse_args(&["sudoku", "generate", "-s", "tasks/1.sudoku"]).is_err());
+    let matches = parse_args(&["sudoku", "generate", "--size", "4"]).unwrap();
+    assert_eq!(
+        Some((2, 2)),
+        box_shape(matches.subcommand_matches("generate").unwrap())
+    );
+    let matches = parse_args(&["sudoku", "generate", "--box-size", "2x3"]).unwrap();
+    assert_eq!(
+        Some((2, 3)),
+        box_shape(matches.subcommand_matches("generate").unwrap())
+    );
+    for shape in ["2x", "2x3x4", "1x6", "4x5"].iter() {
+        assert!(parse_args(&["sudoku", "generate", "--box-size", shape]).is_err());
+    }
+    assert!(parse_args(&["sudoku", "generate", "--size", "4", "--box-size", "2x3"]).is_err());
+    assert!(parse_args(&["sudoku", "generate", "--size", "5"]).is_err());
+}
+
+#[cfg(test)]
+const INCONSISTENT: &str = concat!(
+    "55.......",
+    "........................................................................",
+);
+
+/// Solved grid without four cells whose two values can be swapped, so it has two solutions.
+#[cfg(test)]
+const AMBIGUOUS: &str =
+    "53467891267219534819834256785976.42.42685.79.713924856961537284287419635345286179";
+
+#[test]
+fn validate_valid_puzzle() {
+    let s = load("tasks/1.sudoku").unwrap();
+    let report = validate(&s, None, false).unwrap();
+    assert!(report.starts_with("consistent, "));
+    assert!(validate(&s, None, true)
+        .unwrap()
+        .ends_with("the solution is unique"));
+    let mut solved = s.clone();
+    solved.solve().unwrap();
+    assert_eq!("correct", validate(&solved, None, true).unwrap());
+}
+
+#[test]
+fn summaries_of_tasks() {
+    for (path, clues) in [
+        ("tasks/1.sudoku", 30),
+        ("tasks/2.sudoku", 4),
+        ("tasks/very_hard.sudoku", 25),
+        ("tasks/empty.sudoku", 0),
+    ] {
+        let summary = load(path).unwrap().summary();
+        assert_eq!(
+            (clues, clues, 81 - clues),
+            (summary.clues, summary.filled, summary.empty)
+        );
+        assert!(summary.consistent);
+    }
+}
+
+#[test]
+fn demo_puzzles() {
+    let solve = |name: &str, args: &[&str]| {
+        let output = write_temp_file(name, "");
+        let mut args = args.to_vec();
+        args.extend(&["-o", &output]);
+        let res = run(&parse_args(&args).unwrap());
+        (res, fs::read_to_string(&output).unwrap())
+    };
+    for &(name, line) in examples::ALL.iter() {
+        let matches = parse_args(&["sudoku", "--demo", name]).unwrap();
+        let matches = matches.subcommand_matches("solve").unwrap();
+        assert_eq!(DEMO_INPUT, input_path(matches).unwrap());
+        assert_eq!(line, load_sudoku(matches).unwrap().to_line());
+    }
+    let (res, output) = solve("demo_easy", &["sudoku", "--demo", "--format", "line"]);
+    assert!(res.is_ok());
+    let mut easy: Sudoku = examples::EASY.parse().unwrap();
+    easy.solve().unwrap();
+    assert_eq!(easy.to_line(), output.trim());
+    let (res, _) = solve("demo_unsolvable", &["sudoku", "--demo", "unsolvable"]);
+    let failure = res.unwrap_err();
+    assert!(matches!(failure, Failure::Unsolvable));
+    assert_eq!(1, exit_code(&failure));
+    assert!(parse_args(&["sudoku", "--demo", "medium"]).is_err());
+    let err = parse_args(&["sudoku", "--demo", "hard", "--grid", examples::EASY]).unwrap_err();
+    assert_eq!(clap::ErrorKind::ArgumentConflict, err.kind);
+}
+
+#[test]
+fn compare_grids() {
+    let task = load("tasks/1.sudoku").unwrap();
+    assert_eq!(
+        vec!["Grids are identical"],
+        comparison_report(&task.diff(&task))
+    );
+    let line = task.to_line();
+    let changed: Sudoku = format!("{}4..{}", &line[..2], &line[5..]).parse().unwrap();
+    assert_eq!(
+        vec!["2 cells differ:", "r1c3: . vs 4", "r1c5: 7 vs ."],
+        comparison_report(&task.diff(&changed))
+    );
+    let matches = parse_args(&["sudoku", "compare", "tasks/1.sudoku", "tasks/2.sudoku"]).unwrap();
+    assert!(matches!(run(&matches), Err(Failure::Differs)));
+    assert_eq!(1, exit_code(&Failure::Differs));
+}
+
+#[test]
+fn validate_inconsistent_puzzle() {
+    let s: Sudoku = INCONSISTENT.parse().unwrap();
+    match validate(&s, None, false) {
+        Err(Failure::InvalidInput(msg)) => {
+            assert_eq!("incorrect: 5 is repeated in row 1: r1c1, r1c2; 5 is repeated in square 1: r1c1, r1c2", msg)
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn validate_non_unique_puzzle() {
+    let s = load("tasks/empty.sudoku").unwrap();
+    assert!(validate(&s, None, false).is_ok());
+    match validate(&s, None, true) {
+        Err(Failure::InvalidInput(msg)) => {
+            assert!(
+                msg.starts_with("ambiguous, it has more than one solution with 0 clues, e.g. 123")
+            )
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+    let two: Sudoku = AMBIGUOUS.parse().unwrap();
+    match validate(&two, None, true) {
+        Err(Failure::InvalidInput(msg)) => assert!(
+            msg.starts_with("ambiguous, it has 2 solutions with 77 clues, e.g. 5346789126"),
+            "{}",
+            msg
+        ),
+        res => panic!("Unexpected result {:?}", res),
+    }
+    let unsolvable: Sudoku = UNSOLVABLE.parse().unwrap();
+    assert!(matches!(
+        validate(&unsolvable, None, true),
+        Err(Failure::Unsolvable)
+    ));
+}
+
+#[test]
+fn show_ambiguity_of_two_solutions() {
+    let two: Sudoku = AMBIGUOUS.parse().unwrap();
+    let (res, class) = validate_classified(&two, None, true);
+    assert!(matches!(res, Err(Failure::InvalidInput(_))));
+    let lines = ambiguity_report(&class.unwrap()).unwrap();
+    assert_eq!(
+        vec!["First solution:", "Second solution:", "4 cells differ:"],
+        [0, 2, 4]
+            .iter()
+            .map(|&i| lines[i].as_str())
+            .collect::<Vec<_>>()
+    );
+    let cells: Vec<&str> = lines[5..].iter().map(|line| &line[..4]).collect();
+    assert_eq!(vec!["r4c6", "r4c9", "r5c6", "r5c9"], cells);
+    assert_eq!(
+        None,
+        ambiguity_report(&load("tasks/1.sudoku").unwrap().classify())
+    );
+    let path = write_temp_file("show_ambiguity", AMBIGUOUS);
+    let matches = parse_args(&["sudoku", "verify", "--show-ambiguity", "-s", &path]).unwrap();
+    assert!(matches!(run(&matches), Err(Failure::InvalidInput(_))));
+    let path = write_temp_file(
+        "show_ambiguity_batch",
+        &format!("{}\n{}\n", AMBIGUOUS, AMBIGUOUS),
+    );
+    let matches = parse_args(&["sudoku", "verify", "--show-ambiguity", "-s", &path]).unwrap();
+    match run(&matches) {
+        Err(Failure::InvalidInput(msg)) => assert_eq!(
+            "--show-ambiguity cannot be used with a batch of puzzles",
+            msg
+        ),
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn combined_validation_of_batch() {
+    let valid = load("tasks/1.sudoku").unwrap();
+    let unsolvable: Sudoku = UNSOLVABLE.parse().unwrap();
+    let inconsistent: Sudoku = INCONSISTENT.parse().unwrap();
+    let results = vec![
+        validate(&valid, None, true),
+        validate(&unsolvable, None, true),
+    ];
+    assert!(matches!(
+        combined_validation(&results),
+        Err(Failure::Unsolvable)
+    ));
+    let results = vec![
+        validate(&unsolvable, None, true),
+        validate(&inconsistent, None, true),
+        validate(&valid, None, true),
+    ];
+    assert!(matches!(
+        combined_validation(&results),
+        Err(Failure::InvalidInput(_))
+    ));
+    assert!(combined_validation(&[validate(&valid, None, true)]).is_ok());
+}
+
+#[test]
+fn grade_batch_as_csv() {
+    let data = fs::read_to_string("tasks/grades.txt").unwrap();
+    let mut out = Vec::new();
+    assert!(matches!(
+        grade_batch(&data, &Variant::default(), NodeLimit::Default, &mut out),
+        Err(Failure::InvalidInput(_))
+    ));
+    let out = String::from_utf8(out).unwrap();
+    let rows: Vec<Vec<&str>> = out.lines().map(|row| row.split(',').collect()).collect();
+    assert_eq!(6, rows.len());
+    assert_eq!(1, out.matches(GRADES_HEADER).count());
+    assert_eq!(GRADES_HEADER, out.lines().next().unwrap());
+    // The time of the search differs from run to run and is left out.
+    let easy = load("tasks/1.sudoku").unwrap();
+    assert_eq!(
+        vec!["2", &easy.to_line(), "30", "yes", "easy"],
+        rows[1][..5].to_vec()
+    );
+    // Every empty cell is filled at least once.
+    let easy_effort = rows[1][5].parse::<u64>().unwrap();
+    assert!(easy_effort >= 51);
+    assert!(rows[1][6].parse::<f64>().is_ok());
+    assert_eq!("", rows[1][7]);
+    let hard = load("tasks/very_hard.sudoku").unwrap();
+    assert_eq!(
+        vec!["3", &hard.to_line(), "25", "yes", "hard"],
+        rows[2][..5].to_vec()
+    );
+    assert!(rows[2][5].parse::<u64>().unwrap() >= 56);
+    assert_eq!(
+        vec!["4", &".".repeat(81), "0", "no", "hard"],
+        rows[3][..5].to_vec()
+    );
+    assert_eq!(
+        vec!["5", "53..7....6..195", "", "unknown", "", "", ""],
+        rows[4][..7].to_vec()
+    );
+    // The error holds a comma, so it is quoted.
+    assert!(out
+        .lines()
+        .nth(4)
+        .unwrap()
+        .ends_with(",\"Line 5: Cannot parse sudoku: expected 81 cells, found 15\""));
+    // The rating finds a solution of the sparse 16x16 puzzle, the search of the solver stops at the limit.
+    assert_eq!(
+        vec!["6", "5", "no", "unknown", "unknown", "", ""],
+        [0, 2, 3, 4, 5, 6, 7]
+            .iter()
+            .map(|&i| rows[5][i])
+            .collect::<Vec<_>>()
+    );
+    // A search stopped at the limit cannot tell whether the solution is unique.
+    let limited = easy.with_node_limit(NodeLimit::Max(5));
+    assert_eq!("unknown", grade(&limited)[2]);
+}
+
+#[test]
+fn count_solutions_of_puzzles() {
+    let unique = load("tasks/1.sudoku").unwrap();
+    let unsolvable: Sudoku = UNSOLVABLE.parse().unwrap();
+    let ambiguous = load("tasks/empty.sudoku").unwrap();
+    let mut out = Vec::new();
+    assert!(count_solutions(std::slice::from_ref(&unique), 10, &mut out).is_ok());
+    assert!(matches!(
+        count_solutions(&[unique, unsolvable], 10, &mut out),
+        Err(Failure::Unsolvable)
+    ));
+    assert!(matches!(
+        count_solutions(&[ambiguous], 10, &mut out),
+        Err(Failure::Unsolvable)
+    ));
+    assert_eq!("1\n1\n0\n10\n", String::from_utf8(out).unwrap());
+    // A count stopped at the limit is unknown rather than the solutions found until then.
+    let limited = load("tasks/1.sudoku")
+        .unwrap()
+        .with_node_limit(NodeLimit::Max(5));
+    let mut out = Vec::new();
+    assert!(matches!(
+        count_solutions(&[limited], 10, &mut out),
+        Err(Failure::Unsolvable)
+    ));
+    assert_eq!(
+        "unknown, the search stopped at its limit with 0 solutions found, pass --no-limit to search on\n",
+        String::from_utf8(out).unwrap()
+    );
+}
+
+#[test]
+fn count_solutions_args() {
+    let matches = parse_args(&["sudoku", "--count-solutions"]).unwrap();
+    let solve = matches.subcommand_matches("solve").unwrap();
+    assert!(solve.is_present("count_solutions"));
+    assert_eq!(None, solve.value_of("max"));
+    assert!(parse_args(&["sudoku", "--max", "5"]).is_err());
+    assert!(parse_args(&["sudoku", "--count-solutions", "--max", "0"]).is_err());
+}
+
+#[test]
+fn format_durations() {
+    assert_eq!("0µs", format_duration(Duration::from_nanos(10)));
+    assert_eq!("999µs", format_duration(Duration::from_micros(999)));
+    assert_eq!("1.000ms", format_duration(Duration::from_millis(1)));
+    assert_eq!("12.345ms", format_duration(Duration::from_micros(12_345)));
+    assert_eq!("1.500s", format_duration(Duration::from_millis(1500)));
+    assert_eq!("75.000s", format_duration(Duration::from_secs(75)));
+}
+
+#[test]
+fn explain_solving_steps() {
+    let task = load("tasks/1.sudoku").unwrap();
+    let lines = explanation_report(&task);
+    assert_eq!(51, lines.len());
+    assert_eq!(
+        "1. r5c5 = 5 (naked single): 5 is the only remaining candidate of r5c5",
+        lines[0]
+    );
+    let hard = load("tasks/very_hard.sudoku").unwrap();
+    assert!(explanation_report(&hard)
+        .last()
+        .unwrap()
+        .ends_with("they are found with search"));
+    let inconsistent: Sudoku = INCONSISTENT.parse().unwrap();
+    assert!(explanation_report(&inconsistent).is_empty());
+}
+
+#[test]
+fn rank_of_hinted_cell() {
+    let s = load("tasks/1.sudoku").unwrap();
+    let mut solved = s.clone();
+    solved.solve().unwrap();
+    let (row, col, _) = solved.fill_order()[11];
+    assert_eq!(
+        Some("It is the 12th of 51 cells the solver determines".to_string()),
+        fill_rank(&s, row, col)
+    );
+    assert_eq!(None, fill_rank(&s, 0, 0));
+    assert_eq!(None, fill_rank(&UNSOLVABLE.parse().unwrap(), 0, 1));
+    let ordinals: Vec<String> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 103, 111]
+        .iter()
+        .map(|&n| ordinal(n))
+        .collect();
+    assert_eq!(
+        vec!["1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "22nd", "103rd", "111th"],
+        ordinals
+    );
+}
+
+#[test]
+fn describe_hint_with_1_based_coordinates() {
+    let hint = Hint {
+        source: HintSource::Technique(Technique::HiddenSingle),
+        cell: Pos::new(3, 0),
+        value: 7,
+        unit: Some(Unit::Column(0)),
+    };
+    assert_eq!(
+        "Row 4, column 1 (A4): 7 (hidden single) - digit 7 can only appear in r4c1 within column 1",
+        describe_hint(&hint)
+    );
+}
+
+#[test]
+fn verify_solution_against_original() {
+    let original = load("tasks/1.sudoku").unwrap();
+    let mut solved = original.clone();
+    solved.solve().unwrap();
+    assert_eq!(
+        "correct",
+        validate(&solved, Some(&original), false).unwrap()
+    );
+    // Relabeling keeps the grid correct, but changes every clue.
+    let tampered = solved.relabel([2, 1, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+    assert!(validate(&tampered, None, false).is_ok());
+    match validate(&tampered, Some(&original), false) {
+        Err(Failure::InvalidInput(msg)) => {
+            assert!(msg.starts_with("incorrect: clues of the original puzzle are changed at r2c4, "))
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn digits_of_verified_puzzle() {
+    let s = load("tasks/1.sudoku").unwrap();
+    assert_eq!(
+        "1: 3, 2: 2, 3: 3, 4: 2, 5: 3, 6: 5, 7: 3, 8: 5, 9: 4",
+        describe_digits(&s)
+    );
+    let small = Sudoku::with_box_size(2).unwrap();
+    assert_eq!("1: 0, 2: 0, 3: 0, 4: 0", describe_digits(&small));
+    let big = Sudoku::with_box_size(4).unwrap();
+    assert!(describe_digits(&big).ends_with(", 9: 0, above 9: 0"));
+}
+
+#[test]
+fn repairs_of_broken_puzzles() {
+    let mut line = load("tasks/1.sudoku").unwrap().to_line();
+    line.replace_range(2..3, "1");
+    let s: Sudoku = line.parse().unwrap();
+    assert!(matches!(validate(&s, None, true), Err(Failure::Unsolvable)));
+    assert_eq!(
+        "To fix it, remove 1 from r1c3, which leaves a unique solution",
+        describe_repairs(&s.suggest_repairs())
+    );
+    line.replace_range(72..73, "1");
+    let s: Sudoku = line.parse().unwrap();
+    assert_eq!(
+        "No single clue removal makes the puzzle solvable",
+        describe_repairs(&s.suggest_repairs())
+    );
+    let path = write_temp_file("suggest_fix_batch", &format!("{}\n{}\n", line, line));
+    let matches = parse_args(&["sudoku", "verify", "--suggest-fix", "-s", &path]).unwrap();
+    assert!(matches!(run(&matches), Err(Failure::InvalidInput(_))));
+}
+
+#[test]
+fn check_cells_by_label() {
+    let original = load("tasks/1.sudoku").unwrap();
+    let mut solved = original.clone();
+    solved.solve().unwrap();
+    let e5 = Pos::from_label("e5", 9).unwrap();
+    assert_eq!(
+        Ok("Cell E5 holds 5, which matches the solution".to_string()),
+        check_cell(&solved, Some(&original), e5)
+    );
+    assert_eq!(
+        Ok("Cell E5 is empty".to_string()),
+        check_cell(&original, None, e5)
+    );
+    // r1c3 allows 1, 2 and 4, only 4 solves the puzzle.
+    let mut guess = original.clone();
+    guess.set(0, 2, 1).unwrap();
+    assert_eq!(
+        Err("Cell C1 holds 1, which is not the value of the solution".to_string()),
+        check_cell(&guess, Some(&original), Pos::new(0, 2))
+    );
+    assert_eq!(
+        Ok("Cell C1 holds 1, which follows the rules".to_string()),
+        check_cell(&guess, None, Pos::new(0, 2))
+    );
+    // A search stopped at the limit cannot tell the solution.
+    let limited = original.clone().with_node_limit(NodeLimit::Max(5));
+    assert_eq!(
+        Ok("Cell C1 holds 1, which follows the rules".to_string()),
+        check_cell(&guess, Some(&limited), Pos::new(0, 2))
+    );
+    let mut line = original.to_line();
+    line.replace_range(40..41, "7");
+    let wrong: Sudoku = line.parse().unwrap();
+    assert_eq!(
+        Err(
+            "Cell E5 holds 7, which breaks the rules: 7 is repeated in column 5: r1c5, r5c5"
+                .to_string()
+        ),
+        check_cell(&wrong, None, e5)
+    );
+
+    let matches =
+        parse_args(&["sudoku", "verify", "--cell", "J1", "-s", "tasks/1.sudoku"]).unwrap();
+    let verify = matches.subcommand_matches("verify").unwrap();
+    match selected_cell(verify, &original) {
+        Err(Failure::InvalidInput(msg)) => {
+            assert_eq!("J1 is not a cell of the 9x9 grid, name cells like E5", msg)
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn quiet_output_has_only_the_solution() {
+    let task = load("tasks/1.sudoku").unwrap();
+    let mut solution = task.clone();
+    solution.solve().unwrap();
+    assert!(task_report(&task, "line", false, true, &OutputOptions::default()).is_empty());
+    assert_eq!(
+        vec![solution.to_line()],
+        solution_report(
+            &solution,
+            None,
+            "line",
+            false,
+            &OutputOptions::default(),
+            true,
+            false
+        )
+    );
+    assert!(solution_report(
+        &solution,
+        None,
+        "line",
+        false,
+        &OutputOptions::default(),
+        true,
+        true
+    )
+    .is_empty());
+    let err = Unsolvable {
+        diagnosis: task.diagnose(),
+    };
+    assert!(solution_report(
+        &task,
+        Some(&err),
+        "line",
+        false,
+        &OutputOptions::default(),
+        true,
+        false
+    )
+    .is_empty());
+}
+
+#[test]
+fn verbose_output() {
+    let task = load("tasks/1.sudoku").unwrap();
+    let mut solution = task.clone();
+    solution.solve().unwrap();
+    assert_eq!(
+        vec![
+            "Solving sudoku with 30 clues, 51 empty".to_string(),
+            task.to_line()
+        ],
+        task_report(&task, "line", false, false, &OutputOptions::default())
+    );
+    assert_eq!(
+        vec!["Solved!".to_string(), solution.to_line()],
+        solution_report(
+            &solution,
+            None,
+            "line",
+            false,
+            &OutputOptions::default(),
+            false,
+            false
+        )
+    );
+    let err = Unsolvable {
+        diagnosis: task.diagnose(),
+    };
+    assert_eq!(
+        vec!["Cannot solve sudoku with 30 clues: exhaustive search found no solution"],
+        solution_report(
+            &task,
+            Some(&err),
+            "line",
+            false,
+            &OutputOptions::default(),
+            false,
+            false
+        )
+    );
+}
+
+#[test]
+fn quiet_args() {
+    let matches = parse_args(&["sudoku", "-q", "-s", "tasks/1.sudoku"]).unwrap();
+    let solve = matches.subcommand_matches("solve").unwrap();
+    assert!(solve.is_present("quiet"));
+    assert_eq!(0, solve.occurrences_of("format"));
+}
+
+#[test]
+fn progress_report_follows_search() {
+    let task = load("tasks/1.sudoku").unwrap();
+    let mut report = ProgressReport::new(&task, false);
+    let pos = task
+        .as_slice()
+        .iter()
+        .position(|&value| value == 0)
+        .unwrap();
+    let value = task.candidates(pos / 9, pos % 9)[0];
+    let stats = SolveStats {
+        placements: 1,
+        backtracks: 0,
+        depth: 1,
+    };
+    report.on_event(SolveEvent::Placed { pos, value }, &stats);
+    assert_eq!(Some(value), report.grid().value(pos / 9, pos % 9));
+    assert_eq!(stats, report.stats);
+    report.on_event(SolveEvent::RolledBack { pos, value }, &stats);
+    assert_eq!(task.to_line(), report.grid().to_line());
+    // The grid with the most values placed stays.
+    assert_eq!(Some(value), report.deepest().value(pos / 9, pos % 9));
+}
+
+#[test]
+fn progress_line_format() {
+    let stats = SolveStats {
+        placements: 1200,
+        backtracks: 300,
+        depth: 45,
+    };
+    assert_eq!(
+        "Still solving: 1200 placements, 300 backtracks, 45 cells filled by the search, at most 10^12.3 grids left",
+        progress_line(&stats, 12.34)
+    );
+}
+
+#[test]
+fn parse_own_output() {
+    let task = load("tasks/1.sudoku").unwrap();
+    let mut solution = task.clone();
+    solution.solve().unwrap();
+    let mut output = task_report(&task, "pretty", false, false, &OutputOptions::default());
+    output.extend(solution_report(
+        &solution,
+        None,
+        "pretty",
+        false,
+        &OutputOptions::default(),
+        false,
+        false,
+    ));
+    let output = output.join("\n");
+    assert_eq!(
+        solution,
+        parse_sudoku(&output, "auto", None, &Variant::default()).unwrap()
+    );
+    assert_eq!(
+        vec!["Sudoku is already solved"],
+        task_report(&solution, "pretty", false, false, &OutputOptions::default())
+    );
+}
+
+#[test]
+fn few_clues_warnings() {
+    let task = load("tasks/1.sudoku").unwrap();
+    assert_eq!(None, few_clues_warning(&task));
+    let unsolvable: Sudoku = UNSOLVABLE.parse().unwrap();
+    assert!(few_clues_warning(&unsolvable)
+        .unwrap()
+        .starts_with("Sudoku has only 9 clues, at least 17 are needed"));
+    let mut solved = Sudoku::empty();
+    solved.solve().unwrap();
+    assert_eq!(None, few_clues_warning(&solved));
+    let small = Sudoku::parse_with_box_size("1.....2..3.....4", 2).unwrap();
+    assert_eq!(None, few_clues_warning(&small));
+    let killer = Sudoku::empty().with_cages(Vec::new()).unwrap();
+    assert!(few_clues_warning(&killer).is_some());
+    let cages = cages::parse_cages("3 r1c1 r1c2", Geometry::CLASSIC).unwrap();
+    assert_eq!(None, few_clues_warning(&killer.with_cages(cages).unwrap()));
+    let x = Sudoku::empty().with_rules(Rules {
+        diagonals: true,
+        ..Rules::default()
+    });
+    assert_eq!(None, few_clues_warning(&x));
+    assert!(!may_be_unique(&unsolvable) && !may_be_unique(&Sudoku::empty()));
+    assert!(may_be_unique(&task) && may_be_unique(&solved) && may_be_unique(&small));
+}
+
+#[test]
+fn validate_few_clues() {
+    let s: Sudoku = format!("5{}", ".".repeat(80)).parse().unwrap();
+    match validate(&s, None, true) {
+        Err(Failure::InvalidInput(message)) => {
+            assert!(message
+                .starts_with("ambiguous, it has more than one solution with 1 clues, e.g. 5"))
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+    let unsolvable: Sudoku = UNSOLVABLE.parse().unwrap();
+    assert!(matches!(
+        validate(&unsolvable, None, true),
+        Err(Failure::Unsolvable)
+    ));
+}
+
+#[test]
+fn parse_with_size() {
+    let s = parse_sudoku(
+        "1...\n..2.\n.3..\n...4\n",
+        "line",
+        Some((2, 2)),
+        &Variant::default(),
+    )
+    .unwrap();
+    assert_eq!(4, s.side());
+    assert!(parse_sudoku(
+        "1...\n..2.\n.3..\n...4\n",
+        "line",
+        None,
+        &Variant::default()
+    )
+    .is_err());
+    assert!(parse_sudoku(
+        "1...\n..2.\n.3..\n...4\n",
+        "ss",
+        Some((2, 2)),
+        &Variant::default()
+    )
+    .is_err());
+    assert!(parse_batch(
+        "1...\n..2.\n.3..\n...4\n",
+        "line",
+        Some((2, 2)),
+        &Variant::default()
+    )
+    .is_none());
+    let task = fs::read_to_string("tasks/1.sudoku").unwrap();
+    assert_eq!(
+        load("tasks/1.sudoku").unwrap(),
+        parse_sudoku(&task, "line", Some((3, 3)), &Variant::default()).unwrap()
+    );
+}
+
+#[test]
+fn x_variant() {
+    let matches = parse_args(&["sudoku", "verify", "--variant", "x"]).unwrap();
+    let rules = variant_rules(matches.subcommand_matches("verify").unwrap());
+    assert!(rules.diagonals && !rules.windows);
+    let matches = parse_args(&["sudoku", "hint", "--variant", "hyper"]).unwrap();
+    assert!(variant_rules(matches.subcommand_matches("hint").unwrap()).windows);
+    let matches = parse_args(&["sudoku", "rate"]).unwrap();
+    assert_eq!(
+        Rules::default(),
+        variant_rules(matches.subcommand_matches("rate").unwrap())
+    );
+    // 1 is repeated on the main diagonal only.
+    let task = format!("1{}1{}", ".".repeat(39), ".".repeat(40));
+    assert!(validate(
+        &parse_sudoku(&task, "line", None, &Variant::default()).unwrap(),
+        None,
+        false
+    )
+    .is_ok());
+    let variant = Variant {
+        rules,
+        ..Variant::default()
+    };
+    let x = parse_sudoku(&task, "line", None, &variant).unwrap();
+    match validate(&x, None, false) {
+        Err(Failure::InvalidInput(msg)) => assert!(msg.contains("main diagonal")),
+        res => panic!("unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn non_consecutive_variant() {
+    let matches = parse_args(&["sudoku", "verify", "--variant", "non-consecutive"]).unwrap();
+    let variant = Variant {
+        rules: variant_rules(matches.subcommand_matches("verify").unwrap()),
+        ..Variant::default()
+    };
+    assert!(variant.rules.non_consecutive);
+    let task = format!("21{}", ".".repeat(79));
+    let s = parse_sudoku(&task, "line", None, &variant).unwrap();
+    match validate(&s, None, false) {
+        Err(Failure::InvalidInput(msg)) => {
+            assert_eq!("incorrect: 2 and 1 are neighbours: r1c1, r1c2", msg)
+        }
+        res => panic!("unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn jigsaw_regions() {
+    let matches = parse_args(&[
+        "sudoku",
+        "solve",
+        "-s",
+        "tasks/jigsaw.sudoku",
+        "--regions",
+        "tasks/jigsaw.regions",
+    ])
+    .unwrap();
+    let variant = selected_variant(matches.subcommand_matches("solve").unwrap()).unwrap();
+    let mut s = load_sudoku_from("tasks/jigsaw.sudoku", "auto", None, &variant).unwrap();
+    assert_eq!(Ok(1), s.count_solutions(2));
+    s.solve().unwrap();
+    let layout = fs::read_to_string("tasks/jigsaw.regions").unwrap();
+    let ten_cells = Variant {
+        regions: Some(layout.replacen("11112", "11111", 1)),
+        ..Variant::default()
+    };
+    match load_sudoku_from("tasks/jigsaw.sudoku", "auto", None, &ten_cells) {
+        Err(Failure::InvalidInput(msg)) => {
+            assert!(msg.ends_with("region 1 has 10 cells, expected 9"))
+        }
+        res => panic!("unexpected result {:?}", res),
+    }
+    let missing = parse_args(&["sudoku", "rate", "--regions", "tasks/missing.regions"]).unwrap();
+    assert!(matches!(
+        selected_variant(missing.subcommand_matches("rate").unwrap()),
+        Err(Failure::Io(_))
+    ));
+}
+
+#[test]
+fn killer_cages() {
+    let matches = parse_args(&[
+        "sudoku",
+        "verify",
+        "-s",
+        "tasks/empty.sudoku",
+        "--cages",
+        "tasks/killer.cages",
+    ])
+    .unwrap();
+    let variant = selected_variant(matches.subcommand_matches("verify").unwrap()).unwrap();
+    let mut s = load_sudoku_from("tasks/empty.sudoku", "auto", None, &variant).unwrap();
+    assert_eq!(30, s.cages().len());
+    s.solve().unwrap();
+    assert_eq!("correct", validate(&s, None, false).unwrap());
+    // Swapping rows 1 and 3 keeps rows, columns and squares valid, but breaks the cages.
+    let mut field = s.as_slice().to_vec();
+    let (first, third) = field.split_at_mut(18);
+    first[..9].swap_with_slice(&mut third[..9]);
+    let swapped = Sudoku::new(field.into_iter())
+        .unwrap()
+        .with_cages(s.cages().to_vec())
+        .unwrap();
+    match validate(&swapped, None, false) {
+        Err(Failure::InvalidInput(msg)) => assert!(msg.contains("cage 1")),
+        res => panic!("unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn thermometer_puzzle() {
+    let matches = parse_args(&[
+        "sudoku",
+        "solve",
+        "-s",
+        "tasks/thermo.sudoku",
+        "--thermometers",
+        "tasks/thermo.txt",
+    ])
+    .unwrap();
+    let variant = selected_variant(matches.subcommand_matches("solve").unwrap()).unwrap();
+    let mut s = load_sudoku_from("tasks/thermo.sudoku", "auto", None, &variant).unwrap();
+    assert_eq!(5, s.constraints().len());
+    assert_eq!(14, s.clue_count());
+    assert_eq!(None, few_clues_warning(&s));
+    assert_eq!(Ok(1), s.count_solutions(2));
+    // The thermometers are needed, the clues alone have several solutions.
+    let clues = Sudoku::new(s.as_slice().iter().copied()).unwrap();
+    assert_eq!(Ok(2), clues.count_solutions(2));
+    s.solve().unwrap();
+    assert_eq!("correct", validate(&s, None, false).unwrap());
+    let mut easy = load("tasks/1.sudoku").unwrap();
+    easy.solve().unwrap();
+    assert_eq!(easy.to_line(), s.to_line());
+    // Swapping rows 1 and 3 keeps rows, columns and squares valid, but breaks the thermometer in row 1.
+    let line = easy.to_line();
+    let swapped = format!(
+        "{}{}{}{}",
+        &line[18..27],
+        &line[9..18],
+        &line[..9],
+        &line[27..]
+    );
+    match parse_sudoku(&swapped, "line", None, &variant).map(|s| validate(&s, None, false)) {
+        Ok(Err(Failure::InvalidInput(msg))) => {
+            assert!(
+                msg.contains("the thermometer from r1c2 does not increase"),
+                "{}",
+                msg
+            )
+        }
+        res => panic!("unexpected result {:?}", res),
+    }
+    let bent = Variant {
+        thermometers: Some("r1c1 r1c3".to_string()),
+        ..Variant::default()
+    };
+    match load_sudoku_from("tasks/thermo.sudoku", "auto", None, &bent) {
+        Err(Failure::InvalidInput(msg)) => {
+            assert!(msg.ends_with("r1c3 does not touch r1c1"), "{}", msg)
+        }
+        res => panic!("unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn even_odd_puzzle() {
+    let mut s = load("tasks/parity.sudoku").unwrap();
+    assert_eq!(20, s.clue_count());
+    assert_eq!(21, s.parity_marks().iter().flatten().count());
+    assert_eq!(None, few_clues_warning(&s));
+    assert_eq!(Ok(1), s.count_solutions(2));
+    // The marks are needed, the clues alone have several solutions.
+    let clues = Sudoku::new(s.as_slice().iter().copied()).unwrap();
+    assert_eq!(Ok(2), clues.count_solutions(2));
+    // The same marks from a side file.
+    let text = fs::read_to_string("tasks/parity.sudoku").unwrap();
+    let (grid, _) = parity::split_markers(&text).unwrap();
+    let side_file = Variant {
+        parity: Some(text.replace(|c: char| c.is_ascii_digit(), ".")),
+        ..Variant::default()
+    };
+    assert_eq!(s, parse_sudoku(&grid, "line", None, &side_file).unwrap());
+    s.solve().unwrap();
+    assert_eq!("correct", validate(&s, None, false).unwrap());
+    let mut easy = load("tasks/1.sudoku").unwrap();
+    easy.solve().unwrap();
+    assert_eq!(easy.to_line(), s.to_line());
+    // A clue of the other parity is rejected when the puzzle is loaded.
+    let odd_corner = Variant {
+        parity: Some(format!("e{}", ".".repeat(80))),
+        ..Variant::default()
+    };
+    match load_sudoku_from("tasks/1.sudoku", "auto", None, &odd_corner) {
+        Err(Failure::InvalidInput(msg)) => {
+            assert!(
+                msg.ends_with("Invalid parity marks: r1c1 holds 5, but is marked even"),
+                "{}",
+                msg
+            )
+        }
+        res => panic!("unexpected result {:?}", res),
+    }
+    let matches = parse_args(&["sudoku", "verify", "--parity", "tasks/missing.txt"]).unwrap();
+    assert!(matches!(
+        selected_variant(matches.subcommand_matches("verify").unwrap()),
+        Err(Failure::Io(_))
+    ));
+}
+
+#[test]
+fn filter_collection() {
+    let easy = load("tasks/1.sudoku").unwrap().to_line();
+    let hard = load("tasks/very_hard.sudoku").unwrap().to_line();
+    let data = format!(
+        "# puzzles\n{}\n{}\n{}\n{}\n{}\n",
+        easy,
+        AMBIGUOUS,
+        ".".repeat(81),
+        hard,
+        easy.replace('.', "0")
+    );
+    let selected = |filter: &PuzzleFilter| {
+        let mut out = Vec::new();
+        let report = filter_puzzles(
+            io::Cursor::new(&data),
+            "-",
+            &Variant::default(),
+            NodeLimit::Default,
+            filter,
+            false,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(5, report.total);
+        assert_eq!(
+            report.lines.len(),
+            String::from_utf8(out).unwrap().lines().count()
+        );
+        report.lines
+    };
+    assert_eq!(vec![2, 3, 4, 5, 6], selected(&PuzzleFilter::new()));
+    assert_eq!(
+        vec![2, 5, 6],
+        selected(&PuzzleFilter::new().min_clues(24).unique_only())
+    );
+    assert_eq!(
+        vec![4, 5],
+        selected(
+            &PuzzleFilter::new()
+                .max_clues(30)
+                .difficulty(Difficulty::Hard)
+        )
+    );
+    let path = write_temp_file("filter.txt", &data);
+    let output = std::env::temp_dir().join(format!("sudoku_{}_subset.sdm", std::process::id()));
+    let output = output.to_str().unwrap();
+    let args = [
+        "sudoku",
+        "filter",
+        "-s",
+        &path,
+        "--min-clues",
+        "24",
+        "--unique-only",
+        "-o",
+        output,
+    ];
+    let matches = parse_args(&args).unwrap();
+    assert_eq!(
+        PuzzleFilter::new().min_clues(24).unique_only(),
+        puzzle_filter(matches.subcommand_matches("filter").unwrap())
+    );
+    run(&matches).unwrap();
+    let zeros = easy.replace('.', "0");
+    assert_eq!(
+        format!("{}\n{}\n{}\n", zeros, hard.replace('.', "0"), zeros),
+        fs::read_to_string(output).unwrap()
+    );
+    assert!(parse_args(&["sudoku", "filter", "--max-clues", "many"]).is_err());
+    assert!(parse_args(&["sudoku", "filter", "--difficulty", "fiendish"]).is_err());
+}
+
+#[test]
+fn animate_args() {
+    let delay = |args: &[&str]| {
+        let matches = parse_args(args).unwrap();
+        animate_delay(matches.subcommand_matches("solve").unwrap())
+    };
+    assert_eq!(None, delay(&["sudoku", "-s", "tasks/1.sudoku"]));
+    assert_eq!(
+        Some(DEFAULT_ANIMATION_DELAY),
+        delay(&["sudoku", "--animate", "-s", "tasks/1.sudoku"])
+    );
+    assert_eq!(
+        Some(Duration::from_millis(5)),
+        delay(&["sudoku", "-s", "tasks/1.sudoku", "--animate", "5"])
+    );
+    assert!(parse_args(&["sudoku", "--animate", "fast"]).is_err());
+    assert!(parse_args(&["sudoku", "--animate", "-q"]).is_err());
+}
+
+#[test]
+fn animation_frames() {
+    let mut s = load("tasks/1.sudoku").unwrap();
+    let pos = s.as_slice().iter().position(|&v| v == 0).unwrap();
+    let value = s.candidates(pos / 9, pos % 9)[0];
+    s.set(pos / 9, pos % 9, value).unwrap();
+    let plain = animation_frame(&s, pos, "placed", false, 0);
+    assert_eq!(format!("placed\n{}", s), plain);
+    assert_eq!(20, plain.lines().count());
+    let first = animation_frame(&s, pos, "placed", true, 0);
+    assert!(first.starts_with("\x1b[2Kplaced\n"));
+    assert_eq!(1, first.matches("\x1b[7m").count());
+    let next = animation_frame(&s, pos, "placed", true, 20);
+    assert_eq!(format!("\x1b[20A{}", first), next);
+}
+
+#[test]
+fn animate_search() {
+    let task = load("tasks/1.sudoku").unwrap();
+    let mut s = task.clone();
+    let mut animation = Animation::new(Vec::new(), &task, Duration::ZERO, true);
+    s.solve_with_observer(&mut animation).unwrap();
+    assert_eq!(s, animation.grid);
+    assert_eq!(20, animation.printed);
+    let out = String::from_utf8(animation.out).unwrap();
+    assert!(out.starts_with("\x1b[2K"));
+    assert!(out.contains("\x1b[20A\x1b[2K"));
+}
+
+#[test]
+fn checkpoint() {
+    let path = write_temp_file("checkpoint", "");
+    fs::remove_file(&path).unwrap();
+    let task = load("tasks/1.sudoku").unwrap();
+    let mut expected = task.clone();
+    expected.solve().unwrap();
+    let mut s = resume_from_checkpoint(task.clone(), &path).unwrap();
+    assert!(matches!(
+        solve_with_checkpoint(&mut s, &path, &AtomicBool::new(true)),
+        Err(Failure::Interrupted(_))
+    ));
+    assert!(std::path::Path::new(&path).exists());
+    let mut s = resume_from_checkpoint(task.clone(), &path).unwrap();
+    assert!(
+        solve_with_checkpoint(&mut s, &path, &AtomicBool::new(false))
+            .unwrap()
+            .is_ok()
+    );
+    assert_eq!(expected, s);
+    assert!(!std::path::Path::new(&path).exists());
+    fs::write(&path, "{}").unwrap();
+    assert!(matches!(
+        resume_from_checkpoint(task, &path),
+        Err(Failure::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn no_limit_hint() {
+    let mut s = load("tasks/1.sudoku")
+        .unwrap()
+        .with_node_limit(NodeLimit::Max(2));
+    let err = s.solve().unwrap_err();
+    assert_eq!(
+        "Cannot solve sudoku with 30 clues: the search stopped at its limit after 2 placements and 0 backtracks, \
+         pass --no-limit to search on",
+        unsolvable_message(&s, &err)
+    );
+    // Every subcommand which may print the message takes the flag.
+    for command in [
+        "solve",
+        "hint",
+        "rate",
+        "verify",
+        "interactive",
+        "generate",
+        "filter",
+        "stats",
+    ] {
+        let matches = parse_args(&["sudoku", command, "--no-limit"]).unwrap();
+        let (_, matches) = matches.subcommand();
+        assert_eq!(NodeLimit::Unlimited, node_limit(matches.unwrap()));
+    }
+}
+
+#[test]
+fn daily_args() {
+    let args = [
+        "sudoku",
+        "generate",
+        "--daily",
+        "2024-06-01",
+        "--difficulty",
+        "hard",
+    ];
+    assert!(parse_args(&args).is_ok());
+    for date in [
+        "2024-6-1",
+        "2024-13-01",
+        "2024-06-00",
+        "June 1",
+        "2024-06-01-1",
+    ]
+    .iter()
+    {
+        assert!(parse_args(&["sudoku", "generate", "--daily", date]).is_err());
+    }
+    assert!(parse_args(&["sudoku", "generate", "--difficulty", "hard"]).is_err());
+    assert!(parse_args(&["sudoku", "generate", "--daily", "2024-06-01", "--seed", "4"]).is_err());
+    assert!(parse_args(&["sudoku", "generate", "--daily", "2024-06-01", "--size", "4"]).is_err());
+}
+
+#[test]
+fn convert_between_formats() {
+    let variant = Variant::default();
+    let read = |data: &str, format: &str| parse_puzzles(data, format, None, &variant).unwrap();
+    let hard: Sudoku = examples::HARD.parse().unwrap();
+    let single = vec![examples::EASY.parse::<Sudoku>().unwrap()];
+    let batch = vec![single[0].clone(), hard.clone()];
+    for (puzzles, targets) in [
+        (&single, &["line", "sdm", "ss", "pretty"][..]),
+        (&batch, &["line", "sdm"][..]),
+    ]
+    .iter()
+    {
+        for from in targets.iter() {
+            let data = convert(puzzles, from).unwrap();
+            for to in targets.iter() {
+                let converted = convert(&read(&data, from), to).unwrap();
+                assert_eq!(**puzzles, read(&converted, to), "{} to {}", from, to);
+                assert_eq!(convert(puzzles, to).unwrap(), converted);
+            }
+        }
+    }
+    assert_eq!(
+        format!("{}\n{}\n", examples::EASY, examples::HARD),
+        convert(&read(&convert(&batch, "sdm").unwrap(), "sdm"), "line")
+            .unwrap()
+            .replace('0', ".")
+    );
+}
+
+#[test]
+fn convert_keeps_metadata() {
+    let catalog = fs::read_to_string("tasks/catalog.txt").unwrap();
+    let puzzles = parse_puzzles(&catalog, "line", None, &Variant::default()).unwrap();
+    let line = convert(&puzzles, "line").unwrap();
+    assert!(line.starts_with("# id: "));
+    let again = parse_puzzles(&line, "line", None, &Variant::default()).unwrap();
+    assert_eq!(
+        puzzles.iter().map(Sudoku::metadata).collect::<Vec<_>>(),
+        again.iter().map(Sudoku::metadata).collect::<Vec<_>>()
+    );
+    let nyt = load("tasks/nyt.sudoku").unwrap();
+    let pretty = convert(core::slice::from_ref(&nyt), "pretty").unwrap();
+    assert_eq!(nyt.metadata(), metadata::leading_metadata(&pretty).as_ref());
+    // The sdm and ss formats have no comments.
+    let sdm = convert(&puzzles, "sdm").unwrap();
+    assert!(!sdm.contains('#'));
+    assert_eq!(
+        puzzles,
+        parse_puzzles(&sdm, "sdm", None, &Variant::default()).unwrap()
+    );
+}
+
+#[test]
+fn unsupported_conversions() {
+    let big = Sudoku::with_box_size(4).unwrap();
+    for format in ["sdm", "ss"].iter() {
+        match convert(&[Sudoku::empty(), big.clone()], format) {
+            Err(Failure::InvalidInput(msg)) if *format == "sdm" => assert_eq!(
+                "Cannot convert puzzle 2 to sdm: the format holds 9x9 grids only, the puzzle is 16x16",
+                msg
+            ),
+            Err(Failure::InvalidInput(msg)) => assert_eq!(
+                "Cannot convert 2 puzzles to ss: the format holds a single puzzle",
+                msg
+            ),
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+    assert!(convert(core::slice::from_ref(&big), "ss").is_err());
+    assert_eq!(256 + 1, convert(&[big], "line").unwrap().len());
+    assert!(parse_args(&["sudoku", "convert", "-s", "tasks/1.sudoku"]).is_err());
+    let path = write_temp_file("convert_output.sdm", "");
+    let matches = parse_args(&[
+        "sudoku",
+        "convert",
+        "-s",
+        "tasks/catalog.txt",
+        "--format",
+        "sdm",
+        "-o",
+        &path,
+    ])
+    .unwrap();
+    run(&matches).unwrap();
+    assert_eq!(
+        convert(
+            &parse_puzzles(
+                &fs::read_to_string("tasks/catalog.txt").unwrap(),
+                "line",
+                None,
+                &Variant::default()
+            )
+            .unwrap(),
+            "sdm"
+        )
+        .unwrap(),
+        fs::read_to_string(&path).unwrap()
+    );
+}
+
+#[test]
+fn solve_from_partial_file() {
+    let task = write_temp_file("partial_task.txt", examples::HARD);
+    let mut solution: Sudoku = examples::HARD.parse().unwrap();
+    solution.solve().unwrap();
+    let solution = solution.to_line_with(&OutputOptions::default());
+    let run_partial = |partial: &str| {
+        let partial = write_temp_file("partial_entries.txt", partial);
+        let output = write_temp_file("partial_solution.txt", "");
+        let args = [
+            "sudoku",
+            "solve",
+            "-s",
+            &task,
+            "--partial",
+            &partial,
+            "-o",
+            &output,
+            "--format",
+            "line",
+        ];
+        let res = run(&parse_args(&args).unwrap());
+        (res, fs::read_to_string(&output).unwrap())
+    };
+    // The first ten empty cells are filled with the values of the solution.
+    let mut entries = 0;
+    let partial: String = examples::HARD
+        .chars()
+        .zip(solution.chars())
+        .map(|(clue, value)| match clue {
+            '.' if entries < 10 => {
+                entries += 1;
+                value
+            }
+            clue => clue,
+        })
+        .collect();
+    let (res, output) = run_partial(&partial);
+    assert!(res.is_ok());
+    assert_eq!(solution, output.trim());
+    // An altered clue is invalid input, a wrong entry leaves the task unsolvable.
+    let first_clue = examples::HARD.find(|c| c != '.').unwrap();
+    let mut altered = solution.clone();
+    altered.replace_range(first_clue..first_clue + 1, ".");
+    match run_partial(&altered).0 {
+        Err(Failure::InvalidInput(msg)) => assert!(msg.contains(&format!(
+            "clues of the original puzzle are changed at {}",
+            rules::cell_name(first_clue / 9, first_clue % 9)
+        ))),
+        res => panic!("Unexpected result {:?}", res),
+    }
+    let first_empty = examples::HARD.find('.').unwrap();
+    let mut wrong = examples::HARD.to_string();
+    let s: Sudoku = examples::HARD.parse().unwrap();
+    let value = *s
+        .candidates(first_empty / 9, first_empty % 9)
+        .iter()
+        .find(|&&value| value != solution.as_bytes()[first_empty] - b'0')
+        .unwrap();
+    wrong.replace_range(first_empty..first_empty + 1, &value.to_string());
+    assert!(matches!(run_partial(&wrong).0, Err(Failure::Unsolvable)));
+    assert!(parse_args(&[
+        "sudoku",
+        "solve",
+        "-s",
+        &task,
+        "--partial",
+        &task,
+        "--dedupe"
+    ])
+    .is_err());
+}
+
+#[test]
+fn stress_args() {
+    let matches = parse_args(&["sudoku", "stress", "--iterations", "2", "--seed", "3"]).unwrap();
+    assert!(run(&matches).is_ok());
+    let matches = parse_args(&["sudoku", "stress"]).unwrap();
+    let stress = matches.subcommand_matches("stress").unwrap();
+    assert_eq!(Some("1000"), stress.value_of("iterations"));
+    assert!(parse_args(&["sudoku", "stress", "--iterations", "-1"]).is_err());
+    let report = StressReport {
+        iterations: 5,
+        unique: 2,
+        skipped: 1,
+        inconsistencies: vec![stress::Inconsistency {
+            iteration: 4,
+            puzzle: examples::HARD.to_string(),
+            reason: "the solution of solve changes clues".to_string(),
+        }],
+    };
+    assert_eq!(
+        vec![
+            format!(
+                "iteration 4: the solution of solve changes clues: {}",
+                examples::HARD
+            ),
+            "5 puzzles, 2 with a unique solution, 1 skipped after 50000 placements, 1 inconsistencies"
+                .to_string(),
+        ],
+        stress_report(&report)
+    );
+}
+
+#[test]
+fn best_effort_output() {
+    let mut s: Sudoku = examples::WRONG_CLUE.parse().unwrap();
+    let (solved, observer) = solve_observed(&mut s, false);
+    let err = solved.unwrap_err();
+    let lines = best_effort_report(
+        &s,
+        &err,
+        Some(&observer.deepest()),
+        "line",
+        false,
+        &OutputOptions::default(),
+    );
+    assert_eq!(2, lines.len());
+    assert_eq!(
+        "Without guessing 12 of 50 empty cells are determined before 8 cannot be placed in column 9",
+        lines[0]
+    );
+    let best: Sudoku = lines[1].parse().unwrap();
+    assert_eq!(s.filled_count() + 12, best.filled_count());
+    assert!(best.is_consistent());
+    // Nothing is forced before the clues contradict each other.
+    let unsolvable: Sudoku = examples::UNSOLVABLE.parse().unwrap();
+    let err = unsolvable.clone().solve().unwrap_err();
+    assert_eq!(
+        vec!["Without guessing 0 of 72 empty cells are determined before r1c9 has no candidates"],
+        best_effort_report(
+            &unsolvable,
+            &err,
+            None,
+            "line",
+            false,
+            &OutputOptions::default()
+        )
+    );
+}
+
+#[test]
+fn best_effort_of_stopped_search() {
+    let hard: Sudoku = examples::HARD.parse().unwrap();
+    let mut s = hard.clone().with_node_limit(NodeLimit::Max(40));
+    let (solved, observer) = solve_observed(&mut s, false);
+    let err = solved.unwrap_err();
+    let deepest = observer.deepest();
+    let lines = best_effort_report(
+        &s,
+        &err,
+        Some(&deepest),
+        "line",
+        false,
+        &OutputOptions::default(),
+    );
+    let empty = hard.empty_count();
+    assert_eq!(
+        format!(
+            "The deepest search filled {} of {} empty cells",
+            empty - deepest.empty_count(),
+            empty
+        ),
+        lines[lines.len() - 2]
+    );
+    assert!(deepest.empty_count() < empty && deepest.is_consistent());
+    assert!(deepest.altered_clues(&hard).is_empty());
+    assert_eq!(deepest.to_line(), lines[lines.len() - 1]);
 }
@@ -0,0 +1,218 @@
+//! Python bindings, enabled with the `python` feature.
+//!
+//! The extension module is named `sudoku` and holds the `Sudoku` class, build it with
+//! `maturin develop --features python,pyo3/extension-module`:
+//!
+//! ```python
+//! from sudoku import Sudoku
+//!
+//! s = Sudoku.from_string("53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79")
+//! s.solution_count()  # 1
+//! s.solve()
+//! s.to_list()[0]  # [5, 3, 4, 6, 7, 8, 9, 1, 2]
+//! ```
+
+// The code PyO3 generates for the methods converts their errors into `PyErr` even when they already are.
+#![allow(clippy::useless_conversion)]
+
+use crate::solvers::backtracking::{Sudoku, SudokuError};
+use pyo3::exceptions::{PyIndexError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+/// Sudoku of the Python module, exposed as the `Sudoku` class.
+#[pyclass(name = "Sudoku")]
+#[derive(Clone)]
+pub struct PySudoku {
+    sudoku: Sudoku,
+}
+
+impl From<SudokuError> for PyErr {
+    fn from(err: SudokuError) -> PyErr {
+        PyValueError::new_err(err.to_string())
+    }
+}
+
+impl PySudoku {
+    /// Returns the position of the cell, an `IndexError` if it is outside of the grid.
+    fn check_cell(&self, row: usize, col: usize) -> PyResult<()> {
+        let side = self.sudoku.side();
+        match row < side && col < side {
+            true => Ok(()),
+            false => Err(PyIndexError::new_err(format!(
+                "cell ({}, {}) is outside of the {}x{} grid",
+                row, col, side, side
+            ))),
+        }
+    }
+
+    /// Returns a `ValueError` naming the repeated values unless the clues are consistent.
+    fn check_consistent(&self) -> PyResult<()> {
+        match self.sudoku.is_consistent() {
+            true => Ok(()),
+            false => Err(SudokuError::ConflictingClues(
+                self.sudoku
+                    .conflicts()
+                    .iter()
+                    .map(|c| c.to_string())
+                    .chain(self.sudoku.violations().iter().map(|v| v.to_string()))
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            )
+            .into()),
+        }
+    }
+}
+
+#[pymethods]
+impl PySudoku {
+    /// Parses a sudoku in any format accepted by the command line application, e.g. a line of 81 characters.
+    #[staticmethod]
+    fn from_string(text: &str) -> PyResult<PySudoku> {
+        Ok(PySudoku {
+            sudoku: text.parse()?,
+        })
+    }
+
+    /// Builds a sudoku from a list of rows, each a list of ints with `0` for empty cells.
+    #[staticmethod]
+    fn from_list(rows: Vec<Vec<u8>>) -> PyResult<PySudoku> {
+        if let Some(row) = rows.iter().position(|row| row.len() != rows.len()) {
+            return Err(PyValueError::new_err(format!(
+                "row {} has {} cells, expected {}",
+                row + 1,
+                rows[row].len(),
+                rows.len()
+            )));
+        }
+        let side = rows.len();
+        if let Some((row, col, &value)) = rows.iter().enumerate().find_map(|(row, cells)| {
+            cells
+                .iter()
+                .enumerate()
+                .find(|(_, &value)| value as usize > side)
+                .map(|(col, value)| (row, col, value))
+        }) {
+            return Err(SudokuError::InvalidValue { row, col, value }.into());
+        }
+        let cells = rows.len() * rows.len();
+        Sudoku::new(rows.into_iter().flatten())
+            .map(|sudoku| PySudoku { sudoku })
+            .ok_or_else(|| PyValueError::new_err(format!("unsupported grid of {} cells", cells)))
+    }
+
+    /// Returns the rows of the grid, `0` for empty cells.
+    fn to_list(&self) -> Vec<Vec<u8>> {
+        self.sudoku
+            .as_slice()
+            .chunks(self.sudoku.side())
+            .map(|row| row.to_vec())
+            .collect()
+    }
+
+    /// Returns the grid as a single line with `.` for empty cells.
+    #[pyo3(name = "to_string")]
+    fn to_line(&self) -> String {
+        self.sudoku.to_line()
+    }
+
+    /// Solves the sudoku in place, a `ValueError` is raised if it has no solution.
+    fn solve(&mut self) -> PyResult<()> {
+        self.check_consistent()?;
+        self.sudoku
+            .solve()
+            .map_err(|_| PyValueError::new_err("the sudoku has no solution"))
+    }
+
+    /// Returns the number of solutions, counting stops once `limit` solutions are found. A `RuntimeError` is raised if
+    /// the search stops at its node limit first.
+    #[pyo3(signature = (limit = 2))]
+    fn solution_count(&self, limit: usize) -> PyResult<usize> {
+        self.sudoku
+            .count_solutions(limit)
+            .map_err(|err| PyRuntimeError::new_err(err.to_string()))
+    }
+
+    /// Returns the values allowed in the empty cell (0-based row and column), none for filled cells.
+    fn candidates(&self, row: usize, col: usize) -> PyResult<Vec<u8>> {
+        self.check_cell(row, col)?;
+        Ok(self.sudoku.candidates(row, col))
+    }
+
+    /// Returns `easy`, `medium` or `hard`, see the `rate` command, a `ValueError` is raised if there is no solution.
+    fn rate(&self) -> PyResult<String> {
+        self.check_consistent()?;
+        self.sudoku
+            .rate()
+            .map(|difficulty| difficulty.to_string())
+            .map_err(|_| PyValueError::new_err("the sudoku has no solution"))
+    }
+
+    fn __str__(&self) -> String {
+        self.sudoku.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Sudoku.from_string(\"{}\")", self.sudoku.to_line())
+    }
+}
+
+/// The `sudoku` Python module.
+#[pymodule]
+fn sudoku(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PySudoku>()
+}
+
+#[cfg(test)]
+const PUZZLE: &str =
+    "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+/// Runs the code with the class registered in the globals of a fresh Python interpreter.
+#[cfg(test)]
+fn run_python(code: &str) -> PyResult<()> {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let globals = pyo3::types::PyDict::new_bound(py);
+        globals.set_item("Sudoku", py.get_type_bound::<PySudoku>())?;
+        globals.set_item("PUZZLE", PUZZLE)?;
+        py.run_bound(code, Some(&globals), None)
+    })
+}
+
+#[test]
+fn solve_from_python() {
+    run_python(
+        r#"
+s = Sudoku.from_string(PUZZLE)
+assert s.solution_count() == 1
+assert s.candidates(0, 2) == [1, 2, 4]
+assert s.candidates(0, 0) == []
+assert s.rate() == "easy"
+s.solve()
+assert s.to_list()[0] == [5, 3, 4, 6, 7, 8, 9, 1, 2]
+assert "." not in s.to_string()
+assert Sudoku.from_list(s.to_list()).to_string() == s.to_string()
+assert Sudoku.from_list([[0] * 9] * 9).solution_count(3) == 3
+"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn errors_in_python() {
+    run_python(
+        r#"
+def error(f):
+    try:
+        f()
+    except Exception as err:
+        return type(err).__name__ + ": " + str(err)
+
+assert error(lambda: Sudoku.from_string("123")) == "ValueError: Cannot parse sudoku: expected 81 cells, found 3"
+assert error(lambda: Sudoku.from_string(PUZZLE).candidates(9, 0)).startswith("IndexError: cell (9, 0)")
+assert error(lambda: Sudoku.from_list([[0] * 9] * 8)) == "ValueError: row 1 has 9 cells, expected 8"
+assert error(lambda: Sudoku.from_list([[10] + [0] * 8] + [[0] * 9] * 8)) == "ValueError: Invalid value 10 in row 1, column 1"
+assert error(Sudoku.from_string("11" + "." * 79).solve).startswith("ValueError: Conflicting clues: 1 is repeated in row 1")
+"#,
+    )
+    .unwrap();
+}
@@ -0,0 +1,876 @@
+use crate::prelude::*;
+use crate::solvers::backtracking::{value_char, Sudoku, EMPTY};
+use crate::solvers::geometry::column_letter;
+use crate::solvers::parity::Parity;
+#[cfg(test)]
+use crate::solvers::{backtracking::solvable_field_helper, geometry::Geometry};
+
+/// Characters used to draw one horizontal border of the Unicode grid.
+struct Border {
+    left: char,
+    fill: char,
+    light: char,
+    heavy: char,
+    right: char,
+}
+
+const TOP: Border = Border {
+    left: '┏',
+    fill: '━',
+    light: '┯',
+    heavy: '┳',
+    right: '┓',
+};
+const LIGHT: Border = Border {
+    left: '┠',
+    fill: '─',
+    light: '┼',
+    heavy: '╂',
+    right: '┨',
+};
+const HEAVY: Border = Border {
+    left: '┣',
+    fill: '━',
+    light: '┿',
+    heavy: '╋',
+    right: '┫',
+};
+const BOTTOM: Border = Border {
+    left: '┗',
+    fill: '━',
+    light: '┷',
+    heavy: '┻',
+    right: '┛',
+};
+
+impl Border {
+    /// Draws the border of a grid with cells `width` characters wide.
+    fn draw(&self, out: &mut String, side: usize, box_cols: usize, width: usize) {
+        out.push(self.left);
+        for col in 0..side {
+            if col != 0 {
+                out.push(match col % box_cols {
+                    0 => self.heavy,
+                    _ => self.light,
+                });
+            }
+            (0..width).for_each(|_| out.push(self.fill));
+        }
+        out.push(self.right);
+        out.push('\n');
+    }
+}
+
+/// ANSI escape sequence starting bold text.
+const BOLD: &str = "\x1b[1m";
+/// ANSI escape sequence starting green text.
+const GREEN: &str = "\x1b[32m";
+/// ANSI escape sequence resetting text attributes.
+const RESET: &str = "\x1b[0m";
+/// ANSI escape sequence starting text with swapped foreground and background colors.
+const REVERSE: &str = "\x1b[7m";
+
+/// Settings of `Sudoku::to_svg`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SvgOptions {
+    /// Width and height of a cell in pixels.
+    pub cell_size: u32,
+}
+
+/// Cells of 40x40 pixels.
+impl Default for SvgOptions {
+    fn default() -> SvgOptions {
+        SvgOptions { cell_size: 40 }
+    }
+}
+
+/// Settings of `Sudoku::to_html_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HtmlOptions {
+    /// Pencil marks with the candidates of every empty cell.
+    pub show_candidates: bool,
+}
+
+/// Settings of `Sudoku::to_latex_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatexOptions {
+    /// Candidates of every empty cell in small type, otherwise empty cells are blank.
+    pub show_candidates: bool,
+}
+
+/// Settings of the plain text formats: `Sudoku::to_line_with`, `Sudoku::to_string_with` and the `.sdm` and `.ss`
+/// writers.
+///
+/// Settings may be added, so the options are built from `OutputOptions::default()` with the `with_` methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct OutputOptions {
+    /// Character of empty cells, None keeps the one of the format: `.` in lines and `.ss` grids, a space in the pretty
+    /// grid and `0` in `.sdm` collections.
+    pub empty_char: Option<char>,
+    /// Brackets around the values filled by the solver in the pretty grid, like `[4]`, see `Sudoku::to_string_marked`.
+    pub mark_filled: bool,
+}
+
+impl OutputOptions {
+    /// Returns the options with the character of empty cells, None for the one of the format.
+    pub fn with_empty_char(self, empty_char: Option<char>) -> OutputOptions {
+        OutputOptions { empty_char, ..self }
+    }
+
+    /// Returns the options with or without brackets around the values filled by the solver.
+    pub fn with_mark_filled(self, mark_filled: bool) -> OutputOptions {
+        OutputOptions {
+            mark_filled,
+            ..self
+        }
+    }
+
+    /// Returns the character of empty cells, `default` is the one of the format.
+    pub(crate) fn empty(&self, default: char) -> char {
+        self.empty_char.unwrap_or(default)
+    }
+}
+
+/// Width of the lines between cells in SVG pixels.
+const SVG_THIN: u32 = 1;
+/// Width of the lines on box boundaries and the border in SVG pixels, also the margin around the grid.
+const SVG_THICK: u32 = 3;
+
+impl Sudoku {
+    /// Returns the sudoku as a single line like `to_line`, with the empty cells drawn as the options say.
+    pub fn to_line_with(&self, opts: &OutputOptions) -> String {
+        let empty = opts.empty('.');
+        self.field
+            .iter()
+            .map(|&cell| match cell {
+                EMPTY => empty,
+                v => value_char(v),
+            })
+            .collect()
+    }
+
+    /// Returns the pretty grid printed by `Display`, with the empty cells and the filled ones drawn as the options say.
+    ///
+    /// Like `Display` it frames the cells of an even/odd sudoku like `[4]` if they are marked even and like `(3)` if
+    /// they are marked odd, unless `mark_filled` puts brackets around the filled cells.
+    pub fn to_string_with(&self, opts: &OutputOptions) -> String {
+        let empty = opts.empty(' ');
+        // Without givens, e.g. for a sudoku read from its solution, filled cells cannot be told apart from clues.
+        let mark_filled = opts.mark_filled && self.clue_count() > 0;
+        let mut out = String::new();
+        self.write_cells(&mut out, |pos| match self.field[pos] {
+            EMPTY if mark_filled => format!(" {} ", empty),
+            EMPTY => self.framed(pos, empty),
+            v if mark_filled && !self.givens[pos] => format!("[{}]", value_char(v)),
+            v if mark_filled => format!(" {} ", value_char(v)),
+            v => self.framed(pos, value_char(v)),
+        })
+        .unwrap();
+        out
+    }
+
+    /// Returns the pretty grid of `Display` with the values filled by the solver in brackets like `[4]`, the givens
+    /// stay like ` 4 ` so the columns keep their alignment.
+    ///
+    /// Without color the filled cells still stand out from the clues. A sudoku without givens has no brackets.
+    pub fn to_string_marked(&self) -> String {
+        self.to_string_with(&OutputOptions::default().with_mark_filled(true))
+    }
+
+    /// Returns the pretty grid of `Display` with the column letters from `A` above it and the row numbers from 1 on
+    /// the left, so cells can be named like `E5`, see `Pos::label`.
+    pub fn to_labeled_string(&self) -> String {
+        let side = self.side();
+        let margin = side.to_string().len() + 1;
+        let mut out = " ".repeat(margin);
+        for col in 0..side {
+            out.push_str(&format!("  {} ", column_letter(col)));
+        }
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+        for (i, line) in self.to_string().lines().enumerate() {
+            match i % 2 {
+                1 => out.push_str(&format!("{:>width$} ", i / 2 + 1, width = margin - 1)),
+                _ => out.push_str(&" ".repeat(margin)),
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the sudoku as the pretty grid with givens in bold and solver-filled cells in green.
+    ///
+    /// The result contains ANSI escape sequences, so it is meant to be printed to a terminal.
+    pub fn to_colored_string(&self) -> String {
+        self.to_colored_string_with(&OutputOptions::default())
+    }
+
+    /// Renders the colored grid of `to_colored_string` with the empty cells drawn as the options say.
+    pub fn to_colored_string_with(&self, opts: &OutputOptions) -> String {
+        let empty = opts.empty(' ').to_string();
+        let mut out = String::new();
+        self.write_grid(&mut out, |pos| match self.field[pos] {
+            EMPTY => empty.clone(),
+            v if self.givens[pos] => format!("{}{}{}", BOLD, value_char(v), RESET),
+            v => format!("{}{}{}", GREEN, value_char(v), RESET),
+        })
+        .unwrap();
+        out
+    }
+
+    /// Renders the sudoku as the colored grid with the cell at `changed` in reverse video, a frame of an animated
+    /// search.
+    ///
+    /// Attributes are reset after every highlighted cell, so an interrupted frame leaves the terminal usable.
+    pub fn to_animation_frame(&self, changed: usize) -> String {
+        let mut out = String::new();
+        self.write_grid(&mut out, |pos| match self.field[pos] {
+            EMPTY if pos == changed => format!("{} {}", REVERSE, RESET),
+            v if pos == changed => format!("{}{}{}", REVERSE, value_char(v), RESET),
+            EMPTY => " ".to_string(),
+            v if self.givens[pos] => format!("{}{}{}", BOLD, value_char(v), RESET),
+            v => format!("{}{}{}", GREEN, value_char(v), RESET),
+        })
+        .unwrap();
+        out
+    }
+
+    /// Renders the sudoku as a grid drawn with Unicode box-drawing characters.
+    ///
+    /// Heavy lines separate 3x3 squares and light lines separate cells, empty cells are blank.
+    pub fn to_unicode_string(&self) -> String {
+        let geometry = self.geometry();
+        let (side, box_rows, box_cols) =
+            (geometry.side(), geometry.box_rows(), geometry.box_cols());
+        let mut out = String::new();
+        TOP.draw(&mut out, side, box_cols, 3);
+        for row in 0..side {
+            if row != 0 {
+                match row % box_rows {
+                    0 => HEAVY.draw(&mut out, side, box_cols, 3),
+                    _ => LIGHT.draw(&mut out, side, box_cols, 3),
+                }
+            }
+            for col in 0..side {
+                out.push(match col % box_cols {
+                    0 => '┃',
+                    _ => '│',
+                });
+                out.push_str(&match self.field[row * side + col] {
+                    EMPTY => "   ".to_string(),
+                    v => format!(" {} ", value_char(v)),
+                });
+            }
+            out.push_str("┃\n");
+        }
+        BOTTOM.draw(&mut out, side, box_cols, 3);
+        out
+    }
+
+    /// Renders the sudoku as the Unicode grid with pencil marks: every cell is a mini-grid shaped like a box.
+    ///
+    /// Empty cells list their candidates at the place of each value and `.` for the values ruled out, filled cells
+    /// show their value in the middle of the mini-grid. Once the player took notes, see `add_note`, empty cells list
+    /// the noted values instead of the candidates.
+    pub fn to_marked_string(&self) -> String {
+        let geometry = self.geometry();
+        let (side, box_rows, box_cols) =
+            (geometry.side(), geometry.box_rows(), geometry.box_cols());
+        let width = 2 * box_cols + 1;
+        let mut out = String::new();
+        TOP.draw(&mut out, side, box_cols, width);
+        for row in 0..side {
+            if row != 0 {
+                match row % box_rows {
+                    0 => HEAVY.draw(&mut out, side, box_cols, width),
+                    _ => LIGHT.draw(&mut out, side, box_cols, width),
+                }
+            }
+            let marks: Vec<Vec<char>> = (0..side).map(|col| self.marks(row, col)).collect();
+            for line in 0..box_rows {
+                for (col, marks) in marks.iter().enumerate() {
+                    out.push(match col % box_cols {
+                        0 => '┃',
+                        _ => '│',
+                    });
+                    for mark in marks[line * box_cols..(line + 1) * box_cols].iter() {
+                        out.push(' ');
+                        out.push(*mark);
+                    }
+                    out.push(' ');
+                }
+                out.push_str("┃\n");
+            }
+        }
+        BOTTOM.draw(&mut out, side, box_cols, width);
+        out
+    }
+
+    /// Returns the mini-grid of the cell for `to_marked_string`, row by row.
+    fn marks(&self, row: usize, col: usize) -> Vec<char> {
+        let geometry = self.geometry();
+        match self.field[geometry.pos(row, col)] {
+            EMPTY => {
+                let candidates: Vec<u8> = match self.has_notes() {
+                    true => self.notes(row, col).collect(),
+                    false => self.candidates(row, col),
+                };
+                self.values()
+                    .map(|value| match candidates.contains(&value) {
+                        true => value_char(value),
+                        false => '.',
+                    })
+                    .collect()
+            }
+            value => {
+                let middle =
+                    geometry.box_rows() / 2 * geometry.box_cols() + geometry.box_cols() / 2;
+                (0..geometry.side())
+                    .map(|i| if i == middle { value_char(value) } else { ' ' })
+                    .collect()
+            }
+        }
+    }
+
+    /// Renders the sudoku as a self-contained HTML table with inline CSS, see `to_html_with`.
+    pub fn to_html(&self) -> String {
+        self.to_html_with(&HtmlOptions::default())
+    }
+
+    /// Renders the sudoku as a self-contained HTML table with inline CSS, e.g. to embed it into a page.
+    ///
+    /// Thick borders separate boxes, givens are bold and solver-filled cells gray. With `show_candidates` empty cells
+    /// hold their candidates as small pencil marks. Cells of an even/odd sudoku marked even are shaded, the ones marked
+    /// odd hold a shaded circle.
+    pub fn to_html_with(&self, opts: &HtmlOptions) -> String {
+        let geometry = self.geometry();
+        let side = geometry.side();
+        let mut out = String::from(concat!(
+            "<table style=\"border-collapse:collapse;border:3px solid black;",
+            "font-family:sans-serif;font-size:1.5em\">\n"
+        ));
+        for row in 0..side {
+            out.push_str("<tr>");
+            for col in 0..side {
+                let pos = geometry.pos(row, col);
+                let border = |first: bool| match first {
+                    true => "3px solid black",
+                    false => "1px solid gray",
+                };
+                out.push_str(&format!(
+                    concat!(
+                        "<td style=\"width:2em;height:2em;padding:0;text-align:center;",
+                        "vertical-align:middle;border-top:{};border-left:{}"
+                    ),
+                    border(row % geometry.box_rows() == 0),
+                    border(col % geometry.box_cols() == 0)
+                ));
+                match self.parity(row, col) {
+                    Some(Parity::Even) => out.push_str(";background:lightgray"),
+                    Some(Parity::Odd) => out.push_str(
+                        ";background:radial-gradient(circle,lightgray 65%,transparent 66%)",
+                    ),
+                    None => {}
+                }
+                match self.field[pos] {
+                    EMPTY if opts.show_candidates => {
+                        let marks: Vec<String> = self
+                            .candidates(row, col)
+                            .into_iter()
+                            .map(|value| value_char(value).to_string())
+                            .collect();
+                        out.push_str(&format!(
+                            "\"><span style=\"font-size:0.4em;color:gray\">{}</span>",
+                            marks.join(" ")
+                        ));
+                    }
+                    EMPTY => out.push_str("\">"),
+                    v if self.givens[pos] => {
+                        out.push_str(&format!(";font-weight:bold\">{}", value_char(v)))
+                    }
+                    v => out.push_str(&format!(";color:dimgray\">{}", value_char(v))),
+                }
+                out.push_str("</td>");
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</table>\n");
+        out
+    }
+
+    /// Renders the sudoku as a TikZ picture, see `to_latex_with`.
+    pub fn to_latex(&self) -> String {
+        self.to_latex_with(&LatexOptions::default())
+    }
+
+    /// Renders the sudoku as a TikZ picture to `\input` into a LaTeX document which loads the `tikz` package, the only
+    /// one needed.
+    ///
+    /// Cells are 8mm squares with thin rules between them and thick ones on box boundaries and the border, givens are
+    /// bold. With `show_candidates` empty cells hold their candidates in tiny type, laid out like the cells of a box.
+    pub fn to_latex_with(&self, opts: &LatexOptions) -> String {
+        let geometry = self.geometry();
+        let (side, box_rows, box_cols) =
+            (geometry.side(), geometry.box_rows(), geometry.box_cols());
+        let mut out = format!(
+            concat!(
+                "% Sudoku grid, needs \\usepackage{{tikz}}\n",
+                "\\begin{{tikzpicture}}[x=8mm,y=8mm,every node/.style={{font=\\large}}]\n",
+                "\\draw[thin] (0,0) grid ({0},{0});\n",
+                "\\draw[very thick,xstep={1},ystep={2}] (0,0) grid ({0},{0});\n",
+                "\\draw[very thick] (0,0) rectangle ({0},{0});\n"
+            ),
+            side, box_cols, box_rows
+        );
+        for (pos, &value) in self.field.iter().enumerate() {
+            let (row, col) = geometry.cell(pos);
+            // Rows are numbered from the top, while TikZ places y = 0 at the bottom.
+            let top = side - row;
+            match value {
+                EMPTY if opts.show_candidates => {
+                    for candidate in self.candidates(row, col) {
+                        let i = candidate as usize - 1;
+                        out.push_str(&format!(
+                            "\\node[font=\\tiny] at ({:.2},{:.2}) {{{}}};\n",
+                            col as f64
+                                + (i % box_cols) as f64 / box_cols as f64
+                                + 0.5 / box_cols as f64,
+                            top as f64
+                                - (i / box_cols) as f64 / box_rows as f64
+                                - 0.5 / box_rows as f64,
+                            value_char(candidate)
+                        ));
+                    }
+                }
+                EMPTY => {}
+                v if self.givens[pos] => out.push_str(&format!(
+                    "\\node at ({}.5,{}.5) {{\\textbf{{{}}}}};\n",
+                    col,
+                    top - 1,
+                    value_char(v)
+                )),
+                v => out.push_str(&format!(
+                    "\\node at ({}.5,{}.5) {{{}}};\n",
+                    col,
+                    top - 1,
+                    value_char(v)
+                )),
+            }
+        }
+        out.push_str("\\end{tikzpicture}\n");
+        out
+    }
+
+    /// Renders the sudoku as a standalone SVG image, e.g. for printing.
+    ///
+    /// Thin lines separate cells and thick lines boxes, givens are bold and solver-filled cells lighter. Cells of an
+    /// even/odd sudoku marked even are shaded squares, the ones marked odd hold a shaded circle.
+    pub fn to_svg(&self, opts: &SvgOptions) -> String {
+        let geometry = self.geometry();
+        let side = geometry.side() as u32;
+        let cell = opts.cell_size;
+        let grid = side * cell;
+        let size = grid + 2 * SVG_THICK;
+        let mut out = format!(
+            concat!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\n",
+                "<rect width=\"{0}\" height=\"{0}\" fill=\"white\"/>\n"
+            ),
+            size
+        );
+        // The shading goes below the lines.
+        out.push_str(&self.svg_parity(cell));
+        out.push_str(&format!(
+            "<g transform=\"translate({0} {0})\" stroke=\"black\">\n",
+            SVG_THICK
+        ));
+        for i in 1..side {
+            let at = i * cell;
+            let vertical = match i % geometry.box_cols() as u32 {
+                0 => SVG_THICK,
+                _ => SVG_THIN,
+            };
+            let horizontal = match i % geometry.box_rows() as u32 {
+                0 => SVG_THICK,
+                _ => SVG_THIN,
+            };
+            out.push_str(&format!(
+                "<line x1=\"{0}\" y1=\"0\" x2=\"{0}\" y2=\"{1}\" stroke-width=\"{2}\"/>\n",
+                at, grid, vertical
+            ));
+            out.push_str(&format!(
+                "<line x1=\"0\" y1=\"{0}\" x2=\"{1}\" y2=\"{0}\" stroke-width=\"{2}\"/>\n",
+                at, grid, horizontal
+            ));
+        }
+        out.push_str(&format!(
+            "<rect width=\"{0}\" height=\"{0}\" fill=\"none\" stroke-width=\"{1}\"/>\n</g>\n",
+            grid, SVG_THICK
+        ));
+        out.push_str(&format!(
+            concat!(
+                "<g transform=\"translate({} {})\" font-family=\"sans-serif\" font-size=\"{}\" ",
+                "text-anchor=\"middle\" dominant-baseline=\"central\">\n"
+            ),
+            SVG_THICK,
+            SVG_THICK,
+            cell * 3 / 5
+        ));
+        for (pos, &value) in self.field.iter().enumerate() {
+            if value == EMPTY {
+                continue;
+            }
+            let (row, col) = geometry.cell(pos);
+            let style = match self.givens[pos] {
+                true => "font-weight=\"bold\" fill=\"black\"",
+                false => "font-weight=\"normal\" fill=\"dimgray\"",
+            };
+            out.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" {}>{}</text>\n",
+                col as u32 * cell + cell / 2,
+                row as u32 * cell + cell / 2,
+                style,
+                value_char(value)
+            ));
+        }
+        out.push_str("</g>\n</svg>\n");
+        out
+    }
+
+    /// Returns the SVG group with a shaded square for every cell marked even and a shaded circle for every cell marked
+    /// odd, empty for sudokus without parity marks.
+    fn svg_parity(&self, cell: u32) -> String {
+        if self.parity_marks().is_empty() {
+            return String::new();
+        }
+        let mut out = format!(
+            "<g transform=\"translate({0} {0})\" fill=\"lightgray\">\n",
+            SVG_THICK
+        );
+        for (pos, mark) in self.parity_marks().iter().enumerate() {
+            let (row, col) = self.geometry().cell(pos);
+            let (x, y) = (col as u32 * cell, row as u32 * cell);
+            match mark {
+                Some(Parity::Even) => out.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\"/>\n",
+                    x, y, cell
+                )),
+                Some(Parity::Odd) => out.push_str(&format!(
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\"/>\n",
+                    x + cell / 2,
+                    y + cell / 2,
+                    cell * 2 / 5
+                )),
+                None => {}
+            }
+        }
+        out.push_str("</g>\n");
+        out
+    }
+}
+
+#[test]
+fn unicode_string() {
+    let mut field: Vec<u8> = vec![9; 81];
+    field[0] = 0;
+    field[40] = 5;
+    let sudoku = Sudoku::new(field.into_iter()).unwrap();
+    let expected_out = concat!(
+        "┏━━━┯━━━┯━━━┳━━━┯━━━┯━━━┳━━━┯━━━┯━━━┓\n",
+        "┃   │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃\n",
+        "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨\n",
+        "┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃\n",
+        "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨\n",
+        "┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃\n",
+        "┣━━━┿━━━┿━━━╋━━━┿━━━┿━━━╋━━━┿━━━┿━━━┫\n",
+        "┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃\n",
+        "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨\n",
+        "┃ 9 │ 9 │ 9 ┃ 9 │ 5 │ 9 ┃ 9 │ 9 │ 9 ┃\n",
+        "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨\n",
+        "┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃\n",
+        "┣━━━┿━━━┿━━━╋━━━┿━━━┿━━━╋━━━┿━━━┿━━━┫\n",
+        "┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃\n",
+        "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨\n",
+        "┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃\n",
+        "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨\n",
+        "┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃ 9 │ 9 │ 9 ┃\n",
+        "┗━━━┷━━━┷━━━┻━━━┷━━━┷━━━┻━━━┷━━━┷━━━┛\n"
+    );
+
+    assert_eq!(expected_out, sudoku.to_unicode_string());
+}
+
+#[test]
+fn marked_string() {
+    let s = Sudoku::new(vec![1, 0, 0, 0, 0, 0, 2, 0, 0, 3, 0, 0, 0, 0, 0, 4].into_iter()).unwrap();
+    let expected = concat!(
+        "┏━━━━━┯━━━━━┳━━━━━┯━━━━━┓\n",
+        "┃     │ . 2 ┃ . . │ . . ┃\n",
+        "┃   1 │ . 4 ┃ 3 4 │ 3 . ┃\n",
+        "┠─────┼─────╂─────┼─────┨\n",
+        "┃ . . │ . . ┃     │ 1 . ┃\n",
+        "┃ 3 4 │ . 4 ┃   2 │ 3 . ┃\n",
+        "┣━━━━━┿━━━━━╋━━━━━┿━━━━━┫\n",
+        "┃ . 2 │     ┃ 1 . │ 1 2 ┃\n",
+        "┃ . 4 │   3 ┃ . . │ . . ┃\n",
+        "┠─────┼─────╂─────┼─────┨\n",
+        "┃ . 2 │ 1 2 ┃ 1 . │     ┃\n",
+        "┃ . . │ . . ┃ 3 . │   4 ┃\n",
+        "┗━━━━━┷━━━━━┻━━━━━┷━━━━━┛\n",
+    );
+    assert_eq!(expected, s.to_marked_string());
+    let marked = solvable_field_helper().to_marked_string();
+    assert_eq!(9 * 3 + 10, marked.lines().count());
+    // Givens 5 and 3 of the first row are in the middle of their mini-grids.
+    assert!(marked
+        .lines()
+        .nth(1)
+        .unwrap()
+        .starts_with("┃       │       │ 1 2 . ┃"));
+    assert!(marked
+        .lines()
+        .nth(2)
+        .unwrap()
+        .starts_with("┃   5   │   3   │ 4 . . ┃"));
+    let mut noted = solvable_field_helper();
+    noted.add_note(0, 2, 2).unwrap();
+    let marked = noted.to_marked_string();
+    assert!(marked
+        .lines()
+        .nth(1)
+        .unwrap()
+        .starts_with("┃       │       │ . 2 . ┃ . . . │"));
+}
+
+#[test]
+fn colored_string() {
+    let mut s: Sudoku = "12.".repeat(27).parse().unwrap();
+    s.field[2] = 3;
+    let out = s.to_colored_string();
+    assert!(out.starts_with(
+        "=====================================\n| \x1b[1m1\x1b[0m | \x1b[1m2\x1b[0m | \x1b[32m3\x1b[0m |"
+    ));
+    assert_eq!(
+        s.to_string(),
+        out.replace(BOLD, "").replace(GREEN, "").replace(RESET, "")
+    );
+}
+
+#[test]
+fn marked_filled_cells() {
+    let puzzle = Sudoku::with_box_size(2)
+        .unwrap()
+        .with_field(vec![1, 0, 3, 4, 3, 4, 0, 2, 0, 1, 4, 3, 4, 3, 2, 0]);
+    let mut solved = puzzle.clone();
+    solved.solve().unwrap();
+    let expected = concat!(
+        "=================\n",
+        "| 1 |[2]| 3 | 4 |\n",
+        "|-------|-------|\n",
+        "| 3 | 4 |[1]| 2 |\n",
+        "=================\n",
+        "|[2]| 1 | 4 | 3 |\n",
+        "|-------|-------|\n",
+        "| 4 | 3 | 2 |[1]|\n",
+        "=================\n",
+    );
+    assert_eq!(expected, solved.to_string_marked());
+    // Only filled cells are marked, the puzzle itself looks like its pretty grid.
+    assert_eq!(puzzle.to_string(), puzzle.to_string_marked());
+    // A grid read from the full solution has nothing but givens.
+    let full = puzzle.with_field(solved.field.clone());
+    assert_eq!(solved.to_string(), full.to_string_marked());
+    // Without givens there is no clue to set the filled cells apart from.
+    let mut no_givens = Sudoku::with_box_size(2).unwrap();
+    no_givens.set(0, 0, 1).unwrap();
+    assert_eq!(no_givens.to_string(), no_givens.to_string_marked());
+}
+
+#[test]
+fn labeled_string() {
+    let expected = concat!(
+        "    A   B   C   D   E   F   G   H   I\n",
+        "  =====================================\n",
+        "1 | 5 | 3 |   |   | 7 |   |   |   |   |\n",
+        "  |-----------|-----------|-----------|\n",
+        "2 | 6 |   |   | 1 | 9 | 5 |   |   |   |\n",
+        "  |-----------|-----------|-----------|\n",
+        "3 |   | 9 | 8 |   |   |   |   | 6 |   |\n",
+        "  =====================================\n",
+        "4 | 8 |   |   |   | 6 |   |   |   | 3 |\n",
+        "  |-----------|-----------|-----------|\n",
+        "5 | 4 |   |   | 8 |   | 3 |   |   | 1 |\n",
+        "  |-----------|-----------|-----------|\n",
+        "6 | 7 |   |   |   | 2 |   |   |   | 6 |\n",
+        "  =====================================\n",
+        "7 |   | 6 |   |   |   |   | 2 | 8 |   |\n",
+        "  |-----------|-----------|-----------|\n",
+        "8 |   |   |   | 4 | 1 | 9 |   |   | 5 |\n",
+        "  |-----------|-----------|-----------|\n",
+        "9 |   |   |   |   | 8 |   |   | 7 | 9 |\n",
+        "  =====================================\n",
+    );
+    assert_eq!(expected, solvable_field_helper().to_labeled_string());
+    // Two-digit row numbers widen the margin of every line.
+    let large = Sudoku::with_box_size(4).unwrap().to_labeled_string();
+    let lines: Vec<&str> = large.lines().collect();
+    assert!(lines[0].starts_with("     A   B") && lines[0].ends_with("   P"));
+    assert!(lines[1].starts_with("   ="));
+    assert!(lines[2].starts_with(" 1 |   |"));
+    assert!(lines[32].starts_with("16 |   |"));
+}
+
+#[test]
+fn svg() {
+    let s: Sudoku = "12.".repeat(27).parse().unwrap();
+    let svg = s.to_svg(&SvgOptions::default());
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"366\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(54, svg.matches("<text ").count());
+    assert_eq!(54, svg.matches("font-weight=\"bold\"").count());
+    assert_eq!(12, svg.matches("stroke-width=\"1\"").count());
+    // Four lines between boxes and the border.
+    assert_eq!(5, svg.matches("stroke-width=\"3\"").count());
+    let mut s = solvable_field_helper();
+    s.solve().unwrap();
+    let svg = s.to_svg(&SvgOptions { cell_size: 20 });
+    assert_eq!(81, svg.matches("<text ").count());
+    assert_eq!(51, svg.matches("font-weight=\"normal\"").count());
+    assert!(svg.contains("width=\"186\""));
+}
+
+#[test]
+fn svg_of_rectangular_boxes() {
+    let s = Sudoku::with_geometry(Geometry::new(2, 3).unwrap());
+    let svg = s.to_svg(&SvgOptions::default());
+    assert_eq!(0, svg.matches("<text ").count());
+    // Lines between boxes of 2 rows and 3 columns: two horizontal, one vertical.
+    assert_eq!(4, svg.matches("stroke-width=\"3\"").count());
+    assert_eq!(7, svg.matches("stroke-width=\"1\"").count());
+}
+
+#[test]
+fn html() {
+    let mut s = solvable_field_helper();
+    let html = s.to_html();
+    assert!(html.starts_with("<table "));
+    assert!(html.ends_with("</table>\n"));
+    assert_eq!(9, html.matches("<tr>").count());
+    assert_eq!(81, html.matches("<td ").count());
+    assert_eq!(30, html.matches("font-weight:bold").count());
+    assert!(!html.contains("<span"));
+    // Thick top borders on rows 1, 4 and 7, thick left borders on columns 1, 4 and 7.
+    assert_eq!(27, html.matches("border-top:3px").count());
+    assert_eq!(27, html.matches("border-left:3px").count());
+    let marks = s.to_html_with(&HtmlOptions {
+        show_candidates: true,
+    });
+    assert_eq!(51, marks.matches("<span ").count());
+    assert!(marks.contains("gray\">1 2 4</span>"));
+    s.solve().unwrap();
+    let solved = s.to_html_with(&HtmlOptions {
+        show_candidates: true,
+    });
+    assert!(!solved.contains("<span"));
+    assert_eq!(51, solved.matches("color:dimgray").count());
+}
+
+#[test]
+fn parity_marks_drawn() {
+    let mut marks = vec![None; 81];
+    marks[0] = Some(Parity::Odd);
+    marks[1] = Some(Parity::Odd);
+    marks[2] = Some(Parity::Even);
+    let s = solvable_field_helper().with_parity(marks).unwrap();
+    assert!(s.to_string().contains("\n|(5)|(3)|[ ]| "));
+    // Brackets of filled cells take the place of the marks.
+    assert!(s.to_string_marked().contains("\n| 5 | 3 |   | "));
+    // The framed cells are read back.
+    assert_eq!(
+        s.to_line(),
+        s.to_string().parse::<Sudoku>().unwrap().to_line()
+    );
+    let html = s.to_html();
+    assert_eq!(1, html.matches("background:lightgray").count());
+    assert_eq!(2, html.matches("radial-gradient").count());
+    let svg = s.to_svg(&SvgOptions::default());
+    assert_eq!(1, svg.matches("<rect x=").count());
+    assert_eq!(2, svg.matches("<circle ").count());
+    assert!(!solvable_field_helper()
+        .to_svg(&SvgOptions::default())
+        .contains("lightgray"));
+}
+
+#[test]
+fn latex() {
+    let field = vec![1, 0, 0, 0, 0, 0, 0, 3, 0, 0, 4, 0, 2, 0, 0, 0];
+    let mut s = Sudoku::new(field.into_iter()).unwrap();
+    s.field[1] = 2;
+    let latex = s.to_latex_with(&LatexOptions {
+        show_candidates: true,
+    });
+    assert_eq!(include_str!("../tasks/4x4.tex"), latex);
+    let blank = s.to_latex();
+    assert_eq!(5, blank.matches("\\node").count());
+    assert!(!blank.contains("\\tiny"));
+}
+
+#[test]
+fn animation_frame() {
+    let mut s = solvable_field_helper();
+    s.set(0, 2, 4).unwrap();
+    let frame = s.to_animation_frame(2);
+    assert_eq!(1, frame.matches(REVERSE).count());
+    assert!(frame.contains(&format!("| {}4{} |", REVERSE, RESET)));
+    let plain = frame
+        .replace(BOLD, "")
+        .replace(GREEN, "")
+        .replace(REVERSE, "")
+        .replace(RESET, "");
+    assert_eq!(s.to_string(), plain);
+    // The emptied cell of a rollback is highlighted too.
+    s.clear(0, 2).unwrap();
+    let frame = s.to_animation_frame(2);
+    assert!(frame.contains(&format!("| {} {} |", REVERSE, RESET)));
+    let frame = s.to_animation_frame(80);
+    assert!(frame.ends_with(&format!(
+        "| {}9{} |\n=====================================\n",
+        REVERSE, RESET
+    )));
+}
+
+#[test]
+fn empty_cells_drawn_with_any_character() {
+    let s = Sudoku::with_box_size(2)
+        .unwrap()
+        .with_field(vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4]);
+    let default = OutputOptions::default();
+    assert_eq!(s.to_line(), s.to_line_with(&default));
+    assert_eq!(s.to_string(), s.to_string_with(&default));
+    assert_eq!(s.to_colored_string(), s.to_colored_string_with(&default));
+    for &empty in ['0', '.', ' ', '_'].iter() {
+        let opts = OutputOptions::default().with_empty_char(Some(empty));
+        let line = format!("1{}4", empty.to_string().repeat(14));
+        assert_eq!(line, s.to_line_with(&opts));
+        let grid = concat!(
+            "=================\n",
+            "| 1 | 0 | 0 | 0 |\n",
+            "|-------|-------|\n",
+            "| 0 | 0 | 0 | 0 |\n",
+            "=================\n",
+            "| 0 | 0 | 0 | 0 |\n",
+            "|-------|-------|\n",
+            "| 0 | 0 | 0 | 4 |\n",
+            "=================\n",
+        );
+        assert_eq!(
+            grid.replace('0', &empty.to_string()),
+            s.to_string_with(&opts)
+        );
+        assert!(s
+            .to_colored_string_with(&opts)
+            .contains(&format!(" {} ", empty)));
+    }
+}
@@ -0,0 +1,183 @@
+//! Serde support for sudokus, enabled with the `serde` feature.
+//!
+//! A sudoku is represented as an array of 81 (or 256 for 16x16) numbers in row-major order where `0` is an empty
+//! cell. Only the field is serialized: the backtrack log is skipped and all filled cells become givens after
+//! deserialization.
+//!
+//! `WithNotes` adds the notes of the player: `{"grid":[...],"notes":[[...],...]}` with the noted values of every cell.
+//!
+//! Errors are serialized as a map with the stable `code` of the error kind, the `message` and the fields of the
+//! error, e.g. `{"code":"parse_line","message":"Line 2: ...","line":2}`. Rows and columns are 1-based like in messages.
+use crate::solvers::backtracking::{Sudoku, SudokuError};
+use crate::solvers::geometry::Geometry;
+use crate::solvers::notes::WithNotes;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+impl Serialize for Sudoku {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.field[..].serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Sudoku {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Sudoku, D::Error> {
+        let field = Vec::<u8>::deserialize(deserializer)?;
+        let side = match Geometry::for_cells(field.len()) {
+            Some(geometry) => geometry.side(),
+            None => return Err(de::Error::invalid_length(field.len(), &"81 or 256 cells")),
+        };
+        if let Some(&val) = field.iter().find(|&&val| val as usize > side) {
+            return Err(de::Error::invalid_value(
+                de::Unexpected::Unsigned(val.into()),
+                &format!("a number from 0 to {}", side).as_str(),
+            ));
+        }
+        Ok(Sudoku::new(field.into_iter()).unwrap())
+    }
+}
+
+impl Serialize for WithNotes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("grid", self.0)?;
+        map.serialize_entry("notes", &self.0.all_notes())?;
+        map.end()
+    }
+}
+
+/// Returns the 1-based column a message of `SudokuError::ParseLine` starts with, e.g. 5 for `column 5: ...`.
+fn column_of(msg: &str) -> Option<usize> {
+    msg.strip_prefix("column ")?.split(':').next()?.parse().ok()
+}
+
+impl Serialize for SudokuError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("message", &self.to_string())?;
+        match *self {
+            SudokuError::ParseLine { line, ref msg } => {
+                map.serialize_entry("line", &line)?;
+                if let Some(column) = column_of(msg) {
+                    map.serialize_entry("column", &column)?;
+                }
+            }
+            SudokuError::InvalidValue { row, col, value } => {
+                map.serialize_entry("row", &(row + 1))?;
+                map.serialize_entry("column", &(col + 1))?;
+                map.serialize_entry("value", &value)?;
+            }
+            SudokuError::InvalidCell { row, col } => {
+                map.serialize_entry("row", &(row + 1))?;
+                map.serialize_entry("column", &(col + 1))?;
+            }
+            SudokuError::InvalidTrace { step, .. } => map.serialize_entry("step", &step)?,
+            _ => {}
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+use crate::solvers::backtracking::{replay, solvable_field_helper};
+#[cfg(test)]
+use crate::solvers::observer::TraceEvent;
+#[cfg(test)]
+use crate::solvers::state::SolverState;
+
+#[test]
+fn serde_round_trip() {
+    let s = solvable_field_helper();
+    let json = serde_json::to_string(&s).unwrap();
+    assert!(json.starts_with("[5,3,0,0,7,"));
+    assert_eq!(s, serde_json::from_str(&json).unwrap());
+}
+
+#[test]
+fn serialize_with_notes() {
+    let mut s = solvable_field_helper();
+    s.add_note(0, 2, 4).unwrap();
+    s.add_note(0, 2, 1).unwrap();
+    assert!(!serde_json::to_string(&s).unwrap().contains("notes"));
+    let json = serde_json::to_value(WithNotes(&s)).unwrap();
+    assert_eq!(json["grid"], serde_json::to_value(&s).unwrap());
+    assert_eq!(json["notes"][2], serde_json::json!([1, 4]));
+    assert_eq!(json["notes"][0], serde_json::json!([]));
+}
+
+#[test]
+fn deserialize_wrong_length() {
+    let json = format!("[{}]", vec!["0"; 82].join(","));
+    assert!(serde_json::from_str::<Sudoku>(&json).is_err());
+}
+
+#[test]
+fn deserialize_value_out_of_range() {
+    let mut cells = vec!["0"; 81];
+    cells[5] = "12";
+    let json = format!("[{}]", cells.join(","));
+    let err = serde_json::from_str::<Sudoku>(&json).unwrap_err();
+    assert!(err.to_string().contains("12"));
+}
+
+#[test]
+fn trace_round_trip() {
+    let puzzle = solvable_field_helper();
+    let mut s = puzzle.clone().with_trace();
+    s.solve().unwrap();
+    let json = serde_json::to_string(&s.take_trace()).unwrap();
+    assert!(json.starts_with(r#"[{"placed":{"pos":2,"value":1}}"#));
+    let trace: Vec<TraceEvent> = serde_json::from_str(&json).unwrap();
+    assert_eq!(s, replay(&puzzle, &trace).unwrap());
+}
+
+#[test]
+fn solver_state_round_trip() {
+    let mut s = solvable_field_helper();
+    s.solve_resumable(|stats| stats.placements >= 10).unwrap();
+    let json = serde_json::to_string(&s.save_state()).unwrap();
+    let state: SolverState = serde_json::from_str(&json).unwrap();
+    let mut resumed = Sudoku::resume(state).unwrap();
+    resumed.solve().unwrap();
+    s.solve().unwrap();
+    assert_eq!(s, resumed);
+}
+
+#[test]
+fn serialize_errors() {
+    let json = |err: SudokuError| serde_json::to_string(&err).unwrap();
+    assert_eq!(
+        r#"{"code":"parse","message":"Cannot parse sudoku: expected 81 cells, found 3"}"#,
+        json("123".parse::<Sudoku>().unwrap_err())
+    );
+    assert_eq!(
+        r#"{"code":"parse_line","message":"Line 2: bad","line":2}"#,
+        json(SudokuError::ParseLine {
+            line: 2,
+            msg: "bad".to_string()
+        })
+    );
+    assert_eq!(
+        r#"{"code":"parse_line","message":"Line 3: column 5: `x` is not a cell","line":3,"column":5}"#,
+        json(SudokuError::ParseLine {
+            line: 3,
+            msg: "column 5: `x` is not a cell".to_string()
+        })
+    );
+    assert_eq!(
+        r#"{"code":"invalid_value","message":"Invalid value 12 in row 1, column 6","row":1,"column":6,"value":12}"#,
+        json(SudokuError::InvalidValue {
+            row: 0,
+            col: 5,
+            value: 12
+        })
+    );
+    assert_eq!(
+        r#"{"code":"invalid_trace","message":"Invalid trace at step 3: bad","step":3}"#,
+        json(SudokuError::InvalidTrace {
+            step: 3,
+            msg: "bad".to_string()
+        })
+    );
+}
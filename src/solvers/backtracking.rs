@@ -1,93 +1,870 @@
-extern crate log;
-use std::fmt::Write;
+use super::cages::Cage;
+use super::candidates::Candidates;
+use super::constraint::{Constraint, Shared};
+use super::diagnosis::Diagnosis;
+use super::geometry::{Geometry, Pos};
+use super::limit::{LimitReached, NodeLimit};
+use super::observer::{NoObserver, SolveEvent, SolveObserver, SolveStats, TraceEvent};
+use super::parity::Parity;
+use super::regions::Regions;
+use super::rules::Rules;
+use super::value::{Value, ValueOutOfRange};
+use super::value_order::ValueOrder;
+use crate::formats::is_ignored_line;
+use crate::formats::metadata::Metadata;
+use crate::prelude::*;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
 
-use std::fmt;
+/// Side of a square of the classic 9x9 sudoku, the geometry of other sizes is held by `Sudoku`.
+pub(crate) const SQUARE_SIDE: usize = 3;
+pub(crate) const SIDE: usize = SQUARE_SIDE * 3;
+pub(crate) const SIZE: usize = SIDE * SIDE;
+pub(crate) const EMPTY: u8 = 0;
+/// Side of a square of the smallest supported sudoku, the 4x4 one.
+pub const MIN_SQUARE_SIDE: usize = 2;
+/// Side of a square of the largest supported sudoku, 16x16 values still fit into single characters.
+pub const MAX_SQUARE_SIDE: usize = 4;
+/// Fewest clues a 9x9 sudoku needs to have a unique solution.
+pub const MIN_UNIQUE_CLUES: usize = 17;
 
-const SQUARE_SIDE: usize = 3;
-const SIDE: usize = SQUARE_SIDE * 3;
-const SIZE: usize = SIDE * SIDE;
-const EMPTY: u8 = 0;
-
-struct LogEntry {
-    pos: usize,
-    val: u8,
+/// Value placed by the search, see `Sudoku::rollback`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct LogEntry {
+    pub(crate) pos: usize,
+    pub(crate) val: u8,
 }
 
 #[derive(Debug, Clone)]
-struct ValueNotAllowed {
+pub struct ValueNotAllowed {
     pub msg: String,
+    given: Option<GivenCellImmutable>,
 }
 
-#[derive(Debug, Clone)]
-pub struct Unsolvable {}
+impl ValueNotAllowed {
+    /// Returns the error with the reason, for cells which are not givens.
+    pub(crate) fn new(msg: String) -> ValueNotAllowed {
+        ValueNotAllowed { msg, given: None }
+    }
+
+    /// Returns the cell which cannot be changed when the change is refused because the cell is a given.
+    pub fn given(&self) -> Option<GivenCellImmutable> {
+        self.given
+    }
+}
+
+/// The cell is a given of the puzzle, which `set` and `clear` never change, see `Sudoku::unlock`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GivenCellImmutable {
+    pub row: usize,
+    pub col: usize,
+}
+
+/// Prints the cell with 1-based coordinates, e.g. `r1c1 is a given`.
+impl fmt::Display for GivenCellImmutable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "r{}c{} is a given", self.row + 1, self.col + 1)
+    }
+}
+
+impl From<GivenCellImmutable> for ValueNotAllowed {
+    fn from(given: GivenCellImmutable) -> ValueNotAllowed {
+        ValueNotAllowed {
+            msg: "the cell is a given".to_string(),
+            given: Some(given),
+        }
+    }
+}
 
+/// Returns the error of a value larger than the side of the grid or 0.
+fn out_of_range(value: u8, side: usize) -> ValueNotAllowed {
+    ValueNotAllowed::new(ValueOutOfRange { value, max: side }.to_string())
+}
+
+/// Returns the error of a cell beyond the last row or column of the grid.
+pub(crate) fn outside_of_grid(cell: Pos) -> ValueNotAllowed {
+    ValueNotAllowed::new(format!("{} is outside of the grid", cell))
+}
+
+/// The sudoku has no solution, with the earliest contradiction found in its filled cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unsolvable {
+    pub diagnosis: Diagnosis,
+}
+
+/// Prints the diagnosis, e.g. `r1c9 has no candidates`.
+impl fmt::Display for Unsolvable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.diagnosis)
+    }
+}
+
+/// Result of `Sudoku::solve_resumable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolveStatus {
+    Solved,
+    /// The search was stopped before it finished, solving again continues it.
+    Stopped,
+}
+
+/// Numbers of cells of a sudoku, see `Sudoku::summary`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Summary {
+    /// Cells filled when the sudoku was built.
+    pub clues: usize,
+    /// Cells with a value, the clues included.
+    pub filled: usize,
+    pub empty: usize,
+    /// Whether the filled cells follow the rules, see `Sudoku::is_consistent`.
+    pub consistent: bool,
+}
+
+/// Formats the counts like `27 clues, 54 empty`.
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} clues, {} empty", self.clues, self.empty)
+    }
+}
+
+/// Errors returned by sudoku operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SudokuError {
+    /// Text does not describe a sudoku.
+    Parse(String),
+    /// Line of a multi-puzzle input does not describe a sudoku.
+    ParseLine { line: usize, msg: String },
+    /// Input cannot be read.
+    Io(String),
+    /// Digit mapping is not a permutation of 1..=9.
+    InvalidMapping(String),
+    /// Row, column, band or stack indices are out of range or cross a band/stack boundary.
+    InvalidSwap(String),
+    /// Rotating, mirroring or swapping the lines of the sudoku would break the rules or constraints of its variant.
+    InvalidTransform(String),
+    /// Cell value is greater than the side of the grid, `row` and `col` are 0-based.
+    InvalidValue { row: usize, col: usize, value: u8 },
+    /// Cell is outside of the grid, `row` and `col` are 0-based.
+    InvalidCell { row: usize, col: usize },
+    /// Clues repeat a value in a row, column or square.
+    ConflictingClues(String),
+    /// Layout of a jigsaw sudoku does not split the grid into regions of equal size.
+    InvalidRegions(String),
+    /// Cages of a killer sudoku overlap, leave the grid or have sums their cells cannot reach.
+    InvalidCages(String),
+    /// Parity marks do not cover the grid or contradict a clue.
+    InvalidParity(String),
+    /// Step of a solving trace cannot be replayed, `step` is the 0-based index into the trace.
+    InvalidTrace { step: usize, msg: String },
+    /// Saved state of the search does not belong to the sudoku.
+    InvalidState(String),
+    /// Imported notes do not cover the grid, or note values the cells cannot hold.
+    InvalidNotes(String),
+}
+
+impl fmt::Display for SudokuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SudokuError::Parse(msg) => write!(f, "Cannot parse sudoku: {}", msg),
+            SudokuError::ParseLine { line, msg } => write!(f, "Line {}: {}", line, msg),
+            SudokuError::Io(msg) => write!(f, "Cannot read input: {}", msg),
+            SudokuError::InvalidMapping(msg) => write!(f, "Invalid digit mapping: {}", msg),
+            SudokuError::InvalidSwap(msg) => write!(f, "Invalid swap: {}", msg),
+            SudokuError::InvalidTransform(msg) => write!(f, "Invalid transform: {}", msg),
+            SudokuError::InvalidValue { row, col, value } => write!(
+                f,
+                "Invalid value {} in row {}, column {}",
+                value,
+                row + 1,
+                col + 1
+            ),
+            SudokuError::InvalidCell { row, col } => {
+                write!(f, "Invalid cell in row {}, column {}", row + 1, col + 1)
+            }
+            SudokuError::ConflictingClues(msg) => write!(f, "Conflicting clues: {}", msg),
+            SudokuError::InvalidRegions(msg) => write!(f, "Invalid regions: {}", msg),
+            SudokuError::InvalidCages(msg) => write!(f, "Invalid cages: {}", msg),
+            SudokuError::InvalidParity(msg) => write!(f, "Invalid parity marks: {}", msg),
+            SudokuError::InvalidTrace { step, msg } => {
+                write!(f, "Invalid trace at step {}: {}", step, msg)
+            }
+            SudokuError::InvalidState(msg) => write!(f, "Invalid solver state: {}", msg),
+            SudokuError::InvalidNotes(msg) => write!(f, "Invalid notes: {}", msg),
+        }
+    }
+}
+
+impl SudokuError {
+    /// Returns the stable name of the error kind for machine-readable output, e.g. `parse_line` for `ParseLine`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SudokuError::Parse(_) => "parse",
+            SudokuError::ParseLine { .. } => "parse_line",
+            SudokuError::Io(_) => "io",
+            SudokuError::InvalidMapping(_) => "invalid_mapping",
+            SudokuError::InvalidSwap(_) => "invalid_swap",
+            SudokuError::InvalidTransform(_) => "invalid_transform",
+            SudokuError::InvalidValue { .. } => "invalid_value",
+            SudokuError::InvalidCell { .. } => "invalid_cell",
+            SudokuError::ConflictingClues(_) => "conflicting_clues",
+            SudokuError::InvalidRegions(_) => "invalid_regions",
+            SudokuError::InvalidCages(_) => "invalid_cages",
+            SudokuError::InvalidParity(_) => "invalid_parity",
+            SudokuError::InvalidTrace { .. } => "invalid_trace",
+            SudokuError::InvalidState(_) => "invalid_state",
+            SudokuError::InvalidNotes(_) => "invalid_notes",
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SudokuError {}
+
+#[derive(Debug, Clone)]
 pub struct Sudoku {
-    field: [u8; SIZE],
-    backtrack_log: Vec<LogEntry>,
+    geometry: Geometry,
+    rules: Rules,
+    /// Constraints of the rules followed by the ones added with `with_constraint`.
+    constraints: Vec<Shared<dyn Constraint>>,
+    /// Regions holding every value once, the boxes of the geometry unless this is a jigsaw sudoku.
+    regions: Regions,
+    /// Cages of a killer sudoku, empty for other sudokus.
+    cages: Vec<Cage>,
+    /// Index of the cage of every cell, empty for sudokus without cages.
+    cage_of: Vec<Option<usize>>,
+    /// Parity mark of every cell, empty for sudokus without marks, see `with_parity`.
+    pub(crate) parity: Vec<Option<Parity>>,
+    pub(crate) field: Vec<u8>,
+    /// `true` for cells which were filled when the sudoku was built.
+    pub(crate) givens: Vec<bool>,
+    pub(crate) backtrack_log: Vec<LogEntry>,
+    /// Cell the stopped search continues with and the value it tries after, plus one, see `solve_resumable`. The
+    /// search tries the values from this one on when they are in ascending order.
+    pub(crate) cursor: Option<(usize, u8)>,
+    /// Order in which the search tries the values of a cell.
+    pub(crate) value_order: ValueOrder,
+    /// Seed of the `Random` value order.
+    pub(crate) seed: u64,
+    /// Most placements of a single `solve`.
+    pub(crate) node_limit: NodeLimit,
+    /// Every placement and rollback of the search once recording is enabled with `with_trace`.
+    trace: Option<Vec<TraceEvent>>,
+    /// Notes of the player as a bit per value of every cell, empty until the first note is added, see `add_note`.
+    pub(crate) notes: Vec<u16>,
+    /// Fields of the catalog the puzzle comes from, see `with_metadata`.
+    pub(crate) metadata: Option<Metadata>,
 }
 
 impl Sudoku {
     /// Builds a new sudoku from the provided field.
+    ///
+    /// The field must hold 81 cells for the classic sudoku, 16 cells for the 4x4 one or 256 cells for the 16x16 one.
     pub fn new(field: impl Iterator<Item = u8>) -> Option<Sudoku> {
         debug!("Creating a new sudoku");
         let field: Vec<u8> = field.collect();
-        match field.len() {
-            SIZE => {
-                let mut field_array: [u8; SIZE] = [EMPTY; SIZE];
-                field_array.copy_from_slice(&field);
-                Some(Sudoku {
-                    field: field_array,
-                    backtrack_log: Vec::new(),
-                })
+        Geometry::for_cells(field.len()).map(|geometry| Sudoku::from_geometry(geometry, field))
+    }
+
+    /// Builds a new sudoku with square boxes and an empty backtrack log from the raw field, the size of the grid is
+    /// taken from the length of the field.
+    ///
+    /// All filled cells of the field become givens.
+    pub(crate) fn from_field(field: Vec<u8>) -> Sudoku {
+        let geometry = Geometry::for_cells(field.len()).expect("unsupported number of cells");
+        Sudoku::from_geometry(geometry, field)
+    }
+
+    /// Builds a new sudoku with the given geometry and an empty backtrack log from the raw field.
+    ///
+    /// All filled cells of the field become givens.
+    pub(crate) fn from_geometry(geometry: Geometry, field: Vec<u8>) -> Sudoku {
+        debug_assert_eq!(geometry.size(), field.len());
+        let givens = field.iter().map(|&cell| cell != EMPTY).collect();
+        Sudoku {
+            geometry,
+            rules: Rules::default(),
+            constraints: Vec::new(),
+            regions: Regions::boxes(geometry),
+            cages: Vec::new(),
+            cage_of: Vec::new(),
+            parity: Vec::new(),
+            field,
+            givens,
+            backtrack_log: Vec::new(),
+            cursor: None,
+            value_order: ValueOrder::default(),
+            seed: 0,
+            node_limit: NodeLimit::default(),
+            trace: None,
+            notes: Vec::new(),
+            metadata: None,
+        }
+    }
+
+    /// Builds a new sudoku of the same geometry, constraints, regions, cages and parity marks from the raw field, see
+    /// `from_field`.
+    pub(crate) fn with_field(&self, field: Vec<u8>) -> Sudoku {
+        let mut s = Sudoku::from_geometry(self.geometry, field);
+        s.rules = self.rules;
+        s.constraints = self.constraints.clone();
+        s.regions = self.regions.clone();
+        s.cages = self.cages.clone();
+        s.cage_of = self.cage_of.clone();
+        s.parity = self.parity.clone();
+        s.value_order = self.value_order;
+        s.seed = self.seed;
+        s.node_limit = self.node_limit;
+        s
+    }
+
+    /// Returns the sudoku like `with_field` for a grid of another geometry with as many cells, e.g. the transposed one,
+    /// with the boxes of that geometry as its regions.
+    pub(crate) fn with_geometry_field(&self, geometry: Geometry, field: Vec<u8>) -> Sudoku {
+        let mut s = self.with_field(field);
+        s.geometry = geometry;
+        s.regions = Regions::boxes(geometry);
+        s
+    }
+
+    /// Returns the sudoku following the rules of a variant, e.g. X-Sudoku with `diagonals` set.
+    ///
+    /// The constraints of the previous rules are replaced, the ones added with `with_constraint` are kept.
+    pub fn with_rules(mut self, rules: Rules) -> Sudoku {
+        let custom = self.constraints.split_off(self.rules.constraints().len());
+        self.rules = rules;
+        self.constraints = rules.constraints();
+        self.constraints.extend(custom);
+        self
+    }
+
+    /// Returns the built-in variant rules the sudoku follows on top of the classic ones.
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// Returns the sudoku which also follows the given constraint, see the `constraint` module for an example.
+    pub fn with_constraint(mut self, constraint: impl Constraint + 'static) -> Sudoku {
+        self.constraints.push(Shared::new(constraint));
+        self
+    }
+
+    /// Returns the constraints of the rules followed by the ones added with `with_constraint`.
+    pub fn constraints(&self) -> &[Shared<dyn Constraint>] {
+        &self.constraints
+    }
+
+    /// Returns the sudoku with its boxes replaced by the irregular regions of a jigsaw sudoku.
+    ///
+    /// Returns an error if the regions do not cover the grid.
+    pub fn with_regions(mut self, regions: Regions) -> Result<Sudoku, SudokuError> {
+        if regions.size() != self.field.len() {
+            return Err(SudokuError::InvalidRegions(format!(
+                "expected regions of {} cells, found {}",
+                self.field.len(),
+                regions.size()
+            )));
+        }
+        self.regions = regions;
+        Ok(self)
+    }
+
+    /// Returns the regions which hold every value once, the boxes unless this is a jigsaw sudoku.
+    pub fn regions(&self) -> &Regions {
+        &self.regions
+    }
+
+    /// Returns the killer sudoku with the given cages, their cells must sum to the sum of the cage and be different.
+    ///
+    /// Returns an error if a cage is empty or has cells outside of the grid, a cell belongs to several cages or the
+    /// sum of a cage cannot be reached by its cells.
+    pub fn with_cages(mut self, cages: Vec<Cage>) -> Result<Sudoku, SudokuError> {
+        let mut cage_of = vec![None; self.field.len()];
+        for (i, cage) in cages.iter().enumerate() {
+            if cage.cells.is_empty() || cage.cells.len() > self.side() {
+                return Err(SudokuError::InvalidCages(format!(
+                    "cage {} has {} cells",
+                    i + 1,
+                    cage.cells.len()
+                )));
+            }
+            let (min, max) = Cage::sum_range(cage.cells.len(), self.side());
+            if !(min..=max).contains(&(cage.sum as usize)) {
+                return Err(SudokuError::InvalidCages(format!(
+                    "cage {} of {} cells cannot sum to {}",
+                    i + 1,
+                    cage.cells.len(),
+                    cage.sum
+                )));
+            }
+            for &pos in cage.cells.iter() {
+                match cage_of.get(pos) {
+                    None => {
+                        return Err(SudokuError::InvalidCages(format!(
+                            "cage {} has a cell outside of the grid",
+                            i + 1
+                        )))
+                    }
+                    Some(Some(other)) => {
+                        let (row, col) = self.geometry.cell(pos);
+                        return Err(SudokuError::InvalidCages(format!(
+                            "r{}c{} is in cages {} and {}",
+                            row + 1,
+                            col + 1,
+                            other + 1,
+                            i + 1
+                        )));
+                    }
+                    Some(None) => cage_of[pos] = Some(i),
+                }
+            }
+        }
+        self.cages = cages;
+        self.cage_of = cage_of;
+        Ok(self)
+    }
+
+    /// Returns the cages of a killer sudoku, none for other sudokus.
+    pub fn cages(&self) -> &[Cage] {
+        &self.cages
+    }
+
+    /// Returns the index of the cage holding the cell at the position.
+    pub(crate) fn cage_of(&self, pos: usize) -> Option<usize> {
+        self.cage_of.get(pos).copied().flatten()
+    }
+
+    /// Builds a sudoku of the given geometry without any filled cells.
+    pub fn with_geometry(geometry: Geometry) -> Sudoku {
+        Sudoku::from_geometry(geometry, vec![EMPTY; geometry.size()])
+    }
+
+    /// Returns the shape of the grid.
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    /// Returns the row and column of the cell at the position, e.g. for messages naming the cell like `r4c7`.
+    pub fn cell_pos(&self, pos: usize) -> Pos {
+        Pos::of(self.geometry, pos)
+    }
+
+    /// Returns the number of rows (and columns) of the grid, which is also the largest value.
+    pub fn side(&self) -> usize {
+        self.geometry.side()
+    }
+
+    /// Returns the number of rows of a box.
+    pub fn box_rows(&self) -> usize {
+        self.geometry.box_rows()
+    }
+
+    /// Returns the number of columns of a box.
+    pub fn box_cols(&self) -> usize {
+        self.geometry.box_cols()
+    }
+
+    /// Returns `true` if the cell was given in the original puzzle rather than filled by the solver, `false` for cells
+    /// outside of the grid.
+    pub fn is_given(&self, row: usize, col: usize) -> bool {
+        self.geometry.contains(row, col) && self.givens[self.geometry.pos(row, col)]
+    }
+
+    /// Builds a sudoku without any filled cells.
+    pub fn empty() -> Sudoku {
+        Sudoku::with_geometry(Geometry::CLASSIC)
+    }
+
+    /// Builds an empty sudoku with squares of `box_size` rows and columns, e.g. 2 for the 4x4 sudoku.
+    ///
+    /// Returns None unless the box size is from `MIN_SQUARE_SIDE` to `MAX_SQUARE_SIDE`.
+    pub fn with_box_size(box_size: usize) -> Option<Sudoku> {
+        Sudoku::with_box_shape(box_size, box_size)
+    }
+
+    /// Builds an empty sudoku with boxes of `box_rows` rows and `box_cols` columns, e.g. 2 and 3 for the 6x6 sudoku.
+    ///
+    /// Returns None if the box shape is not supported, see `Geometry::new`.
+    pub fn with_box_shape(box_rows: usize, box_cols: usize) -> Option<Sudoku> {
+        Geometry::new(box_rows, box_cols).map(Sudoku::with_geometry)
+    }
+
+    /// Parses a sudoku with squares of `box_size` rows and columns from text in the format accepted by `FromStr`.
+    ///
+    /// Unlike `FromStr`, which only detects 9x9 and 16x16 sudokus, the text must hold exactly the cells of the given
+    /// size and values above the side of the grid are rejected.
+    pub fn parse_with_box_size(text: &str, box_size: usize) -> Result<Sudoku, SudokuError> {
+        Sudoku::parse_with_box_shape(text, box_size, box_size)
+    }
+
+    /// Parses a sudoku with boxes of `box_rows` rows and `box_cols` columns, see `parse_with_box_size`.
+    pub fn parse_with_box_shape(
+        text: &str,
+        box_rows: usize,
+        box_cols: usize,
+    ) -> Result<Sudoku, SudokuError> {
+        let empty = Sudoku::with_box_shape(box_rows, box_cols).ok_or_else(|| {
+            SudokuError::Parse(format!("unsupported box size {}x{}", box_rows, box_cols))
+        })?;
+        let side = empty.side();
+        let field = parse_cells(text, Alphabet::of(text, side));
+        if field.len() != empty.field.len() {
+            return Err(SudokuError::Parse(format!(
+                "expected {} cells, found {}",
+                empty.field.len(),
+                field.len()
+            )));
+        }
+        if let Some(pos) = field.iter().position(|&value| value as usize > side) {
+            return Err(SudokuError::InvalidValue {
+                row: pos / side,
+                col: pos % side,
+                value: field[pos],
+            });
+        }
+        Ok(empty.with_field(field))
+    }
+
+    /// Returns the rows of the field, `0` for empty cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sudoku is not a classic 9x9 one.
+    pub fn to_rows(&self) -> [[u8; SIDE]; SIDE] {
+        assert_eq!(
+            SIDE,
+            self.side(),
+            "only 9x9 sudokus can be converted to rows"
+        );
+        let mut rows = [[EMPTY; SIDE]; SIDE];
+        for (row, cells) in rows.iter_mut().zip(self.field.chunks(SIDE)) {
+            row.copy_from_slice(cells);
+        }
+        rows
+    }
+
+    /// Returns the field row by row, `0` for empty cells.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.field
+    }
+
+    /// Returns the value of the cell or None if it is empty or outside of the grid.
+    pub fn value(&self, row: usize, col: usize) -> Option<u8> {
+        self.get(Pos::new(row, col)).map(Value::get)
+    }
+
+    /// Returns the value of the cell like `value`, which takes the row and column as numbers.
+    pub fn get(&self, cell: Pos) -> Option<Value> {
+        if !self.geometry.contains(cell.row, cell.col) {
+            return None;
+        }
+        Value::with_side(self.field[cell.index(self.geometry)], self.side())
+    }
+
+    /// Puts the value into the cell, replacing the value put there before.
+    ///
+    /// Returns the reason when the value breaks the rules, the cell is a given or outside of the grid. `put` takes the
+    /// cell and the value as their own types.
+    pub fn set(&mut self, row: usize, col: usize, value: u8) -> Result<(), ValueNotAllowed> {
+        match Value::with_side(value, self.side()) {
+            Some(value) => self.put(Pos::new(row, col), value),
+            None => Err(out_of_range(value, self.side())),
+        }
+    }
+
+    /// Puts the value into the cell like `set`, e.g. `s.put(Pos::new(0, 2), Value::new(4).unwrap())`.
+    pub fn put(&mut self, cell: Pos, value: Value) -> Result<(), ValueNotAllowed> {
+        let (row, col) = (cell.row, cell.col);
+        if !self.geometry.contains(row, col) {
+            return Err(outside_of_grid(cell));
+        }
+        let pos = cell.index(self.geometry);
+        let value = value.get();
+        // Values of larger grids are valid values, only not of this one.
+        if value as usize > self.side() {
+            return Err(out_of_range(value, self.side()));
+        }
+        if self.givens[pos] {
+            return Err(GivenCellImmutable { row, col }.into());
+        }
+        let previous = core::mem::replace(&mut self.field[pos], EMPTY);
+        let reason = if !self.is_allowed_in_row(value, pos) {
+            Some(format!("{} is already in row {}", value, row + 1))
+        } else if !self.is_allowed_in_col(value, pos) {
+            Some(format!("{} is already in column {}", value, col + 1))
+        } else if !self.is_allowed_in_square(value, pos) {
+            Some(format!("{} is already in the square", value))
+        } else if !self.is_allowed_in_cage(value, pos) {
+            let cage = self
+                .cage_of(pos)
+                .expect("only cages can disallow the value");
+            Some(format!(
+                "{} does not fit cage {} with sum {}",
+                value,
+                cage + 1,
+                self.cages[cage].sum
+            ))
+        } else if !self.is_allowed_by_parity(value, pos) {
+            Some(format!(
+                "{} is {}, the cell is marked {}",
+                value,
+                Parity::of(value),
+                self.parity[pos].expect("only marked cells can disallow the value")
+            ))
+        } else {
+            self.constraints
+                .iter()
+                .find(|constraint| !constraint.allows(self, pos, value))
+                .map(|constraint| constraint.reason(self, pos, value))
+        };
+        self.field[pos] = match reason {
+            None => value,
+            Some(_) => previous,
+        };
+        match reason {
+            None => Ok(()),
+            Some(msg) => Err(ValueNotAllowed::new(msg)),
+        }
+    }
+
+    /// Empties the cell unless it is a given or outside of the grid.
+    pub fn clear(&mut self, row: usize, col: usize) -> Result<(), ValueNotAllowed> {
+        if !self.geometry.contains(row, col) {
+            return Err(outside_of_grid(Pos::new(row, col)));
+        }
+        let pos = self.geometry.pos(row, col);
+        if self.givens[pos] {
+            return Err(GivenCellImmutable { row, col }.into());
+        }
+        self.field[pos] = EMPTY;
+        Ok(())
+    }
+
+    /// Empties every cell which is not a given, going back to the puzzle as it was built; the notes stay.
+    pub fn reset(&mut self) {
+        for (cell, &given) in self.field.iter_mut().zip(self.givens.iter()) {
+            if !given {
+                *cell = EMPTY;
             }
-            _ => None,
         }
+        self.backtrack_log.clear();
+        self.cursor = None;
+    }
+
+    /// Makes the given cell an ordinary one which `set` and `clear` change, e.g. to correct a clue in an editor.
+    ///
+    /// This edits the puzzle rather than solving it: the value stays, but it is no longer a clue. `lock` turns it back.
+    /// Cells outside of the grid are ignored.
+    pub fn unlock(&mut self, row: usize, col: usize) {
+        if !self.geometry.contains(row, col) {
+            return;
+        }
+        let pos = self.geometry.pos(row, col);
+        self.givens[pos] = false;
+    }
+
+    /// Makes the filled cell a given of the puzzle, which `set`, `clear` and `reset` no longer change. An empty cell
+    /// cannot be a given and stays as it is.
+    ///
+    /// Like `unlock`, this edits the puzzle: the value becomes one of its clues. Cells outside of the grid are ignored.
+    pub fn lock(&mut self, row: usize, col: usize) {
+        if !self.geometry.contains(row, col) {
+            return;
+        }
+        let pos = self.geometry.pos(row, col);
+        self.givens[pos] = self.field[pos] != EMPTY;
+    }
+
+    /// Returns values allowed in the empty cell, nothing for filled cells and cells outside of the grid.
+    pub fn candidates(&self, row: usize, col: usize) -> Vec<u8> {
+        if !self.geometry.contains(row, col) {
+            return Vec::new();
+        }
+        self.candidate_set(self.geometry.pos(row, col))
+            .iter()
+            .collect()
+    }
+
+    /// Returns the values allowed in the cell at the position, none for filled cells.
+    ///
+    /// The values of the row, the column and the region are taken out with a mask each, only the values left are
+    /// checked against the cage, the parity mark and the constraints.
+    pub(crate) fn candidate_set(&self, pos: usize) -> Candidates {
+        if self.field[pos] != EMPTY {
+            return Candidates::none();
+        }
+        let (row, col) = self.geometry.cell(pos);
+        let used: Candidates = (0..self.side())
+            .flat_map(|i| [self.geometry.pos(row, i), self.geometry.pos(i, col)])
+            .chain(
+                self.regions
+                    .cells(self.regions.region_of(pos))
+                    .iter()
+                    .copied(),
+            )
+            .map(|i| self.field[i])
+            .filter(|&val| val != EMPTY)
+            .collect();
+        self.allowed_by_extra_rules(Candidates::all(self.side()).without(used), pos)
+    }
+
+    /// Returns the values allowed in every cell like `candidate_set`, in a pass over the grid for the values of each
+    /// row, column and region and one over the cells, so a cell takes a few masks instead of a pass over its units.
+    #[cfg(feature = "std")]
+    pub(crate) fn candidate_sets(&self) -> Vec<Candidates> {
+        let side = self.side();
+        let mut rows = vec![Candidates::none(); side];
+        let mut cols = vec![Candidates::none(); side];
+        let mut regions = vec![Candidates::none(); side];
+        for (pos, &val) in self
+            .field
+            .iter()
+            .enumerate()
+            .filter(|&(_, &val)| val != EMPTY)
+        {
+            let (row, col) = self.geometry.cell(pos);
+            rows[row].insert(val);
+            cols[col].insert(val);
+            regions[self.regions.region_of(pos)].insert(val);
+        }
+        (0..self.field.len())
+            .map(|pos| match self.field[pos] {
+                EMPTY => {
+                    let (row, col) = self.geometry.cell(pos);
+                    let used = rows[row]
+                        .union(cols[col])
+                        .union(regions[self.regions.region_of(pos)]);
+                    self.allowed_by_extra_rules(Candidates::all(side).without(used), pos)
+                }
+                _ => Candidates::none(),
+            })
+            .collect()
+    }
+
+    /// Returns the values of the set which the cage, the parity mark and the constraints allow on the position.
+    fn allowed_by_extra_rules(&self, values: Candidates, pos: usize) -> Candidates {
+        if self.cages.is_empty() && self.parity.is_empty() && self.constraints.is_empty() {
+            return values;
+        }
+        values
+            .iter()
+            .filter(|&val| self.is_allowed_by_extra_rules(val, pos))
+            .collect()
+    }
+
+    /// Returns values allowed in the empty cell like `candidates`, which takes the row and column as numbers.
+    ///
+    /// Cells outside of the grid have no candidates.
+    pub fn candidates_of(&self, cell: Pos) -> Vec<Value> {
+        if !self.geometry.contains(cell.row, cell.col) {
+            return Vec::new();
+        }
+        self.candidate_set(cell.index(self.geometry))
+            .iter()
+            .filter_map(|val| Value::with_side(val, self.side()))
+            .collect()
+    }
+
+    /// Returns the number of givens, cells filled when the sudoku was built.
+    pub fn clue_count(&self) -> usize {
+        self.givens.iter().filter(|&&given| given).count()
+    }
+
+    /// Returns the number of cells with a value, givens and values placed later alike.
+    pub fn filled_count(&self) -> usize {
+        self.field.iter().filter(|&&val| val != EMPTY).count()
+    }
+
+    /// Returns the number of empty cells.
+    pub fn empty_count(&self) -> usize {
+        self.field.len() - self.filled_count()
+    }
+
+    /// Returns the numbers of clues, filled and empty cells and whether the sudoku is consistent.
+    pub fn summary(&self) -> Summary {
+        Summary {
+            clues: self.clue_count(),
+            filled: self.filled_count(),
+            empty: self.empty_count(),
+            consistent: self.is_consistent(),
+        }
+    }
+
+    /// Returns all values of the sudoku, from 1 to the side of the grid.
+    pub(crate) fn values(&self) -> impl Iterator<Item = u8> {
+        1..self.side() as u8 + 1
     }
 
     /// Returns true if the given number is allowed in row on the given position.
     fn is_allowed_in_row(&self, number: u8, pos: usize) -> bool {
-        let y = pos / SIDE;
-        !(0..SIDE)
-            .map(|i| self.field[y * SIDE + i])
+        let (y, _) = self.geometry.cell(pos);
+        !(0..self.side())
+            .map(|i| self.field[self.geometry.pos(y, i)])
             .any(|el| el == number)
     }
 
     /// Returns true if the given number is allowed in column on the given position.
     fn is_allowed_in_col(&self, number: u8, pos: usize) -> bool {
-        let x = pos % SIDE;
-        !(0..SIDE)
-            .map(|i| self.field[i * SIDE + x])
+        let (_, x) = self.geometry.cell(pos);
+        !(0..self.side())
+            .map(|i| self.field[self.geometry.pos(i, x)])
             .any(|el| el == number)
     }
 
-    /// Returns true if the given number is allowed in square on the given position.
+    /// Returns true if the given number is allowed in square (region) on the given position.
     fn is_allowed_in_square(&self, number: u8, pos: usize) -> bool {
-        let y = pos / SIDE;
-        let x = pos % SIDE;
-        let square_start_x = (x / SQUARE_SIDE) * SQUARE_SIDE;
-        let square_start_y = (y / SQUARE_SIDE) * SQUARE_SIDE;
-        !(0..SIDE)
-            .map(|i| {
-                let square_x = i % SQUARE_SIDE + square_start_x;
-                let square_y = i / SQUARE_SIDE + square_start_y;
-                self.field[SIDE * square_y + square_x]
-            })
-            .any(|el| el == number)
+        !self
+            .regions
+            .cells(self.regions.region_of(pos))
+            .iter()
+            .any(|&i| self.field[i] == number)
+    }
+
+    /// Returns true if the given number is allowed in the cage of the given position.
+    ///
+    /// The number must not be in the cage yet, and the empty cells left must still be able to reach the sum of the
+    /// cage: so the sum must match exactly once the last cell is filled.
+    fn is_allowed_in_cage(&self, number: u8, pos: usize) -> bool {
+        let cage = match self.cage_of(pos) {
+            Some(i) => &self.cages[i],
+            None => return true,
+        };
+        let mut sum = number as usize;
+        let mut empty = 0;
+        for &i in cage.cells.iter().filter(|&&i| i != pos) {
+            match self.field[i] {
+                EMPTY => empty += 1,
+                val if val == number => return false,
+                val => sum += val as usize,
+            }
+        }
+        let (min, max) = Cage::sum_range(empty, self.side());
+        (sum + min..=sum + max).contains(&(cage.sum as usize))
     }
 
     /// Returns true if the given element is allowed on a given position.
-    fn is_allowed(&self, number: u8, pos: usize) -> bool {
+    pub(crate) fn is_allowed(&self, number: u8, pos: usize) -> bool {
         // Make sure value is not already set and is valid.
-        if self.field[pos] != EMPTY || number > SIDE as u8 {
+        if self.field[pos] != EMPTY || number as usize > self.side() {
             return false;
         }
-        vec![
-            self.is_allowed_in_col(number, pos),
-            self.is_allowed_in_row(number, pos),
-            self.is_allowed_in_square(number, pos),
-        ]
-        .iter()
-        .all(|el| el.to_owned())
+        self.is_allowed_in_col(number, pos)
+            && self.is_allowed_in_row(number, pos)
+            && self.is_allowed_in_square(number, pos)
+            && self.is_allowed_by_extra_rules(number, pos)
+    }
+
+    /// Returns true if the cage, the parity mark and the constraints allow the number on the position, the rules on
+    /// top of the rows, columns and regions.
+    fn is_allowed_by_extra_rules(&self, number: u8, pos: usize) -> bool {
+        self.is_allowed_in_cage(number, pos)
+            && self.is_allowed_by_parity(number, pos)
+            && self
+                .constraints
+                .iter()
+                .all(|constraint| constraint.allows(self, pos, number))
     }
 
     /// Set the value of the given position.
@@ -96,56 +873,199 @@ impl Sudoku {
     /// Otherwise the function will return an error.
     /// Each successful call to the function is tracked in the log, and the last call van be rolled back with the
     /// `self.rollback()` call.
-    fn set_value(&mut self, number: u8, pos: usize) -> Result<(), ValueNotAllowed> {
+    pub(crate) fn set_value(&mut self, number: u8, pos: usize) -> Result<(), ValueNotAllowed> {
         if !self.is_allowed(number, pos) {
-            return Err(ValueNotAllowed {
-                msg: "Value is not allowed in the position".to_string(),
-            });
+            return Err(ValueNotAllowed::new(format!(
+                "{} is not allowed in {}",
+                number,
+                self.cell_pos(pos)
+            )));
         }
         self.field[pos] = number;
-        self.backtrack_log.push(LogEntry {
-            pos: pos,
-            val: number,
-        });
-        debug!("Value {} set for position {}", number, pos);
+        self.backtrack_log.push(LogEntry { pos, val: number });
+        debug!("Value {} set in {}", number, self.cell_pos(pos));
         Ok(())
     }
 
-    /// Try to fill the position with values from `start` to 9.
+    /// Try to fill the position with values from `start` to the largest value, or in the value order with the values
+    /// after `start - 1`.
     ///
     /// Return Ok() if position filled with some value, otherwise None.
     fn fill_position(&mut self, pos: usize, start: u8) -> Option<()> {
-        for val in start..SIDE as u8 + 1 {
-            match self.set_value(val, pos) {
-                Ok(_) => return Some(()),
-                Err(_) => {}
-            }
+        let allowed = self.candidate_set(pos);
+        if self.value_order == ValueOrder::Ascending {
+            return allowed
+                .iter()
+                .filter(|&val| val >= start)
+                .find_map(|val| self.set_value(val, pos).ok());
         }
-        None
+        let values = self.ordered_values(pos);
+        let first = match start {
+            1 => 0,
+            start => values.iter().position(|&val| val == start - 1)? + 1,
+        };
+        values[first..]
+            .iter()
+            .filter(|&&val| allowed.contains(val))
+            .find_map(|&val| self.set_value(val, pos).ok())
     }
 
     /// Rollback the most recent set action.
     ///
     /// Returns error when rollback log is empty.
-    fn rollback(&mut self) -> Result<LogEntry, ()> {
+    pub(crate) fn rollback(&mut self) -> Result<LogEntry, ()> {
         match self.backtrack_log.pop() {
             None => Err(()),
             Some(action) => {
-                debug!("Rollback for position {}", action.pos);
+                debug!(
+                    "Rollback of {} in {}",
+                    action.val,
+                    self.cell_pos(action.pos)
+                );
                 self.field[action.pos] = EMPTY;
+                // The arguments of the macro are only formatted when the level is enabled.
+                trace!("Grid after the rollback:\n{}", self);
                 Ok(action)
             }
         }
     }
 
     /// Returns a position of a next empty cell or None if all all cells are filled.
-    fn next_empty(&self) -> Option<usize> {
-        for i in 0..SIZE {
-            if self.field[i] == EMPTY {
-                return Some(i);
+    pub(crate) fn next_empty(&self) -> Option<usize> {
+        self.field.iter().position(|&cell| cell == EMPTY)
+    }
+
+    /// Returns `true` if no value is repeated in any row, column, square or an extra region of the rules.
+    ///
+    /// Empty cells are ignored, so a partially filled sudoku can be consistent too.
+    pub fn is_consistent(&self) -> bool {
+        let mut s = self.with_field(self.field.clone());
+        (0..s.field.len()).all(|pos| {
+            let val = s.field[pos];
+            if val == EMPTY {
+                return true;
+            }
+            s.field[pos] = EMPTY;
+            let allowed = s.is_allowed(val, pos);
+            s.field[pos] = val;
+            allowed
+        })
+    }
+
+    /// Returns the number of solutions of the sudoku, counting stops once `limit` solutions are found.
+    ///
+    /// Use a `limit` of 2 to check whether the solution is unique. Counting also stops at the node limit of the sudoku,
+    /// see `with_node_limit`, the count is unknown then and `LimitReached` holds the solutions found until then.
+    pub fn count_solutions(&self, limit: usize) -> Result<usize, LimitReached> {
+        let max_nodes = self.node_limit.max_nodes(self.geometry);
+        let (mut count, mut budget) = (0, max_nodes.unwrap_or(u64::MAX));
+        let ran_out = self.is_consistent()
+            && self.with_field(self.field.clone()).count_within(
+                limit,
+                &mut count,
+                &mut budget,
+                &mut |_| {},
+            );
+        match ran_out {
+            true => Err(LimitReached { found: count }),
+            false => Ok(count),
+        }
+    }
+
+    /// Returns the number of solutions like `count_solutions`, None if the search needs more than `max_nodes`
+    /// placements to finish.
+    pub(crate) fn count_solutions_within(&self, limit: usize, max_nodes: u64) -> Option<usize> {
+        let (mut count, mut budget) = (0, max_nodes);
+        let ran_out = self.is_consistent()
+            && self.with_field(self.field.clone()).count_within(
+                limit,
+                &mut count,
+                &mut budget,
+                &mut |_| {},
+            );
+        (!ran_out).then_some(count)
+    }
+
+    /// Counts solutions reachable from the current state, the search branches on the most constrained cell.
+    ///
+    /// Every placement takes one from the budget. Returns `true` if the search ran out of budget, i.e. it had another
+    /// placement to make when none was left. `on_solution` gets the field of every solution found.
+    pub(crate) fn count_within(
+        &mut self,
+        limit: usize,
+        count: &mut usize,
+        budget: &mut u64,
+        on_solution: &mut impl FnMut(&[u8]),
+    ) -> bool {
+        let pos = match self.most_constrained() {
+            None => {
+                *count += 1;
+                on_solution(&self.field);
+                return false;
+            }
+            Some(pos) => pos,
+        };
+        let allowed = self.candidate_set(pos);
+        for val in self.ordered_values(pos) {
+            if *count >= limit {
+                return false;
+            }
+            if !allowed.contains(val) {
+                continue;
+            }
+            if self.set_value(val, pos).is_ok() {
+                let ran_out = *budget == 0 || {
+                    *budget -= 1;
+                    self.count_within(limit, count, budget, on_solution)
+                };
+                self.rollback().unwrap();
+                if ran_out {
+                    return true;
+                }
             }
         }
-        None
+        false
+    }
+
+    /// Returns positions of the other empty cells of the row, the column and the box of the cell.
+    pub(crate) fn empty_peers(&self, pos: usize) -> Vec<usize> {
+        let geometry = self.geometry();
+        let (row, col) = geometry.cell(pos);
+        let mut peers: Vec<usize> = (0..self.side())
+            .flat_map(|i| [geometry.pos(row, i), geometry.pos(i, col)])
+            .chain(
+                self.regions()
+                    .cells(self.regions().region_of(pos))
+                    .iter()
+                    .copied(),
+            )
+            .filter(|&peer| peer != pos && self.field[peer] == EMPTY)
+            .collect();
+        peers.sort_unstable();
+        peers.dedup();
+        peers
+    }
+
+    /// Returns an empty cell of the row, the column or the box of the cell which no value is allowed in, a dead end
+    /// the search would otherwise only find once it gets to that cell.
+    fn dead_peer(&self, pos: usize) -> Option<usize> {
+        self.empty_peers(pos)
+            .into_iter()
+            .find(|&peer| self.candidate_set(peer).is_empty())
+    }
+
+    /// Returns the empty cell with the fewest allowed values or None if all cells are filled.
+    fn most_constrained(&self) -> Option<usize> {
+        (0..self.field.len())
+            .filter(|&pos| self.field[pos] == EMPTY)
+            .min_by_key(|&pos| self.candidate_set(pos).len())
+    }
+
+    /// Returns the sudoku as a single line with a character per cell and `.` for empty cells.
+    ///
+    /// Same as formatting the sudoku with `{:#}`.
+    pub fn to_line(&self) -> String {
+        format!("{:#}", self)
     }
 
     /// Returns `true` if sudoku is solved, otherwise `false`.
@@ -154,60 +1074,402 @@ impl Sudoku {
     }
 
     pub fn solve(&mut self) -> Result<(), Unsolvable> {
+        self.solve_with_observer(&mut NoObserver)
+    }
+
+    /// Solves the sudoku, reporting every placement and rollback of the search to the observer.
+    ///
+    /// The search gives up with `Diagnosis::LimitReached` after the placements of `node_limit`, solving again continues
+    /// it for as many more.
+    pub fn solve_with_observer(
+        &mut self,
+        observer: &mut impl SolveObserver,
+    ) -> Result<(), Unsolvable> {
+        let max = self.node_limit.max_nodes(self.geometry);
+        let mut last = SolveStats::default();
+        let status = self.search(observer, |stats| {
+            last = *stats;
+            max.is_some_and(|max| stats.placements >= max)
+        })?;
+        match status {
+            SolveStatus::Solved => Ok(()),
+            SolveStatus::Stopped => Err(Unsolvable {
+                diagnosis: Diagnosis::LimitReached { stats: last },
+            }),
+        }
+    }
+
+    /// Solves the sudoku like `solve`, but stops the search once `stop` returns `true`, which is asked before every
+    /// step with the counters of this call, e.g. to limit the number of placements or the time spent.
+    ///
+    /// The stopped search keeps its place: solving again continues exactly where it stopped, and so does the sudoku
+    /// resumed from `save_state`.
+    pub fn solve_resumable(
+        &mut self,
+        stop: impl FnMut(&SolveStats) -> bool,
+    ) -> Result<SolveStatus, Unsolvable> {
+        self.search(&mut NoObserver, stop)
+    }
+
+    /// Runs the backtracking search from the cursor of the stopped search, or from the first empty cell.
+    fn search(
+        &mut self,
+        observer: &mut impl SolveObserver,
+        mut stop: impl FnMut(&SolveStats) -> bool,
+    ) -> Result<SolveStatus, Unsolvable> {
+        let mut stats = SolveStats {
+            depth: self.backtrack_log.len(),
+            ..SolveStats::default()
+        };
         // Return solved if there are no empty cells.
-        let mut pos = self.next_empty();
-        let mut start_val = 1;
-
-        loop {
-            let pos_idx = match pos {
-                Some(v) => v,
-                _ => break,
-            };
-            match self.fill_position(pos_idx, start_val) {
-                None => {
-                    match self.rollback() {
-                        Ok(log_entry) => {
-                            pos = Some(log_entry.pos);
-                            start_val = log_entry.val + 1;
-                            // continue
-                        }
-                        Err(_) => {
-                            // Nothing to rollback. Sudoku is unsolvable.
-                            break;
-                        }
+        let (mut pos, mut start_val) = match self.cursor.take() {
+            Some((pos, start_val)) => (Some(pos), start_val),
+            None => (self.next_empty(), 1),
+        };
+
+        while let Some(pos_idx) = pos {
+            if stop(&stats) {
+                self.cursor = Some((pos_idx, start_val));
+                return Ok(SolveStatus::Stopped);
+            }
+            if self.fill_position(pos_idx, start_val).is_some() {
+                stats.placements += 1;
+                stats.depth = self.backtrack_log.len();
+                self.report(
+                    SolveEvent::Placed {
+                        pos: pos_idx,
+                        value: self.field[pos_idx],
+                    },
+                    &stats,
+                    observer,
+                );
+                match self.dead_peer(pos_idx) {
+                    None => {
+                        start_val = 1;
+                        pos = self.next_empty();
+                        continue;
                     }
+                    // Take the value back right away rather than fill the cells up to the dead end first.
+                    Some(peer) => debug!("No value is allowed in {} any more", self.cell_pos(peer)),
                 }
-                Some(_) => {
-                    start_val = 1;
-                    pos = self.next_empty()
+            }
+            match self.rollback() {
+                Ok(log_entry) => {
+                    stats.backtracks += 1;
+                    stats.depth = self.backtrack_log.len();
+                    self.report(
+                        SolveEvent::RolledBack {
+                            pos: log_entry.pos,
+                            value: log_entry.val,
+                        },
+                        &stats,
+                        observer,
+                    );
+                    pos = Some(log_entry.pos);
+                    start_val = log_entry.val + 1;
+                }
+                Err(_) => {
+                    // Nothing to rollback. Sudoku is unsolvable.
+                    break;
                 }
             }
         }
         match self.solved() {
-            true => Ok(()),
-            false => Err(Unsolvable {}),
+            true => Ok(SolveStatus::Solved),
+            false => Err(self.unsolvable()),
+        }
+    }
+
+    /// Passes the step of the search to the observer and records it when the trace is enabled.
+    fn report(&mut self, event: SolveEvent, stats: &SolveStats, observer: &mut impl SolveObserver) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(event);
+        }
+        observer.on_event(event, stats);
+    }
+
+    /// Returns the sudoku which records every placement and rollback of `solve`, see `take_trace`.
+    ///
+    /// Recording is off by default: a hard puzzle takes millions of steps.
+    pub fn with_trace(mut self) -> Sudoku {
+        self.trace = Some(Vec::new());
+        self
+    }
+
+    /// Returns the steps recorded since the trace was enabled or last taken, in order, nothing unless the trace is
+    /// enabled with `with_trace`.
+    pub fn take_trace(&mut self) -> Vec<TraceEvent> {
+        self.trace.as_mut().map(core::mem::take).unwrap_or_default()
+    }
+
+    /// Returns the (row, col, value) of the cells filled by the solver, in the order it placed them, once the sudoku
+    /// is solved.
+    ///
+    /// Only the placements standing in the solution count: values the search took back are left out, and so are the
+    /// givens and the values placed with `set`. Empty until the sudoku is solved and after `reset`.
+    pub fn fill_order(&self) -> Vec<(usize, usize, u8)> {
+        if !self.solved() {
+            return Vec::new();
+        }
+        let log = &self.backtrack_log;
+        log.iter()
+            .enumerate()
+            .filter(|&(i, entry)| {
+                self.field[entry.pos] == entry.val
+                    && !self.givens[entry.pos]
+                    && log[i + 1..].iter().all(|later| later.pos != entry.pos)
+            })
+            .map(|(_, entry)| {
+                let (row, col) = self.geometry.cell(entry.pos);
+                (row, col, entry.val)
+            })
+            .collect()
+    }
+}
+
+/// Applies the trace of a search to the puzzle and returns the sudoku it leads to, e.g. the solution for the trace of
+/// `solve`.
+///
+/// Every placement must be allowed by the rules in an empty cell and every rollback must take back the latest
+/// placement still standing, otherwise `InvalidTrace` names the first bad step.
+pub fn replay(puzzle: &Sudoku, trace: &[TraceEvent]) -> Result<Sudoku, SudokuError> {
+    let mut s = puzzle.with_field(puzzle.field.clone());
+    for (step, &event) in trace.iter().enumerate() {
+        let invalid = |msg: String| SudokuError::InvalidTrace { step, msg };
+        match event {
+            SolveEvent::Placed { pos, value } => {
+                if pos >= s.field.len() || s.set_value(value, pos).is_err() {
+                    return Err(invalid(format!(
+                        "{} is not allowed in {}",
+                        value,
+                        s.cell_pos(pos)
+                    )));
+                }
+            }
+            SolveEvent::RolledBack { pos, value } => match s.rollback() {
+                Ok(entry) if entry.pos == pos && entry.val == value => {}
+                Ok(entry) => {
+                    return Err(invalid(format!(
+                        "the latest placement is {} in {}, not {} in {}",
+                        entry.val,
+                        s.cell_pos(entry.pos),
+                        value,
+                        s.cell_pos(pos)
+                    )))
+                }
+                Err(_) => return Err(invalid("nothing to roll back".to_string())),
+            },
+        }
+    }
+    s.backtrack_log.clear();
+    Ok(s)
+}
+
+/// Builds a sudoku from its rows where `0` is an empty cell, all filled cells become givens.
+impl TryFrom<[[u8; SIDE]; SIDE]> for Sudoku {
+    type Error = SudokuError;
+
+    fn try_from(rows: [[u8; SIDE]; SIDE]) -> Result<Sudoku, SudokuError> {
+        let mut field = vec![EMPTY; SIZE];
+        for (row, cells) in rows.iter().enumerate() {
+            for (col, &value) in cells.iter().enumerate() {
+                if value > SIDE as u8 {
+                    return Err(SudokuError::InvalidValue { row, col, value });
+                }
+                field[row * SIDE + col] = value;
+            }
+        }
+        Ok(Sudoku::from_field(field))
+    }
+}
+
+/// Sudokus are equal when their geometries, rules, regions, cages, parity marks and fields are equal and they share
+/// the constraints added with `with_constraint`, the backtrack log is not compared.
+impl PartialEq for Sudoku {
+    fn eq(&self, other: &Sudoku) -> bool {
+        let custom = self.rules.constraints().len();
+        let other_custom = other.rules.constraints().len();
+        self.geometry == other.geometry
+            && self.rules == other.rules
+            && self.constraints.len() == other.constraints.len()
+            && self.constraints[custom..]
+                .iter()
+                .zip(other.constraints[other_custom..].iter())
+                .all(|(a, b)| Shared::ptr_eq(a, b))
+            && self.regions == other.regions
+            && self.cages == other.cages
+            && self.parity == other.parity
+            && self.field[..] == other.field[..]
+    }
+}
+
+impl Eq for Sudoku {}
+
+impl Hash for Sudoku {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.field.hash(state);
+    }
+}
+
+/// Parses a sudoku from text where digits `1`-`9` are values and `0` or `.` are empty cells.
+///
+/// Comment lines starting with `#` or `//` are skipped, all other characters are ignored, so both a single line and a
+/// grid split into rows are accepted. The pretty grid printed by `Display` is accepted too: there a blank slot between
+/// `|` separators is an empty cell.
+///
+/// Text with 256 cells is a 16x16 sudoku, there letters `A`-`G` (in any case) are values from 10 to 16. Letters are
+/// only taken as cells when the digits alone do not make a 9x9 sudoku. The values may also be written from `0` for 1
+/// to `F` for 16, with `.` for empty cells: text with a `0` and without a `G` is read that way, unless it holds more `0`s
+/// than the 16 cells of a value, then they are empty cells.
+impl FromStr for Sudoku {
+    type Err = SudokuError;
+
+    fn from_str(s: &str) -> Result<Sudoku, SudokuError> {
+        let field = parse_cells(s, Alphabet::Digits);
+        let len = field.len();
+        if len == SIZE {
+            return Ok(Sudoku::from_field(field));
+        }
+        let side = MAX_SQUARE_SIDE * MAX_SQUARE_SIDE;
+        match parse_cells(s, Alphabet::of(s, side)) {
+            field if field.len() == MAX_SQUARE_SIDE.pow(4) => Ok(Sudoku::from_field(field)),
+            _ => Err(SudokuError::Parse(format!(
+                "expected {} cells, found {}",
+                SIZE, len
+            ))),
         }
     }
 }
 
+/// Characters the text parsed with `FromStr` writes the values with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alphabet {
+    /// Digits from `1`, `0` is an empty cell and letters are ignored.
+    Digits,
+    /// Digits from `1`, then letters from `A` for 10; `0` is an empty cell.
+    FromOne,
+    /// Digits from `0` for 1, then letters up to `F` for 16.
+    FromZero,
+}
+
+impl Alphabet {
+    /// Returns the alphabet of the text of a grid with the side: letters are only needed above 9 values, and only the
+    /// 16 values of the 16x16 grid may be written from `0` to `F`. The text tells that by a `0` without a `G`, as long
+    /// as there are no more `0`s than the grid has cells of one value: more of them can only be empty cells.
+    fn of(text: &str, side: usize) -> Alphabet {
+        let mut lines = text.lines().filter(|line| !is_ignored_line(line));
+        let zeros = lines
+            .clone()
+            .map(|line| line.matches('0').count())
+            .sum::<usize>();
+        match side {
+            side if side <= 9 => Alphabet::Digits,
+            16 if (1..=side).contains(&zeros) && !lines.any(|line| line.contains(['G', 'g'])) => {
+                Alphabet::FromZero
+            }
+            _ => Alphabet::FromOne,
+        }
+    }
+}
+
+/// Returns values of the cells found in the text parsed with `FromStr`, comment lines are skipped.
+fn parse_cells(text: &str, alphabet: Alphabet) -> Vec<u8> {
+    text.lines()
+        .filter(|line| !is_ignored_line(line))
+        .flat_map(|line| parse_line(line, alphabet))
+        .collect()
+}
+
+/// Returns values of the cells found in the line of the text parsed with `FromStr`, in the alphabet of the text.
+fn parse_line(line: &str, alphabet: Alphabet) -> Vec<u8> {
+    let line = line.trim();
+    if line.starts_with('|') && !line.contains('-') {
+        return line
+            .split('|')
+            .skip(1)
+            .filter(|slot| !slot.is_empty())
+            .flat_map(
+                |slot| match slot.trim().trim_matches(['[', ']', '(', ')']).trim() {
+                    "" => vec![EMPTY],
+                    slot => parse_line(slot, alphabet),
+                },
+            )
+            .collect();
+    }
+    line.chars()
+        .filter_map(|c| match (c, alphabet) {
+            ('.', _) => Some(EMPTY),
+            (c, Alphabet::FromZero) => c.to_digit(16).map(|d| d as u8 + 1),
+            ('A'..='G' | 'a'..='g', Alphabet::FromOne) => {
+                Some(c.to_ascii_uppercase() as u8 - b'A' + 10)
+            }
+            (c, _) => c.to_digit(10).map(|d| d as u8),
+        })
+        .collect()
+}
+
+/// Returns the character of the value in the text form of a sudoku: digits up to 9 and letters from `A` for 10.
+pub(crate) fn value_char(value: u8) -> char {
+    match value {
+        EMPTY => '.',
+        v if v < 10 => (b'0' + v) as char,
+        v => (b'A' + v - 10) as char,
+    }
+}
+
+/// Pretty prints the sudoku as a grid.
+///
+/// The alternate form (`{:#}`) prints the sudoku as a single line with `.` for empty cells.
 impl fmt::Display for Sudoku {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "{}", "=====================================")?;
-        for i in 0..SIDE {
-            for j in 0..SIDE {
-                write!(
-                    f,
-                    "| {} ",
-                    match self.field[SIDE * i + j] {
-                        0 => " ".to_string(),
-                        v => v.to_string(),
-                    }
-                )?;
+        if f.alternate() {
+            for &cell in self.field.iter() {
+                write!(f, "{}", value_char(cell))?;
             }
-            write!(f, "|\n")?;
-            match i == SIDE - 1 || (i != 0 && i % 3 == 2) {
-                false => writeln!(f, "{}", "|-----------|-----------|-----------|")?,
-                true => writeln!(f, "{}", "=====================================")?,
+            return Ok(());
+        }
+        self.write_cells(f, |pos| match self.field[pos] {
+            0 => self.framed(pos, ' '),
+            v => self.framed(pos, value_char(v)),
+        })
+    }
+}
+
+impl Sudoku {
+    /// Writes the pretty grid, using `cell` to render the content of the cell at the given position.
+    pub(crate) fn write_grid(
+        &self,
+        f: &mut impl fmt::Write,
+        cell: impl Fn(usize) -> String,
+    ) -> fmt::Result {
+        self.write_cells(f, |pos| format!(" {} ", cell(pos)))
+    }
+
+    /// Writes the pretty grid like `write_grid`, `cell` renders the three characters between the borders of a cell.
+    pub(crate) fn write_cells(
+        &self,
+        f: &mut impl fmt::Write,
+        cell: impl Fn(usize) -> String,
+    ) -> fmt::Result {
+        let (side, box_rows, box_cols) = (
+            self.side(),
+            self.geometry.box_rows(),
+            self.geometry.box_cols(),
+        );
+        let heavy = "=".repeat(4 * side + 1);
+        let light = format!(
+            "|{}",
+            format!("{}|", "-".repeat(4 * box_cols - 1)).repeat(box_rows)
+        );
+        writeln!(f, "{}", heavy)?;
+        for i in 0..side {
+            for j in 0..side {
+                write!(f, "|{}", cell(side * i + j))?;
+            }
+            writeln!(f, "|")?;
+            match (i + 1) % box_rows == 0 {
+                false => writeln!(f, "{}", light)?,
+                true => writeln!(f, "{}", heavy)?,
             }
         }
         Ok(())
@@ -216,6 +1478,8 @@ impl fmt::Display for Sudoku {
 
 #[test]
 fn new_sudoku() {
+    use core::fmt::Write;
+
     let mut field: Vec<u8> = vec![9; SIZE];
     field[0] = 0;
     let sudoku = Sudoku::new(field.into_iter()).unwrap();
@@ -247,34 +1511,36 @@ fn new_sudoku() {
     assert_eq!(&expected_out, &res);
 }
 
+#[cfg(test)]
 fn test_field_helper() -> Sudoku {
-    let mut field: Vec<u8> = vec![0; SIZE];
-    // First square if filled except of central cell. Allowed value is 5.
-    field[0] = 1;
-    field[1] = 2;
-    field[2] = 3;
-    field[9] = 4;
-    field[11] = 6;
-    field[18] = 7;
-    field[19] = 8;
-    field[20] = 9;
-    // First row is filled except of cell #3. Allowed value is 4.
-    field[4] = 5;
-    field[5] = 6;
-    field[6] = 7;
-    field[7] = 8;
-    field[8] = 9;
-    // First col is filled except of cell #3. Allowed value is 2.
-    field[36] = 3;
-    field[45] = 5;
-    field[54] = 6;
-    field[63] = 8;
-    field[72] = 9;
-
-    Sudoku::new(field.into_iter()).unwrap()
+    Sudoku::builder()
+        // First square if filled except of central cell. Allowed value is 5.
+        .set(0, 0, 1)
+        .set(0, 1, 2)
+        .set(0, 2, 3)
+        .set(1, 0, 4)
+        .set(1, 2, 6)
+        .set(2, 0, 7)
+        .set(2, 1, 8)
+        .set(2, 2, 9)
+        // First row is filled except of cell #3. Allowed value is 4.
+        .set(0, 4, 5)
+        .set(0, 5, 6)
+        .set(0, 6, 7)
+        .set(0, 7, 8)
+        .set(0, 8, 9)
+        // First col is filled except of cell #3. Allowed value is 2.
+        .set(4, 0, 3)
+        .set(5, 0, 5)
+        .set(6, 0, 6)
+        .set(7, 0, 8)
+        .set(8, 0, 9)
+        .build()
+        .unwrap()
 }
 
-fn solvable_field_helper() -> Sudoku {
+#[cfg(test)]
+pub(crate) fn solvable_field_helper() -> Sudoku {
     let field: Vec<u8> = vec![
         5, 3, 0, 0, 7, 0, 0, 0, 0, 6, 0, 0, 1, 9, 5, 0, 0, 0, 0, 9, 8, 0, 0, 0, 0, 6, 0, 8, 0, 0,
         0, 6, 0, 0, 0, 3, 4, 0, 0, 8, 0, 3, 0, 0, 1, 7, 0, 0, 0, 2, 0, 0, 0, 6, 0, 6, 0, 0, 0, 0,
@@ -334,7 +1600,7 @@ fn set_value_which_is_already_set() {
 #[test]
 fn rollback() {
     let mut s = test_field_helper();
-    s.set_value(2, 27).is_ok();
+    assert!(s.set_value(2, 27).is_ok());
     let log = s.rollback().unwrap();
     assert_eq!(27, log.pos);
     assert_eq!(2, log.val);
@@ -403,6 +1669,94 @@ fn solve() {
     let res = s.solve();
     assert!(res.is_ok());
     assert!(s.solved());
+    let expected: Vec<u8> = vec![
+        5, 3, 4, 6, 7, 8, 9, 1, 2, 6, 7, 2, 1, 9, 5, 3, 4, 8, 1, 9, 8, 3, 4, 2, 5, 6, 7, 8, 5, 9,
+        7, 6, 1, 4, 2, 3, 4, 2, 6, 8, 5, 3, 7, 9, 1, 7, 1, 3, 9, 2, 4, 8, 5, 6, 9, 6, 1, 5, 3, 7,
+        2, 8, 4, 2, 8, 7, 4, 1, 9, 6, 3, 5, 3, 4, 5, 2, 8, 6, 1, 7, 9,
+    ];
+    assert_eq!(Sudoku::new(expected.into_iter()).unwrap(), s);
+}
+
+#[test]
+fn dead_ends_found_early() {
+    let puzzle: Sudoku = include_str!("../../tasks/very_hard.sudoku")
+        .parse()
+        .unwrap();
+    let mut s = puzzle.clone();
+    let mut placements = 0;
+    s.solve_resumable(|stats| {
+        placements = stats.placements;
+        false
+    })
+    .unwrap();
+    assert!(s.solved());
+    assert!(puzzle.diff(&s).iter().all(|diff| diff.value == EMPTY));
+    assert_eq!(Ok(1), s.count_solutions(2));
+    // Before the check of the neighbouring cells the search reached every dead end and took 2_171 placements.
+    assert_eq!(542, placements);
+    // r1c3 allows 1, 2 and 4 only: with 1 and 2 in its row, a 4 in its column leaves it no value.
+    let mut s = solvable_field_helper();
+    assert_eq!(None, s.dead_peer(0));
+    s.field[3] = 1;
+    s.field[5] = 2;
+    assert_eq!(None, s.dead_peer(5));
+    s.field[11] = 4;
+    assert_eq!(Some(2), s.dead_peer(11));
+}
+
+#[test]
+fn equality_ignores_backtrack_log() {
+    let mut s = test_field_helper();
+    let original = s.clone();
+    s.set_value(2, 27).unwrap();
+    assert_ne!(original, s);
+    s.rollback().unwrap();
+    assert_eq!(original, s);
+}
+
+#[test]
+fn order_of_filled_cells() {
+    for puzzle in [
+        solvable_field_helper(),
+        crate::examples::HARD.parse().unwrap(),
+    ]
+    .iter()
+    {
+        let mut s = puzzle.clone();
+        assert!(s.fill_order().is_empty());
+        s.solve().unwrap();
+        let order = s.fill_order();
+        assert_eq!(puzzle.empty_count(), order.len());
+        let mut replayed = puzzle.clone();
+        for &(row, col, value) in order.iter() {
+            assert_eq!(None, replayed.value(row, col));
+            replayed.set(row, col, value).unwrap();
+        }
+        assert_eq!(s, replayed);
+        s.reset();
+        assert!(s.fill_order().is_empty());
+    }
+    // Values placed with `set` were not determined by the solver, a cleared one leaves its cell to the solver.
+    let mut s = solvable_field_helper();
+    s.set(0, 2, 4).unwrap();
+    s.set(0, 3, 2).unwrap();
+    s.clear(0, 3).unwrap();
+    s.solve().unwrap();
+    let order = s.fill_order();
+    assert_eq!(50, order.len());
+    assert_eq!((0, 3, 6), order[0]);
+    assert!(order.iter().all(|&(row, col, _)| (row, col) != (0, 2)));
+}
+
+#[test]
+fn sudokus_in_hash_set() {
+    use std::collections::HashSet;
+
+    let mut set = HashSet::new();
+    set.insert(solvable_field_helper());
+    set.insert(solvable_field_helper());
+    set.insert(test_field_helper());
+    assert_eq!(2, set.len());
 }
 
 #[test]
@@ -411,3 +1765,709 @@ fn solve_empty() {
     assert!(s.solve().is_ok());
     assert!(s.solved());
 }
+
+#[test]
+fn to_line() {
+    let s = solvable_field_helper();
+    assert_eq!(
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+        s.to_line()
+    );
+}
+
+#[test]
+fn to_line_round_trip() {
+    let s = solvable_field_helper();
+    assert_eq!(s, s.to_line().parse().unwrap());
+}
+
+#[test]
+fn parse_grid_with_zeros() {
+    let s: Sudoku = "530070000\n600195000\n098000060\n800060003\n400803001\n700020006\n060000280\n000419005\n000080079"
+        .parse()
+        .unwrap();
+    assert_eq!(solvable_field_helper(), s);
+}
+
+#[test]
+fn parse_wrong_number_of_cells() {
+    assert_eq!(
+        Err(SudokuError::Parse("expected 81 cells, found 3".to_string())),
+        "1.3".parse::<Sudoku>()
+    );
+}
+
+#[test]
+fn givens() {
+    let mut s = test_field_helper();
+    s.set_value(2, 27).unwrap();
+    assert!(s.is_given(0, 0));
+    assert!(!s.is_given(0, 3));
+    assert!(!s.is_given(3, 0));
+    // Cells outside of the grid are not givens, rather than the cells their index would wrap to.
+    assert!(s.is_given(1, 0));
+    assert!(!s.is_given(0, 9));
+    assert!(!s.is_given(9, 0));
+}
+
+#[test]
+fn givens_are_immutable() {
+    let mut s = solvable_field_helper();
+    let cell = GivenCellImmutable { row: 0, col: 0 };
+    let given = Some(cell);
+    // The same value is refused too, the clue is not edited by solving.
+    for value in [5, 2].iter() {
+        let err = s.set(0, 0, *value).unwrap_err();
+        assert_eq!(given, err.given());
+    }
+    assert_eq!(
+        given,
+        s.put(Pos::new(0, 0), Value::new(2).unwrap())
+            .unwrap_err()
+            .given()
+    );
+    assert_eq!(given, s.clear(0, 0).unwrap_err().given());
+    assert_eq!("r1c1 is a given", cell.to_string());
+    // Errors about the rules are not about givens.
+    assert_eq!(None, s.set(0, 2, 5).unwrap_err().given());
+    assert_eq!(Some(5), s.value(0, 0));
+    // Cells outside of the grid are refused, rather than the cells their index would wrap to.
+    s.set(1, 1, 7).unwrap();
+    let err = s.clear(0, 10).unwrap_err();
+    assert_eq!("r1c11 is outside of the grid", err.msg);
+    assert_eq!(None, err.given());
+    assert!(s.clear(9, 0).is_err());
+    assert_eq!(Some(7), s.value(1, 1));
+    s.unlock(0, 9);
+    assert!(s.is_given(1, 0));
+    s.lock(0, 10);
+    assert!(!s.is_given(1, 1));
+    s.lock(9, 0);
+    s.unlock(9, 0);
+}
+
+#[test]
+fn reset_keeps_givens() {
+    let puzzle = solvable_field_helper();
+    let mut s = puzzle.clone();
+    s.set(0, 2, 4).unwrap();
+    s.solve().unwrap();
+    s.reset();
+    assert_eq!(puzzle, s);
+    assert!(s.backtrack_log.is_empty());
+    assert_eq!(puzzle.clue_count(), s.clue_count());
+    let mut empty = Sudoku::empty();
+    empty.set(4, 4, 1).unwrap();
+    empty.reset();
+    assert_eq!(Sudoku::empty(), empty);
+}
+
+#[test]
+fn lock_and_unlock_clues() {
+    let puzzle = solvable_field_helper();
+    let mut s = puzzle.clone();
+    s.unlock(0, 0);
+    assert!(!s.is_given(0, 0));
+    assert_eq!(Some(5), s.value(0, 0));
+    s.set(0, 0, 2).unwrap();
+    s.clear(0, 0).unwrap();
+    s.set(0, 0, 5).unwrap();
+    s.lock(0, 0);
+    assert!(s.is_given(0, 0));
+    assert!(s.clear(0, 0).is_err());
+    assert_eq!(puzzle.clue_count(), s.clue_count());
+    s.reset();
+    assert_eq!(Some(5), s.value(0, 0));
+    // A value placed later becomes a clue which reset keeps, an empty cell stays editable.
+    s.set(0, 2, 4).unwrap();
+    s.lock(0, 2);
+    s.lock(0, 3);
+    s.reset();
+    assert_eq!(Some(4), s.value(0, 2));
+    assert_eq!(puzzle.clue_count() + 1, s.clue_count());
+    assert!(!s.is_given(0, 3));
+    s.set(0, 3, 6).unwrap();
+}
+
+#[test]
+fn consistency() {
+    assert!(solvable_field_helper().is_consistent());
+    let mut field = solvable_field_helper().field;
+    // 5 is already in the first row.
+    field[2] = 5;
+    assert!(!Sudoku::from_field(field).is_consistent());
+}
+
+#[test]
+fn count_solutions() {
+    assert_eq!(Ok(1), solvable_field_helper().count_solutions(10));
+    let mut field = solvable_field_helper().field;
+    field[2] = 5;
+    assert_eq!(Ok(0), Sudoku::from_field(field).count_solutions(10));
+    let empty = Sudoku::new(vec![0; SIZE].into_iter()).unwrap();
+    assert_eq!(Ok(2), empty.count_solutions(2));
+}
+
+#[test]
+fn set_and_clear_cells() {
+    let mut s = test_field_helper();
+    assert_eq!(None, s.value(0, 3));
+    assert_eq!(vec![4], s.candidates(0, 3));
+    assert!(s.candidates(0, 0).is_empty());
+    assert!(s.candidates(0, 9).is_empty());
+    assert!(s.candidates(9, 0).is_empty());
+    s.set(0, 3, 4).unwrap();
+    assert_eq!(Some(4), s.value(0, 3));
+    // A value put before can be replaced, the cell itself doesn't block it.
+    s.set(0, 3, 4).unwrap();
+    s.clear(0, 3).unwrap();
+    assert_eq!(None, s.value(0, 3));
+}
+
+#[test]
+fn set_not_allowed_values() {
+    let mut s = test_field_helper();
+    let reason = |res: Result<(), ValueNotAllowed>| res.unwrap_err().msg;
+    assert_eq!("5 is already in row 1", reason(s.set(0, 3, 5)));
+    assert_eq!("3 is already in column 1", reason(s.set(3, 0, 3)));
+    assert_eq!("1 is already in the square", reason(s.set(1, 1, 1)));
+    assert_eq!("the cell is a given", reason(s.set(0, 0, 5)));
+    assert_eq!("value must be between 1 and 9", reason(s.set(0, 3, 10)));
+    assert_eq!("the cell is a given", reason(s.clear(0, 0)));
+    assert_eq!(None, s.value(0, 3));
+}
+
+#[test]
+fn typed_cells_and_values() {
+    let mut s = test_field_helper();
+    let cell = Pos { row: 0, col: 3 };
+    let four = Value::new(4).unwrap();
+    assert_eq!(vec![four], s.candidates_of(cell));
+    assert_eq!(None, s.get(cell));
+    s.put(cell, four).unwrap();
+    assert_eq!(Some(four), s.get(cell));
+    assert_eq!(Some(4), s.value(0, 3));
+    assert_eq!(Value::new(1), s.get(Pos::new(0, 0)));
+    let sixteen = Value::with_side(16, 16).unwrap();
+    assert_eq!(
+        "value must be between 1 and 9",
+        s.put(Pos::new(0, 4), sixteen).unwrap_err().msg
+    );
+    let mut large = Sudoku::with_box_size(4).unwrap();
+    large.put(Pos::new(15, 15), sixteen).unwrap();
+    assert_eq!("r16c16", Pos::of(large.geometry(), 255).to_string());
+    assert_eq!(Some(sixteen), large.get(Pos::new(15, 15)));
+    // Cells outside of the grid are refused instead of wrapping to the next row.
+    assert_eq!(None, s.get(Pos::new(0, 9)));
+    assert_eq!(None, s.get(Pos::new(9, 0)));
+    assert_eq!(None, s.value(9, 0));
+    assert!(s.candidates_of(Pos::new(0, 9)).is_empty());
+    assert_eq!(
+        "r10c1 is outside of the grid",
+        s.put(Pos::new(9, 0), four).unwrap_err().msg
+    );
+    assert!(s.set(0, 9, 4).is_err());
+}
+
+#[test]
+fn parse_with_comments() {
+    let commented = "# from NYT 2024-05-01\n530070000\n600195000\n\n// 3 more rows\n098000060\n800060003\n400803001\n700020006\n060000280\n000419005\n000080079\n";
+    assert_eq!(solvable_field_helper(), commented.parse().unwrap());
+}
+
+#[test]
+fn parse_pretty_grid() {
+    let s = solvable_field_helper();
+    assert_eq!(s, s.to_string().parse().unwrap());
+    let mut solved = s.clone();
+    solved.solve().unwrap();
+    assert_eq!(solved, solved.to_string().parse().unwrap());
+}
+
+#[test]
+fn clue_count() {
+    let mut s = solvable_field_helper();
+    assert_eq!(30, s.clue_count());
+    s.solve().unwrap();
+    assert_eq!(30, s.clue_count());
+    assert_eq!(0, Sudoku::empty().clue_count());
+}
+
+#[test]
+fn cell_counts() {
+    let mut s = solvable_field_helper();
+    assert_eq!((30, 51), (s.filled_count(), s.empty_count()));
+    s.set(0, 2, 4).unwrap();
+    assert_eq!(
+        (30, 31, 50),
+        (s.clue_count(), s.filled_count(), s.empty_count())
+    );
+    s.solve().unwrap();
+    assert_eq!((81, 0), (s.filled_count(), s.empty_count()));
+    let small = Sudoku::with_box_size(2).unwrap();
+    assert_eq!((0, 16), (small.filled_count(), small.empty_count()));
+}
+
+#[test]
+fn summaries() {
+    let s = solvable_field_helper();
+    let summary = s.summary();
+    assert_eq!(
+        Summary {
+            clues: 30,
+            filled: 30,
+            empty: 51,
+            consistent: true
+        },
+        summary
+    );
+    assert_eq!("30 clues, 51 empty", summary.to_string());
+    let mut inconsistent = s;
+    inconsistent.field[2] = 5;
+    assert!(!inconsistent.summary().consistent);
+}
+
+#[test]
+fn rows_round_trip() {
+    let s = solvable_field_helper();
+    let rows = s.to_rows();
+    assert_eq!([5, 3, 0, 0, 7, 0, 0, 0, 0], rows[0]);
+    assert_eq!([0, 0, 0, 0, 8, 0, 0, 7, 9], rows[8]);
+    let from_rows = Sudoku::try_from(rows).unwrap();
+    assert_eq!(s, from_rows);
+    assert!(from_rows.is_given(0, 0));
+    assert!(!from_rows.is_given(0, 2));
+    assert_eq!(&s.field, from_rows.as_slice());
+}
+
+#[test]
+fn rows_with_invalid_value() {
+    let mut rows = solvable_field_helper().to_rows();
+    rows[2][7] = 10;
+    let err = Sudoku::try_from(rows).unwrap_err();
+    assert_eq!(
+        SudokuError::InvalidValue {
+            row: 2,
+            col: 7,
+            value: 10
+        },
+        err
+    );
+    assert_eq!("Invalid value 10 in row 3, column 8", err.to_string());
+}
+
+#[cfg(test)]
+pub(crate) const PUZZLE_16X16: &str = concat!(
+    "..8A.3.41F.52..DF.5EA..8.CD2.7.BCG..E1.5..B.89.A..4..G.2.9A85F1E.9B.3...F..A.2...7..........A.F.2.EG1F5A7.3..",
+    "896.F..698B.2GED473G.C...1...8..........A....4.7...6.958.37E..FCGD4.B78.D...659F1E2...7.2E18B.3....B8..74DG.A.",
+    "6.E2C.56.98B32.C1GD.7......A.4D7.3...",
+);
+
+#[cfg(test)]
+const SOLUTION_16X16: &str = concat!(
+    "968AB3741FE52CGDF15EA698GCD2473BCG2DE1F537B4896A734BDGC269A85F1E89B6374DF51AE2CG47D3GC2E986BA5F12CEG1F5A743DB8",
+    "965FA1698BC2GED473GDC42E1FB38796A51EF25A69DG4C73B86A958B37E12FCGD43B784DGCA659F1E2D4G7C2E18B936A5FB83974DG5AF61",
+    "E2CA56F98B32EC1GD47E21CF5A64D7G3B89",
+);
+
+#[test]
+fn solve_16x16() {
+    let mut s: Sudoku = PUZZLE_16X16.parse().unwrap();
+    assert_eq!((16, 4, 4), (s.side(), s.box_rows(), s.box_cols()));
+    assert_eq!(Geometry::square(4), Some(s.geometry()));
+    assert_eq!(Some(10), s.value(0, 3));
+    assert_eq!(Some(16), s.value(2, 1));
+    assert_eq!(Ok(1), s.count_solutions(2));
+    s.solve().unwrap();
+    assert_eq!(SOLUTION_16X16.parse::<Sudoku>().unwrap(), s);
+    assert_eq!(SOLUTION_16X16, s.to_line());
+}
+
+#[test]
+fn display_16x16() {
+    let s: Sudoku = PUZZLE_16X16.to_lowercase().parse().unwrap();
+    assert_eq!(PUZZLE_16X16, s.to_line());
+    let grid = s.to_string();
+    let lines: Vec<&str> = grid.lines().collect();
+    assert_eq!(33, lines.len());
+    assert_eq!("=".repeat(65), lines[0]);
+    assert!(lines[1].starts_with("|   |   | 8 | A |   | 3 |"));
+    assert_eq!("|---------------".repeat(4) + "|", lines[2]);
+    assert_eq!(s, grid.parse().unwrap());
+}
+
+#[test]
+fn parse_16x16_from_zero() {
+    // The same puzzle with the values written from 0 for 1 to F for 16.
+    let from_zero: String = PUZZLE_16X16
+        .chars()
+        .map(|c| match c {
+            '.' => '.',
+            c => char::from_digit(c.to_digit(17).unwrap() - 1, 16).unwrap(),
+        })
+        .collect();
+    assert!(from_zero.starts_with("..79.2.30e.41..c"));
+    let s: Sudoku = from_zero.parse().unwrap();
+    assert_eq!(PUZZLE_16X16, s.to_line());
+    assert_eq!(s, Sudoku::parse_with_box_size(&from_zero, 4).unwrap());
+    // The same puzzle written from 1 to G.
+    assert_eq!(PUZZLE_16X16.parse::<Sudoku>().unwrap(), s);
+    // A sparse puzzle with `0` for empty cells has more of them than the cells of a value, whether it has a `G` or not.
+    let sparse = format!("123456789ABCDEF{}", "0".repeat(241));
+    let s: Sudoku = sparse.parse().unwrap();
+    assert_eq!(15, s.clue_count());
+    assert_eq!(s, sparse.replace('0', ".").parse().unwrap());
+    assert_eq!(s, Sudoku::parse_with_box_size(&sparse, 4).unwrap());
+}
+
+#[cfg(test)]
+const PUZZLE_4X4: &str = "1...\n..2.\n.3..\n...4\n";
+
+#[test]
+fn parse_and_solve_4x4() {
+    let mut s = Sudoku::parse_with_box_size(PUZZLE_4X4, 2).unwrap();
+    assert_eq!((4, 2, 2), (s.side(), s.box_rows(), s.box_cols()));
+    assert_eq!(Geometry::square(2), Some(s.geometry()));
+    assert_eq!("1.....2..3.....4", s.to_line());
+    assert_eq!(Ok(1), s.count_solutions(2));
+    s.solve().unwrap();
+    assert_eq!("1243342143122134", s.to_line());
+    assert!(s.is_consistent());
+    // The auto-detecting parser only knows 9x9 and 16x16 sudokus.
+    assert!(PUZZLE_4X4.parse::<Sudoku>().is_err());
+}
+
+#[test]
+fn parse_4x4_errors() {
+    assert_eq!(
+        Err(SudokuError::InvalidValue {
+            row: 1,
+            col: 2,
+            value: 5
+        }),
+        Sudoku::parse_with_box_size("1.....5..3.....4", 2)
+    );
+    assert_eq!(
+        Err(SudokuError::Parse(
+            "expected 16 cells, found 15".to_string()
+        )),
+        Sudoku::parse_with_box_size("1.....2..3.....", 2)
+    );
+    assert!(Sudoku::parse_with_box_size(PUZZLE_4X4, 5).is_err());
+    assert!(Sudoku::with_box_size(1).is_none());
+}
+
+#[test]
+fn empty_sudokus_of_every_size() {
+    for box_size in MIN_SQUARE_SIDE..=MAX_SQUARE_SIDE {
+        let s = Sudoku::with_box_size(box_size).unwrap();
+        assert_eq!(box_size.pow(4), s.as_slice().len());
+        assert_eq!(0, s.clue_count());
+    }
+    assert_eq!(Sudoku::empty(), Sudoku::with_box_size(3).unwrap());
+}
+
+#[test]
+fn values_above_9_are_rejected_in_9x9() {
+    let mut s = solvable_field_helper();
+    assert!(s.set(0, 2, 10).is_err());
+    assert!(!s.is_allowed(10, 2));
+}
+
+#[test]
+fn box_membership_at_seams_of_6x6() {
+    let mut s = Sudoku::with_box_shape(2, 3).unwrap();
+    assert_eq!((6, 2, 3), (s.side(), s.box_rows(), s.box_cols()));
+    s.set(0, 0, 1).unwrap();
+    // The last column and the last row of the top left box.
+    assert!(!s.candidates(1, 2).contains(&1));
+    assert_eq!(
+        "1 is already in the square",
+        s.clone().set(1, 2, 1).unwrap_err().msg
+    );
+    // The first column of the next box and the first row of the box below.
+    assert!(s.candidates(1, 3).contains(&1));
+    assert!(s.candidates(2, 1).contains(&1));
+    s.set(3, 5, 2).unwrap();
+    assert!(!s.candidates(2, 3).contains(&2));
+    assert!(s.candidates(4, 3).contains(&2));
+    assert!(s.candidates(1, 4).contains(&2));
+    assert!(Sudoku::with_box_shape(1, 6).is_none());
+    assert!(Sudoku::with_box_shape(4, 5).is_none());
+}
+
+#[test]
+fn solve_6x6() {
+    let mut s = Sudoku::parse_with_box_shape(".6.....4...35...2.4....1........1.6.", 2, 3).unwrap();
+    assert_eq!(Ok(1), s.count_solutions(2));
+    s.solve().unwrap();
+    assert_eq!("365142142653513426426531634215251364", s.to_line());
+    assert!(s.conflicts().is_empty());
+    assert!(Sudoku::parse_with_box_shape("7", 2, 3).is_err());
+}
+
+#[test]
+fn display_6x6() {
+    let s = Sudoku::parse_with_box_shape("365142142653513426426531634215251364", 2, 3).unwrap();
+    let grid = s.to_string();
+    let lines: Vec<&str> = grid.lines().collect();
+    assert_eq!("=========================", lines[0]);
+    assert_eq!("| 3 | 6 | 5 | 1 | 4 | 2 |", lines[1]);
+    assert_eq!("|-----------|-----------|", lines[2]);
+    assert_eq!("=========================", lines[4]);
+    assert_eq!(13, lines.len());
+}
+
+#[test]
+fn solve_x_sudoku() {
+    let puzzle =
+        ".1.5......47....3....6.4..9......3.4...3..2..9.5..............2......917.......8.";
+    let mut x: Sudoku = puzzle.parse().unwrap();
+    x = x.with_rules(Rules {
+        diagonals: true,
+        ..Rules::default()
+    });
+    assert_eq!(Ok(1), x.count_solutions(2));
+    x.solve().unwrap();
+    assert_eq!(
+        "219573468647982135358614729862751394174398256935426871786139542423865917591247683",
+        x.to_line()
+    );
+    let mut classic: Sudoku = puzzle.parse().unwrap();
+    classic.solve().unwrap();
+    assert_ne!(x.to_line(), classic.to_line());
+    assert!(!classic
+        .with_rules(Rules {
+            diagonals: true,
+            ..Rules::default()
+        })
+        .is_consistent());
+}
+
+#[test]
+fn center_lies_on_both_diagonals() {
+    let mut s = Sudoku::empty().with_rules(Rules {
+        diagonals: true,
+        ..Rules::default()
+    });
+    s.set(0, 8, 5).unwrap();
+    assert!(s.set(4, 4, 5).is_err());
+    assert!(s.set(4, 4, 6).is_ok());
+    assert!(s.set(8, 8, 6).is_err());
+    assert!(s.set(8, 7, 6).is_ok());
+}
+
+#[cfg(test)]
+const WINDOKU: &str =
+    ".4...........1..5.7.1..2...5..6...7.....9...............7....25.....3..76.9......";
+
+#[test]
+fn solve_windoku() {
+    let hyper = Rules {
+        windows: true,
+        ..Rules::default()
+    };
+    let mut s = WINDOKU.parse::<Sudoku>().unwrap().with_rules(hyper);
+    assert_eq!(Ok(1), s.count_solutions(2));
+    s.solve().unwrap();
+    assert_eq!(
+        "246359718983716452751482396592648173378195264164237589437861925815923647629574831",
+        s.to_line()
+    );
+    assert!(s.conflicts().is_empty());
+}
+
+#[test]
+fn windoku_clues_are_ambiguous_under_classic_rules() {
+    let s: Sudoku = WINDOKU.parse().unwrap();
+    assert_eq!(Ok(2), s.count_solutions(2));
+}
+
+#[test]
+fn set_in_window() {
+    let mut s = Sudoku::empty().with_rules(Rules {
+        windows: true,
+        ..Rules::default()
+    });
+    s.set(1, 1, 7).unwrap();
+    match s.set(3, 3, 7) {
+        Err(err) => assert_eq!("7 is already in window 1", err.msg),
+        Ok(_) => panic!("7 is repeated in the window"),
+    }
+    assert!(s.set(4, 4, 7).is_ok());
+}
+
+#[test]
+fn solve_jigsaw() {
+    use super::regions::JIGSAW_LAYOUT;
+
+    let puzzle =
+        "..4.....91...5.4..8........7.85..1.........2..2.48....6.....87.....1....2....76.4";
+    let regions = Regions::parse(JIGSAW_LAYOUT, Geometry::CLASSIC).unwrap();
+    let mut s = puzzle
+        .parse::<Sudoku>()
+        .unwrap()
+        .with_regions(regions)
+        .unwrap();
+    assert_eq!(Ok(1), s.count_solutions(2));
+    s.solve().unwrap();
+    assert_eq!(
+        "354268719176359482892741365738592146465173928921486537643925871587614293219837654",
+        s.to_line()
+    );
+    assert!(s.conflicts().is_empty());
+    let classic: Sudoku = puzzle.parse().unwrap();
+    assert_eq!(Ok(2), classic.count_solutions(2));
+    let small = Regions::boxes(Geometry::square(2).unwrap());
+    assert!(classic.with_regions(small).is_err());
+}
+
+#[test]
+fn solve_killer_without_clues() {
+    use super::cages::parse_cages;
+
+    let text = std::fs::read_to_string("tasks/killer.cages").unwrap();
+    let cages = parse_cages(&text, Geometry::CLASSIC).unwrap();
+    let mut s = Sudoku::empty().with_cages(cages).unwrap();
+    assert_eq!(Ok(1), s.count_solutions(2));
+    s.solve().unwrap();
+    assert_eq!(
+        "534678912672195348198342567859761423426853791713924856961537284287419635345286179",
+        s.to_line()
+    );
+    assert!(s.conflicts().is_empty() && s.wrong_sums().is_empty());
+}
+
+#[test]
+fn cage_rules() {
+    let cage = |cells: Vec<usize>, sum| Cage { cells, sum };
+    let mut s = Sudoku::empty()
+        .with_cages(vec![cage(vec![0, 1, 2], 10), cage(vec![9, 10], 3)])
+        .unwrap();
+    s.set(0, 0, 6).unwrap();
+    // The last cell needs at least 1, so 4 would exceed the sum.
+    assert!(s.set(0, 1, 4).is_err());
+    s.set(0, 1, 3).unwrap();
+    match s.set(0, 2, 2) {
+        Err(err) => assert_eq!("2 does not fit cage 1 with sum 10", err.msg),
+        Ok(_) => panic!("cage 1 would sum to 11"),
+    }
+    s.set(0, 2, 1).unwrap();
+    // 1 and 2 are the only values summing to 3, but 1 is already in the square.
+    assert!(s.set(1, 0, 1).is_err());
+    assert!(s.set(1, 0, 2).is_ok());
+}
+
+#[test]
+fn invalid_cages() {
+    let cage = |cells: Vec<usize>, sum| Cage { cells, sum };
+    let overlapping = vec![cage(vec![0, 1], 3), cage(vec![1, 2], 4)];
+    assert_eq!(
+        Err(SudokuError::InvalidCages(
+            "r1c2 is in cages 1 and 2".to_string()
+        )),
+        Sudoku::empty().with_cages(overlapping)
+    );
+    assert!(Sudoku::empty()
+        .with_cages(vec![cage(vec![0, 1], 18)])
+        .is_err());
+    assert!(Sudoku::empty().with_cages(vec![cage(vec![], 0)]).is_err());
+    assert!(Sudoku::empty().with_cages(vec![cage(vec![81], 5)]).is_err());
+    assert!(Sudoku::empty()
+        .with_cages(vec![cage((0..10).collect(), 45)])
+        .is_err());
+    assert_eq!(
+        Err(SudokuError::InvalidCages("cage 1 has 30 cells".to_string())),
+        Sudoku::empty().with_cages(vec![cage((0..30).collect(), 45)])
+    );
+}
+
+#[test]
+fn solve_anti_knight() {
+    let puzzle =
+        "......18......1.32..9.....4.................3.369.5.4.9.1.5.47665.1.4.2.4.329.8.5";
+    let anti_knight = Rules {
+        anti_knight: true,
+        ..Rules::default()
+    };
+    let mut s = puzzle.parse::<Sudoku>().unwrap().with_rules(anti_knight);
+    assert_eq!(Ok(1), s.count_solutions(2));
+    s.solve().unwrap();
+    assert_eq!(
+        "562439187784561932319728564197342658845617293236985741921853476658174329473296815",
+        s.to_line()
+    );
+    let classic: Sudoku = puzzle.parse().unwrap();
+    assert_eq!(Ok(2), classic.count_solutions(2));
+    let mut empty = Sudoku::empty().with_rules(anti_knight);
+    empty.set(0, 2, 3).unwrap();
+    match empty.set(1, 4, 3) {
+        Err(err) => assert_eq!("3 is a knight's move away in row 1, column 3", err.msg),
+        Ok(_) => panic!("3 is a knight's move away"),
+    }
+}
+
+#[test]
+fn solve_non_consecutive() {
+    let puzzle =
+        "13.6..4..4..9..8..7..3..1..2..8..5..8..5..2..5..27.9..9..7..6..6..4..........2..9";
+    let non_consecutive = Rules {
+        non_consecutive: true,
+        ..Rules::default()
+    };
+    let mut s = puzzle
+        .parse::<Sudoku>()
+        .unwrap()
+        .with_rules(non_consecutive);
+    assert_eq!(Ok(1), s.count_solutions(2));
+    s.solve().unwrap();
+    assert_eq!(
+        "138627495462951837795384162273849516849516273516273948951738624627495381384162759",
+        s.to_line()
+    );
+    let classic: Sudoku = puzzle.parse().unwrap();
+    assert_eq!(Ok(2), classic.count_solutions(2));
+    let mut empty = Sudoku::empty().with_rules(non_consecutive);
+    empty.set(0, 0, 5).unwrap();
+    empty.set(1, 1, 4).unwrap();
+    match empty.set(0, 1, 6) {
+        Err(err) => assert_eq!("6 is next to 5 in row 1, column 1", err.msg),
+        Ok(_) => panic!("6 is next to 5"),
+    }
+}
+
+#[test]
+fn replay_trace() {
+    let puzzle = solvable_field_helper();
+    let mut s = puzzle.clone().with_trace();
+    s.solve().unwrap();
+    let trace = s.take_trace();
+    assert!(trace
+        .iter()
+        .any(|event| matches!(event, SolveEvent::RolledBack { .. })));
+    assert_eq!(s, replay(&puzzle, &trace).unwrap());
+    assert!(s.take_trace().is_empty());
+    assert!(solvable_field_helper().take_trace().is_empty());
+}
+
+#[test]
+fn replay_tampered_trace() {
+    let puzzle = solvable_field_helper();
+    let mut s = puzzle.clone().with_trace();
+    s.solve().unwrap();
+    let mut trace = s.take_trace();
+    // The first row already holds 5 in its first cell.
+    trace.insert(3, SolveEvent::Placed { pos: 2, value: 5 });
+    match replay(&puzzle, &trace) {
+        Err(SudokuError::InvalidTrace { step, msg }) => {
+            assert_eq!(3, step);
+            assert_eq!("5 is not allowed in r1c3", msg);
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+    let rollback = SolveEvent::RolledBack { pos: 2, value: 1 };
+    assert!(matches!(
+        replay(&puzzle, &[rollback]),
+        Err(SudokuError::InvalidTrace { step: 0, .. })
+    ));
+}
@@ -0,0 +1,103 @@
+//! Progress possible on a puzzle the search cannot solve: the cells forced without guessing, up to the first
+//! contradiction they run into.
+use super::backtracking::Sudoku;
+use super::diagnosis::Diagnosis;
+use core::fmt;
+
+/// Contradiction the forced cells of a puzzle run into, see `Sudoku::best_effort`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contradiction {
+    /// Empty cells the techniques filled before the contradiction showed.
+    pub determined: usize,
+    /// The contradiction, e.g. a cell without candidates or a value without a place in a unit.
+    pub diagnosis: Diagnosis,
+}
+
+/// Prints the contradiction and the forced cells before it, e.g. `r3c4 has no candidates after 12 forced cells`.
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} after {} forced cells",
+            self.diagnosis, self.determined
+        )
+    }
+}
+
+impl Sudoku {
+    /// Fills the cells the techniques force from the givens, the progress possible without guessing, and returns the
+    /// grid with the contradiction it ran into, None if there is none.
+    ///
+    /// The values placed since the sudoku was built, e.g. by a search which stopped, are left out. Filling stops at
+    /// the first contradiction `diagnose` finds, a puzzle solved by the techniques has none.
+    pub fn best_effort(&self) -> (Sudoku, Option<Contradiction>) {
+        let mut s = self.clone();
+        s.reset();
+        let mut determined = 0;
+        loop {
+            let diagnosis = s.diagnose();
+            if diagnosis != Diagnosis::Exhausted {
+                return (
+                    s,
+                    Some(Contradiction {
+                        determined,
+                        diagnosis,
+                    }),
+                );
+            }
+            match s.next_deduction() {
+                Some(deduction) => {
+                    let pos = s.geometry().pos(deduction.row, deduction.col);
+                    s.field[pos] = deduction.value;
+                    determined += 1;
+                }
+                None => return (s, None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+use super::rules::Unit;
+
+#[test]
+fn forced_cells_before_contradiction() {
+    let s: Sudoku = crate::examples::WRONG_CLUE.parse().unwrap();
+    assert_eq!(Diagnosis::Exhausted, s.diagnose());
+    let (best, contradiction) = s.best_effort();
+    let contradiction = contradiction.unwrap();
+    assert_eq!(
+        Diagnosis::NoPlace {
+            unit: Unit::Column(8),
+            value: 8
+        },
+        contradiction.diagnosis
+    );
+    assert_eq!(12, contradiction.determined);
+    assert_eq!(s.filled_count() + 12, best.filled_count());
+    assert!(best.is_consistent());
+    assert_eq!(
+        "8 cannot be placed in column 9 after 12 forced cells",
+        contradiction.to_string()
+    );
+    // Clues which contradict each other right away force nothing.
+    let unsolvable: Sudoku = crate::examples::UNSOLVABLE.parse().unwrap();
+    let (best, contradiction) = unsolvable.best_effort();
+    assert_eq!(unsolvable, best);
+    assert_eq!(0, contradiction.unwrap().determined);
+}
+
+#[test]
+fn forced_cells_of_solvable_puzzles() {
+    // The easy example is solved by the techniques alone, the search placements of the hard one are left out.
+    let easy: Sudoku = crate::examples::EASY.parse().unwrap();
+    let (best, contradiction) = easy.best_effort();
+    assert!(best.solved() && contradiction.is_none());
+    let hard: Sudoku = crate::examples::HARD.parse().unwrap();
+    let mut solved = hard.clone();
+    solved.solve().unwrap();
+    let (best, contradiction) = solved.best_effort();
+    assert_eq!(None, contradiction);
+    assert!(!best.solved());
+    assert_eq!(hard.best_effort().0, best);
+}
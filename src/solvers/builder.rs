@@ -0,0 +1,98 @@
+use super::backtracking::{Sudoku, SudokuError, EMPTY, SIDE, SIZE};
+use crate::prelude::*;
+
+/// Builds a sudoku cell by cell, see `Sudoku::builder`.
+///
+/// Coordinates and values are checked as soon as they are set, the first error is returned by `build`.
+#[derive(Debug, Clone)]
+pub struct SudokuBuilder {
+    field: Vec<u8>,
+    error: Option<SudokuError>,
+}
+
+impl Sudoku {
+    /// Returns a builder of a sudoku with no filled cells.
+    pub fn builder() -> SudokuBuilder {
+        SudokuBuilder {
+            field: vec![EMPTY; SIZE],
+            error: None,
+        }
+    }
+
+    /// Builds a sudoku from (row, col, value) clues, all 0-based except for the value.
+    pub fn from_clues(clues: &[(usize, usize, u8)]) -> Result<Sudoku, SudokuError> {
+        clues
+            .iter()
+            .fold(Sudoku::builder(), |builder, &(row, col, value)| {
+                builder.set(row, col, value)
+            })
+            .build()
+    }
+}
+
+impl SudokuBuilder {
+    /// Puts the value (1-9) into the cell at 0-based `row` and `col`.
+    pub fn set(mut self, row: usize, col: usize, value: u8) -> SudokuBuilder {
+        if self.error.is_some() {
+            return self;
+        }
+        if row >= SIDE || col >= SIDE {
+            self.error = Some(SudokuError::InvalidCell { row, col });
+        } else if value == EMPTY || value > SIDE as u8 {
+            self.error = Some(SudokuError::InvalidValue { row, col, value });
+        } else {
+            self.field[row * SIDE + col] = value;
+        }
+        self
+    }
+
+    /// Returns the sudoku with all set cells as givens.
+    ///
+    /// Fails with the first invalid cell or value, or when clues repeat a value in a row, column or square.
+    pub fn build(self) -> Result<Sudoku, SudokuError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let sudoku = Sudoku::from_field(self.field);
+        let conflicts: Vec<String> = sudoku.conflicts().iter().map(|c| c.to_string()).collect();
+        match conflicts.is_empty() {
+            true => Ok(sudoku),
+            false => Err(SudokuError::ConflictingClues(conflicts.join("; "))),
+        }
+    }
+}
+
+#[test]
+fn build_sudoku() {
+    let s = Sudoku::builder().set(0, 0, 5).set(0, 4, 7).build().unwrap();
+    assert_eq!(Some(5), s.value(0, 0));
+    assert_eq!(Some(7), s.value(0, 4));
+    assert_eq!(None, s.value(0, 1));
+    assert!(s.is_given(0, 4));
+    assert_eq!(s, Sudoku::from_clues(&[(0, 4, 7), (0, 0, 5)]).unwrap());
+}
+
+#[test]
+fn build_with_invalid_cell_or_value() {
+    assert_eq!(
+        Err(SudokuError::InvalidCell { row: 9, col: 0 }),
+        Sudoku::builder().set(9, 0, 1).set(0, 0, 10).build()
+    );
+    assert_eq!(
+        Err(SudokuError::InvalidValue {
+            row: 0,
+            col: 0,
+            value: 0
+        }),
+        Sudoku::from_clues(&[(0, 0, 0)])
+    );
+}
+
+#[test]
+fn build_with_conflicting_clues() {
+    let err = Sudoku::from_clues(&[(0, 0, 5), (4, 0, 5)]).unwrap_err();
+    assert_eq!(
+        "Conflicting clues: 5 is repeated in column 1: r1c1, r5c1",
+        err.to_string()
+    );
+}
@@ -0,0 +1,195 @@
+//! Solutions of puzzles seen before, so a batch repeating puzzles solves each of them once.
+use super::backtracking::{Sudoku, Unsolvable};
+use super::transform::CanonicalKey;
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+
+/// Outcome of solving a puzzle stored by `SolveCache`.
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Field of the puzzle, equivalent puzzles share the key but not the field.
+    puzzle: Vec<u8>,
+    solution: Result<Vec<u8>, Unsolvable>,
+    /// Tick of the last use, the key of the entry in `SolveCache::recent`.
+    used: u64,
+}
+
+/// Solutions of the last puzzles solved, keyed by their canonical form, see `Sudoku::canonical_form`.
+///
+/// Only the same puzzle again is answered from the cache: a puzzle equivalent by symmetry has the same key, but a
+/// solution cannot be mapped back to it, so it is solved and replaces the entry. When the cache is full the least
+/// recently used entry is dropped. The rules are not part of the key, use one cache for puzzles of the same variant.
+#[derive(Debug, Clone)]
+pub struct SolveCache {
+    capacity: usize,
+    entries: BTreeMap<CanonicalKey, Entry>,
+    /// Keys of the entries by the tick of their last use, the first one is dropped when the cache is full.
+    recent: BTreeMap<u64, CanonicalKey>,
+    tick: u64,
+    hits: usize,
+    misses: usize,
+}
+
+impl SolveCache {
+    /// Returns an empty cache keeping the solutions of at most `capacity` puzzles, 0 stores nothing.
+    pub fn new(capacity: usize) -> SolveCache {
+        SolveCache {
+            capacity,
+            entries: BTreeMap::new(),
+            recent: BTreeMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of puzzles stored.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of puzzles answered from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Returns the number of puzzles which had to be solved.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Solves the sudoku like `Sudoku::solve`, unless the same puzzle is in the cache.
+    pub fn solve(&mut self, sudoku: &mut Sudoku) -> Result<(), Unsolvable> {
+        self.solve_with(sudoku, Sudoku::solve)
+    }
+
+    /// Fills the sudoku with the cached solution of the same puzzle, or solves it with `solve` and stores the result.
+    ///
+    /// A cached puzzle without a solution returns its error and leaves the sudoku as it is.
+    pub fn solve_with(
+        &mut self,
+        sudoku: &mut Sudoku,
+        solve: impl FnOnce(&mut Sudoku) -> Result<(), Unsolvable>,
+    ) -> Result<(), Unsolvable> {
+        let key = sudoku.canonical_form();
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(&key) {
+            if entry.puzzle == sudoku.field {
+                self.recent.remove(&entry.used);
+                self.recent.insert(self.tick, key);
+                entry.used = self.tick;
+                self.hits += 1;
+                return match &entry.solution {
+                    Ok(solution) => {
+                        *sudoku = sudoku.filled_from(solution);
+                        Ok(())
+                    }
+                    Err(unsolvable) => Err(unsolvable.clone()),
+                };
+            }
+        }
+        self.misses += 1;
+        let puzzle = sudoku.field.clone();
+        let res = solve(sudoku);
+        if self.capacity > 0 {
+            let solution = res.clone().map(|_| sudoku.field.clone());
+            self.insert(key, puzzle, solution);
+        }
+        res
+    }
+
+    /// Stores the solution of the puzzle, dropping the least recently used entry when the cache is full.
+    fn insert(
+        &mut self,
+        key: CanonicalKey,
+        puzzle: Vec<u8>,
+        solution: Result<Vec<u8>, Unsolvable>,
+    ) {
+        let entry = Entry {
+            puzzle,
+            solution,
+            used: self.tick,
+        };
+        if let Some(replaced) = self.entries.insert(key.clone(), entry) {
+            self.recent.remove(&replaced.used);
+        } else if self.entries.len() > self.capacity {
+            let oldest = self.recent.keys().next().copied();
+            if let Some(key) = oldest.and_then(|used| self.recent.remove(&used)) {
+                self.entries.remove(&key);
+            }
+        }
+        self.recent.insert(self.tick, key);
+    }
+}
+
+#[cfg(test)]
+use crate::examples;
+
+#[test]
+fn repeated_puzzles() {
+    let easy: Sudoku = examples::EASY.parse().unwrap();
+    let mut solved = easy.clone();
+    solved.solve().unwrap();
+    let mut cache = SolveCache::new(4);
+    for _ in 0..3 {
+        let mut s = easy.clone();
+        cache.solve(&mut s).unwrap();
+        assert_eq!(solved, s);
+        assert_eq!(easy.clue_count(), s.clue_count());
+    }
+    assert_eq!((2, 1, 1), (cache.hits(), cache.misses(), cache.len()));
+    let unsolvable: Sudoku = examples::UNSOLVABLE.parse().unwrap();
+    let err = unsolvable.clone().solve().unwrap_err();
+    for _ in 0..2 {
+        let mut s = unsolvable.clone();
+        assert_eq!(Err(err.clone()), cache.solve(&mut s));
+    }
+    assert_eq!((3, 2), (cache.hits(), cache.misses()));
+}
+
+#[test]
+fn equivalent_puzzles_are_solved() {
+    let easy: Sudoku = examples::EASY.parse().unwrap();
+    let mut cache = SolveCache::new(4);
+    cache.solve(&mut easy.clone()).unwrap();
+    let mut transposed = easy.transpose().unwrap();
+    cache.solve(&mut transposed).unwrap();
+    let mut solved = easy.clone();
+    solved.solve().unwrap();
+    assert_eq!(solved.transpose().unwrap(), transposed);
+    assert_eq!((0, 2, 1), (cache.hits(), cache.misses(), cache.len()));
+}
+
+#[test]
+fn least_recently_used_is_dropped() {
+    let puzzles: Vec<Sudoku> = [examples::EASY, examples::HARD, examples::UNSOLVABLE]
+        .iter()
+        .map(|line| line.parse().unwrap())
+        .collect();
+    let mut cache = SolveCache::new(2);
+    let mut solve = |i: usize| {
+        let hits = cache.hits();
+        let _ = cache.solve(&mut puzzles[i].clone());
+        cache.hits() > hits
+    };
+    // The easy puzzle is used again before the third one needs room, the hard one is dropped.
+    let hits: Vec<bool> = [0, 1, 0, 2, 0, 1].iter().map(|&i| solve(i)).collect();
+    assert_eq!(vec![false, false, true, false, true, false], hits);
+    assert_eq!(2, cache.len());
+    let mut disabled = SolveCache::new(0);
+    for _ in 0..2 {
+        disabled.solve(&mut puzzles[0].clone()).unwrap();
+    }
+    assert_eq!(
+        (0, 2, 0),
+        (disabled.hits(), disabled.misses(), disabled.len())
+    );
+}
@@ -0,0 +1,125 @@
+//! Cages of killer sudokus.
+//!
+//! Cages are read from text with a cage per line: the sum followed by the cells of the cage, named like `r1c2` with
+//! 1-based rows and columns and separated by spaces or commas. Lines starting with `#` or `//` are comments:
+//!
+//! ```text
+//! # Cells r1c1 and r1c2 sum to 3
+//! 3 r1c1 r1c2
+//! 15: r2c1, r3c1, r3c2
+//! ```
+use super::backtracking::SudokuError;
+use super::geometry::Geometry;
+use crate::formats::is_ignored_line;
+use crate::prelude::*;
+
+/// Cells of a killer sudoku whose values sum to `sum` and are all different.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cage {
+    /// Positions of the cells.
+    pub cells: Vec<usize>,
+    pub sum: u8,
+}
+
+impl Cage {
+    /// Returns the smallest and the largest sum of `cells` different values from 1 to `side`.
+    ///
+    /// `cells` must not exceed `side`, there are no such values otherwise.
+    pub fn sum_range(cells: usize, side: usize) -> (usize, usize) {
+        let min = cells * (cells + 1) / 2;
+        let max = cells * (2 * side + 1 - cells) / 2;
+        (min, max)
+    }
+}
+
+/// Parses cages in the format described in the module documentation for a grid of the given geometry.
+///
+/// Only the format is checked here, see `Sudoku::with_cages` for the rules cages must follow.
+pub fn parse_cages(text: &str, geometry: Geometry) -> Result<Vec<Cage>, SudokuError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !is_ignored_line(line))
+        .map(|(num, line)| {
+            parse_cage(line, geometry).map_err(|msg| SudokuError::ParseLine { line: num + 1, msg })
+        })
+        .collect()
+}
+
+/// Parses a single line with the sum and the cells of a cage.
+fn parse_cage(line: &str, geometry: Geometry) -> Result<Cage, String> {
+    let mut words = line
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty());
+    let sum = words.next().unwrap_or_default().trim_end_matches(':');
+    let sum = sum
+        .parse()
+        .map_err(|_| format!("expected the sum of the cage, found `{}`", sum))?;
+    let cells = words
+        .map(|cell| {
+            parse_cell_name(cell, geometry).ok_or_else(|| format!("invalid cell `{}`", cell))
+        })
+        .collect::<Result<Vec<usize>, String>>()?;
+    Ok(Cage { cells, sum })
+}
+
+/// Returns the position of a cell named like `r1c2`, None if the name is malformed or the cell is outside of the grid.
+pub(crate) fn parse_cell_name(name: &str, geometry: Geometry) -> Option<usize> {
+    let (row, col) = name
+        .to_ascii_lowercase()
+        .strip_prefix('r')?
+        .split_once('c')
+        .and_then(
+            |(row, col)| match (row.parse::<usize>(), col.parse::<usize>()) {
+                (Ok(row), Ok(col)) => Some((row, col)),
+                _ => None,
+            },
+        )?;
+    match (1..=geometry.side()).contains(&row) && (1..=geometry.side()).contains(&col) {
+        true => Some(geometry.pos(row - 1, col - 1)),
+        false => None,
+    }
+}
+
+#[test]
+fn parse_cages_in_both_styles() {
+    let cages = parse_cages(
+        "# comment\n3 r1c1 r1c2\n\n15: r2c1, R3C1, r3c2\n",
+        Geometry::CLASSIC,
+    )
+    .unwrap();
+    assert_eq!(
+        vec![
+            Cage {
+                cells: vec![0, 1],
+                sum: 3
+            },
+            Cage {
+                cells: vec![9, 18, 19],
+                sum: 15
+            }
+        ],
+        cages
+    );
+}
+
+#[test]
+fn parse_malformed_cages() {
+    match parse_cages("3 r1c1 r1c2\nr1c3 4\n", Geometry::CLASSIC) {
+        Err(SudokuError::ParseLine { line, msg }) => {
+            assert_eq!(2, line);
+            assert_eq!("expected the sum of the cage, found `r1c3`", msg);
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+    assert!(parse_cages("3 r1c10", Geometry::CLASSIC).is_err());
+    assert!(parse_cages("3 r0c1", Geometry::CLASSIC).is_err());
+    assert!(parse_cages("3 c1r1", Geometry::CLASSIC).is_err());
+}
+
+#[test]
+fn sum_ranges() {
+    assert_eq!((1, 9), Cage::sum_range(1, 9));
+    assert_eq!((3, 17), Cage::sum_range(2, 9));
+    assert_eq!((45, 45), Cage::sum_range(9, 9));
+    assert_eq!((10, 10), Cage::sum_range(4, 4));
+}
@@ -0,0 +1,130 @@
+//! Values allowed in a cell as a bit per value, so the values of a whole unit are taken out with a single mask.
+use core::fmt;
+use core::iter::FromIterator;
+
+/// Set of values from 1 to 32 with a bit per value, bit 0 for the value 1.
+///
+/// The 16 values of the largest grid leave room to spare, see `MAX_SQUARE_SIDE`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Candidates(u32);
+
+impl Candidates {
+    /// Returns the set without values.
+    pub fn none() -> Candidates {
+        Candidates(0)
+    }
+
+    /// Returns the set of every value of a grid with the side, from 1 to `side`.
+    pub fn all(side: usize) -> Candidates {
+        Candidates(((1u64 << side) - 1) as u32)
+    }
+
+    /// Returns the set with the bits of the mask, bit 0 for the value 1.
+    pub fn from_bits(bits: u32) -> Candidates {
+        Candidates(bits)
+    }
+
+    /// Returns the bits of the set, bit 0 for the value 1.
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if the value is in the set.
+    pub fn contains(self, value: u8) -> bool {
+        (1..=32).contains(&value) && self.0 & bit(value) != 0
+    }
+
+    /// Adds the value, which must be from 1 to 32.
+    pub fn insert(&mut self, value: u8) {
+        self.0 |= bit(value);
+    }
+
+    /// Takes the value out of the set.
+    pub fn remove(&mut self, value: u8) {
+        if (1..=32).contains(&value) {
+            self.0 &= !bit(value);
+        }
+    }
+
+    /// Returns the values of either set.
+    pub fn union(self, other: Candidates) -> Candidates {
+        Candidates(self.0 | other.0)
+    }
+
+    /// Returns the values of the set which are not in the other one.
+    pub fn without(self, other: Candidates) -> Candidates {
+        Candidates(self.0 & !other.0)
+    }
+
+    /// Returns the number of values in the set.
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Returns `true` if the set has no values.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the values of the set in ascending order.
+    pub fn iter(self) -> impl Iterator<Item = u8> {
+        let mut bits = self.0;
+        core::iter::from_fn(move || match bits {
+            0 => None,
+            _ => {
+                let value = bits.trailing_zeros() as u8 + 1;
+                bits &= bits - 1;
+                Some(value)
+            }
+        })
+    }
+}
+
+/// Returns the bit of the value, which must be from 1 to 32.
+fn bit(value: u8) -> u32 {
+    1 << (value - 1)
+}
+
+impl FromIterator<u8> for Candidates {
+    fn from_iter<I: IntoIterator<Item = u8>>(values: I) -> Candidates {
+        let mut set = Candidates::none();
+        for value in values {
+            set.insert(value);
+        }
+        set
+    }
+}
+
+/// Prints the values in braces, e.g. `{1, 4, 9}`.
+impl fmt::Debug for Candidates {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+use crate::prelude::*;
+
+#[test]
+fn values_of_candidates() {
+    let mut set: Candidates = [9, 1, 4].iter().copied().collect();
+    assert_eq!(vec![1, 4, 9], set.iter().collect::<Vec<u8>>());
+    assert_eq!((3, 0b1_0000_1001), (set.len(), set.bits()));
+    assert!(set.contains(4) && !set.contains(5) && !set.contains(0));
+    set.remove(4);
+    set.remove(0);
+    assert_eq!("{1, 9}", format!("{:?}", set));
+    assert_eq!(
+        Candidates::from_bits(0b1111_1110),
+        Candidates::all(8).without(set)
+    );
+    assert_eq!(16, Candidates::all(16).len());
+    assert_eq!(32, Candidates::all(32).iter().last().unwrap());
+    assert!(Candidates::none().is_empty());
+    assert_eq!(
+        vec![1, 2, 9],
+        set.union(Candidates::from_bits(2))
+            .iter()
+            .collect::<Vec<u8>>()
+    );
+}
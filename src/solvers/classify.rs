@@ -0,0 +1,262 @@
+//! Whether a puzzle has a unique solution, none or several, the outcomes which matter when a puzzle is checked.
+use super::backtracking::{Sudoku, EMPTY};
+use super::rules::CellDiff;
+use crate::prelude::*;
+use core::fmt;
+
+/// Number of solutions `Sudoku::classify` looks for before it stops counting.
+pub const CLASSIFY_CAP: usize = 10;
+
+/// Outcome of `Sudoku::classify`.
+#[derive(Debug, Clone)]
+pub enum PuzzleClass {
+    /// The puzzle has exactly one solution, which is given.
+    Unique(Sudoku),
+    /// The puzzle has no solution.
+    None,
+    /// The puzzle has more than one solution: `found` of them were counted before the cap stopped the search, the
+    /// `sample` is the first one and `second` the next one, which differs from it in some cells.
+    Multiple {
+        found: usize,
+        sample: Sudoku,
+        second: Box<Sudoku>,
+    },
+    /// The search reached the node limit of the sudoku, see `Sudoku::with_node_limit`, before it could tell: `found`
+    /// solutions were counted.
+    Unknown { found: usize },
+}
+
+impl PuzzleClass {
+    /// Returns the name of the class: `unique`, `none`, `multiple` or `unknown`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PuzzleClass::Unique(_) => "unique",
+            PuzzleClass::None => "none",
+            PuzzleClass::Multiple { .. } => "multiple",
+            PuzzleClass::Unknown { .. } => "unknown",
+        }
+    }
+
+    /// Returns the number of solutions found, at most the cap of the search.
+    pub fn found(&self) -> usize {
+        match self {
+            PuzzleClass::Unique(_) => 1,
+            PuzzleClass::None => 0,
+            PuzzleClass::Multiple { found, .. } | PuzzleClass::Unknown { found } => *found,
+        }
+    }
+
+    /// Returns the solution of a unique puzzle or the sample of one with several solutions.
+    pub fn solution(&self) -> Option<&Sudoku> {
+        match self {
+            PuzzleClass::Unique(solution)
+            | PuzzleClass::Multiple {
+                sample: solution, ..
+            } => Some(solution),
+            PuzzleClass::None | PuzzleClass::Unknown { .. } => None,
+        }
+    }
+
+    /// Returns the cells where the two solutions of a puzzle with several solutions differ, None for other puzzles.
+    ///
+    /// The values of `sample` come first, e.g. the four corners of a rectangle whose values can be swapped.
+    pub fn ambiguity(&self) -> Option<Vec<CellDiff>> {
+        match self {
+            PuzzleClass::Multiple { sample, second, .. } => Some(sample.diff(second)),
+            _ => None,
+        }
+    }
+}
+
+/// Prints the class like `unique solution`, `no solution`, `multiple solutions, 10 found` or
+/// `unknown, the search reached its limit`.
+impl fmt::Display for PuzzleClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PuzzleClass::Unique(_) => write!(f, "unique solution"),
+            PuzzleClass::None => write!(f, "no solution"),
+            PuzzleClass::Multiple { found, .. } => {
+                write!(f, "multiple solutions, {} found", found)
+            }
+            PuzzleClass::Unknown { .. } => write!(f, "unknown, the search reached its limit"),
+        }
+    }
+}
+
+impl Sudoku {
+    /// Tells whether the puzzle has a unique solution, none or several, counting at most `CLASSIFY_CAP` solutions.
+    pub fn classify(&self) -> PuzzleClass {
+        self.classify_up_to(CLASSIFY_CAP)
+    }
+
+    /// Tells whether the puzzle has a unique solution, none or several, counting at most `cap` solutions.
+    ///
+    /// The solutions keep the givens of the puzzle, the cells they fill are placed like the solver places them. A cap
+    /// below 2 is raised to 2, fewer solutions cannot tell a unique puzzle apart. The search stops at the cap, so the
+    /// two solutions of an ambiguous puzzle are found without enumerating all of them.
+    ///
+    /// The search stops at the node limit of the sudoku, see `Sudoku::with_node_limit`, the class is `Unknown` then.
+    pub fn classify_up_to(&self, cap: usize) -> PuzzleClass {
+        let max_nodes = self.node_limit().max_nodes(self.geometry());
+        self.classify_within(cap, max_nodes.unwrap_or(u64::MAX))
+    }
+
+    /// Classifies the puzzle like `classify_up_to`, `Unknown` if the search needs more than `max_nodes` placements.
+    pub(crate) fn classify_within(&self, cap: usize, max_nodes: u64) -> PuzzleClass {
+        let (mut count, mut budget) = (0, max_nodes);
+        let mut first: Option<Vec<u8>> = None;
+        let mut second: Option<Vec<u8>> = None;
+        let ran_out = self.is_consistent()
+            && self.with_field(self.field.clone()).count_within(
+                cap.max(2),
+                &mut count,
+                &mut budget,
+                &mut |field| match first {
+                    None => first = Some(field.to_vec()),
+                    Some(_) => {
+                        second.get_or_insert_with(|| field.to_vec());
+                    }
+                },
+            );
+        if ran_out {
+            return PuzzleClass::Unknown { found: count };
+        }
+        let solution = match first {
+            Some(field) => self.filled_from(&field),
+            None => return PuzzleClass::None,
+        };
+        match second {
+            None => PuzzleClass::Unique(solution),
+            Some(field) => PuzzleClass::Multiple {
+                found: count,
+                sample: solution,
+                second: Box::new(self.filled_from(&field)),
+            },
+        }
+    }
+
+    /// Returns a copy of the sudoku with its empty cells filled with the values of the solution's field.
+    pub(crate) fn filled_from(&self, field: &[u8]) -> Sudoku {
+        let mut s = self.clone();
+        for pos in (0..field.len()).filter(|&pos| self.field[pos] == EMPTY) {
+            s.set_value(field[pos], pos)
+                .expect("the values of a solution are allowed");
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+use super::limit::{LimitReached, NodeLimit};
+#[cfg(test)]
+use crate::examples;
+
+#[test]
+fn unique_puzzle() {
+    let easy: Sudoku = examples::EASY.parse().unwrap();
+    let class = easy.classify();
+    assert_eq!("unique", class.name());
+    assert_eq!("unique solution", class.to_string());
+    match class {
+        PuzzleClass::Unique(solution) => {
+            assert!(solution.solved());
+            assert_eq!(easy.clue_count(), solution.clue_count());
+            let mut solved = easy.clone();
+            solved.solve().unwrap();
+            assert_eq!(solved.to_line(), solution.to_line());
+        }
+        other => panic!("Unexpected class {:?}", other),
+    }
+}
+
+#[test]
+fn puzzles_without_solution() {
+    let unsolvable: Sudoku = examples::UNSOLVABLE.parse().unwrap();
+    assert!(matches!(unsolvable.classify(), PuzzleClass::None));
+    let repeated: Sudoku = format!("55{}", ".".repeat(79)).parse().unwrap();
+    let class = repeated.classify();
+    assert_eq!(("none", 0), (class.name(), class.found()));
+    assert_eq!("no solution", class.to_string());
+    assert!(class.solution().is_none());
+}
+
+#[test]
+fn puzzles_with_several_solutions() {
+    // The solution of the easy example with a rectangle of four cells removed, their two values can be swapped.
+    let two: Sudoku =
+        "53467891267219534819834256785976.42.42685.79.713924856961537284287419635345286179"
+            .parse()
+            .unwrap();
+    let class = two.classify();
+    match &class {
+        PuzzleClass::Multiple {
+            found,
+            sample,
+            second,
+        } => {
+            assert_eq!(2, *found);
+            for solution in [sample, second].iter() {
+                assert!(solution.solved());
+                assert!(solution.is_consistent());
+                assert_eq!(two.clue_count(), solution.clue_count());
+                assert!(two.diff(solution).iter().all(|d| d.value == EMPTY));
+            }
+        }
+        other => panic!("Unexpected class {:?}", other),
+    }
+    let cells: Vec<(usize, usize)> = class
+        .ambiguity()
+        .unwrap()
+        .iter()
+        .map(|d| (d.cell.row, d.cell.col))
+        .collect();
+    assert_eq!(vec![(3, 5), (3, 8), (4, 5), (4, 8)], cells);
+    assert!(class
+        .ambiguity()
+        .unwrap()
+        .iter()
+        .all(|d| d.value != d.other));
+    assert_eq!(
+        None,
+        examples::EASY
+            .parse::<Sudoku>()
+            .unwrap()
+            .classify()
+            .ambiguity()
+    );
+    // Counting stops at the cap.
+    let class = Sudoku::empty().classify();
+    assert_eq!(("multiple", CLASSIFY_CAP), (class.name(), class.found()));
+    assert_eq!("multiple solutions, 10 found", class.to_string());
+    assert!(class.solution().unwrap().solved());
+    assert_eq!(2, Sudoku::empty().classify_up_to(0).found());
+}
+
+#[test]
+fn classify_within_node_limit() {
+    // The empty 16x16 grid needs 256 placements for its first solution.
+    let large = Sudoku::with_box_size(4)
+        .unwrap()
+        .with_node_limit(NodeLimit::Max(5));
+    let class = large.classify();
+    assert_eq!(("unknown", 0), (class.name(), class.found()));
+    assert_eq!("unknown, the search reached its limit", class.to_string());
+    let err = large.count_solutions(2).unwrap_err();
+    assert_eq!(LimitReached { found: 0 }, err);
+    assert_eq!(
+        "the search stopped at its limit with 0 solutions found",
+        err.to_string()
+    );
+    let unlimited = large.with_node_limit(NodeLimit::Unlimited);
+    assert_eq!("multiple", unlimited.classify_up_to(2).name());
+    assert_eq!(Ok(2), unlimited.count_solutions(2));
+    // A search which uses up the budget with its last placement finished.
+    let mut solution = Sudoku::empty();
+    solution.solve().unwrap();
+    let mut field = solution.as_slice().to_vec();
+    field[0] = EMPTY;
+    let one_empty = Sudoku::from_field(field);
+    assert_eq!(Some(1), one_empty.count_solutions_within(2, 1));
+    assert_eq!("unique", one_empty.classify_within(2, 1).name());
+    assert_eq!(None, Sudoku::empty().count_solutions_within(2, 1));
+}
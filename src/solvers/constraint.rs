@@ -0,0 +1,481 @@
+//! Constraints of sudoku variants on top of the classic rows, columns and squares.
+//!
+//! The rules of the built-in variants are constraints too, see `Rules::constraints`. A library user adds a rule of
+//! their own by implementing `Constraint` and registering it with `Sudoku::with_constraint`. Solving, counting
+//! solutions, hints and validation then all follow it. A thermometer, whose values increase from the bulb, could be
+//! as simple as the one below, `thermometers::Thermometer` is the built-in one:
+//!
+//! ```
+//! use sudoku::solvers::backtracking::Sudoku;
+//! use sudoku::solvers::constraint::{Constraint, Violation};
+//!
+//! /// Cells from the bulb of the thermometer on hold increasing values.
+//! #[derive(Debug)]
+//! struct Thermometer(Vec<(usize, usize)>);
+//!
+//! impl Constraint for Thermometer {
+//!     fn allows(&self, grid: &Sudoku, pos: usize, value: u8) -> bool {
+//!         let cell = grid.geometry().cell(pos);
+//!         let at = match self.0.iter().position(|&c| c == cell) {
+//!             Some(at) => at,
+//!             None => return true,
+//!         };
+//!         let below = self.0[..at].iter().filter_map(|&(row, col)| grid.value(row, col));
+//!         let above = self.0[at + 1..].iter().filter_map(|&(row, col)| grid.value(row, col));
+//!         below.max().map_or(true, |max| max < value) && above.min().map_or(true, |min| min > value)
+//!     }
+//!
+//!     fn check_complete(&self, grid: &Sudoku) -> Result<(), Violation> {
+//!         let values: Vec<u8> = self.0.iter().filter_map(|&(row, col)| grid.value(row, col)).collect();
+//!         match values.windows(2).all(|pair| pair[0] < pair[1]) {
+//!             true => Ok(()),
+//!             false => Err(Violation::new("the thermometer does not increase", self.0.clone())),
+//!         }
+//!     }
+//! }
+//!
+//! let mut s = Sudoku::empty().with_constraint(Thermometer(vec![(0, 0), (0, 1), (0, 2)]));
+//! s.set(0, 1, 5).unwrap();
+//! assert!(s.set(0, 2, 4).is_err());
+//! ```
+use super::backtracking::{Sudoku, EMPTY};
+use super::rules::{repeated_values, Conflict, ConsecutiveNeighbours, Unit};
+use crate::prelude::*;
+use core::fmt;
+
+#[cfg(not(feature = "std"))]
+pub use alloc::rc::Rc as Shared;
+/// Pointer sharing constraints between sudokus, `Arc` with the `std` feature and `Rc` without it: targets like the
+/// RP2040 lack the atomics `Arc` needs.
+#[cfg(feature = "std")]
+pub use alloc::sync::Arc as Shared;
+
+/// Rule a sudoku follows on top of the classic rows, columns and squares.
+pub trait Constraint: fmt::Debug + Send + Sync {
+    /// Returns `true` if the value may be put into the empty cell at the position given the filled cells of the grid.
+    ///
+    /// The check runs at every placement, so a rule between two cells only needs to look at the filled ones.
+    fn allows(&self, grid: &Sudoku, pos: usize, value: u8) -> bool;
+
+    /// Returns the violation of the rule by the filled cells of the grid, empty cells are ignored.
+    fn check_complete(&self, grid: &Sudoku) -> Result<(), Violation>;
+
+    /// Returns why the value is not allowed at the position, used when a value is set by hand.
+    fn reason(&self, _grid: &Sudoku, _pos: usize, value: u8) -> String {
+        format!("{} is not allowed by {:?}", value, self)
+    }
+
+    /// Returns the extra regions which hold every value once under the rule, they are used by logical techniques.
+    fn regions(&self, _grid: &Sudoku) -> Vec<(Unit, Vec<usize>)> {
+        Vec::new()
+    }
+
+    /// Returns the positions of the cells whose allowed values may depend on the cell at the position under the rule,
+    /// None if it may be any cell. `Sudoku::apply_edit` compares the candidates of these cells only.
+    fn peers(&self, _grid: &Sudoku, _pos: usize) -> Option<Vec<usize>> {
+        None
+    }
+}
+
+/// Broken rule of a constraint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub msg: String,
+    /// (row, col) of the cells breaking the rule.
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl Violation {
+    pub fn new(msg: &str, cells: Vec<(usize, usize)>) -> Violation {
+        Violation {
+            msg: msg.to_string(),
+            cells,
+        }
+    }
+
+    /// Returns the violation listing all problems, Ok if there are none.
+    fn of<T: fmt::Display>(
+        problems: Vec<T>,
+        cells: impl Fn(&T) -> Vec<(usize, usize)>,
+    ) -> Result<(), Violation> {
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let msgs: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+        Err(Violation {
+            msg: msgs.join("; "),
+            cells: problems.iter().flat_map(cells).collect(),
+        })
+    }
+}
+
+/// Prints the description of the violation.
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+/// Both main diagonals hold every value once, as in X-Sudoku.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Diagonals;
+
+/// Windows, the extra regions of Windoku (Hyper Sudoku), hold every value once, see `Geometry::windows`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Windows;
+
+/// Cells a chess knight's move apart hold different values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AntiKnight;
+
+/// Cells sharing a side do not hold consecutive values, which differ by 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonConsecutive;
+
+/// Returns the region through the position which already holds the value.
+///
+/// A position may lie in several regions, e.g. the center cell of an odd grid lies on both diagonals.
+fn region_with(
+    regions: Vec<(Unit, Vec<usize>)>,
+    grid: &Sudoku,
+    pos: usize,
+    value: u8,
+) -> Option<Unit> {
+    regions
+        .into_iter()
+        .find(|(_, cells)| cells.contains(&pos) && cells.iter().any(|&i| grid.field[i] == value))
+        .map(|(unit, _)| unit)
+}
+
+/// Returns every value repeated within the regions as a violation.
+fn check_regions(regions: Vec<(Unit, Vec<usize>)>, grid: &Sudoku) -> Result<(), Violation> {
+    let conflicts: Vec<Conflict> = regions
+        .into_iter()
+        .flat_map(|(unit, cells)| repeated_values(grid, unit, &cells))
+        .collect();
+    Violation::of(conflicts, |c| c.cells.clone())
+}
+
+/// Returns the cells of the regions through the position.
+fn region_peers(regions: Vec<(Unit, Vec<usize>)>, pos: usize) -> Option<Vec<usize>> {
+    Some(
+        regions
+            .into_iter()
+            .filter(|(_, cells)| cells.contains(&pos))
+            .flat_map(|(_, cells)| cells)
+            .collect(),
+    )
+}
+
+/// Returns why the value cannot be put into a region which already holds it.
+fn region_reason(regions: Vec<(Unit, Vec<usize>)>, grid: &Sudoku, pos: usize, value: u8) -> String {
+    match region_with(regions, grid, pos, value) {
+        Some(unit) => format!("{} is already in {}", value, unit),
+        None => format!("{} is allowed", value),
+    }
+}
+
+impl Constraint for Diagonals {
+    fn allows(&self, grid: &Sudoku, pos: usize, value: u8) -> bool {
+        region_with(self.regions(grid), grid, pos, value).is_none()
+    }
+
+    fn check_complete(&self, grid: &Sudoku) -> Result<(), Violation> {
+        check_regions(self.regions(grid), grid)
+    }
+
+    fn reason(&self, grid: &Sudoku, pos: usize, value: u8) -> String {
+        region_reason(self.regions(grid), grid, pos, value)
+    }
+
+    fn peers(&self, grid: &Sudoku, pos: usize) -> Option<Vec<usize>> {
+        region_peers(self.regions(grid), pos)
+    }
+
+    fn regions(&self, grid: &Sudoku) -> Vec<(Unit, Vec<usize>)> {
+        let geometry = grid.geometry();
+        vec![
+            (Unit::Diagonal(0), geometry.diagonal_cells(0)),
+            (Unit::Diagonal(1), geometry.diagonal_cells(1)),
+        ]
+    }
+}
+
+impl Constraint for Windows {
+    fn allows(&self, grid: &Sudoku, pos: usize, value: u8) -> bool {
+        region_with(self.regions(grid), grid, pos, value).is_none()
+    }
+
+    fn check_complete(&self, grid: &Sudoku) -> Result<(), Violation> {
+        check_regions(self.regions(grid), grid)
+    }
+
+    fn reason(&self, grid: &Sudoku, pos: usize, value: u8) -> String {
+        region_reason(self.regions(grid), grid, pos, value)
+    }
+
+    fn peers(&self, grid: &Sudoku, pos: usize) -> Option<Vec<usize>> {
+        region_peers(self.regions(grid), pos)
+    }
+
+    fn regions(&self, grid: &Sudoku) -> Vec<(Unit, Vec<usize>)> {
+        grid.geometry()
+            .windows()
+            .into_iter()
+            .enumerate()
+            .map(|(i, cells)| (Unit::Window(i), cells))
+            .collect()
+    }
+}
+
+impl AntiKnight {
+    /// Returns the position a knight's move away from the given one which already holds the value.
+    fn knight_move_with(&self, grid: &Sudoku, pos: usize, value: u8) -> Option<usize> {
+        let (row, col) = grid.geometry().cell(pos);
+        grid.geometry()
+            .knight_moves(row, col)
+            .find(|&i| grid.field[i] == value)
+    }
+
+    /// Returns every pair of cells a knight's move apart with the same value, each pair once.
+    pub fn conflicts(&self, grid: &Sudoku) -> Vec<Conflict> {
+        let geometry = grid.geometry();
+        let mut conflicts = Vec::new();
+        for (pos, &value) in grid.field.iter().enumerate() {
+            let (row, col) = geometry.cell(pos);
+            for other in geometry.knight_moves(row, col) {
+                if value != EMPTY && other > pos && grid.field[other] == value {
+                    conflicts.push(Conflict {
+                        unit: Unit::KnightMove,
+                        value,
+                        cells: vec![(row, col), geometry.cell(other)],
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+impl Constraint for AntiKnight {
+    fn allows(&self, grid: &Sudoku, pos: usize, value: u8) -> bool {
+        self.knight_move_with(grid, pos, value).is_none()
+    }
+
+    fn check_complete(&self, grid: &Sudoku) -> Result<(), Violation> {
+        Violation::of(self.conflicts(grid), |c| c.cells.clone())
+    }
+
+    fn reason(&self, grid: &Sudoku, pos: usize, value: u8) -> String {
+        let (row, col) = grid
+            .geometry()
+            .cell(self.knight_move_with(grid, pos, value).unwrap_or(pos));
+        format!(
+            "{} is a knight's move away in row {}, column {}",
+            value,
+            row + 1,
+            col + 1
+        )
+    }
+
+    fn peers(&self, grid: &Sudoku, pos: usize) -> Option<Vec<usize>> {
+        let (row, col) = grid.geometry().cell(pos);
+        Some(grid.geometry().knight_moves(row, col).collect())
+    }
+}
+
+impl NonConsecutive {
+    /// Returns the filled position next to the given one which holds a value consecutive to the given one.
+    fn consecutive_neighbour(&self, grid: &Sudoku, pos: usize, value: u8) -> Option<usize> {
+        let (row, col) = grid.geometry().cell(pos);
+        grid.geometry()
+            .orthogonal_neighbours(row, col)
+            .find(|&i| grid.field[i] != EMPTY && grid.field[i].abs_diff(value) == 1)
+    }
+
+    /// Returns every pair of neighbouring cells with consecutive values, each pair once.
+    pub fn pairs(&self, grid: &Sudoku) -> Vec<ConsecutiveNeighbours> {
+        let geometry = grid.geometry();
+        let mut pairs = Vec::new();
+        for (pos, &value) in grid.field.iter().enumerate() {
+            let (row, col) = geometry.cell(pos);
+            for other in geometry.orthogonal_neighbours(row, col) {
+                let other_value = grid.field[other];
+                if value != EMPTY
+                    && other > pos
+                    && other_value != EMPTY
+                    && value.abs_diff(other_value) == 1
+                {
+                    pairs.push(ConsecutiveNeighbours {
+                        cells: [(row, col), geometry.cell(other)],
+                        values: [value, other_value],
+                    });
+                }
+            }
+        }
+        pairs
+    }
+}
+
+impl Constraint for NonConsecutive {
+    fn allows(&self, grid: &Sudoku, pos: usize, value: u8) -> bool {
+        self.consecutive_neighbour(grid, pos, value).is_none()
+    }
+
+    fn check_complete(&self, grid: &Sudoku) -> Result<(), Violation> {
+        Violation::of(self.pairs(grid), |p| p.cells.to_vec())
+    }
+
+    fn reason(&self, grid: &Sudoku, pos: usize, value: u8) -> String {
+        let other = self.consecutive_neighbour(grid, pos, value).unwrap_or(pos);
+        let (row, col) = grid.geometry().cell(other);
+        format!(
+            "{} is next to {} in row {}, column {}",
+            value,
+            grid.field[other],
+            row + 1,
+            col + 1
+        )
+    }
+
+    fn peers(&self, grid: &Sudoku, pos: usize) -> Option<Vec<usize>> {
+        let (row, col) = grid.geometry().cell(pos);
+        Some(grid.geometry().orthogonal_neighbours(row, col).collect())
+    }
+}
+
+#[cfg(test)]
+use super::rules::Rules;
+
+#[test]
+fn conflicts_on_diagonals() {
+    let mut field = vec![EMPTY; 81];
+    field[0] = 1;
+    field[40] = 1;
+    field[72] = 2;
+    field[16] = 2;
+    let classic = Sudoku::from_field(field);
+    assert!(classic.violations().is_empty());
+    let x = classic.with_rules(Rules {
+        diagonals: true,
+        ..Rules::default()
+    });
+    assert_eq!(
+        vec![Violation {
+            msg: "1 is repeated in main diagonal: r1c1, r5c5; 2 is repeated in anti-diagonal: r2c8, r9c1"
+                .to_string(),
+            cells: vec![(0, 0), (4, 4), (1, 7), (8, 0)],
+        }],
+        x.violations()
+    );
+    assert!(x.conflicts().is_empty());
+    assert!(!x.is_consistent());
+}
+
+#[test]
+fn knight_move_conflicts() {
+    let mut field = vec![EMPTY; 81];
+    field[2] = 3;
+    field[13] = 3;
+    field[40] = 7;
+    field[57] = 7;
+    let s = Sudoku::from_field(field);
+    assert!(AntiKnight.check_complete(&s).is_err());
+    assert!(s.violations().is_empty());
+    let anti_knight = s.with_rules(Rules {
+        anti_knight: true,
+        ..Rules::default()
+    });
+    let violations: Vec<String> = anti_knight
+        .violations()
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    assert_eq!(
+        vec![
+            "3 is repeated a knight's move apart: r1c3, r2c5; 7 is repeated a knight's move apart: r5c5, r7c4"
+        ],
+        violations
+    );
+}
+
+#[test]
+fn consecutive_neighbours() {
+    let mut field = vec![EMPTY; 81];
+    field[0] = 4;
+    field[1] = 5;
+    field[9] = 3;
+    field[10] = 7;
+    let s = Sudoku::from_field(field);
+    let pairs: Vec<String> = NonConsecutive
+        .pairs(&s)
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+    assert_eq!(
+        vec![
+            "4 and 5 are neighbours: r1c1, r1c2",
+            "4 and 3 are neighbours: r1c1, r2c1"
+        ],
+        pairs
+    );
+    assert!(s.violations().is_empty());
+    let non_consecutive = s.with_rules(Rules {
+        non_consecutive: true,
+        ..Rules::default()
+    });
+    assert_eq!(1, non_consecutive.violations().len());
+    assert!(non_consecutive.conflicts().is_empty());
+}
+
+/// Toy rule for tests: the top-left cell holds an even value.
+#[cfg(test)]
+#[derive(Debug)]
+struct EvenCorner;
+
+#[cfg(test)]
+impl Constraint for EvenCorner {
+    fn allows(&self, _grid: &Sudoku, pos: usize, value: u8) -> bool {
+        pos != 0 || value % 2 == 0
+    }
+
+    fn check_complete(&self, grid: &Sudoku) -> Result<(), Violation> {
+        match grid.value(0, 0) {
+            Some(value) if value % 2 != 0 => {
+                Err(Violation::new("the top-left cell is odd", vec![(0, 0)]))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn custom_constraint() {
+    // The empty grid is ambiguous, the solver fills the top-left cell with the smallest value it can.
+    let mut classic = Sudoku::empty();
+    classic.solve().unwrap();
+    assert_eq!(Some(1), classic.value(0, 0));
+    let mut even = Sudoku::empty().with_constraint(EvenCorner);
+    even.solve().unwrap();
+    assert_eq!(Some(2), even.value(0, 0));
+    assert!(even.violations().is_empty());
+    let mut s = Sudoku::empty().with_constraint(EvenCorner);
+    match s.set(0, 0, 3) {
+        Err(err) => assert_eq!("3 is not allowed by EvenCorner", err.msg),
+        Ok(_) => panic!("3 is odd"),
+    }
+    // A single solution under the classic rules is left, it has an odd top-left cell.
+    let mut field = classic.as_slice().to_vec();
+    field[0] = EMPTY;
+    assert_eq!(Ok(1), Sudoku::from_field(field.clone()).count_solutions(2));
+    assert_eq!(
+        Ok(0),
+        Sudoku::from_field(field)
+            .with_constraint(EvenCorner)
+            .count_solutions(2)
+    );
+    assert_eq!(
+        vec![Violation::new("the top-left cell is odd", vec![(0, 0)])],
+        classic.with_constraint(EvenCorner).violations()
+    );
+}
@@ -0,0 +1,161 @@
+//! Statistics of the techniques and the search over a corpus of puzzles, to tune the logical solver.
+use super::backtracking::Sudoku;
+use super::logical::{Explanation, Technique};
+use super::observer::{SolveEvent, SolveObserver, SolveStats};
+use crate::prelude::*;
+use core::time::Duration;
+
+/// How `Sudoku::solve_with_techniques` solved a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TechniqueSolve {
+    /// Deductions of the techniques in the order they were made.
+    pub explanations: Vec<Explanation>,
+    /// The techniques left empty cells, so the search had to guess.
+    pub guessed: bool,
+    /// Counters of the search for the cells the techniques left, all 0 without guessing.
+    pub search: SolveStats,
+    pub solved: bool,
+}
+
+impl TechniqueSolve {
+    /// Returns the number of values placed by the techniques and by the search.
+    pub fn placements(&self) -> u64 {
+        self.explanations.len() as u64 + self.search.placements
+    }
+}
+
+/// Observer keeping the counters of the latest step of the search.
+#[derive(Default)]
+struct LastStats(SolveStats);
+
+impl SolveObserver for LastStats {
+    fn on_event(&mut self, _event: SolveEvent, stats: &SolveStats) {
+        self.0 = *stats;
+    }
+}
+
+impl Sudoku {
+    /// Solves the sudoku with the techniques while any of them applies, then with the search for the cells they left.
+    ///
+    /// Clues breaking the rules are left to the search, which finds no solution.
+    pub fn solve_with_techniques(&mut self) -> TechniqueSolve {
+        let explanations = match self.is_consistent() {
+            true => self.solve_logical(),
+            false => Vec::new(),
+        };
+        let guessed = !self.solved();
+        let mut search = LastStats::default();
+        let solved = !guessed || self.solve_with_observer(&mut search).is_ok();
+        TechniqueSolve {
+            explanations,
+            guessed,
+            search: search.0,
+            solved,
+        }
+    }
+}
+
+/// Tally of the puzzles of a corpus, see `record`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorpusStats {
+    /// Deductions of every technique, in the order of `Technique::ALL`.
+    pub deductions: [u64; Technique::ALL.len()],
+    pub solved: usize,
+    pub unsolvable: usize,
+    /// Puzzles the techniques did not solve alone.
+    pub guessed: usize,
+    /// Puzzles which cannot be read, they are not counted otherwise.
+    pub parse_errors: usize,
+    /// Backtracks of the search of every puzzle in the order they were recorded.
+    pub backtracks: Vec<u64>,
+    /// Values placed in every puzzle, see `TechniqueSolve::placements`.
+    pub placements: Vec<u64>,
+    /// Solve time of every puzzle.
+    pub times: Vec<Duration>,
+}
+
+impl CorpusStats {
+    /// Counts the solve of a puzzle which took `time`.
+    pub fn record(&mut self, solve: &TechniqueSolve, time: Duration) {
+        for explanation in solve.explanations.iter() {
+            self.deductions[explanation.technique as usize] += 1;
+        }
+        match solve.solved {
+            true => self.solved += 1,
+            false => self.unsolvable += 1,
+        }
+        self.guessed += solve.guessed as usize;
+        self.backtracks.push(solve.search.backtracks);
+        self.placements.push(solve.placements());
+        self.times.push(time);
+    }
+
+    /// Returns the number of deductions of the technique.
+    pub fn deductions_of(&self, technique: Technique) -> u64 {
+        self.deductions[technique as usize]
+    }
+
+    /// Returns the number of puzzles, read or not.
+    pub fn puzzles(&self) -> usize {
+        self.solved + self.unsolvable + self.parse_errors
+    }
+}
+
+/// Returns the value below which `percent` percent of the values lie by the nearest-rank method, e.g. the median for
+/// 50, None if there are no values.
+pub fn percentile<T: Copy + Ord>(values: &[T], percent: u8) -> Option<T> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let rank = (sorted.len() * percent.min(100) as usize + 99) / 100;
+    sorted.get(rank.max(1) - 1).copied()
+}
+
+#[cfg(test)]
+use crate::examples;
+
+#[test]
+fn techniques_of_easy_puzzle() {
+    // Every one of the 51 empty cells of the easy example is a naked single in turn.
+    let mut easy: Sudoku = examples::EASY.parse().unwrap();
+    let solve = easy.solve_with_techniques();
+    assert!(easy.solved() && solve.solved && !solve.guessed);
+    assert_eq!(51, solve.placements());
+    assert_eq!(SolveStats::default(), solve.search);
+    let mut stats = CorpusStats::default();
+    stats.record(&solve, Duration::from_millis(2));
+    assert_eq!([51, 0], stats.deductions);
+    assert_eq!(0, stats.deductions_of(Technique::HiddenSingle));
+    let mut hard: Sudoku = examples::HARD.parse().unwrap();
+    let hard_solve = hard.solve_with_techniques();
+    assert!(hard.solved() && hard_solve.solved && hard_solve.guessed);
+    assert!(hard_solve.search.placements > 0);
+    stats.record(&hard_solve, Duration::from_millis(9));
+    let mut unsolvable: Sudoku = examples::UNSOLVABLE.parse().unwrap();
+    let solve = unsolvable.solve_with_techniques();
+    assert!(!solve.solved && solve.guessed);
+    stats.record(&solve, Duration::from_millis(1));
+    assert_eq!(
+        (2, 1, 2, 3),
+        (
+            stats.solved,
+            stats.unsolvable,
+            stats.guessed,
+            stats.puzzles()
+        )
+    );
+    assert_eq!(
+        vec![0, hard_solve.search.backtracks, solve.search.backtracks],
+        stats.backtracks
+    );
+}
+
+#[test]
+fn nearest_rank_percentiles() {
+    let values: Vec<u64> = (1..=10).rev().collect();
+    assert_eq!(Some(5), percentile(&values, 50));
+    assert_eq!(Some(9), percentile(&values, 90));
+    assert_eq!(Some(10), percentile(&values, 99));
+    assert_eq!(Some(1), percentile(&values, 0));
+    assert_eq!(Some(7), percentile(&[7], 50));
+    assert_eq!(None, percentile::<u64>(&[], 50));
+}
@@ -0,0 +1,188 @@
+//! Runs a puzzle through every solver implementation and checks that they agree, a harness for new solvers.
+use super::backtracking::Sudoku;
+use super::rules::cell_name;
+use crate::prelude::*;
+use alloc::boxed::Box;
+use core::fmt;
+
+/// Implementation of a solver taking part in `cross_check`.
+pub trait Solver {
+    /// Name of the solver in reports of a mismatch.
+    fn name(&self) -> &str;
+
+    /// Returns the solution of the puzzle, None if the puzzle has no solution.
+    fn solve(&self, puzzle: &Sudoku) -> Option<Sudoku>;
+}
+
+/// The backtracking search of `Sudoku::solve`.
+pub struct Backtracking;
+
+impl Solver for Backtracking {
+    fn name(&self) -> &str {
+        "backtracking"
+    }
+
+    fn solve(&self, puzzle: &Sudoku) -> Option<Sudoku> {
+        let mut s = puzzle.clone();
+        match s.is_consistent() && s.solve().is_ok() {
+            true => Some(s),
+            false => None,
+        }
+    }
+}
+
+/// `Sudoku::solve_logical` followed by the backtracking search for the cells no technique fills.
+pub struct LogicalThenBacktracking;
+
+impl Solver for LogicalThenBacktracking {
+    fn name(&self) -> &str {
+        "logical+backtracking"
+    }
+
+    fn solve(&self, puzzle: &Sudoku) -> Option<Sudoku> {
+        if !puzzle.is_consistent() {
+            return None;
+        }
+        let mut s = puzzle.clone();
+        s.solve_logical();
+        // Deductions only fill a cell with its single remaining value, an inconsistent grid means a contradiction.
+        match s.is_consistent() && s.solve().is_ok() {
+            true => Some(s),
+            false => None,
+        }
+    }
+}
+
+/// Two solvers which came to different results, see `cross_check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub first: String,
+    pub second: String,
+    /// (row, col) of the first cell the solutions differ in, None if only one of the solvers found a solution.
+    pub cell: Option<(usize, usize)>,
+    /// Values of the cell in the solutions of the first and the second solver.
+    pub values: Option<(u8, u8)>,
+}
+
+/// Names the diverging solvers and the cell with 1-based coordinates, e.g.
+/// `backtracking and logical+backtracking disagree at r1c3: 4 vs 2`.
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} and {} disagree", self.first, self.second)?;
+        match (self.cell, self.values) {
+            (Some((row, col)), Some((first, second))) => {
+                write!(f, " at {}: {} vs {}", cell_name(row, col), first, second)
+            }
+            _ => write!(f, " whether the puzzle has a solution"),
+        }
+    }
+}
+
+/// Returns the solvers of the crate: the backtracking search alone and after the logical solver.
+pub fn solvers() -> Vec<Box<dyn Solver>> {
+    vec![Box::new(Backtracking), Box::new(LogicalThenBacktracking)]
+}
+
+/// Solves the puzzle with every solver of the crate, see `cross_check_with`.
+pub fn cross_check(puzzle: &Sudoku) -> Result<Option<Sudoku>, Mismatch> {
+    let solvers = solvers();
+    let solvers: Vec<&dyn Solver> = solvers.iter().map(|solver| solver.as_ref()).collect();
+    cross_check_with(puzzle, &solvers)
+}
+
+/// Returns the solution every solver agrees on, None if they all find no solution.
+///
+/// Fails with the first solver which disagrees with the first one. Solvers may pick different solutions of a puzzle
+/// with several ones, that is a mismatch too.
+pub fn cross_check_with(
+    puzzle: &Sudoku,
+    solvers: &[&dyn Solver],
+) -> Result<Option<Sudoku>, Mismatch> {
+    let (reference, others) = match solvers.split_first() {
+        Some(split) => split,
+        None => return Ok(None),
+    };
+    let expected = reference.solve(puzzle);
+    for solver in others {
+        let solution = solver.solve(puzzle);
+        let diverged = match (&expected, &solution) {
+            (Some(expected), Some(solution)) => expected.diff(solution).first().map(|diff| {
+                (
+                    Some((diff.cell.row, diff.cell.col)),
+                    Some((diff.value, diff.other)),
+                )
+            }),
+            (None, None) => None,
+            _ => Some((None, None)),
+        };
+        if let Some((cell, values)) = diverged {
+            return Err(Mismatch {
+                first: reference.name().to_string(),
+                second: solver.name().to_string(),
+                cell,
+                values,
+            });
+        }
+    }
+    Ok(expected)
+}
+
+#[cfg(test)]
+use super::backtracking::solvable_field_helper;
+
+/// Solver returning a fixed answer, to check how disagreements are reported.
+#[cfg(test)]
+struct Fake(Option<Sudoku>);
+
+#[cfg(test)]
+impl Solver for Fake {
+    fn name(&self) -> &str {
+        "fake"
+    }
+
+    fn solve(&self, _puzzle: &Sudoku) -> Option<Sudoku> {
+        self.0.clone()
+    }
+}
+
+#[test]
+fn solvers_agree() {
+    let puzzle = solvable_field_helper();
+    let mut expected = puzzle.clone();
+    expected.solve().unwrap();
+    assert_eq!(Some(expected), cross_check(&puzzle).unwrap());
+    let mut unsolvable = puzzle;
+    unsolvable.field[2] = 5;
+    assert_eq!(None, cross_check(&unsolvable).unwrap());
+    let mut hard: Sudoku =
+        "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4.."
+            .parse()
+            .unwrap();
+    let solution = cross_check(&hard).unwrap().unwrap();
+    hard.solve().unwrap();
+    assert_eq!(hard, solution);
+}
+
+#[test]
+fn fake_solver_disagrees() {
+    let puzzle = solvable_field_helper();
+    let mut solution = puzzle.clone();
+    solution.solve().unwrap();
+    let mut wrong = solution.clone();
+    wrong.field.swap(2, 3);
+    let fake = Fake(Some(wrong));
+    let mismatch = cross_check_with(&puzzle, &[&Backtracking, &fake]).unwrap_err();
+    assert_eq!(Some((0, 2)), mismatch.cell);
+    assert_eq!(
+        "backtracking and fake disagree at r1c3: 4 vs 6",
+        mismatch.to_string()
+    );
+    let none = Fake(None);
+    let mismatch = cross_check_with(&puzzle, &[&LogicalThenBacktracking, &none]).unwrap_err();
+    assert_eq!(None, mismatch.cell);
+    assert_eq!(
+        "logical+backtracking and fake disagree whether the puzzle has a solution",
+        mismatch.to_string()
+    );
+    assert!(cross_check_with(&puzzle, &[&Backtracking, &Fake(Some(solution))]).is_ok());
+}
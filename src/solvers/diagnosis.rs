@@ -0,0 +1,143 @@
+//! Why a sudoku has no solution, reported with `Unsolvable` when the search fails.
+use super::backtracking::{Sudoku, Unsolvable, EMPTY};
+use super::observer::SolveStats;
+use super::rules::{cell_name, Conflict, Unit};
+use core::fmt;
+
+/// Earliest contradiction found in the filled cells of a sudoku without a solution, see `Sudoku::diagnose`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnosis {
+    /// A value is repeated within a unit.
+    Conflict(Conflict),
+    /// No value is allowed in the empty cell.
+    NoCandidates { row: usize, col: usize },
+    /// The value is missing from a unit holding every value, and none of its empty cells allows it.
+    NoPlace { unit: Unit, value: u8 },
+    /// The filled cells allow a value in every cell and a place for every value, the search still found no solution.
+    Exhausted,
+    /// The search stopped at the limit of its placements before it finished, see `Sudoku::with_node_limit`.
+    LimitReached { stats: SolveStats },
+}
+
+/// Prints the contradiction with 1-based coordinates, e.g. `r1c9 has no candidates` or
+/// `7 cannot be placed in row 3`.
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Diagnosis::Conflict(conflict) => write!(f, "{}", conflict),
+            Diagnosis::NoCandidates { row, col } => {
+                write!(f, "{} has no candidates", cell_name(*row, *col))
+            }
+            Diagnosis::NoPlace { unit, value } => {
+                write!(f, "{} cannot be placed in {}", value, unit)
+            }
+            Diagnosis::Exhausted => write!(f, "exhaustive search found no solution"),
+            Diagnosis::LimitReached { stats } => write!(
+                f,
+                "the search stopped at its limit after {} placements and {} backtracks",
+                stats.placements, stats.backtracks
+            ),
+        }
+    }
+}
+
+impl Sudoku {
+    /// Returns the most useful reason the filled cells leave no solution: a repeated value, then the first empty cell
+    /// without candidates, then the first unit without a place for one of its values, `Exhausted` if there is none.
+    ///
+    /// The sudoku is not solved, only the filled cells are looked at: `Exhausted` doesn't mean there is no solution.
+    pub fn diagnose(&self) -> Diagnosis {
+        if let Some(conflict) = self.conflicts().into_iter().next() {
+            return Diagnosis::Conflict(conflict);
+        }
+        let side = self.side();
+        if let Some(pos) = (0..self.field.len()).find(|&pos| {
+            self.field[pos] == EMPTY && self.values().all(|val| !self.is_allowed(val, pos))
+        }) {
+            let (row, col) = self.geometry().cell(pos);
+            return Diagnosis::NoCandidates { row, col };
+        }
+        for (unit, cells) in self.units() {
+            // Cages and the cells a knight's move apart need not hold every value.
+            if cells.len() != side {
+                continue;
+            }
+            let missing = self.values().find(|&val| {
+                cells.iter().all(|&pos| {
+                    self.field[pos] != val
+                        && (self.field[pos] != EMPTY || !self.is_allowed(val, pos))
+                })
+            });
+            if let Some(value) = missing {
+                return Diagnosis::NoPlace { unit, value };
+            }
+        }
+        Diagnosis::Exhausted
+    }
+
+    /// Returns the error of a failed search, with the diagnosis of the filled cells.
+    pub(crate) fn unsolvable(&self) -> Unsolvable {
+        Unsolvable {
+            diagnosis: self.diagnose(),
+        }
+    }
+}
+
+#[test]
+fn repeated_given() {
+    let s: Sudoku =
+        "5.5.............................................................................."
+            .parse()
+            .unwrap();
+    let diagnosis = s.diagnose();
+    assert_eq!("5 is repeated in row 1: r1c1, r1c3", diagnosis.to_string());
+    match diagnosis {
+        Diagnosis::Conflict(conflict) => assert_eq!(vec![(0, 0), (0, 2)], conflict.cells),
+        other => panic!("Unexpected diagnosis {:?}", other),
+    }
+}
+
+#[test]
+fn cell_without_candidates() {
+    // r1c9 sees 1 to 8 in its row and 9 in its column.
+    let s: Sudoku = crate::examples::UNSOLVABLE.parse().unwrap();
+    assert_eq!(Diagnosis::NoCandidates { row: 0, col: 8 }, s.diagnose());
+    assert_eq!("r1c9 has no candidates", s.diagnose().to_string());
+    assert_eq!(
+        Diagnosis::NoCandidates { row: 0, col: 8 },
+        s.clone().solve().unwrap_err().diagnosis
+    );
+}
+
+#[test]
+fn value_without_place() {
+    // The empty cells of the first row allow 8 and 9, but 1 is already given in their square.
+    let s: Sudoku =
+        "...234567.1......................................................................"
+            .parse()
+            .unwrap();
+    let diagnosis = s.diagnose();
+    assert_eq!(
+        Diagnosis::NoPlace {
+            unit: Unit::Row(0),
+            value: 1
+        },
+        diagnosis
+    );
+    assert_eq!("1 cannot be placed in row 1", diagnosis.to_string());
+}
+
+#[test]
+fn search_exhausted() {
+    assert_eq!(Diagnosis::Exhausted, Sudoku::empty().diagnose());
+    // The easy example with 2 instead of 5 in r1c1: every cell keeps a candidate and every value a place.
+    let s: Sudoku = format!("2{}", &crate::examples::EASY[1..]).parse().unwrap();
+    assert_eq!(
+        "exhaustive search found no solution",
+        s.diagnose().to_string()
+    );
+    assert_eq!(
+        Diagnosis::Exhausted,
+        s.clone().solve().unwrap_err().diagnosis
+    );
+}
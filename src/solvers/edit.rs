@@ -0,0 +1,297 @@
+//! Editing a single cell of a sudoku being solved, reporting what the edit changed around it, so an editor updates
+//! only the cells it touched instead of the candidates of the whole grid.
+use super::backtracking::{
+    outside_of_grid, GivenCellImmutable, LogEntry, Sudoku, ValueNotAllowed, EMPTY,
+};
+use super::geometry::Pos;
+use crate::prelude::*;
+
+/// Candidates of an empty cell which an edit changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateChange {
+    pub row: usize,
+    pub col: usize,
+    /// Values allowed before the edit which are not any more, in ascending order.
+    pub removed: Vec<u8>,
+    /// Values allowed after the edit which were not before, in ascending order.
+    pub added: Vec<u8>,
+}
+
+/// What `Sudoku::apply_edit` changed besides the edited cell.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditOutcome {
+    /// Cells whose candidates changed, in reading order.
+    pub changed: Vec<CandidateChange>,
+    /// Empty cells the edit left without candidates, dead ends of the grid, in reading order.
+    pub dead_ends: Vec<(usize, usize)>,
+    /// (row, col, value) of the values placed by the search which the edit invalidated and which were taken back, in
+    /// the order they were placed.
+    pub rolled_back: Vec<(usize, usize, u8)>,
+}
+
+impl EditOutcome {
+    /// Returns `true` if the edit left an empty cell without candidates.
+    pub fn is_dead_end(&self) -> bool {
+        !self.dead_ends.is_empty()
+    }
+}
+
+impl Sudoku {
+    /// Puts the value into the cell, or empties it with None, and returns the cells whose candidates changed.
+    ///
+    /// A value placed by the search which no longer fits is taken back together with everything placed after it, and
+    /// so is the edited cell if the search filled it. Other filled cells stay: the value must fit them like a value of
+    /// `set`, otherwise the reason is returned and nothing changes. Only the cells sharing a row, column, square or
+    /// cage with a changed cell, or tied to it by a constraint, are compared.
+    pub fn apply_edit(
+        &mut self,
+        row: usize,
+        col: usize,
+        new: Option<u8>,
+    ) -> Result<EditOutcome, ValueNotAllowed> {
+        let cell = Pos::new(row, col);
+        if !self.geometry().contains(row, col) {
+            return Err(outside_of_grid(cell));
+        }
+        let pos = cell.index(self.geometry());
+        if self.givens[pos] {
+            return Err(GivenCellImmutable { row, col }.into());
+        }
+        if let Some(value) = new {
+            // The values placed by the search give way to the edit, the others must allow it.
+            let placed: Vec<(usize, u8)> = core::iter::once(pos)
+                .chain(self.backtrack_log.iter().map(|entry| entry.pos))
+                .map(|i| (i, self.field[i]))
+                .collect();
+            for &(i, _) in placed.iter() {
+                self.field[i] = EMPTY;
+            }
+            let allowed = self.set(row, col, value);
+            for &(i, value) in placed.iter() {
+                self.field[i] = value;
+            }
+            allowed?;
+        }
+        let old = self.field[pos];
+        let mut rolled_back = Vec::new();
+        if let Some(at) = self.backtrack_log.iter().position(|entry| entry.pos == pos) {
+            rolled_back = self.rollback_to(at);
+        }
+        self.field[pos] = new.unwrap_or(EMPTY);
+        if let Some(at) = self.first_invalid_placement() {
+            rolled_back.extend(self.rollback_to(at));
+        }
+        // The values of the changed cells before the edit, the candidates of their peers are compared with them.
+        let mut previous: Vec<(usize, u8)> = rolled_back
+            .iter()
+            .map(|entry| (entry.pos, entry.val))
+            .collect();
+        previous.push((pos, old));
+        let touched: Vec<usize> = previous.iter().map(|&(i, _)| i).collect();
+        let affected: Vec<usize> = self
+            .affected_cells(&touched)
+            .into_iter()
+            .filter(|&cell| cell != pos)
+            .collect();
+        let current: Vec<u8> = touched.iter().map(|&i| self.field[i]).collect();
+        for &(i, value) in previous.iter() {
+            self.field[i] = value;
+        }
+        let old_candidates: Vec<Vec<u8>> = affected
+            .iter()
+            .map(|&cell| {
+                let (row, col) = self.geometry().cell(cell);
+                self.candidates(row, col)
+            })
+            .collect();
+        for (&i, &value) in touched.iter().zip(current.iter()) {
+            self.field[i] = value;
+        }
+        let mut outcome = EditOutcome {
+            rolled_back: rolled_back
+                .iter()
+                .map(|entry| {
+                    let (row, col) = self.geometry().cell(entry.pos);
+                    (row, col, entry.val)
+                })
+                .collect(),
+            ..EditOutcome::default()
+        };
+        for (&cell, old) in affected.iter().zip(old_candidates) {
+            let (row, col) = self.geometry().cell(cell);
+            let new = self.candidates(row, col);
+            if self.field[cell] == EMPTY && new.is_empty() {
+                outcome.dead_ends.push((row, col));
+            }
+            if old != new {
+                outcome.changed.push(CandidateChange {
+                    row,
+                    col,
+                    removed: old
+                        .iter()
+                        .copied()
+                        .filter(|val| !new.contains(val))
+                        .collect(),
+                    added: new
+                        .iter()
+                        .copied()
+                        .filter(|val| !old.contains(val))
+                        .collect(),
+                });
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Takes back the placements of the search from the one at the index of the log on, returns them in the order
+    /// they were placed. The stopped search cannot continue any more.
+    fn rollback_to(&mut self, at: usize) -> Vec<LogEntry> {
+        let mut entries = Vec::new();
+        while self.backtrack_log.len() > at {
+            entries.extend(self.rollback());
+        }
+        self.cursor = None;
+        entries.reverse();
+        entries
+    }
+
+    /// Returns the index in the log of the earliest placement of the search which the filled cells no longer allow.
+    fn first_invalid_placement(&mut self) -> Option<usize> {
+        for at in 0..self.backtrack_log.len() {
+            let LogEntry { pos, val } = self.backtrack_log[at];
+            self.field[pos] = EMPTY;
+            let allowed = self.is_allowed(val, pos);
+            self.field[pos] = val;
+            if !allowed {
+                return Some(at);
+            }
+        }
+        None
+    }
+
+    /// Returns the positions of the cells sharing a row, column, square, extra region or cage with any of the cells, or
+    /// tied to them by a constraint, in reading order; every cell if a constraint may tie any cells together.
+    fn affected_cells(&self, cells: &[usize]) -> Vec<usize> {
+        let mut affected: Vec<usize> = Vec::new();
+        for constraint in self.constraints() {
+            for &cell in cells {
+                match constraint.peers(self, cell) {
+                    Some(peers) => affected.extend(peers),
+                    None => return (0..self.field.len()).collect(),
+                }
+            }
+        }
+        affected.extend(
+            self.units()
+                .into_iter()
+                .map(|(_, unit)| unit)
+                .chain(self.cages().iter().map(|cage| cage.cells.clone()))
+                .filter(|unit| unit.iter().any(|cell| cells.contains(cell)))
+                .flatten()
+                .chain(cells.iter().copied()),
+        );
+        affected.sort_unstable();
+        affected.dedup();
+        affected
+    }
+}
+
+#[cfg(test)]
+use super::backtracking::solvable_field_helper;
+#[cfg(test)]
+use super::rules::Rules;
+
+#[test]
+fn edit_changes_candidates_of_peers() {
+    let mut s = Sudoku::empty();
+    let outcome = s.apply_edit(4, 4, Some(5)).unwrap();
+    assert_eq!(Some(5), s.value(4, 4));
+    assert_eq!(20, outcome.changed.len());
+    assert!(outcome.changed.iter().all(|change| {
+        let square = (change.row / 3, change.col / 3) == (1, 1);
+        change.removed == vec![5]
+            && change.added.is_empty()
+            && (change.row == 4 || change.col == 4 || square)
+    }));
+    assert!(!outcome.is_dead_end() && outcome.rolled_back.is_empty());
+    // Emptying the cell gives the value back to the same peers.
+    let outcome = s.apply_edit(4, 4, None).unwrap();
+    assert_eq!(None, s.value(4, 4));
+    assert_eq!(20, outcome.changed.len());
+    assert!(outcome.changed.iter().all(|change| change.added == vec![5]));
+    // The value takes the last candidate of r1c9.
+    let mut field = vec![EMPTY; 81];
+    field[..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    let mut row = Sudoku::from_field(field);
+    let outcome = row.apply_edit(1, 8, Some(9)).unwrap();
+    assert_eq!(vec![(0, 8)], outcome.dead_ends);
+    assert!(outcome.is_dead_end());
+}
+
+#[test]
+fn edit_with_constraints_and_outside_of_grid() {
+    // The knight's moves from r5c5 share no unit with it, they are compared on top of the 20 peers.
+    let mut s = Sudoku::empty().with_rules(Rules {
+        anti_knight: true,
+        ..Rules::default()
+    });
+    let outcome = s.apply_edit(4, 4, Some(5)).unwrap();
+    assert_eq!(28, outcome.changed.len());
+    assert!(outcome
+        .changed
+        .iter()
+        .all(|change| change.removed == vec![5]));
+    let mut s = Sudoku::empty();
+    assert_eq!(
+        "r10c1 is outside of the grid",
+        s.apply_edit(9, 0, Some(1)).unwrap_err().msg
+    );
+    assert!(s.apply_edit(0, 9, None).is_err());
+    assert_eq!(Sudoku::empty(), s);
+}
+
+#[test]
+fn edit_rolls_back_search() {
+    let mut s = solvable_field_helper();
+    let puzzle = s.clone();
+    s.solve().unwrap();
+    let (row, col) = (0..81)
+        .map(|pos| (pos / 9, pos % 9))
+        .find(|&(row, col)| !s.is_given(row, col))
+        .unwrap();
+    let solved = s.value(row, col).unwrap();
+    // Another value the search put into the row of the cell, which the givens allow there.
+    let (other_col, value) = (0..9)
+        .filter(|&other| other != col && !s.is_given(row, other))
+        .map(|other| (other, s.value(row, other).unwrap()))
+        .find(|&(_, value)| puzzle.candidates(row, col).contains(&value))
+        .unwrap();
+    let outcome = s.apply_edit(row, col, Some(value)).unwrap();
+    assert_eq!(Some(value), s.value(row, col));
+    assert!(outcome.rolled_back.contains(&(row, col, solved)));
+    assert!(outcome.rolled_back.contains(&(row, other_col, value)));
+    assert_eq!(None, s.value(row, other_col));
+    // Every value placed by the search is taken back or still fits.
+    assert!(s.is_consistent());
+    assert!(outcome
+        .changed
+        .iter()
+        .any(|change| (change.row, change.col) == (row, other_col)));
+    // Givens and values breaking the filled cells are refused, nothing changes then.
+    let (given_row, given_col) = (0..81)
+        .map(|pos| (pos / 9, pos % 9))
+        .find(|&(row, col)| puzzle.is_given(row, col))
+        .unwrap();
+    let mut edited = s.clone();
+    assert!(edited
+        .apply_edit(given_row, given_col, None)
+        .unwrap_err()
+        .given()
+        .is_some());
+    let given = puzzle.value(given_row, given_col).unwrap();
+    let free = (0..9)
+        .find(|&c| !puzzle.is_given(given_row, c) && c != given_col)
+        .unwrap();
+    assert!(edited.apply_edit(given_row, free, Some(given)).is_err());
+    assert_eq!(s, edited);
+}
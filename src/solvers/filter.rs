@@ -0,0 +1,116 @@
+//! Selecting puzzles from a collection by their clues, rules, uniqueness and difficulty.
+use super::backtracking::Sudoku;
+use super::classify::PuzzleClass;
+use super::logical::Difficulty;
+
+/// Predicates a puzzle must all meet, built like `PuzzleFilter::new().min_clues(24).unique_only()`.
+///
+/// A filter without predicates matches every puzzle. The cheap predicates are checked first, uniqueness and
+/// difficulty need a search and run only when they are asked for and the others hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PuzzleFilter {
+    pub min_clues: Option<usize>,
+    pub max_clues: Option<usize>,
+    /// Clues follow the rules of the sudoku, including the ones of its variant, see `Sudoku::is_consistent`.
+    pub valid_only: bool,
+    /// The puzzle has exactly one solution, a search stopped at the node limit of the sudoku doesn't tell.
+    pub unique_only: bool,
+    /// Difficulty of `Sudoku::rate`, puzzles without a solution have none.
+    pub difficulty: Option<Difficulty>,
+}
+
+impl PuzzleFilter {
+    /// Returns the filter matching every puzzle.
+    pub fn new() -> PuzzleFilter {
+        PuzzleFilter::default()
+    }
+
+    /// Returns the filter which also requires at least `clues` clues.
+    pub fn min_clues(mut self, clues: usize) -> PuzzleFilter {
+        self.min_clues = Some(clues);
+        self
+    }
+
+    /// Returns the filter which also requires at most `clues` clues.
+    pub fn max_clues(mut self, clues: usize) -> PuzzleFilter {
+        self.max_clues = Some(clues);
+        self
+    }
+
+    /// Returns the filter which also requires clues following the rules.
+    pub fn valid_only(mut self) -> PuzzleFilter {
+        self.valid_only = true;
+        self
+    }
+
+    /// Returns the filter which also requires a unique solution.
+    pub fn unique_only(mut self) -> PuzzleFilter {
+        self.unique_only = true;
+        self
+    }
+
+    /// Returns the filter which also requires the difficulty.
+    pub fn difficulty(mut self, difficulty: Difficulty) -> PuzzleFilter {
+        self.difficulty = Some(difficulty);
+        self
+    }
+
+    /// Returns `true` if the puzzle meets every predicate of the filter.
+    pub fn matches(&self, sudoku: &Sudoku) -> bool {
+        let clues = sudoku.clue_count();
+        self.min_clues.map_or(true, |min| clues >= min)
+            && self.max_clues.map_or(true, |max| clues <= max)
+            && (!self.valid_only || sudoku.is_consistent())
+            && (!self.unique_only || matches!(sudoku.classify_up_to(2), PuzzleClass::Unique(_)))
+            && self
+                .difficulty
+                .map_or(true, |difficulty| sudoku.rate() == Ok(difficulty))
+    }
+}
+
+#[cfg(test)]
+use super::limit::NodeLimit;
+#[cfg(test)]
+use crate::examples;
+
+#[test]
+fn filter_by_clues() {
+    let easy: Sudoku = examples::EASY.parse().unwrap();
+    assert!(PuzzleFilter::new().matches(&easy));
+    assert!(PuzzleFilter::new()
+        .min_clues(30)
+        .max_clues(30)
+        .matches(&easy));
+    assert!(!PuzzleFilter::new().min_clues(31).matches(&easy));
+    assert!(!PuzzleFilter::new().max_clues(29).matches(&easy));
+    // Bounds which cannot both hold match nothing.
+    assert!(!PuzzleFilter::new()
+        .min_clues(30)
+        .max_clues(20)
+        .matches(&easy));
+}
+
+#[test]
+fn filter_by_search() {
+    let easy: Sudoku = examples::EASY.parse().unwrap();
+    let hard: Sudoku = examples::HARD.parse().unwrap();
+    let empty = Sudoku::empty();
+    let unique = PuzzleFilter::new().unique_only();
+    assert!(unique.matches(&easy) && unique.matches(&hard) && !unique.matches(&empty));
+    assert!(!unique.matches(&hard.clone().with_node_limit(NodeLimit::Max(5))));
+    let hard_unique = unique.difficulty(Difficulty::Hard);
+    assert_eq!(
+        vec![false, true, false],
+        [&easy, &hard, &empty]
+            .iter()
+            .map(|s| hard_unique.matches(s))
+            .collect::<Vec<_>>()
+    );
+    let unsolvable: Sudoku = examples::UNSOLVABLE.parse().unwrap();
+    assert!(!PuzzleFilter::new()
+        .difficulty(Difficulty::Hard)
+        .matches(&unsolvable));
+    let repeated: Sudoku = format!("55{}", ".".repeat(79)).parse().unwrap();
+    assert!(PuzzleFilter::new().matches(&repeated));
+    assert!(!PuzzleFilter::new().valid_only().matches(&repeated));
+}
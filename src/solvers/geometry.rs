@@ -0,0 +1,348 @@
+use super::backtracking::{MAX_SQUARE_SIDE, MIN_SQUARE_SIDE, SQUARE_SIDE};
+use crate::prelude::*;
+use core::fmt;
+
+/// Row and column of a cell, both 0-based, displayed 1-based like `r4c7`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pos {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Pos {
+    pub fn new(row: usize, col: usize) -> Pos {
+        Pos { row, col }
+    }
+
+    /// Returns the cell at the position (index of the cell counted row by row) of the grid.
+    pub fn of(geometry: Geometry, pos: usize) -> Pos {
+        let (row, col) = geometry.cell(pos);
+        Pos { row, col }
+    }
+
+    /// Returns the position (index of the cell counted row by row) of the cell in the grid.
+    pub fn index(&self, geometry: Geometry) -> usize {
+        geometry.pos(self.row, self.col)
+    }
+
+    /// Returns the name of the cell with the column as a letter from `A` and the row from 1, e.g. `E5`.
+    pub fn label(&self) -> String {
+        format!("{}{}", column_letter(self.col), self.row + 1)
+    }
+
+    /// Parses the label of a cell in a grid with the side, e.g. `E5` or `e5`, see `label`.
+    ///
+    /// Returns None if the label is malformed or the cell is outside the grid.
+    pub fn from_label(label: &str, side: usize) -> Option<Pos> {
+        let mut chars = label.trim().chars();
+        let letter = chars.next()?.to_ascii_uppercase();
+        let col = match letter {
+            'A'..='Z' => letter as usize - 'A' as usize,
+            _ => return None,
+        };
+        let digits = chars.as_str();
+        if !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let row: usize = digits.parse().ok()?;
+        match row >= 1 && row <= side && col < side {
+            true => Some(Pos::new(row - 1, col)),
+            false => None,
+        }
+    }
+}
+
+/// Returns the letter of the column in cell labels, `A` for the first one.
+pub(crate) fn column_letter(col: usize) -> char {
+    (b'A' + col as u8) as char
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "r{}c{}", self.row + 1, self.col + 1)
+    }
+}
+
+/// Shape of the grid: boxes (squares) of `box_rows` x `box_cols` cells, the grid has as many rows, columns and boxes
+/// as there are cells in a box.
+///
+/// Cells are numbered row by row from 0, boxes are numbered the same way from the top left one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Geometry {
+    box_rows: usize,
+    box_cols: usize,
+}
+
+impl Geometry {
+    /// Geometry of the classic 9x9 sudoku with 3x3 boxes.
+    pub const CLASSIC: Geometry = Geometry {
+        box_rows: SQUARE_SIDE,
+        box_cols: SQUARE_SIDE,
+    };
+
+    /// Returns the geometry with boxes of `box_rows` rows and `box_cols` columns.
+    ///
+    /// Returns None if a box has less than `MIN_SQUARE_SIDE` rows or columns or the grid would be larger than 16x16.
+    pub fn new(box_rows: usize, box_cols: usize) -> Option<Geometry> {
+        let fits = box_rows * box_cols <= MAX_SQUARE_SIDE * MAX_SQUARE_SIDE;
+        match box_rows.min(box_cols) >= MIN_SQUARE_SIDE && fits {
+            true => Some(Geometry { box_rows, box_cols }),
+            false => None,
+        }
+    }
+
+    /// Returns the geometry with square boxes of `box_size` rows and columns.
+    pub fn square(box_size: usize) -> Option<Geometry> {
+        Geometry::new(box_size, box_size)
+    }
+
+    /// Returns the geometry with square boxes which has the given number of cells.
+    pub(crate) fn for_cells(cells: usize) -> Option<Geometry> {
+        (MIN_SQUARE_SIDE..=MAX_SQUARE_SIDE)
+            .find(|side| side.pow(4) == cells)
+            .and_then(Geometry::square)
+    }
+
+    /// Returns the number of rows of a box.
+    pub fn box_rows(&self) -> usize {
+        self.box_rows
+    }
+
+    /// Returns the number of columns of a box.
+    pub fn box_cols(&self) -> usize {
+        self.box_cols
+    }
+
+    /// Returns the number of rows (and columns) of the grid, which is also the largest value.
+    pub fn side(&self) -> usize {
+        self.box_rows * self.box_cols
+    }
+
+    /// Returns the number of cells of the grid.
+    pub fn size(&self) -> usize {
+        self.side() * self.side()
+    }
+
+    /// Returns `true` if the cell of the 0-based row and column is in the grid.
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        row < self.side() && col < self.side()
+    }
+
+    /// Returns the position of the cell.
+    pub fn pos(&self, row: usize, col: usize) -> usize {
+        row * self.side() + col
+    }
+
+    /// Returns (row, col) of the cell at the position.
+    pub fn cell(&self, pos: usize) -> (usize, usize) {
+        (pos / self.side(), pos % self.side())
+    }
+
+    /// Returns the index of the box holding the cell.
+    pub fn box_of(&self, row: usize, col: usize) -> usize {
+        row / self.box_rows * self.box_rows + col / self.box_cols
+    }
+
+    /// Returns positions of the cells of the box, row by row.
+    pub fn box_cells(&self, index: usize) -> Vec<usize> {
+        let top = index / self.box_rows * self.box_rows;
+        let left = index % self.box_rows * self.box_cols;
+        (0..self.side())
+            .map(|i| self.pos(top + i / self.box_cols, left + i % self.box_cols))
+            .collect()
+    }
+
+    /// Returns positions of the cells of the diagonal from the top-left corner for index 0 and of the diagonal from the
+    /// top-right corner otherwise, row by row.
+    pub fn diagonal_cells(&self, index: usize) -> Vec<usize> {
+        let last = self.side() - 1;
+        (0..self.side())
+            .map(|row| match index {
+                0 => self.pos(row, row),
+                _ => self.pos(row, last - row),
+            })
+            .collect()
+    }
+
+    /// Returns positions of the cells of every window, the extra box-sized regions of Windoku, row by row.
+    ///
+    /// Windows start one cell after the top-left corner and are separated by a single line of cells, so a 9x9 grid
+    /// has four windows at rows and columns 1-3 and 5-7. Grids with rectangular boxes have no windows.
+    pub fn windows(&self) -> Vec<Vec<usize>> {
+        if self.box_rows != self.box_cols {
+            return Vec::new();
+        }
+        let width = self.box_rows;
+        let starts: Vec<usize> = (0..width - 1).map(|k| 1 + k * (width + 1)).collect();
+        let mut windows = Vec::with_capacity(starts.len() * starts.len());
+        for &top in starts.iter() {
+            for &left in starts.iter() {
+                windows.push(
+                    (0..self.side())
+                        .map(|i| self.pos(top + i / width, left + i % width))
+                        .collect(),
+                );
+            }
+        }
+        windows
+    }
+
+    /// Returns positions of the (up to eight) cells a chess knight's move away from the cell.
+    pub fn knight_moves(&self, row: usize, col: usize) -> impl Iterator<Item = usize> {
+        const MOVES: [(isize, isize); 8] = [
+            (-2, -1),
+            (-2, 1),
+            (-1, -2),
+            (-1, 2),
+            (1, -2),
+            (1, 2),
+            (2, -1),
+            (2, 1),
+        ];
+        self.offsets(row, col, &MOVES)
+    }
+
+    /// Returns positions of the (up to four) cells sharing a side with the cell.
+    pub fn orthogonal_neighbours(&self, row: usize, col: usize) -> impl Iterator<Item = usize> {
+        const SIDES: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+        self.offsets(row, col, &SIDES)
+    }
+
+    /// Returns positions of the cells at the (row, col) offsets from the cell which lie within the grid.
+    fn offsets(
+        &self,
+        row: usize,
+        col: usize,
+        offsets: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = usize> {
+        let geometry = *self;
+        let side = self.side() as isize;
+        offsets
+            .iter()
+            .map(move |&(dr, dc)| (row as isize + dr, col as isize + dc))
+            .filter(move |&(r, c)| (0..side).contains(&r) && (0..side).contains(&c))
+            .map(move |(r, c)| geometry.pos(r as usize, c as usize))
+    }
+
+    /// Returns the geometry of the grid mirrored over its main diagonal, where boxes swap rows and columns.
+    pub fn transposed(&self) -> Geometry {
+        Geometry {
+            box_rows: self.box_cols,
+            box_cols: self.box_rows,
+        }
+    }
+}
+
+/// The classic 9x9 geometry.
+impl Default for Geometry {
+    fn default() -> Geometry {
+        Geometry::CLASSIC
+    }
+}
+
+#[test]
+fn classic_geometry() {
+    let geometry = Geometry::default();
+    assert_eq!((9, 81), (geometry.side(), geometry.size()));
+    assert_eq!(4, geometry.box_of(4, 5));
+    assert_eq!(
+        vec![30, 31, 32, 39, 40, 41, 48, 49, 50],
+        geometry.box_cells(4)
+    );
+    assert_eq!((8, 0), geometry.cell(geometry.pos(8, 0)));
+    assert_eq!(
+        vec![8, 16, 24, 32, 40, 48, 56, 64, 72],
+        geometry.diagonal_cells(1)
+    );
+    assert_eq!(
+        vec![11, 19],
+        geometry.knight_moves(0, 0).collect::<Vec<_>>()
+    );
+    assert_eq!(8, geometry.knight_moves(4, 4).count());
+    assert_eq!(
+        vec![1, 9],
+        geometry.orthogonal_neighbours(0, 0).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![31, 39, 41, 49],
+        geometry.orthogonal_neighbours(4, 4).collect::<Vec<_>>()
+    );
+    let windows = geometry.windows();
+    assert_eq!(4, windows.len());
+    assert_eq!(vec![10, 11, 12, 19, 20, 21, 28, 29, 30], windows[0]);
+    assert_eq!(vec![50, 51, 52, 59, 60, 61, 68, 69, 70], windows[3]);
+}
+
+#[test]
+fn small_and_large_geometries() {
+    let small = Geometry::square(2).unwrap();
+    assert_eq!((4, 16), (small.side(), small.size()));
+    assert_eq!(vec![10, 11, 14, 15], small.box_cells(3));
+    assert_eq!(Some(small), Geometry::for_cells(16));
+    let large = Geometry::square(4).unwrap();
+    assert_eq!((16, 256), (large.side(), large.size()));
+    assert_eq!(15, large.box_of(15, 15));
+    assert_eq!(4, large.box_of(4, 0));
+    assert_eq!(
+        vec![0, 1, 2, 3, 16, 17, 18, 19],
+        large.box_cells(0)[..8].to_vec()
+    );
+    assert_eq!(Some(large), Geometry::for_cells(256));
+    assert_eq!(vec![vec![5, 6, 9, 10]], small.windows());
+    assert_eq!(9, large.windows().len());
+    assert_eq!(Some(&(15 * 16 - 2)), large.windows()[8].last());
+    assert_eq!(None, Geometry::for_cells(36));
+    assert_eq!(None, Geometry::square(5));
+}
+
+#[test]
+fn rectangular_geometry() {
+    let geometry = Geometry::new(2, 3).unwrap();
+    assert_eq!(6, geometry.side());
+    assert_eq!(1, geometry.box_of(1, 3));
+    assert_eq!(2, geometry.box_of(2, 2));
+    assert_eq!(Geometry::new(3, 2).unwrap(), geometry.transposed());
+    assert_eq!(None, Geometry::new(1, 6));
+    assert!(geometry.windows().is_empty());
+}
+
+#[test]
+fn positions_of_cells() {
+    assert_eq!("r4c7", Pos::new(3, 6).to_string());
+    assert_eq!("r1c1", Pos::of(Geometry::CLASSIC, 0).to_string());
+    assert_eq!("r9c9", Pos::of(Geometry::CLASSIC, 80).to_string());
+    assert_eq!("r16c10", Pos::new(15, 9).to_string());
+    for &geometry in [Geometry::CLASSIC, Geometry::new(2, 3).unwrap()].iter() {
+        for pos in 0..geometry.size() {
+            assert_eq!(pos, Pos::of(geometry, pos).index(geometry));
+        }
+    }
+    let six = Geometry::new(2, 3).unwrap();
+    assert_eq!(Pos::new(1, 1), Pos::of(six, 7));
+}
+
+#[test]
+fn labels_of_cells() {
+    assert_eq!("E5", Pos::new(4, 4).label());
+    assert_eq!("A1", Pos::new(0, 0).label());
+    assert_eq!("P16", Pos::new(15, 15).label());
+    assert_eq!(Some(Pos::new(4, 4)), Pos::from_label("E5", 9));
+    assert_eq!(Some(Pos::new(8, 0)), Pos::from_label("a9", 9));
+    assert_eq!(Some(Pos::new(2, 8)), Pos::from_label(" i3 ", 9));
+    assert_eq!(Some(Pos::new(15, 15)), Pos::from_label("p16", 16));
+    for label in ["J1", "A10", "A0", "P16"].iter() {
+        assert_eq!(None, Pos::from_label(label, 9), "{}", label);
+    }
+    for label in ["", "E", "5E", "E+5", "r5c5", "E 5", "É5"].iter() {
+        assert_eq!(None, Pos::from_label(label, 9), "{}", label);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_positions() {
+    let json = serde_json::to_string(&Pos::new(3, 6)).unwrap();
+    assert_eq!(r#"{"row":3,"col":6}"#, json);
+    assert_eq!(Pos::new(3, 6), serde_json::from_str(&json).unwrap());
+}
@@ -0,0 +1,165 @@
+//! Limit on the placements of the backtracking search, so a sparse grid of a large geometry cannot keep `solve`
+//! running for hours.
+//!
+//! Grids of up to 9x9 cells are searched without a limit: even the hardest ones take seconds. Larger grids get
+//! `NODES_PER_CELL` placements per cell by default, a 16x16 grid with five clues can take longer than anyone waits.
+use super::backtracking::{Sudoku, Unsolvable};
+use super::diagnosis::Diagnosis;
+use super::geometry::Geometry;
+use super::observer::SolveStats;
+#[cfg(test)]
+use super::{backtracking::PUZZLE_16X16, value_order::SolveOptions};
+use core::fmt;
+
+/// Placements per cell the search of a grid larger than 9x9 makes by default, 256000 for a 16x16 grid.
+pub const NODES_PER_CELL: u64 = 1000;
+
+/// Most placements a single `Sudoku::solve` makes before it gives up, see `SolveOptions::max_nodes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeLimit {
+    /// No limit for grids of up to 9x9 cells, `NODES_PER_CELL` placements per cell for larger ones.
+    Default,
+    Max(u64),
+    /// The search runs until it finishes, however long it takes.
+    Unlimited,
+}
+
+/// The limit derived from the size of the grid.
+impl Default for NodeLimit {
+    fn default() -> NodeLimit {
+        NodeLimit::Default
+    }
+}
+
+impl NodeLimit {
+    /// Returns the most placements of the search of a grid of the geometry, None if there is no limit.
+    pub fn max_nodes(&self, geometry: Geometry) -> Option<u64> {
+        match *self {
+            NodeLimit::Default if geometry.size() <= Geometry::CLASSIC.size() => None,
+            NodeLimit::Default => Some(NODES_PER_CELL * geometry.size() as u64),
+            NodeLimit::Max(max) => Some(max),
+            NodeLimit::Unlimited => None,
+        }
+    }
+}
+
+/// Counting stopped at the node limit before it finished, see `Sudoku::count_solutions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitReached {
+    /// Solutions counted before the search stopped.
+    pub found: usize,
+}
+
+/// Prints the stop like `the search stopped at its limit with 1 solution found`.
+impl fmt::Display for LimitReached {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the search stopped at its limit with {} solution{} found",
+            self.found,
+            if self.found == 1 { "" } else { "s" }
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LimitReached {}
+
+impl Sudoku {
+    /// Returns the sudoku whose search stops at the limit, with `Diagnosis::LimitReached`.
+    pub fn with_node_limit(mut self, limit: NodeLimit) -> Sudoku {
+        self.node_limit = limit;
+        self
+    }
+
+    /// Returns the limit on the placements of the search.
+    pub fn node_limit(&self) -> NodeLimit {
+        self.node_limit
+    }
+
+    /// Returns the error of a search which made every placement of the limit, for searches without `SolveStats`.
+    pub(crate) fn limit_reached(&self) -> Unsolvable {
+        let stats = SolveStats {
+            placements: self
+                .node_limit
+                .max_nodes(self.geometry())
+                .unwrap_or(u64::MAX),
+            ..SolveStats::default()
+        };
+        Unsolvable {
+            diagnosis: Diagnosis::LimitReached { stats },
+        }
+    }
+}
+
+#[test]
+fn limits_of_geometries() {
+    assert_eq!(None, NodeLimit::Default.max_nodes(Geometry::CLASSIC));
+    assert_eq!(
+        None,
+        NodeLimit::Default.max_nodes(Geometry::square(2).unwrap())
+    );
+    assert_eq!(
+        Some(256_000),
+        NodeLimit::Default.max_nodes(Geometry::square(4).unwrap())
+    );
+    assert_eq!(Some(5), NodeLimit::Max(5).max_nodes(Geometry::CLASSIC));
+    assert_eq!(
+        None,
+        NodeLimit::Unlimited.max_nodes(Geometry::square(4).unwrap())
+    );
+    assert_eq!(
+        NodeLimit::Unlimited,
+        SolveOptions::default().max_nodes(None).node_limit
+    );
+    assert_eq!(
+        NodeLimit::Max(7),
+        SolveOptions::default().max_nodes(Some(7)).node_limit
+    );
+}
+
+#[test]
+fn sparse_16x16_reaches_limit() {
+    let mut field = vec![0; 256];
+    for &(row, col, value) in [(1, 0, 2), (2, 4, 1), (3, 8, 5), (5, 12, 1), (12, 1, 1)].iter() {
+        field[row * 16 + col] = value;
+    }
+    let mut s = Sudoku::new(field.iter().copied()).unwrap();
+    match s.solve().unwrap_err().diagnosis {
+        Diagnosis::LimitReached { stats } => assert_eq!(256_000, stats.placements),
+        diagnosis => panic!("Unexpected diagnosis {:?}", diagnosis),
+    }
+    assert!(!s.solved());
+    let mut limited = Sudoku::new(field.into_iter())
+        .unwrap()
+        .with_node_limit(NodeLimit::Max(10));
+    let err = limited.solve().unwrap_err();
+    assert_eq!(
+        "the search stopped at its limit after 10 placements and 0 backtracks",
+        err.to_string()
+    );
+    // Puzzles with enough clues solve well within the limit.
+    let mut puzzle: Sudoku = PUZZLE_16X16.parse().unwrap();
+    puzzle.solve().unwrap();
+}
+
+#[test]
+fn limit_leaves_9x9_alone() {
+    let hard: Sudoku = crate::examples::HARD.parse().unwrap();
+    let mut expected = hard.clone();
+    expected.solve().unwrap();
+    let mut unlimited = hard
+        .clone()
+        .with_options(&SolveOptions::default().max_nodes(None));
+    unlimited.solve().unwrap();
+    assert_eq!(expected, unlimited);
+    // A limit large enough changes nothing, one too small stops the search.
+    let mut limited = hard.clone().with_node_limit(NodeLimit::Max(1_000_000));
+    limited.solve().unwrap();
+    assert_eq!(expected, limited);
+    let mut limited = hard.with_node_limit(NodeLimit::Max(3));
+    assert!(matches!(
+        limited.solve().unwrap_err().diagnosis,
+        Diagnosis::LimitReached { .. }
+    ));
+}
@@ -0,0 +1,623 @@
+use super::backtracking::{Sudoku, Unsolvable, EMPTY};
+use super::classify::PuzzleClass;
+use super::geometry::Pos;
+use super::rules::{cell_name, Unit};
+use crate::prelude::*;
+use core::fmt;
+
+/// Techniques used to fill cells without guessing, ordered from the easiest to the hardest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+    /// The value is the only one allowed in the cell.
+    NakedSingle,
+    /// The cell is the only one in a row, column or square where the value is allowed.
+    HiddenSingle,
+}
+
+impl Technique {
+    /// Every technique, from the easiest to the hardest.
+    pub const ALL: [Technique; 2] = [Technique::NakedSingle, Technique::HiddenSingle];
+
+    /// Returns the name of the technique in options like `--max-technique`, e.g. `hidden-single`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Technique::NakedSingle => "naked-single",
+            Technique::HiddenSingle => "hidden-single",
+        }
+    }
+
+    /// Returns the technique of the name like `hidden-single`, None for unknown names.
+    pub fn from_name(name: &str) -> Option<Technique> {
+        Technique::ALL
+            .iter()
+            .copied()
+            .find(|technique| technique.name() == name)
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Technique::NakedSingle => write!(f, "naked single"),
+            Technique::HiddenSingle => write!(f, "hidden single"),
+        }
+    }
+}
+
+/// Value for a single cell found by one of the techniques.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deduction {
+    pub technique: Technique,
+    pub row: usize,
+    pub col: usize,
+    pub value: u8,
+    /// Unit where the cell is the only place of the value, None for naked singles.
+    pub unit: Option<Unit>,
+}
+
+impl Deduction {
+    /// Returns the deduction with the reason behind it.
+    pub fn explain(&self) -> Explanation {
+        let cell = cell_name(self.row, self.col);
+        let reason = match self.unit {
+            Some(unit) => format!(
+                "digit {} can only appear in {} within {}",
+                self.value, cell, unit
+            ),
+            None => format!("{} is the only remaining candidate of {}", self.value, cell),
+        };
+        Explanation {
+            technique: self.technique,
+            cell: (self.row, self.col),
+            value: self.value,
+            reason,
+        }
+    }
+}
+
+/// Deduction with a human-readable reason, see `Sudoku::solve_logical`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub technique: Technique,
+    /// (row, col) of the filled cell.
+    pub cell: (usize, usize),
+    pub value: u8,
+    /// Why the value goes into the cell with 1-based coordinates, e.g. `digit 7 can only appear in r2c5 within
+    /// square 2`.
+    pub reason: String,
+}
+
+/// Prints the filled cell and the reason, e.g. `r2c5 = 7 (hidden single): digit 7 can only appear in r2c5 within
+/// square 2`.
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} = {} ({}): {}",
+            cell_name(self.cell.0, self.cell.1),
+            self.value,
+            self.technique,
+            self.reason
+        )
+    }
+}
+
+/// Where the value of a hinted cell comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintSource {
+    /// The value is deduced with the technique.
+    Technique(Technique),
+    /// No technique applies, the value is taken from the unique solution found with search.
+    Solution,
+    /// No technique applies and the puzzle has several solutions, or the search stopped at its limit before it could
+    /// tell, the value is taken from one of them.
+    SomeSolution,
+}
+
+impl fmt::Display for HintSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HintSource::Technique(technique) => write!(f, "{}", technique),
+            HintSource::Solution => write!(f, "from the unique solution"),
+            HintSource::SomeSolution => write!(f, "from one of the solutions"),
+        }
+    }
+}
+
+/// Value of a single empty cell suggested to a player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hint {
+    pub source: HintSource,
+    /// The empty cell the hint fills.
+    pub cell: Pos,
+    pub value: u8,
+    /// Unit where the cell is the only place of the value, None unless the source is a hidden single.
+    pub unit: Option<Unit>,
+}
+
+impl Hint {
+    /// Returns why the value goes into the cell, see `Explanation::reason`.
+    pub fn reason(&self) -> String {
+        match self.source {
+            HintSource::Technique(technique) => {
+                let deduction = Deduction {
+                    technique,
+                    row: self.cell.row,
+                    col: self.cell.col,
+                    value: self.value,
+                    unit: self.unit,
+                };
+                deduction.explain().reason
+            }
+            HintSource::Solution => "no technique applies, the value is taken from the unique solution".to_string(),
+            HintSource::SomeSolution => {
+                "no technique applies, the value is taken from a solution which may not be the only one".to_string()
+            }
+        }
+    }
+}
+
+/// Hint filling the cell of the deduction.
+impl From<Deduction> for Hint {
+    fn from(deduction: Deduction) -> Hint {
+        Hint {
+            source: HintSource::Technique(deduction.technique),
+            cell: Pos::new(deduction.row, deduction.col),
+            value: deduction.value,
+            unit: deduction.unit,
+        }
+    }
+}
+
+/// How hard it is to solve a puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    /// Solved with naked singles only.
+    Easy,
+    /// Solved with naked and hidden singles.
+    Medium,
+    /// Cannot be solved without guessing.
+    Hard,
+}
+
+impl Difficulty {
+    /// Returns the difficulty of the name printed by `Display`, e.g. `medium`, None for unknown names.
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "easy"),
+            Difficulty::Medium => write!(f, "medium"),
+            Difficulty::Hard => write!(f, "hard"),
+        }
+    }
+}
+
+impl Sudoku {
+    /// Returns the value of a cell which can be filled without guessing, using the easiest technique possible.
+    pub fn next_deduction(&self) -> Option<Deduction> {
+        Technique::ALL
+            .iter()
+            .find_map(|&technique| self.deduction_with(technique))
+    }
+
+    /// Returns the value of a cell found like `next_deduction`, but only with the techniques up to `max`.
+    pub fn next_deduction_up_to(&self, max: Technique) -> Option<Deduction> {
+        Technique::ALL
+            .iter()
+            .filter(|&&technique| technique <= max)
+            .find_map(|&technique| self.deduction_with(technique))
+    }
+
+    fn deduction_with(&self, technique: Technique) -> Option<Deduction> {
+        match technique {
+            Technique::NakedSingle => self.naked_single(),
+            Technique::HiddenSingle => self.hidden_single(),
+        }
+    }
+
+    /// Returns the deduction of the cell at the position with the easiest technique up to `max`.
+    fn deduction_at(&self, pos: usize, max: Technique) -> Option<Deduction> {
+        Technique::ALL
+            .iter()
+            .filter(|&&technique| technique <= max)
+            .find_map(|&technique| match technique {
+                Technique::NakedSingle => self.naked_single_at(pos),
+                Technique::HiddenSingle => self.hidden_single_at(pos),
+            })
+    }
+
+    /// Fills cells with deductions until the sudoku is solved or no technique applies.
+    ///
+    /// Returns the applied deductions in order, each with the reason behind it.
+    pub fn solve_logical(&mut self) -> Vec<Explanation> {
+        let mut explanations = Vec::new();
+        while let Some(deduction) = self.next_deduction() {
+            let pos = deduction.row * self.side() + deduction.col;
+            self.field[pos] = deduction.value;
+            explanations.push(deduction.explain());
+        }
+        explanations
+    }
+
+    /// Returns the value of one empty cell, or None if the sudoku is already solved.
+    ///
+    /// Cells which can be filled with a technique are preferred, otherwise the value of the first empty cell is
+    /// taken from the solution. For puzzles with several solutions that is just one of them. Returns an error if
+    /// the sudoku has no solution.
+    pub fn hint(&self) -> Result<Option<Hint>, Unsolvable> {
+        if !self.is_consistent() {
+            return Err(self.unsolvable());
+        }
+        if let Some(deduction) = self.next_deduction() {
+            return Ok(Some(deduction.into()));
+        }
+        match self.next_empty() {
+            None => Ok(None),
+            Some(pos) => self.solution_hint(pos).map(Some),
+        }
+    }
+
+    /// Returns the value of the cell like `hint`, None if the cell is already filled or outside of the grid.
+    ///
+    /// A technique filling this very cell is preferred, otherwise the value is taken from the solution.
+    pub fn hint_at(&self, cell: Pos) -> Result<Option<Hint>, Unsolvable> {
+        if !self.is_consistent() {
+            return Err(self.unsolvable());
+        }
+        if !self.geometry().contains(cell.row, cell.col) {
+            return Ok(None);
+        }
+        let pos = cell.index(self.geometry());
+        if self.field[pos] != EMPTY {
+            return Ok(None);
+        }
+        match self.deduction_at(pos, Technique::HiddenSingle) {
+            Some(deduction) => Ok(Some(deduction.into())),
+            None => self.solution_hint(pos).map(Some),
+        }
+    }
+
+    /// Returns the value of one empty cell found with a technique no harder than `max`, unlike `hint` the value is
+    /// never taken from the solution, so the hint stays within the techniques the player practices.
+    ///
+    /// Returns None if the sudoku is solved or none of these techniques fills a cell.
+    pub fn hint_up_to(&self, max: Technique) -> Result<Option<Hint>, Unsolvable> {
+        if !self.is_consistent() {
+            return Err(self.unsolvable());
+        }
+        Ok(self.next_deduction_up_to(max).map(Hint::from))
+    }
+
+    /// Returns the value of the cell like `hint_up_to`, None if the cell is filled, outside of the grid or none of the
+    /// techniques up to `max` fills it.
+    pub fn hint_at_up_to(&self, cell: Pos, max: Technique) -> Result<Option<Hint>, Unsolvable> {
+        if !self.is_consistent() {
+            return Err(self.unsolvable());
+        }
+        if !self.geometry().contains(cell.row, cell.col) {
+            return Ok(None);
+        }
+        let pos = cell.index(self.geometry());
+        match self.field[pos] {
+            EMPTY => Ok(self.deduction_at(pos, max).map(Hint::from)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the hint of the empty cell at the position with the value of a solution, the unique one if there is
+    /// only one.
+    fn solution_hint(&self, pos: usize) -> Result<Hint, Unsolvable> {
+        let (source, solution) = match self.classify_up_to(2) {
+            PuzzleClass::Unique(solution) => (HintSource::Solution, solution),
+            PuzzleClass::Multiple { sample, .. } => (HintSource::SomeSolution, sample),
+            PuzzleClass::None => return Err(self.unsolvable()),
+            PuzzleClass::Unknown { .. } => {
+                let mut solution = self.with_field(self.field.clone());
+                solution.solve()?;
+                (HintSource::SomeSolution, solution)
+            }
+        };
+        Ok(Hint {
+            source,
+            cell: Pos::of(self.geometry(), pos),
+            value: solution.field[pos],
+            unit: None,
+        })
+    }
+
+    /// Fills the cell suggested by the hint.
+    pub fn apply_hint(&mut self, hint: &Hint) {
+        let pos = hint.cell.index(self.geometry());
+        self.field[pos] = hint.value;
+    }
+
+    /// Rates the puzzle by the hardest technique needed to solve it.
+    ///
+    /// Returns an error if the puzzle has no solution, with `Diagnosis::LimitReached` if the search stopped at the node
+    /// limit of the sudoku before it found one.
+    pub fn rate(&self) -> Result<Difficulty, Unsolvable> {
+        match self.count_solutions(1) {
+            Ok(0) => return Err(self.unsolvable()),
+            Ok(_) => {}
+            Err(_) => return Err(self.limit_reached()),
+        }
+        let mut s = self.clone();
+        let hardest = s
+            .solve_logical()
+            .iter()
+            .map(|explanation| explanation.technique)
+            .max();
+        Ok(match hardest {
+            _ if !s.solved() => Difficulty::Hard,
+            Some(Technique::HiddenSingle) => Difficulty::Medium,
+            _ => Difficulty::Easy,
+        })
+    }
+
+    fn naked_single(&self) -> Option<Deduction> {
+        (0..self.field.len()).find_map(|pos| self.naked_single_at(pos))
+    }
+
+    fn naked_single_at(&self, pos: usize) -> Option<Deduction> {
+        let mut allowed = self.values().filter(|&val| self.is_allowed(val, pos));
+        match (allowed.next(), allowed.next()) {
+            (Some(value), None) => Some(Deduction {
+                technique: Technique::NakedSingle,
+                row: pos / self.side(),
+                col: pos % self.side(),
+                value,
+                unit: None,
+            }),
+            _ => None,
+        }
+    }
+
+    fn hidden_single(&self) -> Option<Deduction> {
+        let side = self.side();
+        self.units().iter().find_map(|(unit, cells)| {
+            self.values().find_map(|value| {
+                let mut allowed = cells.iter().filter(|&&pos| self.is_allowed(value, pos));
+                match (allowed.next(), allowed.next()) {
+                    (Some(&pos), None) => Some(Deduction {
+                        technique: Technique::HiddenSingle,
+                        row: pos / side,
+                        col: pos % side,
+                        value,
+                        unit: Some(*unit),
+                    }),
+                    _ => None,
+                }
+            })
+        })
+    }
+
+    /// Returns the hidden single of the first unit of the cell in which a value has no other place.
+    fn hidden_single_at(&self, pos: usize) -> Option<Deduction> {
+        self.units()
+            .into_iter()
+            .filter(|(_, cells)| cells.contains(&pos))
+            .find_map(|(unit, cells)| {
+                self.values()
+                    .filter(|&value| self.is_allowed(value, pos))
+                    .find(|&value| {
+                        cells
+                            .iter()
+                            .all(|&other| other == pos || !self.is_allowed(value, other))
+                    })
+                    .map(|value| Deduction {
+                        technique: Technique::HiddenSingle,
+                        row: pos / self.side(),
+                        col: pos % self.side(),
+                        value,
+                        unit: Some(unit),
+                    })
+            })
+    }
+}
+
+#[cfg(test)]
+use super::backtracking::{solvable_field_helper, SIZE};
+#[cfg(test)]
+use super::diagnosis::Diagnosis;
+#[cfg(test)]
+use super::limit::NodeLimit;
+
+#[test]
+fn naked_single_is_preferred() {
+    let mut solved = solvable_field_helper();
+    solved.solve().unwrap();
+    // Leave the top left cell as the only empty cell of the solved sudoku.
+    let mut field = solved.field.clone();
+    field[0] = EMPTY;
+    let deduction = Sudoku::from_field(field).next_deduction().unwrap();
+    assert_eq!(Technique::NakedSingle, deduction.technique);
+    assert_eq!((0, 0, 5), (deduction.row, deduction.col, deduction.value));
+}
+
+#[test]
+fn solve_logical_matches_backtracking() {
+    let mut logical = solvable_field_helper();
+    let deductions = logical.solve_logical();
+    let mut backtracking = solvable_field_helper();
+    backtracking.solve().unwrap();
+    assert!(logical.solved());
+    assert_eq!(backtracking, logical);
+    assert_eq!(51, deductions.len());
+}
+
+#[test]
+fn explanations_of_easy_puzzle() {
+    let puzzle = solvable_field_helper();
+    let explanations = puzzle.clone().solve_logical();
+    // Every empty cell is explained once.
+    let mut cells: Vec<(usize, usize)> = explanations.iter().map(|e| e.cell).collect();
+    cells.sort_unstable();
+    cells.dedup();
+    assert_eq!(puzzle.empty_count(), explanations.len());
+    assert_eq!(puzzle.empty_count(), cells.len());
+    // r5c5 sees 4 8 3 1 in its row, 7 9 6 2 1 8 in its column and 8 3 6 2 in its square, only 5 is left.
+    // Then r5c2 sees 4 8 5 3 1, 3 9 6 and 8 4 7, and r5c8 sees 4 2 8 5 3 1, 6 8 7 and 3 1 6.
+    let first: Vec<String> = explanations[..3].iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        vec![
+            "r5c5 = 5 (naked single): 5 is the only remaining candidate of r5c5",
+            "r5c2 = 2 (naked single): 2 is the only remaining candidate of r5c2",
+            "r5c8 = 9 (naked single): 9 is the only remaining candidate of r5c8",
+        ],
+        first
+    );
+}
+
+#[test]
+fn explain_hidden_single() {
+    let deduction = Deduction {
+        technique: Technique::HiddenSingle,
+        row: 1,
+        col: 4,
+        value: 7,
+        unit: Some(Unit::Square(1)),
+    };
+    assert_eq!(
+        "r2c5 = 7 (hidden single): digit 7 can only appear in r2c5 within square 2",
+        deduction.explain().to_string()
+    );
+}
+
+#[test]
+fn rate() {
+    assert!(solvable_field_helper().rate().unwrap() <= Difficulty::Medium);
+    let empty = Sudoku::new(vec![0; SIZE].into_iter()).unwrap();
+    assert_eq!(Difficulty::Hard, empty.rate().unwrap());
+    let mut field = solvable_field_helper().field;
+    field[2] = 5;
+    assert!(Sudoku::from_field(field).rate().is_err());
+    // A search stopped at the limit doesn't tell that the puzzle has no solution.
+    let limited = solvable_field_helper().with_node_limit(NodeLimit::Max(5));
+    assert!(matches!(
+        limited.rate().unwrap_err().diagnosis,
+        Diagnosis::LimitReached { .. }
+    ));
+}
+
+#[test]
+fn hint_names_naked_single() {
+    let mut solved = solvable_field_helper();
+    solved.solve().unwrap();
+    let mut field = solved.field.clone();
+    field[0] = EMPTY;
+    let mut s = Sudoku::from_field(field);
+    let hint = s.hint().unwrap().unwrap();
+    assert_eq!(HintSource::Technique(Technique::NakedSingle), hint.source);
+    assert_eq!((0, 0, 5), (hint.cell.row, hint.cell.col, hint.value));
+    assert_eq!("5 is the only remaining candidate of r1c1", hint.reason());
+    assert_eq!(Pos::new(0, 0), hint.cell);
+    // Hints are Copy, the hint stays usable after it is moved.
+    let copy = hint;
+    assert_eq!(copy, hint);
+    s.apply_hint(&hint);
+    assert_eq!(solved, s);
+}
+
+#[test]
+fn hint_from_solution() {
+    let empty = Sudoku::new(vec![0; SIZE].into_iter()).unwrap();
+    let hint = empty.hint().unwrap().unwrap();
+    assert_eq!(HintSource::SomeSolution, hint.source);
+    assert_eq!((0, 0, 1), (hint.cell.row, hint.cell.col, hint.value));
+    assert_eq!("from one of the solutions", hint.source.to_string());
+    assert_eq!(
+        "no technique applies, the value is taken from a solution which may not be the only one",
+        hint.reason()
+    );
+    // The hard example needs search, its solution is unique.
+    let hard: Sudoku = crate::examples::HARD.parse().unwrap();
+    let hint = hard.hint_at(Pos::new(0, 1)).unwrap().unwrap();
+    assert_eq!(HintSource::Solution, hint.source);
+    assert_eq!("from the unique solution", hint.source.to_string());
+}
+
+#[test]
+fn hint_of_a_cell() {
+    let s = solvable_field_helper();
+    // r5c5 is a naked single, r5c3 the only place of 6 in its column, no technique fills r1c3 and r1c1 is a given.
+    let hint = s.hint_at(Pos::new(4, 4)).unwrap().unwrap();
+    assert_eq!(HintSource::Technique(Technique::NakedSingle), hint.source);
+    assert_eq!((4, 4, 5), (hint.cell.row, hint.cell.col, hint.value));
+    let hint = s.hint_at(Pos::new(4, 2)).unwrap().unwrap();
+    assert_eq!(HintSource::Technique(Technique::HiddenSingle), hint.source);
+    assert_eq!(
+        "digit 6 can only appear in r5c3 within column 3",
+        hint.reason()
+    );
+    let hint = s.hint_at(Pos::new(0, 2)).unwrap().unwrap();
+    assert_eq!((HintSource::Solution, 4), (hint.source, hint.value));
+    assert_eq!(None, s.hint_at(Pos::new(0, 0)).unwrap());
+    assert_eq!(Pos::new(0, 2), hint.cell);
+    // Cells outside of the grid get no hint, rather than the one of the cell their index would wrap to.
+    assert_eq!(None, s.hint_at(Pos::new(0, 11)).unwrap());
+    assert_eq!(None, s.hint_at(Pos::new(9, 0)).unwrap());
+}
+
+#[test]
+fn hint_limited_to_techniques() {
+    // No cell of the hard example has a single candidate, but 6 has a single place around r2c3.
+    let hard: Sudoku = crate::examples::HARD.parse().unwrap();
+    assert_eq!(None, hard.next_deduction_up_to(Technique::NakedSingle));
+    assert_eq!(None, hard.hint_up_to(Technique::NakedSingle).unwrap());
+    let hint = hard.hint_up_to(Technique::HiddenSingle).unwrap().unwrap();
+    assert_eq!(HintSource::Technique(Technique::HiddenSingle), hint.source);
+    assert_eq!(Ok(Some(hint)), hard.hint());
+    let cell = Pos::new(1, 2);
+    assert_eq!(
+        None,
+        hard.hint_at_up_to(cell, Technique::NakedSingle).unwrap()
+    );
+    let hint = hard
+        .hint_at_up_to(cell, Technique::HiddenSingle)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (HintSource::Technique(Technique::HiddenSingle), 6),
+        (hint.source, hint.value)
+    );
+    assert_eq!(
+        None,
+        hard.hint_at_up_to(Pos::new(0, 0), Technique::HiddenSingle)
+            .unwrap()
+    );
+    // r1c12 would wrap to r2c3.
+    assert_eq!(
+        None,
+        hard.hint_at_up_to(Pos::new(0, 11), Technique::HiddenSingle)
+            .unwrap()
+    );
+    assert_eq!(
+        None,
+        hard.hint_at_up_to(Pos::new(9, 0), Technique::HiddenSingle)
+            .unwrap()
+    );
+    // Levels are the order of the techniques the rater uses.
+    assert!(Technique::NakedSingle < Technique::HiddenSingle);
+    assert_eq!(
+        Some(Technique::NakedSingle),
+        Technique::from_name("naked-single")
+    );
+    assert_eq!(None, Technique::from_name("x-wing"));
+}
+
+#[test]
+fn nothing_to_hint_in_solved_sudoku() {
+    let mut s = solvable_field_helper();
+    s.solve().unwrap();
+    assert_eq!(None, s.hint().unwrap());
+}
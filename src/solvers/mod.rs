@@ -1 +1,31 @@
 pub mod backtracking;
+pub mod best_effort;
+pub mod builder;
+pub mod cache;
+pub mod cages;
+pub mod candidates;
+pub mod classify;
+pub mod constraint;
+pub mod corpus;
+pub mod cross_check;
+pub mod diagnosis;
+pub mod edit;
+pub mod filter;
+pub mod geometry;
+pub mod limit;
+pub mod logical;
+pub mod notes;
+pub mod observer;
+pub mod parity;
+pub mod partial;
+pub mod regions;
+pub mod repair;
+pub mod rules;
+pub mod state;
+pub mod stats;
+pub mod stress;
+pub mod symmetry;
+pub mod thermometers;
+pub mod transform;
+pub mod value;
+pub mod value_order;
@@ -0,0 +1,326 @@
+//! Notes of the player, pencil marks kept apart from the candidates computed from the rules.
+//!
+//! Notes of a filled cell are hidden, not lost: they come back once the cell is emptied again, e.g. by `clear` or a
+//! rollback of the search. They are left out of equality and hashing of sudokus.
+//!
+//! `export_candidates` saves the notes of every cell as a line of groups separated by commas, a group holds the noted
+//! values of a cell like the characters of the grid, e.g. `,,14,,` for notes 1 and 4 in the third of five cells.
+use super::backtracking::{
+    outside_of_grid, value_char, Sudoku, SudokuError, ValueNotAllowed, EMPTY,
+};
+use super::geometry::Pos;
+use crate::prelude::*;
+
+/// What `Sudoku::import_candidates_with` refuses besides values out of range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImportCheck {
+    /// Notes of filled cells and values the filled cells rule out, see `Sudoku::candidates`.
+    Candidates,
+    /// Nothing else, notes of filled cells are kept hidden until the cells are emptied.
+    Range,
+}
+
+/// Values the filled cells rule out are refused.
+impl Default for ImportCheck {
+    fn default() -> ImportCheck {
+        ImportCheck::Candidates
+    }
+}
+
+/// Sudoku serialized together with the notes of its empty cells, e.g. `{"grid":[5,3,0,...],"notes":[[],[],[1,2],...]}`.
+///
+/// Plain serialization of a sudoku leaves the notes out.
+#[cfg(feature = "serde")]
+pub struct WithNotes<'a>(pub &'a Sudoku);
+
+impl Sudoku {
+    /// Adds the value to the notes of the empty cell.
+    ///
+    /// Returns the reason when the value is out of range or the cell is filled.
+    pub fn add_note(&mut self, row: usize, col: usize, value: u8) -> Result<(), ValueNotAllowed> {
+        let pos = self.note_pos(row, col, value)?;
+        if self.field[pos] != EMPTY {
+            return Err(ValueNotAllowed::new("the cell is filled".to_string()));
+        }
+        if self.notes.is_empty() {
+            self.notes = vec![0; self.field.len()];
+        }
+        self.notes[pos] |= 1 << (value - 1);
+        Ok(())
+    }
+
+    /// Removes the value from the notes of the cell, nothing changes if the value isn't noted.
+    pub fn remove_note(
+        &mut self,
+        row: usize,
+        col: usize,
+        value: u8,
+    ) -> Result<(), ValueNotAllowed> {
+        let pos = self.note_pos(row, col, value)?;
+        if let Some(notes) = self.notes.get_mut(pos) {
+            *notes &= !(1 << (value - 1));
+        }
+        Ok(())
+    }
+
+    /// Returns the noted values of the cell in ascending order, nothing for filled cells and cells outside of the grid.
+    pub fn notes(&self, row: usize, col: usize) -> impl Iterator<Item = u8> {
+        let pos = self.geometry().pos(row, col);
+        let notes = match self.field.get(pos) {
+            Some(&EMPTY) if self.geometry().contains(row, col) => {
+                self.notes.get(pos).copied().unwrap_or_default()
+            }
+            _ => 0,
+        };
+        self.values()
+            .filter(move |&value| notes & 1 << (value - 1) != 0)
+    }
+
+    /// Returns `true` if any empty cell has notes.
+    pub fn has_notes(&self) -> bool {
+        self.notes
+            .iter()
+            .zip(self.field.iter())
+            .any(|(&notes, &val)| notes != 0 && val == EMPTY)
+    }
+
+    /// Returns the noted values of every cell in reading order, nothing for filled cells.
+    pub(crate) fn all_notes(&self) -> Vec<Vec<u8>> {
+        (0..self.field.len())
+            .map(|pos| {
+                let (row, col) = self.geometry().cell(pos);
+                self.notes(row, col).collect()
+            })
+            .collect()
+    }
+
+    /// Returns the notes of every cell in the format of the module documentation.
+    pub fn export_candidates(&self) -> String {
+        let groups: Vec<String> = self
+            .all_notes()
+            .iter()
+            .map(|values| values.iter().map(|&val| value_char(val)).collect())
+            .collect();
+        groups.join(",")
+    }
+
+    /// Returns the notes of every cell as a JSON array with an array of noted values per cell, e.g. `[[],[1,4],...]`.
+    #[cfg(feature = "serde")]
+    pub fn export_candidates_json(&self) -> String {
+        serde_json::to_string(&self.all_notes()).expect("notes serialize to JSON")
+    }
+
+    /// Replaces the notes of every cell with the exported ones, refusing values the filled cells rule out.
+    pub fn import_candidates(&mut self, s: &str) -> Result<(), SudokuError> {
+        self.import_candidates_with(s, ImportCheck::default())
+    }
+
+    /// Replaces the notes of every cell with the ones of `export_candidates`, or of `export_candidates_json` with the
+    /// `serde` feature.
+    ///
+    /// Returns an error naming the first cell whose notes are refused by the check, the notes stay as they were then.
+    pub fn import_candidates_with(
+        &mut self,
+        s: &str,
+        check: ImportCheck,
+    ) -> Result<(), SudokuError> {
+        let groups = parse_candidates(s.trim())?;
+        if groups.len() != self.field.len() {
+            return Err(SudokuError::InvalidNotes(format!(
+                "expected notes of {} cells, found {}",
+                self.field.len(),
+                groups.len()
+            )));
+        }
+        let mut notes = vec![0; self.field.len()];
+        for (pos, values) in groups.iter().enumerate() {
+            let (row, col) = self.geometry().cell(pos);
+            for &value in values.iter() {
+                let refused = if value == EMPTY || value as usize > self.side() {
+                    Some(format!("value must be between 1 and {}", self.side()))
+                } else if check == ImportCheck::Range {
+                    None
+                } else if self.field[pos] != EMPTY {
+                    Some("the cell is filled".to_string())
+                } else if !self.is_allowed(value, pos) {
+                    Some(format!("{} is ruled out by the filled cells", value))
+                } else {
+                    None
+                };
+                if let Some(msg) = refused {
+                    return Err(SudokuError::InvalidNotes(format!(
+                        "r{}c{}: {}",
+                        row + 1,
+                        col + 1,
+                        msg
+                    )));
+                }
+                notes[pos] |= 1 << (value - 1);
+            }
+        }
+        self.notes = match notes.iter().any(|&cell| cell != 0) {
+            true => notes,
+            false => Vec::new(),
+        };
+        Ok(())
+    }
+
+    /// Returns the position of the cell, or the reason why the value cannot be noted.
+    fn note_pos(&self, row: usize, col: usize, value: u8) -> Result<usize, ValueNotAllowed> {
+        if !self.geometry().contains(row, col) {
+            return Err(outside_of_grid(Pos::new(row, col)));
+        }
+        match value != EMPTY && value as usize <= self.side() {
+            true => Ok(self.geometry().pos(row, col)),
+            false => Err(ValueNotAllowed::new(format!(
+                "value must be between 1 and {}",
+                self.side()
+            ))),
+        }
+    }
+}
+
+/// Returns the noted values of every cell of exported notes.
+fn parse_candidates(s: &str) -> Result<Vec<Vec<u8>>, SudokuError> {
+    #[cfg(feature = "serde")]
+    if s.starts_with('[') {
+        return serde_json::from_str(s).map_err(|err| SudokuError::InvalidNotes(err.to_string()));
+    }
+    s.split(',')
+        .map(|group| {
+            group
+                .trim()
+                .chars()
+                .map(|c| match c {
+                    '1'..='9' => Ok(c as u8 - b'0'),
+                    'A'..='G' | 'a'..='g' => Ok(c.to_ascii_uppercase() as u8 - b'A' + 10),
+                    c => Err(SudokuError::InvalidNotes(format!("`{}` is not a value", c))),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+use super::backtracking::solvable_field_helper;
+
+#[test]
+fn add_and_remove_notes() {
+    let mut s = solvable_field_helper();
+    assert!(!s.has_notes());
+    s.add_note(0, 2, 4).unwrap();
+    s.add_note(0, 2, 1).unwrap();
+    s.add_note(0, 2, 9).unwrap();
+    assert_eq!(vec![1, 4, 9], s.notes(0, 2).collect::<Vec<_>>());
+    s.remove_note(0, 2, 9).unwrap();
+    s.remove_note(0, 3, 9).unwrap();
+    assert_eq!(vec![1, 4], s.notes(0, 2).collect::<Vec<_>>());
+    assert!(s.has_notes());
+    assert!(s.add_note(0, 0, 1).is_err());
+    assert!(s.add_note(0, 2, 10).is_err());
+    assert!(s.add_note(0, 2, EMPTY).is_err());
+    // Cells outside of the grid have no notes.
+    let mut small = Sudoku::with_box_size(2).unwrap();
+    assert_eq!(
+        "r5c5 is outside of the grid",
+        small.add_note(4, 4, 1).unwrap_err().msg
+    );
+    assert!(small.remove_note(0, 4, 1).is_err());
+    assert_eq!(0, small.notes(4, 4).count());
+    // Notes are not a part of the grid.
+    assert_eq!(solvable_field_helper(), s);
+}
+
+#[test]
+fn notes_of_placed_and_rolled_back_values() {
+    let mut s = solvable_field_helper();
+    s.add_note(0, 2, 1).unwrap();
+    s.add_note(0, 2, 4).unwrap();
+    s.set(0, 2, 4).unwrap();
+    assert_eq!(0, s.notes(0, 2).count());
+    assert!(!s.has_notes());
+    assert!(s.add_note(0, 2, 2).is_err());
+    s.clear(0, 2).unwrap();
+    assert_eq!(vec![1, 4], s.notes(0, 2).collect::<Vec<_>>());
+    // The search fills cells and empties them again when it backtracks.
+    let mut solved = s.clone();
+    solved.solve().unwrap();
+    assert_eq!(0, solved.notes(0, 2).count());
+    solved.clear(0, 2).unwrap();
+    assert_eq!(vec![1, 4], solved.notes(0, 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn export_and_import_candidates() {
+    let mut s = solvable_field_helper();
+    s.add_note(0, 2, 4).unwrap();
+    s.add_note(0, 2, 1).unwrap();
+    s.add_note(8, 0, 3).unwrap();
+    let exported = s.export_candidates();
+    assert_eq!(81, exported.split(',').count());
+    assert!(exported.starts_with(",,14,,"));
+    assert!(exported.ends_with(",,3,,,,,,,,"));
+    let mut imported = solvable_field_helper();
+    imported.import_candidates(&exported).unwrap();
+    assert_eq!(s.export_candidates(), imported.export_candidates());
+    assert_eq!(vec![1, 4], imported.notes(0, 2).collect::<Vec<_>>());
+    // Importing notes of no cell removes them all.
+    imported.import_candidates(&",".repeat(80)).unwrap();
+    assert!(!imported.has_notes());
+    #[cfg(feature = "serde")]
+    {
+        let json = s.export_candidates_json();
+        assert!(json.starts_with("[[],[],[1,4],[]"));
+        imported.import_candidates(&json).unwrap();
+        assert_eq!(exported, imported.export_candidates());
+    }
+    let mut big = Sudoku::with_box_size(4).unwrap();
+    big.add_note(0, 0, 16).unwrap();
+    big.add_note(0, 0, 9).unwrap();
+    assert!(big.export_candidates().starts_with("9G,,"));
+    let mut copy = Sudoku::with_box_size(4).unwrap();
+    copy.import_candidates(&big.export_candidates()).unwrap();
+    assert_eq!(vec![9, 16], copy.notes(0, 0).collect::<Vec<_>>());
+}
+
+#[test]
+fn import_refused_candidates() {
+    let mut s = solvable_field_helper();
+    s.add_note(0, 2, 4).unwrap();
+    let notes = |cell: usize, group: &str| {
+        let mut groups = vec![""; 81];
+        groups[cell] = group;
+        groups.join(",")
+    };
+    // 5 is the clue of r1c1, so it is ruled out in r1c3, and r1c1 is filled.
+    let errors = [
+        (
+            notes(2, "15"),
+            "Invalid notes: r1c3: 5 is ruled out by the filled cells",
+        ),
+        (notes(0, "1"), "Invalid notes: r1c1: the cell is filled"),
+        (
+            notes(2, "A"),
+            "Invalid notes: r1c3: value must be between 1 and 9",
+        ),
+        (notes(2, "1x"), "Invalid notes: `x` is not a value"),
+        (
+            ",,1".to_string(),
+            "Invalid notes: expected notes of 81 cells, found 3",
+        ),
+    ];
+    for (text, msg) in errors.iter() {
+        assert_eq!(*msg, s.import_candidates(text).unwrap_err().to_string());
+    }
+    // Refused notes leave the old ones.
+    assert_eq!(vec![4], s.notes(0, 2).collect::<Vec<_>>());
+    s.import_candidates_with(&notes(2, "15"), ImportCheck::Range)
+        .unwrap();
+    assert_eq!(vec![1, 5], s.notes(0, 2).collect::<Vec<_>>());
+    s.import_candidates_with(&notes(0, "1"), ImportCheck::Range)
+        .unwrap();
+    assert_eq!(0, s.notes(0, 0).count());
+    assert!(s
+        .import_candidates_with(&notes(2, "A"), ImportCheck::Range)
+        .is_err());
+}
@@ -0,0 +1,104 @@
+/// Step of the backtracking search reported to a `SolveObserver`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SolveEvent {
+    /// The value is put into the cell at `pos`.
+    Placed { pos: usize, value: u8 },
+    /// The value put into the cell at `pos` is taken back.
+    RolledBack { pos: usize, value: u8 },
+}
+
+/// Step of a recorded search, see `Sudoku::take_trace` and `replay`.
+pub type TraceEvent = SolveEvent;
+
+/// Counters of the backtracking search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolveStats {
+    /// Values put into cells so far.
+    pub placements: u64,
+    /// Values taken back so far.
+    pub backtracks: u64,
+    /// Values currently placed by the search.
+    pub depth: usize,
+}
+
+/// Receives every step of the search, see `Sudoku::solve_with_observer`.
+pub trait SolveObserver {
+    fn on_event(&mut self, event: SolveEvent, stats: &SolveStats);
+}
+
+/// Observer which ignores all events.
+pub struct NoObserver;
+
+impl SolveObserver for NoObserver {
+    fn on_event(&mut self, _event: SolveEvent, _stats: &SolveStats) {}
+}
+
+/// Observer which calls `callback` after every `every` placements.
+///
+/// The check is a single counter comparison, so it doesn't slow the search down.
+pub struct Progress<F: FnMut(&SolveStats)> {
+    every: u64,
+    callback: F,
+}
+
+impl<F: FnMut(&SolveStats)> Progress<F> {
+    pub fn new(every: u64, callback: F) -> Progress<F> {
+        Progress {
+            every: every.max(1),
+            callback,
+        }
+    }
+}
+
+impl<F: FnMut(&SolveStats)> SolveObserver for Progress<F> {
+    fn on_event(&mut self, event: SolveEvent, stats: &SolveStats) {
+        if let SolveEvent::Placed { .. } = event {
+            if stats.placements % self.every == 0 {
+                (self.callback)(stats);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+use super::backtracking::Sudoku;
+
+#[test]
+fn progress_fires_at_interval() {
+    let mut reports = Vec::new();
+    let mut progress = Progress::new(10, |stats: &SolveStats| reports.push(*stats));
+    let mut s = Sudoku::empty();
+    s.solve_with_observer(&mut progress).unwrap();
+    assert!(reports.len() >= 8);
+    for (i, stats) in reports.iter().enumerate() {
+        assert_eq!((i as u64 + 1) * 10, stats.placements);
+        assert!(stats.depth <= 81);
+    }
+}
+
+#[test]
+fn observer_sees_every_step() {
+    struct Counter(SolveStats, u64, u64);
+    impl SolveObserver for Counter {
+        fn on_event(&mut self, event: SolveEvent, stats: &SolveStats) {
+            match event {
+                SolveEvent::Placed { .. } => self.1 += 1,
+                SolveEvent::RolledBack { .. } => self.2 += 1,
+            }
+            self.0 = *stats;
+        }
+    }
+    let mut counter = Counter(SolveStats::default(), 0, 0);
+    let mut s = Sudoku::empty();
+    s.solve_with_observer(&mut counter).unwrap();
+    let Counter(stats, placed, rolled_back) = counter;
+    assert_eq!((stats.placements, stats.backtracks), (placed, rolled_back));
+    assert_eq!(81, stats.depth);
+    assert_eq!(81, placed - rolled_back);
+}
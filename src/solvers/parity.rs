@@ -0,0 +1,310 @@
+//! Even/odd sudokus, whose marked cells hold only even or only odd values.
+//!
+//! Marks are read from a side file with a mark for every cell in reading order: `e` for even cells, `o` for odd ones
+//! and `.` or `0` for unmarked ones. Whitespace, `|` and `,` separate the marks, lines starting with `#` or `//` are
+//! comments:
+//!
+//! ```text
+//! # Row 1 starts with an even and an odd cell
+//! eo.|...|...
+//! ```
+//!
+//! Marks can also be embedded into a grid of digits in the line or the nine-line layout, `e` and `o` stand for empty
+//! cells with the mark, see `split_markers`.
+use super::backtracking::{Sudoku, SudokuError, EMPTY};
+use super::constraint::Violation;
+#[cfg(test)]
+use super::geometry::Geometry;
+use crate::formats::is_ignored_line;
+use crate::prelude::*;
+use core::fmt;
+
+/// Parity a marked cell requires of its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Parity {
+    /// 2, 4, 6 and so on, drawn as a shaded square.
+    Even,
+    /// 1, 3, 5 and so on, drawn as a shaded circle.
+    Odd,
+}
+
+impl Parity {
+    /// Returns the parity of the value.
+    pub fn of(value: u8) -> Parity {
+        match value % 2 {
+            0 => Parity::Even,
+            _ => Parity::Odd,
+        }
+    }
+
+    /// Returns the parity of a mark like `e` or `O`, None for other characters.
+    pub fn from_mark(mark: char) -> Option<Parity> {
+        match mark {
+            'e' | 'E' => Some(Parity::Even),
+            'o' | 'O' => Some(Parity::Odd),
+            _ => None,
+        }
+    }
+}
+
+/// Prints the parity like `even`.
+impl fmt::Display for Parity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Parity::Even => "even",
+            Parity::Odd => "odd",
+        })
+    }
+}
+
+impl Sudoku {
+    /// Returns the even/odd sudoku with the parity marks of the cells, given in reading order, None for unmarked cells.
+    ///
+    /// Returns an error if there is not a mark for every cell or a filled cell holds a value of the other parity.
+    pub fn with_parity(mut self, marks: Vec<Option<Parity>>) -> Result<Sudoku, SudokuError> {
+        if marks.len() != self.field.len() {
+            return Err(SudokuError::InvalidParity(format!(
+                "expected {} marks, found {}",
+                self.field.len(),
+                marks.len()
+            )));
+        }
+        for (pos, (&value, &mark)) in self.field.iter().zip(marks.iter()).enumerate() {
+            if let Some(parity) =
+                mark.filter(|&parity| value != EMPTY && Parity::of(value) != parity)
+            {
+                let (row, col) = self.geometry().cell(pos);
+                return Err(SudokuError::InvalidParity(format!(
+                    "r{}c{} holds {}, but is marked {}",
+                    row + 1,
+                    col + 1,
+                    value,
+                    parity
+                )));
+            }
+        }
+        self.parity = match marks.iter().any(Option::is_some) {
+            true => marks,
+            false => Vec::new(),
+        };
+        Ok(self)
+    }
+
+    /// Returns the parity marks of the cells in reading order, empty for sudokus without marks.
+    pub fn parity_marks(&self) -> &[Option<Parity>] {
+        &self.parity
+    }
+
+    /// Returns the parity mark of the cell, None for unmarked cells.
+    pub fn parity(&self, row: usize, col: usize) -> Option<Parity> {
+        self.parity
+            .get(self.geometry().pos(row, col))
+            .copied()
+            .flatten()
+    }
+
+    /// Returns true if the value has the parity the cell at the position is marked with, or the cell is unmarked.
+    pub(crate) fn is_allowed_by_parity(&self, value: u8, pos: usize) -> bool {
+        self.parity
+            .get(pos)
+            .copied()
+            .flatten()
+            .map_or(true, |parity| Parity::of(value) == parity)
+    }
+
+    /// Returns the filled cells whose values do not have the parity they are marked with, None if there are none.
+    pub(crate) fn parity_violation(&self) -> Option<Violation> {
+        let wrong: Vec<(usize, usize)> = (0..self.parity.len())
+            .filter(|&pos| {
+                self.field[pos] != EMPTY && !self.is_allowed_by_parity(self.field[pos], pos)
+            })
+            .map(|pos| self.geometry().cell(pos))
+            .collect();
+        let names: Vec<String> = wrong
+            .iter()
+            .map(|&(row, col)| format!("r{}c{}", row + 1, col + 1))
+            .collect();
+        match wrong.is_empty() {
+            true => None,
+            false => Some(Violation {
+                msg: format!(
+                    "values do not have the parity of their marks: {}",
+                    names.join(", ")
+                ),
+                cells: wrong,
+            }),
+        }
+    }
+
+    /// Returns the three characters of the cell at the position in the pretty grid with the content in the middle,
+    /// framed like `[4]` if the cell is marked even and like `(3)` if it is marked odd.
+    pub(crate) fn framed(&self, pos: usize, content: char) -> String {
+        match self.parity.get(pos).copied().flatten() {
+            Some(Parity::Even) => format!("[{}]", content),
+            Some(Parity::Odd) => format!("({})", content),
+            None => format!(" {} ", content),
+        }
+    }
+}
+
+/// Parses parity marks in the format described in the module documentation, see `Sudoku::with_parity` for the number
+/// of marks.
+pub fn parse_parity(text: &str) -> Result<Vec<Option<Parity>>, SudokuError> {
+    let mut marks = Vec::new();
+    for (num, line) in text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !is_ignored_line(line))
+    {
+        for (col, c) in line.chars().enumerate() {
+            match c {
+                '.' | '0' => marks.push(None),
+                c if c.is_whitespace() || c == '|' || c == ',' => {}
+                c => match Parity::from_mark(c) {
+                    Some(parity) => marks.push(Some(parity)),
+                    None => {
+                        return Err(SudokuError::ParseLine {
+                            line: num + 1,
+                            msg: format!(
+                                "column {}: `{}` is not a parity mark, expected `e`, `o` or `.`",
+                                col + 1,
+                                c
+                            ),
+                        })
+                    }
+                },
+            }
+        }
+    }
+    Ok(marks)
+}
+
+/// Splits a grid of digits with parity markers into the grid with empty cells in place of the markers and the marks
+/// of its cells, None if the text has no markers.
+///
+/// Markers are a lowercase `e` or `o` in an empty cell. Only grids of digits and `.` separated by whitespace, `|` or
+/// `,` are looked at, so letters of other formats and 16x16 grids are left alone.
+pub fn split_markers(text: &str) -> Option<(String, Vec<Option<Parity>>)> {
+    let lines: Vec<&str> = text.lines().filter(|line| !is_ignored_line(line)).collect();
+    let is_grid = |c: char| {
+        c.is_ascii_digit() || c.is_whitespace() || matches!(c, '.' | '|' | ',' | 'e' | 'o')
+    };
+    if !lines.iter().all(|line| line.chars().all(is_grid))
+        || !lines.iter().any(|line| line.contains(['e', 'o']))
+    {
+        return None;
+    }
+    let marks = lines
+        .iter()
+        .flat_map(|line| line.chars())
+        .filter(|&c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'o'))
+        .map(Parity::from_mark)
+        .collect();
+    let grid: Vec<String> = text
+        .lines()
+        .map(|line| match is_ignored_line(line) {
+            true => line.to_string(),
+            false => line.replace(['e', 'o'], "."),
+        })
+        .collect();
+    Some((grid.join("\n"), marks))
+}
+
+/// Returns the marks of the text like `eo.` for the first cells of a 9x9 grid, the others are unmarked.
+#[cfg(test)]
+fn marks(text: &str) -> Vec<Option<Parity>> {
+    let mut marks: Vec<Option<Parity>> = text.chars().map(Parity::from_mark).collect();
+    marks.resize(Geometry::CLASSIC.size(), None);
+    marks
+}
+
+#[test]
+fn parse_marks() {
+    assert_eq!(
+        marks("eo.o"),
+        parse_parity(&format!("# marks\ne o . | O\n{}\n", "0".repeat(77))).unwrap()
+    );
+    match parse_parity("e.\nex") {
+        Err(SudokuError::ParseLine { line, msg }) => {
+            assert_eq!(2, line);
+            assert_eq!(
+                "column 2: `x` is not a parity mark, expected `e`, `o` or `.`",
+                msg
+            );
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn marks_follow_clues() {
+    let s: Sudoku = format!("4{}", ".".repeat(80)).parse().unwrap();
+    let marked = s.clone().with_parity(marks("eo")).unwrap();
+    assert_eq!(Some(Parity::Even), marked.parity(0, 0));
+    assert_eq!(Some(Parity::Odd), marked.parity(0, 1));
+    assert_eq!(None, marked.parity(0, 2));
+    assert_ne!(s, marked);
+    match s.clone().with_parity(marks("o")) {
+        Err(err) => assert_eq!(
+            "Invalid parity marks: r1c1 holds 4, but is marked odd",
+            err.to_string()
+        ),
+        Ok(_) => panic!("4 is even"),
+    }
+    assert!(matches!(
+        s.clone().with_parity(vec![None; 80]),
+        Err(SudokuError::InvalidParity(_))
+    ));
+    // Without any mark the sudoku stays a classic one.
+    assert_eq!(s, s.clone().with_parity(vec![None; 81]).unwrap());
+    assert!(Sudoku::empty()
+        .with_parity(marks("e"))
+        .unwrap()
+        .parity_violation()
+        .is_none());
+}
+
+#[test]
+fn marks_restrict_values() {
+    let mut s = Sudoku::empty().with_parity(marks("eo")).unwrap();
+    match s.set(0, 0, 3) {
+        Err(err) => assert_eq!("3 is odd, the cell is marked even", err.msg),
+        Ok(_) => panic!("3 is odd"),
+    }
+    assert!(s.set(0, 1, 2).is_err());
+    assert_eq!(vec![2, 4, 6, 8], s.candidates(0, 0));
+    s.solve().unwrap();
+    assert_eq!(Some(Parity::Even), s.value(0, 0).map(Parity::of));
+    assert_eq!(Some(Parity::Odd), s.value(0, 1).map(Parity::of));
+    let mut field = s.as_slice().to_vec();
+    field.swap(0, 1);
+    let swapped = s.with_field(field);
+    assert_eq!(
+        Some(Violation {
+            msg: "values do not have the parity of their marks: r1c1, r1c2".to_string(),
+            cells: vec![(0, 0), (0, 1)],
+        }),
+        swapped.parity_violation()
+    );
+}
+
+#[test]
+fn embedded_markers() {
+    let (grid, found) = split_markers("# even/odd\n4e.\no..\n").unwrap();
+    assert_eq!("# even/odd\n4..\n...", grid);
+    assert_eq!(
+        vec![
+            None,
+            Some(Parity::Even),
+            None,
+            Some(Parity::Odd),
+            None,
+            None
+        ],
+        found
+    );
+    assert_eq!(None, split_markers(&"5.".repeat(40)));
+    // Words and letters of other formats are not markers.
+    assert_eq!(None, split_markers("r1c1=4 o"));
+    assert_eq!(None, split_markers("Solved sudoku\n4e"));
+}
@@ -0,0 +1,223 @@
+//! Finishing a puzzle solved partly by hand, keeping the entries of the solver and telling which of them is wrong.
+use super::backtracking::{Sudoku, Unsolvable, EMPTY};
+use super::diagnosis::Diagnosis;
+use super::rules::{cell_name, Conflict};
+use crate::prelude::*;
+use core::fmt;
+
+/// Reason why `Sudoku::solve_from` cannot finish the partial solution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartialError {
+    /// The grids have different sides.
+    SizeMismatch { puzzle: usize, partial: usize },
+    /// (row, col) of the clues of the puzzle which the partial solution changes or empties, in reading order.
+    AlteredClues(Vec<(usize, usize)>),
+    /// The filled cells of the partial solution break the rules, with the values they repeat.
+    Inconsistent(Vec<Conflict>),
+    /// The earliest entry in reading order which leaves the puzzle without a solution together with the entries
+    /// before it.
+    WrongEntry { row: usize, col: usize, value: u8 },
+    /// The puzzle itself has no solution, or the search stopped at its limit.
+    Unsolvable(Unsolvable),
+}
+
+/// Prints the reason, e.g. `4 at r2c3 is wrong, the puzzle has no solution with it`.
+impl fmt::Display for PartialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PartialError::SizeMismatch { puzzle, partial } => write!(
+                f,
+                "the partial solution is {}x{}, the puzzle {}x{}",
+                partial, partial, puzzle, puzzle
+            ),
+            PartialError::AlteredClues(cells) => {
+                let cells: Vec<String> = cells
+                    .iter()
+                    .map(|&(row, col)| cell_name(row, col))
+                    .collect();
+                write!(
+                    f,
+                    "clues of the original puzzle are changed at {}",
+                    cells.join(", ")
+                )
+            }
+            PartialError::Inconsistent(conflicts) if conflicts.is_empty() => {
+                write!(f, "the entries break the rules")
+            }
+            PartialError::Inconsistent(conflicts) => {
+                let conflicts: Vec<String> = conflicts.iter().map(|c| c.to_string()).collect();
+                write!(f, "the entries break the rules: {}", conflicts.join("; "))
+            }
+            PartialError::WrongEntry { row, col, value } => write!(
+                f,
+                "{} at {} is wrong, the puzzle has no solution with it",
+                value,
+                cell_name(*row, *col)
+            ),
+            PartialError::Unsolvable(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PartialError {}
+
+impl Sudoku {
+    /// Solves the puzzle from the partial solution, whose values stay, and returns the solution.
+    ///
+    /// The partial solution must keep every clue of the puzzle and follow its rules. When its entries leave no
+    /// solution of a solvable puzzle, the earliest wrong entry is found by halving the entries taken in reading order,
+    /// counting the solutions of the puzzle with each half. The solution keeps the givens of the puzzle, the entries
+    /// are ordinary filled cells.
+    pub fn solve_from(&self, partial: &Sudoku) -> Result<Sudoku, PartialError> {
+        if self.side() != partial.side() {
+            return Err(PartialError::SizeMismatch {
+                puzzle: self.side(),
+                partial: partial.side(),
+            });
+        }
+        let altered = partial.altered_clues(self);
+        if !altered.is_empty() {
+            return Err(PartialError::AlteredClues(altered));
+        }
+        let mut s = self.with_field(partial.field.clone());
+        s.givens = self.givens.clone();
+        s.metadata = self.metadata.clone();
+        if !s.is_consistent() {
+            return Err(PartialError::Inconsistent(s.conflicts()));
+        }
+        let err = match s.solve() {
+            Ok(()) => return Ok(s),
+            Err(err) => err,
+        };
+        if let Diagnosis::LimitReached { .. } = err.diagnosis {
+            return Err(PartialError::Unsolvable(err));
+        }
+        self.clone().solve().map_err(PartialError::Unsolvable)?;
+        let entries: Vec<usize> = (0..self.field.len())
+            .filter(|&pos| self.field[pos] == EMPTY && partial.field[pos] != EMPTY)
+            .collect();
+        let max_nodes = self
+            .node_limit()
+            .max_nodes(self.geometry())
+            .unwrap_or(u64::MAX);
+        let solvable_with = |count: usize| {
+            let mut field = self.field.clone();
+            for &pos in entries[..count].iter() {
+                field[pos] = partial.field[pos];
+            }
+            self.with_field(field).count_solutions_within(1, max_nodes)
+        };
+        // The puzzle is solvable without the entries and is not with all of them.
+        let (mut solvable, mut unsolvable) = (0, entries.len());
+        while unsolvable - solvable > 1 {
+            let middle = (solvable + unsolvable) / 2;
+            match solvable_with(middle) {
+                Some(0) => unsolvable = middle,
+                Some(_) => solvable = middle,
+                None => return Err(PartialError::Unsolvable(self.limit_reached())),
+            }
+        }
+        let pos = entries[unsolvable - 1];
+        let (row, col) = self.geometry().cell(pos);
+        Err(PartialError::WrongEntry {
+            row,
+            col,
+            value: partial.field[pos],
+        })
+    }
+}
+
+#[cfg(test)]
+use crate::examples;
+
+/// Returns the hard example and its solution.
+#[cfg(test)]
+fn hard_and_solution() -> (Sudoku, Sudoku) {
+    let puzzle: Sudoku = examples::HARD.parse().unwrap();
+    let mut solution = puzzle.clone();
+    solution.solve().unwrap();
+    (puzzle, solution)
+}
+
+#[test]
+fn solve_from_partial_solution() {
+    let (puzzle, solution) = hard_and_solution();
+    let mut field = puzzle.field.clone();
+    for pos in (0..81).filter(|&pos| !puzzle.givens[pos]).take(10) {
+        field[pos] = solution.field[pos];
+    }
+    let solved = puzzle.solve_from(&Sudoku::from_field(field)).unwrap();
+    assert!(solved.solved());
+    assert_eq!(solution.field, solved.field);
+    assert_eq!(puzzle.givens, solved.givens);
+    // A complete partial solution is its own solution.
+    assert_eq!(solution.field, puzzle.solve_from(&solution).unwrap().field);
+}
+
+#[test]
+fn altered_clues_of_partial_solution() {
+    let (puzzle, solution) = hard_and_solution();
+    let clues: Vec<usize> = (0..81).filter(|&pos| puzzle.givens[pos]).collect();
+    let mut field = solution.field.clone();
+    field[clues[0]] = EMPTY;
+    field[clues[2]] = field[clues[2]] % 9 + 1;
+    let err = puzzle.solve_from(&Sudoku::from_field(field)).unwrap_err();
+    let cells = vec![
+        puzzle.geometry().cell(clues[0]),
+        puzzle.geometry().cell(clues[2]),
+    ];
+    assert_eq!(PartialError::AlteredClues(cells), err);
+    assert!(err
+        .to_string()
+        .starts_with("clues of the original puzzle are changed at "));
+    let big = Sudoku::with_box_size(4).unwrap();
+    assert_eq!(
+        "the partial solution is 16x16, the puzzle 9x9",
+        puzzle.solve_from(&big).unwrap_err().to_string()
+    );
+}
+
+#[test]
+fn wrong_entry_of_partial_solution() {
+    let (puzzle, solution) = hard_and_solution();
+    let empty: Vec<usize> = (0..81).filter(|&pos| !puzzle.givens[pos]).collect();
+    let mut field = solution.field.clone();
+    for &pos in empty[..empty.len() / 2].iter() {
+        field[pos] = EMPTY;
+    }
+    // A value the clues and the later entries allow, which is not the one of the solution.
+    let (pos, value) = empty[..empty.len() / 2]
+        .iter()
+        .rev()
+        .find_map(|&pos| {
+            let (row, col) = puzzle.geometry().cell(pos);
+            let filled = puzzle.with_field(field.clone());
+            filled
+                .candidates(row, col)
+                .into_iter()
+                .find(|&value| value != solution.field[pos])
+                .map(|value| (pos, value))
+        })
+        .unwrap();
+    field[pos] = value;
+    let (row, col) = puzzle.geometry().cell(pos);
+    assert_eq!(
+        PartialError::WrongEntry { row, col, value },
+        puzzle
+            .solve_from(&Sudoku::from_field(field.clone()))
+            .unwrap_err()
+    );
+    // Entries repeating a value in a row break the rules.
+    let first = empty[0];
+    let second = *empty[1..]
+        .iter()
+        .find(|&&pos| pos / 9 == first / 9)
+        .unwrap();
+    field[first] = 1;
+    field[second] = 1;
+    match puzzle.solve_from(&Sudoku::from_field(field)) {
+        Err(PartialError::Inconsistent(_)) => {}
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
@@ -0,0 +1,223 @@
+use super::backtracking::SudokuError;
+use super::geometry::Geometry;
+use crate::formats::is_ignored_line;
+use crate::prelude::*;
+
+/// Assignment of cells to the regions which replace rows and columns as the third unit of a sudoku.
+///
+/// The regions of a classic sudoku are its boxes, jigsaw sudokus have irregular regions instead. Every region holds
+/// as many cells as there are values, regions are numbered from 0.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Regions {
+    /// Region of every cell.
+    region_of: Vec<usize>,
+    /// Positions of the cells of every region, row by row.
+    cells: Vec<Vec<usize>>,
+}
+
+impl Regions {
+    /// Returns the boxes of the geometry as regions.
+    pub fn boxes(geometry: Geometry) -> Regions {
+        let region_of = (0..geometry.size())
+            .map(|pos| {
+                let (row, col) = geometry.cell(pos);
+                geometry.box_of(row, col)
+            })
+            .collect();
+        Regions::from_map(geometry.side(), region_of)
+    }
+
+    /// Builds the regions from the region of every cell, `count` is the number of regions.
+    pub(crate) fn from_map(count: usize, region_of: Vec<usize>) -> Regions {
+        let mut cells = vec![Vec::new(); count];
+        for (pos, &region) in region_of.iter().enumerate() {
+            cells[region].push(pos);
+        }
+        Regions { region_of, cells }
+    }
+
+    /// Parses the layout of a jigsaw sudoku, a region label for every cell of the geometry.
+    ///
+    /// Labels follow the values: digits from `1` with letters (in any case) from `A` after `9`, so `1`-`9` for a 9x9
+    /// grid and `1`-`9`, `A`-`G` for a 16x16 one. A layout without digits may use letters from `A` alone, e.g. `A`-`I`
+    /// for a 9x9 grid. Comment lines and whitespace are skipped. Every region must hold as many cells as there are values and be connected,
+    /// otherwise an error naming the bad region is returned.
+    pub fn parse(text: &str, geometry: Geometry) -> Result<Regions, SudokuError> {
+        let side = geometry.side();
+        let labels: Vec<char> = text
+            .lines()
+            .filter(|line| !is_ignored_line(line))
+            .flat_map(|line| line.chars())
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        if labels.len() != geometry.size() {
+            return Err(SudokuError::InvalidRegions(format!(
+                "expected {} region labels, found {}",
+                geometry.size(),
+                labels.len()
+            )));
+        }
+        // Letters come after the digits, unless there are no digits, so a label names the same region everywhere.
+        let first_letter = match labels.iter().any(|c| c.is_ascii_digit()) {
+            true => 9,
+            false => 0,
+        };
+        let region_of = labels
+            .iter()
+            .map(|&label| match label.to_ascii_uppercase() {
+                c @ '1'..='9' => Some(c as usize - '1' as usize),
+                c @ 'A'..='Z' => Some(c as usize - 'A' as usize + first_letter),
+                _ => None,
+            })
+            .zip(labels.iter())
+            .map(|(region, label)| match region {
+                Some(region) if region < side => Ok(region),
+                _ => Err(SudokuError::InvalidRegions(format!(
+                    "unexpected region label `{}`",
+                    label
+                ))),
+            })
+            .collect::<Result<Vec<usize>, SudokuError>>()?;
+        let regions = Regions::from_map(side, region_of);
+        for (region, cells) in regions.cells.iter().enumerate() {
+            if cells.len() != side {
+                return Err(SudokuError::InvalidRegions(format!(
+                    "region {} has {} cells, expected {}",
+                    region + 1,
+                    cells.len(),
+                    side
+                )));
+            }
+            if !regions.is_connected(region, geometry) {
+                return Err(SudokuError::InvalidRegions(format!(
+                    "region {} is not connected",
+                    region + 1
+                )));
+            }
+        }
+        Ok(regions)
+    }
+
+    /// Returns `true` if every cell of the region can be reached from any other one through horizontal and vertical
+    /// neighbours within the region.
+    fn is_connected(&self, region: usize, geometry: Geometry) -> bool {
+        let cells = &self.cells[region];
+        let mut reached = vec![cells[0]];
+        let mut next = 0;
+        while next < reached.len() {
+            let (row, col) = geometry.cell(reached[next]);
+            next += 1;
+            for pos in geometry.orthogonal_neighbours(row, col) {
+                if self.region_of[pos] == region && !reached.contains(&pos) {
+                    reached.push(pos);
+                }
+            }
+        }
+        reached.len() == cells.len()
+    }
+
+    /// Returns the number of cells covered by the regions.
+    pub fn size(&self) -> usize {
+        self.region_of.len()
+    }
+
+    /// Returns the region of the cell at the position.
+    pub fn region_of(&self, pos: usize) -> usize {
+        self.region_of[pos]
+    }
+
+    /// Returns positions of the cells of the region, row by row.
+    pub fn cells(&self, region: usize) -> &[usize] {
+        &self.cells[region]
+    }
+}
+
+/// Layout of a 9x9 jigsaw sudoku, boxes trade cells with their neighbours.
+#[cfg(test)]
+pub(crate) const JIGSAW_LAYOUT: &str = "
+111122333
+111222333
+112222333
+444555666
+444455666
+445555666
+777788999
+777888999
+778888999
+";
+
+#[test]
+fn boxes_are_classic_regions() {
+    let regions = Regions::boxes(Geometry::CLASSIC);
+    assert_eq!(81, regions.size());
+    assert_eq!(4, regions.region_of(40));
+    assert_eq!(Geometry::CLASSIC.box_cells(8), regions.cells(8));
+    let rectangular = Geometry::new(2, 3).unwrap();
+    assert_eq!(
+        rectangular.box_cells(3),
+        Regions::boxes(rectangular).cells(3)
+    );
+}
+
+#[test]
+fn parse_jigsaw_layout() {
+    let regions = Regions::parse(JIGSAW_LAYOUT, Geometry::CLASSIC).unwrap();
+    assert_eq!(1, regions.region_of(4));
+    assert_eq!(4, regions.region_of(47));
+    assert_eq!(&[4, 5, 12, 13, 14, 20, 21, 22, 23], regions.cells(1));
+    let letters = JIGSAW_LAYOUT
+        .chars()
+        .map(|c| match c.to_digit(10) {
+            Some(d) => (b'a' + d as u8 - 1) as char,
+            None => c,
+        })
+        .collect::<String>();
+    assert_eq!(
+        regions,
+        Regions::parse(&letters, Geometry::CLASSIC).unwrap()
+    );
+    // Letters after the digits name regions from 10 on, a 9x9 layout has none.
+    let mixed = JIGSAW_LAYOUT.replace('9', "a");
+    assert!(Regions::parse(&mixed, Geometry::CLASSIC).is_err());
+}
+
+#[test]
+fn parse_16x16_layout() {
+    let geometry = Geometry::new(4, 4).unwrap();
+    let boxes = Regions::boxes(geometry);
+    let labels = |alphabet: &[u8]| {
+        (0..geometry.size())
+            .map(|pos| alphabet[boxes.region_of(pos)] as char)
+            .collect::<String>()
+    };
+    let mixed = Regions::parse(&labels(b"123456789ABCDEFG"), geometry).unwrap();
+    assert_eq!(boxes, mixed);
+    assert_eq!(9, mixed.region_of(geometry.pos(8, 4)));
+    let letters = Regions::parse(&labels(b"abcdefghijklmnop"), geometry).unwrap();
+    assert_eq!(boxes, letters);
+}
+
+#[test]
+fn malformed_layouts() {
+    let ten_cells = JIGSAW_LAYOUT.replacen('2', "1", 1);
+    assert_eq!(
+        Err(SudokuError::InvalidRegions(
+            "region 1 has 10 cells, expected 9".to_string()
+        )),
+        Regions::parse(&ten_cells, Geometry::CLASSIC)
+    );
+    let short = &JIGSAW_LAYOUT[..JIGSAW_LAYOUT.len() - 3];
+    assert!(Regions::parse(short, Geometry::CLASSIC).is_err());
+    let unknown_label = JIGSAW_LAYOUT.replacen('9', "0", 1);
+    assert!(Regions::parse(&unknown_label, Geometry::CLASSIC).is_err());
+    // The corners of region 1 and region 9 trade places, both regions fall apart.
+    let mut split: Vec<char> = JIGSAW_LAYOUT.chars().collect();
+    let (first, last) = (1, split.len() - 2);
+    split.swap(first, last);
+    assert_eq!(
+        Err(SudokuError::InvalidRegions(
+            "region 1 is not connected".to_string()
+        )),
+        Regions::parse(&split.into_iter().collect::<String>(), Geometry::CLASSIC)
+    );
+}
@@ -0,0 +1,149 @@
+//! Fixes for puzzles without a solution: a single clue whose removal makes the puzzle solvable again.
+use super::backtracking::{Sudoku, EMPTY};
+use super::rules::cell_name;
+use crate::prelude::*;
+use core::fmt;
+
+/// Clue whose removal gives the puzzle a solution, see `Sudoku::suggest_repairs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repair {
+    pub row: usize,
+    pub col: usize,
+    /// Value of the clue.
+    pub value: u8,
+    /// Whether the puzzle without the clue has exactly one solution.
+    pub unique: bool,
+}
+
+/// Prints the repair like `remove 1 from r1c3, which leaves a unique solution`.
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "remove {} from {}, which leaves {}",
+            self.value,
+            cell_name(self.row, self.col),
+            match self.unique {
+                true => "a unique solution",
+                false => "several solutions",
+            }
+        )
+    }
+}
+
+impl Sudoku {
+    /// Returns every given whose removal alone makes the puzzle solvable, the ones leaving a unique solution first.
+    ///
+    /// When values are repeated only the givens of the conflicts are tried, other removals cannot resolve them. The
+    /// search is bounded to single removals: the result is empty when two clues or more are wrong, as well as for a
+    /// puzzle which already has a solution. Searches stopped at the node limit of the sudoku, see
+    /// `Sudoku::with_node_limit`, decide nothing: the result is empty when the puzzle itself is undecided, and removals
+    /// whose check is undecided are left out.
+    pub fn suggest_repairs(&self) -> Vec<Repair> {
+        let max_nodes = self
+            .node_limit()
+            .max_nodes(self.geometry())
+            .unwrap_or(u64::MAX);
+        if self.count_solutions_within(1, max_nodes) != Some(0) {
+            return Vec::new();
+        }
+        let conflicts = self.conflicts();
+        let geometry = self.geometry();
+        let mut repairs: Vec<Repair> = (0..self.field.len())
+            .filter(|&pos| self.givens[pos])
+            .map(|pos| geometry.cell(pos))
+            .filter(|cell| conflicts.is_empty() || conflicts.iter().any(|c| c.cells.contains(cell)))
+            .filter_map(|(row, col)| {
+                let pos = geometry.pos(row, col);
+                let mut field = self.field.clone();
+                field[pos] = EMPTY;
+                match self
+                    .with_field(field)
+                    .count_solutions_within(2, max_nodes)?
+                {
+                    0 => None,
+                    found => Some(Repair {
+                        row,
+                        col,
+                        value: self.field[pos],
+                        unique: found == 1,
+                    }),
+                }
+            })
+            .collect();
+        repairs.sort_by_key(|repair| !repair.unique);
+        repairs
+    }
+}
+
+#[cfg(test)]
+use super::backtracking::solvable_field_helper;
+#[cfg(test)]
+use super::limit::NodeLimit;
+
+/// Returns the puzzle of `solvable_field_helper` with the extra clues.
+#[cfg(test)]
+fn with_clues(clues: &[(usize, usize, u8)]) -> Sudoku {
+    let mut s = solvable_field_helper();
+    for &(row, col, value) in clues {
+        let pos = s.geometry().pos(row, col);
+        s.field[pos] = value;
+    }
+    s.with_field(s.field.clone())
+}
+
+#[test]
+fn single_wrong_clue() {
+    // r1c3 allows 1, 2 and 4, only 4 solves the puzzle.
+    let repair = Repair {
+        row: 0,
+        col: 2,
+        value: 1,
+        unique: true,
+    };
+    assert_eq!(vec![repair], with_clues(&[(0, 2, 1)]).suggest_repairs());
+    assert_eq!(
+        "remove 1 from r1c3, which leaves a unique solution",
+        repair.to_string()
+    );
+    // The 5 repeats the clue at r1c1, the clue added last is the wrong one.
+    let repeated = with_clues(&[(0, 2, 5)]);
+    assert!(!repeated.conflicts().is_empty());
+    assert_eq!(
+        vec![Repair { value: 5, ..repair }],
+        repeated.suggest_repairs()
+    );
+    // A search stopped at the limit cannot tell that the puzzle has no solution.
+    let limited = with_clues(&[(0, 2, 1)]).with_node_limit(NodeLimit::Max(0));
+    assert!(limited.suggest_repairs().is_empty());
+}
+
+#[test]
+fn either_clue_of_conflict() {
+    let s: Sudoku = format!("55{}", ".".repeat(79)).parse().unwrap();
+    let repairs = s.suggest_repairs();
+    assert_eq!(
+        vec![(0, 0, false), (0, 1, false)],
+        repairs
+            .iter()
+            .map(|r| (r.row, r.col, r.unique))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        "remove 5 from r1c2, which leaves several solutions",
+        repairs[1].to_string()
+    );
+}
+
+#[test]
+fn no_single_repair() {
+    assert!(with_clues(&[(0, 2, 1), (8, 0, 1)])
+        .suggest_repairs()
+        .is_empty());
+    assert!(with_clues(&[(0, 2, 5), (8, 0, 1)])
+        .suggest_repairs()
+        .is_empty());
+    // Puzzles with a solution need no repair.
+    assert!(solvable_field_helper().suggest_repairs().is_empty());
+    assert!(Sudoku::empty().suggest_repairs().is_empty());
+}
@@ -0,0 +1,615 @@
+use super::backtracking::{value_char, Sudoku, EMPTY};
+use super::constraint::{
+    AntiKnight, Constraint, Diagonals, NonConsecutive, Shared, Violation, Windows,
+};
+use super::geometry::Pos;
+use crate::prelude::*;
+use core::fmt;
+
+/// Constraints of a sudoku variant on top of the classic rows, columns and squares, see `Sudoku::with_rules`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Rules {
+    /// Both main diagonals hold every value once, as in X-Sudoku.
+    pub diagonals: bool,
+    /// Windows, the extra regions of Windoku (Hyper Sudoku), hold every value once, see `Geometry::windows`.
+    pub windows: bool,
+    /// Cells a chess knight's move apart hold different values.
+    pub anti_knight: bool,
+    /// Cells sharing a side do not hold consecutive values, which differ by 1.
+    pub non_consecutive: bool,
+}
+
+impl Rules {
+    /// Returns the constraints enforcing the rules.
+    pub fn constraints(&self) -> Vec<Shared<dyn Constraint>> {
+        let mut constraints: Vec<Shared<dyn Constraint>> = Vec::new();
+        if self.diagonals {
+            constraints.push(Shared::new(Diagonals));
+        }
+        if self.windows {
+            constraints.push(Shared::new(Windows));
+        }
+        if self.anti_knight {
+            constraints.push(Shared::new(AntiKnight));
+        }
+        if self.non_consecutive {
+            constraints.push(Shared::new(NonConsecutive));
+        }
+        constraints
+    }
+}
+
+/// Row, column, square or an extra region of a variant, numbered from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    Square(usize),
+    /// Diagonal 0 runs from the top-left corner, diagonal 1 from the top-right one.
+    Diagonal(usize),
+    Window(usize),
+    Cage(usize),
+    /// Two cells a chess knight's move apart, they are only constrained by the anti-knight rule.
+    KnightMove,
+}
+
+/// Prints the unit numbered from 1, e.g. `row 4`.
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unit::Row(i) => write!(f, "row {}", i + 1),
+            Unit::Column(i) => write!(f, "column {}", i + 1),
+            Unit::Square(i) => write!(f, "square {}", i + 1),
+            Unit::Diagonal(0) => write!(f, "main diagonal"),
+            Unit::Diagonal(_) => write!(f, "anti-diagonal"),
+            Unit::Window(i) => write!(f, "window {}", i + 1),
+            Unit::Cage(i) => write!(f, "cage {}", i + 1),
+            Unit::KnightMove => write!(f, "a knight's move"),
+        }
+    }
+}
+
+/// Value repeated within a unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub unit: Unit,
+    pub value: u8,
+    /// (row, col) of every cell with the value.
+    pub cells: Vec<(usize, usize)>,
+}
+
+/// Prints the conflict with 1-based coordinates, e.g. `5 is repeated in row 1: r1c1, r1c3` or
+/// `5 is repeated a knight's move apart: r1c1, r2c3`.
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cells: Vec<String> = self
+            .cells
+            .iter()
+            .map(|&(row, col)| cell_name(row, col))
+            .collect();
+        match self.unit {
+            Unit::KnightMove => write!(f, "{} is repeated {} apart", self.value, self.unit),
+            unit => write!(f, "{} is repeated in {}", self.value, unit),
+        }?;
+        write!(f, ": {}", cells.join(", "))
+    }
+}
+
+/// Cage of a killer sudoku whose filled cells already sum to more than its sum, or whose complete cells miss it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrongSum {
+    /// Index of the cage, numbered from 0.
+    pub cage: usize,
+    /// Sum of the filled cells of the cage.
+    pub sum: usize,
+    pub expected: u8,
+}
+
+/// Prints the wrong sum with the 1-based cage, e.g. `cage 3 sums to 17 instead of 15`.
+impl fmt::Display for WrongSum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "cage {} sums to {} instead of {}",
+            self.cage + 1,
+            self.sum,
+            self.expected
+        )
+    }
+}
+
+/// Two cells sharing a side which hold consecutive values, forbidden by the non-consecutive rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsecutiveNeighbours {
+    /// (row, col) of both cells, row by row.
+    pub cells: [(usize, usize); 2],
+    /// Values of the cells in the same order.
+    pub values: [u8; 2],
+}
+
+/// Prints the pair with 1-based coordinates, e.g. `4 and 5 are neighbours: r1c1, r1c2`.
+impl fmt::Display for ConsecutiveNeighbours {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [(row, col), (other_row, other_col)] = self.cells;
+        write!(
+            f,
+            "{} and {} are neighbours: {}, {}",
+            self.values[0],
+            self.values[1],
+            cell_name(row, col),
+            cell_name(other_row, other_col)
+        )
+    }
+}
+
+/// Cell holding different values in two grids, see `Sudoku::diff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellDiff {
+    pub cell: Pos,
+    /// Value of the cell in the sudoku compared, `EMPTY` for an empty cell.
+    pub value: u8,
+    /// Value of the cell in the other sudoku.
+    pub other: u8,
+}
+
+/// Prints the difference with the 1-based cell and `.` for empty cells, e.g. `r1c3: 4 vs .`.
+impl fmt::Display for CellDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} vs {}",
+            self.cell,
+            value_char(self.value),
+            value_char(self.other)
+        )
+    }
+}
+
+/// Reason why two grids cannot be merged, see `Sudoku::merge`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeConflict {
+    /// Cells with different values in the two grids where at least one of them is a given: the grids are of
+    /// different puzzles. Reported before any other disagreement.
+    Givens(Vec<CellDiff>),
+    /// Cells filled with different values in the two grids.
+    Values(Vec<CellDiff>),
+    /// No cell disagrees, but the merged grid breaks the rules.
+    Rules {
+        conflicts: Vec<Conflict>,
+        violations: Vec<Violation>,
+        wrong_sums: Vec<WrongSum>,
+    },
+}
+
+/// Lists every disagreeing cell or every broken rule, e.g. `cells differ: r1c3: 4 vs 2`.
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let problems: Vec<String> = match self {
+            MergeConflict::Givens(diff) => {
+                write!(f, "givens differ: ")?;
+                diff.iter().map(|d| d.to_string()).collect()
+            }
+            MergeConflict::Values(diff) => {
+                write!(f, "cells differ: ")?;
+                diff.iter().map(|d| d.to_string()).collect()
+            }
+            MergeConflict::Rules {
+                conflicts,
+                violations,
+                wrong_sums,
+            } => {
+                write!(f, "the merged grid breaks the rules: ")?;
+                conflicts
+                    .iter()
+                    .map(|c| c.to_string())
+                    .chain(violations.iter().map(|v| v.to_string()))
+                    .chain(wrong_sums.iter().map(|w| w.to_string()))
+                    .collect()
+            }
+        };
+        write!(f, "{}", problems.join("; "))
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MergeConflict {}
+
+/// Returns every value repeated within the cells of the unit.
+pub(crate) fn repeated_values(s: &Sudoku, unit: Unit, cells: &[usize]) -> Vec<Conflict> {
+    s.values()
+        .filter_map(|value| {
+            let cells: Vec<(usize, usize)> = cells
+                .iter()
+                .filter(|&&pos| s.field[pos] == value)
+                .map(|&pos| s.geometry().cell(pos))
+                .collect();
+            match cells.len() > 1 {
+                true => Some(Conflict { unit, value, cells }),
+                false => None,
+            }
+        })
+        .collect()
+}
+
+/// Returns the 1-based name of the cell, e.g. `r1c3`, see `Pos`.
+pub fn cell_name(row: usize, col: usize) -> String {
+    Pos::new(row, col).to_string()
+}
+
+impl Sudoku {
+    /// Returns every row, column and square and the extra regions of the constraints with positions of their cells.
+    pub(crate) fn units(&self) -> Vec<(Unit, Vec<usize>)> {
+        let mut units = self.classic_units();
+        for constraint in self.constraints() {
+            units.extend(constraint.regions(self));
+        }
+        units
+    }
+
+    /// Returns every row, column and square with positions of their cells.
+    pub(crate) fn classic_units(&self) -> Vec<(Unit, Vec<usize>)> {
+        let geometry = self.geometry();
+        let side = geometry.side();
+        let mut units = Vec::with_capacity(3 * side);
+        for i in 0..side {
+            let row = (0..side).map(|j| geometry.pos(i, j)).collect();
+            let col = (0..side).map(|j| geometry.pos(j, i)).collect();
+            units.push((Unit::Row(i), row));
+            units.push((Unit::Column(i), col));
+            units.push((Unit::Square(i), self.regions().cells(i).to_vec()));
+        }
+        units
+    }
+
+    /// Returns every value repeated within a row, column, square or cage.
+    ///
+    /// Values breaking the constraints of a variant are reported by `violations`.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let cages = self
+            .cages()
+            .iter()
+            .enumerate()
+            .map(|(i, cage)| (Unit::Cage(i), cage.cells.clone()));
+        self.classic_units()
+            .into_iter()
+            .chain(cages)
+            .flat_map(|(unit, cells)| repeated_values(self, unit, &cells))
+            .collect()
+    }
+
+    /// Returns the violation of every constraint the sudoku breaks, see `Constraint::check_complete`, followed by the
+    /// values which break the parity marks of an even/odd sudoku.
+    pub fn violations(&self) -> Vec<Violation> {
+        self.constraints()
+            .iter()
+            .filter_map(|constraint| constraint.check_complete(self).err())
+            .chain(self.parity_violation())
+            .collect()
+    }
+
+    /// Returns every cage of a killer sudoku whose filled cells exceed its sum or, once all of them are filled, do not
+    /// reach it.
+    pub fn wrong_sums(&self) -> Vec<WrongSum> {
+        self.cages()
+            .iter()
+            .enumerate()
+            .filter_map(|(cage, c)| {
+                let values: Vec<u8> = c.cells.iter().map(|&pos| self.field[pos]).collect();
+                let sum = values.iter().map(|&val| val as usize).sum();
+                let complete = values.iter().all(|&val| val != EMPTY);
+                match sum > c.sum as usize || (complete && sum != c.sum as usize) {
+                    true => Some(WrongSum {
+                        cage,
+                        sum,
+                        expected: c.sum,
+                    }),
+                    false => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns (row, col) of every clue of `original` which has a different value in this sudoku.
+    pub fn altered_clues(&self, original: &Sudoku) -> Vec<(usize, usize)> {
+        (0..self.field.len())
+            .filter(|&pos| original.field[pos] != EMPTY && original.field[pos] != self.field[pos])
+            .map(|pos| self.geometry().cell(pos))
+            .collect()
+    }
+
+    /// Returns every cell whose value, or emptiness, differs in the other sudoku, row by row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the grids are of different sizes.
+    pub fn diff(&self, other: &Sudoku) -> Vec<CellDiff> {
+        assert_eq!(
+            self.side(),
+            other.side(),
+            "cannot compare grids of different sizes"
+        );
+        (0..self.field.len())
+            .filter(|&pos| self.field[pos] != other.field[pos])
+            .map(|pos| CellDiff {
+                cell: Pos::of(self.geometry(), pos),
+                value: self.field[pos],
+                other: other.field[pos],
+            })
+            .collect()
+    }
+
+    /// Returns the grid with the values filled in either sudoku, givens of either stay givens.
+    ///
+    /// Fails with every given which the other grid fills differently, then with every other cell filled differently
+    /// in the two grids or, if there is none, with the rules the merged grid breaks. The rules, regions and cages are
+    /// the ones of this sudoku.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the grids are of different sizes.
+    pub fn merge(&self, other: &Sudoku) -> Result<Sudoku, MergeConflict> {
+        let (givens, disagreeing): (Vec<CellDiff>, Vec<CellDiff>) = self
+            .diff(other)
+            .into_iter()
+            .filter(|d| d.value != EMPTY && d.other != EMPTY)
+            .partition(|d| {
+                let pos = d.cell.index(self.geometry());
+                self.givens[pos] || other.givens[pos]
+            });
+        if !givens.is_empty() {
+            return Err(MergeConflict::Givens(givens));
+        }
+        if !disagreeing.is_empty() {
+            return Err(MergeConflict::Values(disagreeing));
+        }
+        let field = self
+            .field
+            .iter()
+            .zip(other.field.iter())
+            .map(|(&val, &other)| if val == EMPTY { other } else { val })
+            .collect();
+        let mut merged = self.with_field(field);
+        merged.givens = self
+            .givens
+            .iter()
+            .zip(other.givens.iter())
+            .map(|(&given, &other)| given || other)
+            .collect();
+        match merged.is_consistent() {
+            true => Ok(merged),
+            false => Err(MergeConflict::Rules {
+                conflicts: merged.conflicts(),
+                violations: merged.violations(),
+                wrong_sums: merged.wrong_sums(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+use super::backtracking::solvable_field_helper;
+
+#[test]
+fn units_cover_every_cell_three_times() {
+    let s = Sudoku::empty();
+    let mut seen = vec![0; s.as_slice().len()];
+    for (_, cells) in s.units() {
+        for pos in cells.iter() {
+            seen[*pos] += 1;
+        }
+    }
+    assert!(seen.iter().all(|&count| count == 3));
+}
+
+#[test]
+fn no_conflicts_in_solution() {
+    let mut s = solvable_field_helper();
+    assert!(s.conflicts().is_empty());
+    s.solve().unwrap();
+    assert!(s.conflicts().is_empty());
+}
+
+#[test]
+fn conflicts_of_swapped_pair() {
+    let mut s = solvable_field_helper();
+    s.solve().unwrap();
+    // The first row starts with 5 3, swapping them breaks the first two columns.
+    let mut field = s.field;
+    field.swap(0, 1);
+    let conflicts = Sudoku::from_field(field).conflicts();
+    assert_eq!(2, conflicts.len());
+    assert_eq!(
+        Conflict {
+            unit: Unit::Column(0),
+            value: 3,
+            cells: vec![(0, 0), (8, 0)],
+        },
+        conflicts[0]
+    );
+    assert_eq!(
+        "5 is repeated in column 2: r1c2, r4c2",
+        conflicts[1].to_string()
+    );
+}
+
+#[test]
+fn altered_clues() {
+    let original = solvable_field_helper();
+    let mut solved = original.clone();
+    solved.solve().unwrap();
+    assert!(solved.altered_clues(&original).is_empty());
+    let mut field = solved.field;
+    field[0] = 1;
+    assert_eq!(
+        vec![(0, 0)],
+        Sudoku::from_field(field).altered_clues(&original)
+    );
+}
+
+#[test]
+fn diff_of_grids() {
+    let original = solvable_field_helper();
+    assert!(original.diff(&original.clone()).is_empty());
+    let mut solved = original.clone();
+    solved.solve().unwrap();
+    let mut changed = original.clone();
+    changed.field[2] = 4;
+    changed.field[80] = EMPTY;
+    let diff = original.diff(&changed);
+    assert_eq!(
+        vec![
+            CellDiff {
+                cell: Pos::new(0, 2),
+                value: EMPTY,
+                other: 4
+            },
+            CellDiff {
+                cell: Pos::new(8, 8),
+                value: 9,
+                other: EMPTY
+            }
+        ],
+        diff
+    );
+    assert_eq!("r1c3: . vs 4", diff[0].to_string());
+    assert_eq!(Pos::new(8, 8), diff[1].cell);
+    assert_eq!(51, original.diff(&solved).len());
+}
+
+#[test]
+#[should_panic]
+fn diff_of_different_sizes() {
+    Sudoku::empty().diff(&Sudoku::with_box_size(2).unwrap());
+}
+
+#[test]
+fn merge_partial_grids() {
+    let puzzle = solvable_field_helper();
+    let mut first = puzzle.clone();
+    first.set(0, 2, 4).unwrap();
+    let mut second = puzzle.clone();
+    second.set(0, 3, 6).unwrap();
+    second.set(0, 2, 4).unwrap();
+    let merged = first.merge(&second).unwrap();
+    assert_eq!(Some(4), merged.value(0, 2));
+    assert_eq!(Some(6), merged.value(0, 3));
+    assert_eq!(32, merged.filled_count());
+    assert_eq!(30, merged.clue_count());
+    assert_eq!(merged, second.merge(&first).unwrap());
+}
+
+#[test]
+fn merge_conflicting_values() {
+    let puzzle = solvable_field_helper();
+    let mut first = puzzle.clone();
+    first.set(0, 2, 4).unwrap();
+    first.set(0, 3, 6).unwrap();
+    let mut second = puzzle.clone();
+    second.set(0, 2, 1).unwrap();
+    second.set(0, 3, 2).unwrap();
+    match first.merge(&second) {
+        Err(MergeConflict::Values(diff)) => {
+            assert_eq!(
+                vec![(0, 2, 4, 1), (0, 3, 6, 2)],
+                diff.iter()
+                    .map(|d| (d.cell.row, d.cell.col, d.value, d.other))
+                    .collect::<Vec<_>>()
+            )
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn merge_conflicting_givens() {
+    let puzzle = solvable_field_helper();
+    let mut first = puzzle.clone();
+    first.set(0, 3, 6).unwrap();
+    // The other grid has a different clue in r1c1 and a different value in r1c4.
+    let mut line = puzzle.to_line();
+    line.replace_range(0..1, "1");
+    let mut second: Sudoku = line.parse().unwrap();
+    second.set(0, 3, 2).unwrap();
+    let err = first.merge(&second).unwrap_err();
+    assert_eq!(
+        MergeConflict::Givens(vec![CellDiff {
+            cell: Pos::new(0, 0),
+            value: 5,
+            other: 1
+        }]),
+        err
+    );
+    assert_eq!("givens differ: r1c1: 5 vs 1", err.to_string());
+    // A given against a value placed in the other grid is a conflict of givens too.
+    let mut unlocked = puzzle.clone();
+    unlocked.unlock(0, 0);
+    unlocked.set(0, 0, 2).unwrap();
+    assert!(matches!(
+        puzzle.merge(&unlocked),
+        Err(MergeConflict::Givens(_))
+    ));
+}
+
+#[test]
+fn merge_breaking_the_rules() {
+    let puzzle = solvable_field_helper();
+    let mut first = puzzle.clone();
+    first.set(0, 2, 2).unwrap();
+    let mut second = puzzle;
+    second.set(0, 3, 2).unwrap();
+    let err = first.merge(&second).unwrap_err();
+    match &err {
+        MergeConflict::Rules { conflicts, .. } => {
+            assert_eq!(vec![(0, 2), (0, 3)], conflicts[0].cells)
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+    assert_eq!(
+        "the merged grid breaks the rules: 2 is repeated in row 1: r1c3, r1c4",
+        err.to_string()
+    );
+}
+
+#[test]
+fn units_of_6x6() {
+    let s = Sudoku::with_box_shape(2, 3).unwrap();
+    let units = s.units();
+    assert_eq!(18, units.len());
+    assert_eq!((Unit::Square(1), vec![3, 4, 5, 9, 10, 11]), units[5]);
+    assert_eq!((Unit::Square(2), vec![12, 13, 14, 18, 19, 20]), units[8]);
+}
+
+#[test]
+fn conflicts_and_wrong_sums_of_cages() {
+    use super::cages::Cage;
+
+    let mut field = vec![EMPTY; 81];
+    field[0] = 4;
+    field[10] = 4;
+    field[30] = 9;
+    let cages = vec![
+        Cage {
+            cells: vec![0, 10],
+            sum: 9,
+        },
+        Cage {
+            cells: vec![30, 31],
+            sum: 8,
+        },
+    ];
+    let s = Sudoku::from_field(field).with_cages(cages).unwrap();
+    let conflicts = s.conflicts();
+    assert_eq!(2, conflicts.len());
+    assert_eq!(
+        "4 is repeated in cage 1: r1c1, r2c2",
+        conflicts[1].to_string()
+    );
+    let wrong = s.wrong_sums();
+    assert_eq!(
+        vec![
+            "cage 1 sums to 8 instead of 9",
+            "cage 2 sums to 9 instead of 8"
+        ],
+        wrong.iter().map(|w| w.to_string()).collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,192 @@
+//! Checkpoints of the backtracking search, so a long solve can be stopped and continued later, even by another process.
+//!
+//! The state holds the grid and the place of the search only: the rules, regions, cages and constraints come from the
+//! puzzle it is applied to with `Sudoku::with_state`.
+use super::backtracking::{LogEntry, Sudoku, SudokuError, EMPTY};
+use super::geometry::Geometry;
+use crate::prelude::*;
+
+/// Saved state of a stopped search, see `Sudoku::save_state`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolverState {
+    box_rows: usize,
+    box_cols: usize,
+    field: Vec<u8>,
+    givens: Vec<bool>,
+    /// Values placed by the search, in order.
+    log: Vec<LogEntry>,
+    /// Cell the search continues with and the value it tries after, plus one, None when the search hasn't started.
+    /// In ascending order this is the smallest value it tries there.
+    cursor: Option<(usize, u8)>,
+}
+
+impl Sudoku {
+    /// Returns the state of the search, e.g. once `solve_resumable` stopped, to continue it later with `with_state`
+    /// or `Sudoku::resume`.
+    pub fn save_state(&self) -> SolverState {
+        SolverState {
+            box_rows: self.geometry().box_rows(),
+            box_cols: self.geometry().box_cols(),
+            field: self.field.clone(),
+            givens: self.givens.clone(),
+            log: self.backtrack_log.clone(),
+            cursor: self.cursor,
+        }
+    }
+
+    /// Returns the classic sudoku the state was saved from, with its search ready to continue.
+    ///
+    /// Variants lose their rules on the way, apply the state to the puzzle with `with_state` to keep them.
+    pub fn resume(state: SolverState) -> Result<Sudoku, SudokuError> {
+        let geometry = Geometry::new(state.box_rows, state.box_cols).ok_or_else(|| {
+            invalid(format!(
+                "boxes of {}x{} cells",
+                state.box_rows, state.box_cols
+            ))
+        })?;
+        if state.field.len() != geometry.size() || state.givens.len() != geometry.size() {
+            return Err(invalid(format!(
+                "expected {} cells, found {}",
+                geometry.size(),
+                state.field.len()
+            )));
+        }
+        let clues = state
+            .field
+            .iter()
+            .zip(state.givens.iter())
+            .map(|(&value, &given)| if given { value } else { EMPTY })
+            .collect();
+        Sudoku::from_geometry(geometry, clues).with_state(state)
+    }
+
+    /// Returns the sudoku with the search continuing from the state saved from this puzzle.
+    ///
+    /// The state must have the same givens, and every other filled cell must have been filled by the search.
+    pub fn with_state(mut self, state: SolverState) -> Result<Sudoku, SudokuError> {
+        if (state.box_rows, state.box_cols)
+            != (self.geometry().box_rows(), self.geometry().box_cols())
+        {
+            return Err(invalid(format!(
+                "boxes of {}x{} cells, expected {}x{}",
+                state.box_rows,
+                state.box_cols,
+                self.geometry().box_rows(),
+                self.geometry().box_cols()
+            )));
+        }
+        if state.field.len() != self.field.len() || state.givens != self.givens {
+            return Err(invalid("the givens do not match the puzzle".to_string()));
+        }
+        let placed = (0..self.field.len())
+            .filter(|&pos| !self.givens[pos] && state.field[pos] != EMPTY)
+            .count();
+        let mut field = self.field.clone();
+        for entry in state.log.iter() {
+            match field.get(entry.pos) {
+                Some(&EMPTY) if entry.val != EMPTY && state.field[entry.pos] == entry.val => {
+                    field[entry.pos] = entry.val
+                }
+                _ => {
+                    return Err(invalid(format!(
+                        "{} cannot be placed in {}",
+                        entry.val,
+                        self.cell_pos(entry.pos)
+                    )))
+                }
+            }
+        }
+        if field != state.field || placed != state.log.len() {
+            return Err(invalid(
+                "the grid does not match the placed values".to_string(),
+            ));
+        }
+        if let Some((pos, start)) = state.cursor {
+            if field.get(pos) != Some(&EMPTY) || start == 0 || start as usize > self.side() + 1 {
+                return Err(invalid(format!(
+                    "cannot continue in {}",
+                    self.cell_pos(pos)
+                )));
+            }
+        }
+        self.field = field;
+        self.backtrack_log = state.log;
+        self.cursor = state.cursor;
+        match self.is_consistent() {
+            true => Ok(self),
+            false => Err(invalid("the placed values break the rules".to_string())),
+        }
+    }
+}
+
+fn invalid(msg: String) -> SudokuError {
+    SudokuError::InvalidState(msg)
+}
+
+#[cfg(test)]
+use super::backtracking::{solvable_field_helper, SolveStatus};
+
+#[test]
+fn resume_stopped_search() {
+    let mut expected = solvable_field_helper();
+    let mut placements = 0;
+    expected
+        .solve_resumable(|stats| {
+            placements = stats.placements;
+            false
+        })
+        .unwrap();
+    let mut s = solvable_field_helper();
+    let mut resumed_placements = 0;
+    let mut stops = 0;
+    loop {
+        let mut last = 0;
+        let status = s
+            .solve_resumable(|stats| {
+                last = stats.placements;
+                stats.placements >= 20
+            })
+            .unwrap();
+        resumed_placements += last;
+        if status == SolveStatus::Solved {
+            break;
+        }
+        stops += 1;
+        let state = s.save_state();
+        s = Sudoku::resume(state.clone()).unwrap();
+        assert_eq!(state, s.save_state());
+    }
+    assert!(stops > 1);
+    assert_eq!(expected, s);
+    // Every step is done once, however often the search stops.
+    assert_eq!(placements, resumed_placements);
+}
+
+#[test]
+fn state_of_another_puzzle() {
+    let mut s = solvable_field_helper();
+    s.solve_resumable(|stats| stats.placements >= 5).unwrap();
+    let state = s.save_state();
+    assert!(solvable_field_helper().with_state(state.clone()).is_ok());
+    match Sudoku::empty().with_state(state.clone()) {
+        Err(SudokuError::InvalidState(msg)) => {
+            assert_eq!("the givens do not match the puzzle", msg)
+        }
+        res => panic!("Unexpected result {:?}", res),
+    }
+    let mut tampered = state.clone();
+    tampered.log.pop();
+    assert!(Sudoku::resume(tampered).is_err());
+    let mut tampered = state.clone();
+    tampered.cursor = Some((0, 1));
+    assert!(Sudoku::resume(tampered).is_err());
+    // Values are tried from 1, a cursor before it would underflow in the value orders other than ascending.
+    let empty = state.field.iter().position(|&val| val == EMPTY).unwrap();
+    let mut tampered = state;
+    tampered.cursor = Some((empty, 0));
+    match Sudoku::resume(tampered) {
+        Err(SudokuError::InvalidState(msg)) => assert!(msg.starts_with("cannot continue in ")),
+        res => panic!("Unexpected result {:?}", res),
+    }
+}
@@ -0,0 +1,201 @@
+//! Counts of the digits and of the filled cells of every unit, e.g. for a dashboard of the progress.
+use super::backtracking::{Sudoku, EMPTY};
+use super::rules::Unit;
+use crate::prelude::*;
+use core::fmt;
+
+/// Numbers of filled cells in every row, column and square, see `Sudoku::unit_fill`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitFill {
+    /// Number of cells of every unit.
+    pub side: usize,
+    pub rows: Vec<usize>,
+    pub columns: Vec<usize>,
+    pub squares: Vec<usize>,
+}
+
+impl UnitFill {
+    /// Returns every row, then every column and square with the number of its filled cells.
+    pub fn units(&self) -> impl Iterator<Item = (Unit, usize)> + '_ {
+        let rows = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| (Unit::Row(i), n));
+        let columns = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| (Unit::Column(i), n));
+        let squares = self
+            .squares
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| (Unit::Square(i), n));
+        rows.chain(columns).chain(squares)
+    }
+
+    /// Returns the units without an empty cell.
+    pub fn complete(&self) -> Vec<Unit> {
+        self.units()
+            .filter(|&(_, n)| n == self.side)
+            .map(|(unit, _)| unit)
+            .collect()
+    }
+}
+
+/// Prints the complete units and the least filled one, like `row 7 is complete, column 3 has 1/9`.
+impl fmt::Display for UnitFill {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let least = self
+            .units()
+            .filter(|&(_, n)| n < self.side)
+            .min_by_key(|&(_, n)| n);
+        let (unit, n) = match least {
+            Some(least) => least,
+            None => return write!(f, "every unit is complete"),
+        };
+        let mut parts: Vec<String> = self
+            .complete()
+            .iter()
+            .map(|unit| format!("{} is complete", unit))
+            .collect();
+        if parts.is_empty() {
+            parts.push("no unit is complete".to_string());
+        }
+        parts.push(format!("{} has {}/{}", unit, n, self.side));
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+impl Sudoku {
+    /// Returns the number of empty cells at index 0 and the number of cells holding every digit from 1 to 9.
+    ///
+    /// Larger values, like those of a 16x16 sudoku, are counted by `digit_overflow` instead.
+    pub fn digit_histogram(&self) -> [usize; 10] {
+        let mut counts = [0; 10];
+        for &val in self.field.iter().filter(|&&val| val <= 9) {
+            counts[val as usize] += 1;
+        }
+        counts
+    }
+
+    /// Returns the number of cells holding a value above 9, which `digit_histogram` leaves out.
+    pub fn digit_overflow(&self) -> usize {
+        self.field.iter().filter(|&&val| val > 9).count()
+    }
+
+    /// Returns the number of filled cells in every row, column and square.
+    pub fn unit_fill(&self) -> UnitFill {
+        let mut fill = UnitFill {
+            side: self.side(),
+            rows: Vec::with_capacity(self.side()),
+            columns: Vec::with_capacity(self.side()),
+            squares: Vec::with_capacity(self.side()),
+        };
+        for (unit, cells) in self.classic_units() {
+            let filled = cells
+                .iter()
+                .filter(|&&pos| self.field[pos] != EMPTY)
+                .count();
+            match unit {
+                Unit::Row(_) => fill.rows.push(filled),
+                Unit::Column(_) => fill.columns.push(filled),
+                _ => fill.squares.push(filled),
+            }
+        }
+        fill
+    }
+
+    /// Returns log10 of the product of the numbers of candidates of the empty cells, 0 for a solved grid.
+    ///
+    /// This is an upper bound of the grids the search may still try, and it can be wildly pessimistic: it ignores that
+    /// every placed value takes candidates away from other cells. An empty cell without candidates makes it negative
+    /// infinity.
+    ///
+    /// The candidates come from a mask per row, column and region built in a single pass, so the estimate costs a pass
+    /// over the 81 cells of the classic grid; cages, parity marks and constraints add a check per candidate left.
+    #[cfg(feature = "std")]
+    pub fn search_space_estimate(&self) -> f64 {
+        self.candidate_sets()
+            .into_iter()
+            .zip(self.field.iter())
+            .filter(|&(_, &val)| val == EMPTY)
+            .map(|(candidates, _)| (candidates.len() as f64).log10())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+use super::backtracking::solvable_field_helper;
+
+#[test]
+fn digits_of_puzzle() {
+    let mut s = solvable_field_helper();
+    assert_eq!([51, 3, 2, 3, 2, 3, 5, 3, 5, 4], s.digit_histogram());
+    assert_eq!(0, s.digit_overflow());
+    s.solve().unwrap();
+    assert_eq!([0, 9, 9, 9, 9, 9, 9, 9, 9, 9], s.digit_histogram());
+    assert_eq!(
+        [81, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        Sudoku::empty().digit_histogram()
+    );
+}
+
+#[test]
+fn digits_above_nine() {
+    let big = Sudoku::with_box_size(4).unwrap();
+    let mut field = vec![0; 256];
+    field[0] = 9;
+    field[1] = 10;
+    field[2] = 16;
+    let s = big.with_field(field);
+    let histogram = s.digit_histogram();
+    assert_eq!((253, 1), (histogram[0], histogram[9]));
+    assert_eq!(254, histogram.iter().sum::<usize>());
+    assert_eq!(2, s.digit_overflow());
+}
+
+#[test]
+fn fill_of_units() {
+    let mut s = solvable_field_helper();
+    let fill = s.unit_fill();
+    assert_eq!(vec![3, 4, 3, 3, 4, 3, 3, 4, 3], fill.rows);
+    assert_eq!(vec![5, 3, 1, 3, 6, 3, 1, 3, 5], fill.columns);
+    assert_eq!(vec![5, 4, 1, 3, 4, 3, 1, 4, 5], fill.squares);
+    assert!(fill.complete().is_empty());
+    assert_eq!("no unit is complete, column 3 has 1/9", fill.to_string());
+    // Completing row 7 fills column 3 too, column 7 is the least filled now.
+    for (col, value) in [(0, 9), (2, 1), (3, 5), (4, 3), (5, 7), (8, 4)].iter() {
+        s.set(6, *col, *value).unwrap();
+    }
+    let fill = s.unit_fill();
+    assert_eq!(vec![Unit::Row(6)], fill.complete());
+    assert_eq!("row 7 is complete, column 7 has 1/9", fill.to_string());
+    s.solve().unwrap();
+    assert_eq!(27, s.unit_fill().complete().len());
+    assert_eq!("every unit is complete", s.unit_fill().to_string());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn search_space_of_grids() {
+    let mut s = solvable_field_helper();
+    let puzzle = s.search_space_estimate();
+    let each: Vec<_> = (0..81).map(|pos| s.candidate_set(pos)).collect();
+    assert_eq!(each, s.candidate_sets());
+    assert!(puzzle > 0.0 && puzzle < Sudoku::empty().search_space_estimate());
+    assert!((81.0 * 9f64.log10() - Sudoku::empty().search_space_estimate()).abs() < 1e-9);
+    s.solve().unwrap();
+    assert_eq!(0.0, s.search_space_estimate());
+    // The first row holds 1 to 6 around the only empty cell, which leaves it 7, 8 and 9.
+    let field = (0..81)
+        .map(|pos| match pos {
+            0 => EMPTY,
+            _ => (pos % 6) as u8 + 1,
+        })
+        .collect();
+    let one_empty = Sudoku::empty().with_field(field);
+    assert_eq!(vec![7, 8, 9], one_empty.candidates(0, 0));
+    assert_eq!(3f64.log10(), one_empty.search_space_estimate());
+}
@@ -0,0 +1,178 @@
+//! Stress test of the solvers on random puzzles, which catches disagreements that hand-written fixtures never hit.
+use super::backtracking::{Sudoku, EMPTY};
+use super::classify::{PuzzleClass, CLASSIFY_CAP};
+use super::diagnosis::Diagnosis;
+use super::limit::NodeLimit;
+use super::value_order::SolveOptions;
+use crate::generator::SplitMix64;
+use crate::prelude::*;
+use core::fmt;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Placements each solver may make for a puzzle of `stress`, a few sparse puzzles would take the search minutes.
+pub const STRESS_NODES: u64 = 50_000;
+
+/// Puzzle of `stress` on which the solvers disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inconsistency {
+    /// 0-based iteration which generated the puzzle.
+    pub iteration: u32,
+    /// The puzzle in the line format.
+    pub puzzle: String,
+    /// What the solvers disagree on, e.g. `solve found no solution, count_solutions found 2`.
+    pub reason: String,
+}
+
+/// Prints the iteration, the reason and the puzzle, e.g.
+/// `iteration 3: solve found no solution, count_solutions found 2: 5.3..7....`.
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "iteration {}: {}: {}",
+            self.iteration, self.reason, self.puzzle
+        )
+    }
+}
+
+/// Outcome of `stress`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StressReport {
+    pub iterations: u32,
+    /// Puzzles with a unique solution.
+    pub unique: u32,
+    /// Puzzles left unchecked because a solver ran out of `STRESS_NODES` placements.
+    pub skipped: u32,
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+impl StressReport {
+    /// Returns `true` if the solvers agreed on every puzzle.
+    pub fn is_ok(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+}
+
+/// Checks that the solvers agree on `iterations` random puzzles generated from the seed, see `check_agreement`.
+///
+/// Each puzzle is a random solved grid with a random number of its cells cleared, from none to all of them, so most
+/// puzzles have several solutions. Counting stops at `CLASSIFY_CAP` solutions and every solver at `STRESS_NODES`
+/// placements, which bounds the time of a puzzle. The same seed gives the same puzzles on every platform.
+pub fn stress(iterations: u32, seed: u64) -> StressReport {
+    let mut rng = SplitMix64(seed);
+    let mut report = StressReport {
+        iterations,
+        ..StressReport::default()
+    };
+    for iteration in 0..iterations {
+        let puzzle = random_puzzle(&mut rng);
+        match check_agreement(&puzzle) {
+            Ok(Some(1)) => report.unique += 1,
+            Ok(Some(_)) => {}
+            Ok(None) => report.skipped += 1,
+            Err(reason) => report.inconsistencies.push(Inconsistency {
+                iteration,
+                puzzle: puzzle.to_line(),
+                reason,
+            }),
+        }
+    }
+    report
+}
+
+/// Returns a random solved grid with a random number of cells cleared, uniqueness is not kept.
+fn random_puzzle(rng: &mut impl Rng) -> Sudoku {
+    let mut full = Sudoku::empty().with_options(&SolveOptions::random(Some(rng.gen())));
+    full.solve().expect("an empty grid has a solution");
+    let mut field = full.field.clone();
+    let mut positions: Vec<usize> = (0..field.len()).collect();
+    positions.shuffle(rng);
+    let cleared = rng.gen_range(0..=field.len());
+    for &pos in positions[..cleared].iter() {
+        field[pos] = EMPTY;
+    }
+    Sudoku::empty().with_field(field)
+}
+
+/// Returns the number of solutions of the puzzle up to `CLASSIFY_CAP`, or what the solvers disagree on, None if a
+/// solver needs more than `STRESS_NODES` placements.
+///
+/// `solve` must succeed if and only if `count_solutions` finds a solution, and fill every cell without breaking the
+/// rules or changing a clue. `classify` must find as many solutions, and the one of `solve` for a unique puzzle.
+pub fn check_agreement(puzzle: &Sudoku) -> Result<Option<usize>, String> {
+    let count = match puzzle.count_solutions_within(CLASSIFY_CAP, STRESS_NODES) {
+        Some(count) => count,
+        None => return Ok(None),
+    };
+    let mut solution = puzzle.clone().with_node_limit(NodeLimit::Max(STRESS_NODES));
+    let solved = match solution.solve() {
+        Ok(()) => true,
+        Err(err) if matches!(err.diagnosis, Diagnosis::LimitReached { .. }) => return Ok(None),
+        Err(_) => false,
+    };
+    match (solved, count) {
+        (false, 0) => {}
+        (false, count) => {
+            return Err(format!(
+                "solve found no solution, count_solutions found {}",
+                count
+            ))
+        }
+        (true, 0) => return Err("solve found a solution, count_solutions found none".to_string()),
+        (true, _) if !solution.solved() || !solution.is_consistent() => {
+            return Err("the solution of solve has empty cells or breaks the rules".to_string())
+        }
+        (true, _) if !solution.altered_clues(puzzle).is_empty() => {
+            return Err("the solution of solve changes clues".to_string())
+        }
+        (true, _) => {}
+    }
+    let class = match puzzle.classify_within(CLASSIFY_CAP, STRESS_NODES) {
+        PuzzleClass::Unknown { .. } => return Ok(None),
+        class => class,
+    };
+    if class.found() != count {
+        return Err(format!(
+            "classify found {} solutions, count_solutions found {}",
+            class.found(),
+            count
+        ));
+    }
+    match class {
+        PuzzleClass::Unique(unique) if unique.field != solution.field => {
+            Err("classify and solve found different unique solutions".to_string())
+        }
+        _ => Ok(Some(count)),
+    }
+}
+
+#[test]
+fn solvers_agree_on_random_puzzles() {
+    let report = stress(20, 7);
+    assert!(report.is_ok(), "{:?}", report.inconsistencies);
+    assert_eq!(20, report.iterations);
+    assert!(report.unique + report.skipped < 20);
+    assert_eq!(stress(3, 11), stress(3, 11));
+}
+
+#[test]
+fn disagreements_of_solvers() {
+    let unsolvable: Sudoku = crate::examples::UNSOLVABLE.parse().unwrap();
+    assert_eq!(Ok(Some(0)), check_agreement(&unsolvable));
+    let hard: Sudoku = crate::examples::HARD.parse().unwrap();
+    assert_eq!(Ok(Some(1)), check_agreement(&hard));
+    assert_eq!(Ok(Some(CLASSIFY_CAP)), check_agreement(&Sudoku::empty()));
+    let inconsistency = Inconsistency {
+        iteration: 3,
+        puzzle: hard.to_line(),
+        reason: "solve found no solution, count_solutions found 1".to_string(),
+    };
+    assert_eq!(
+        format!(
+            "iteration 3: solve found no solution, count_solutions found 1: {}",
+            hard.to_line()
+        ),
+        inconsistency.to_string()
+    );
+}
@@ -0,0 +1,156 @@
+//! Symmetries of the pattern of clues, which curators of puzzles care about.
+use super::backtracking::Sudoku;
+use crate::prelude::*;
+use core::fmt;
+
+/// Symmetry of the positions of the givens, see `Sudoku::clue_symmetry`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SymmetryKind {
+    /// The pattern is the same when the grid is turned by 180 degrees around its center.
+    Rotational,
+    /// The pattern is the same with the order of columns reversed, see `Sudoku::flip_horizontal`.
+    Horizontal,
+    /// The pattern is the same with the order of rows reversed, see `Sudoku::flip_vertical`.
+    Vertical,
+    /// The pattern is mirrored over the main (top-left to bottom-right) diagonal, see `Sudoku::transpose`.
+    Diagonal,
+    /// The pattern is mirrored over the anti-diagonal (top-right to bottom-left).
+    AntiDiagonal,
+}
+
+impl SymmetryKind {
+    /// Every kind of symmetry, in the order `Sudoku::clue_symmetry` reports them.
+    pub const ALL: [SymmetryKind; 5] = [
+        SymmetryKind::Rotational,
+        SymmetryKind::Horizontal,
+        SymmetryKind::Vertical,
+        SymmetryKind::Diagonal,
+        SymmetryKind::AntiDiagonal,
+    ];
+
+    /// Returns the (row, col) of the cell which the cell at (row, col) is mapped to in a grid with the side.
+    fn image(self, row: usize, col: usize, side: usize) -> (usize, usize) {
+        let last = side - 1;
+        match self {
+            SymmetryKind::Rotational => (last - row, last - col),
+            SymmetryKind::Horizontal => (row, last - col),
+            SymmetryKind::Vertical => (last - row, col),
+            SymmetryKind::Diagonal => (col, row),
+            SymmetryKind::AntiDiagonal => (last - col, last - row),
+        }
+    }
+}
+
+/// Prints the symmetry like `180° rotational` or `horizontal mirror`.
+impl fmt::Display for SymmetryKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            SymmetryKind::Rotational => "180° rotational",
+            SymmetryKind::Horizontal => "horizontal mirror",
+            SymmetryKind::Vertical => "vertical mirror",
+            SymmetryKind::Diagonal => "diagonal mirror",
+            SymmetryKind::AntiDiagonal => "anti-diagonal mirror",
+        })
+    }
+}
+
+impl Sudoku {
+    /// Returns every symmetry of the positions of the givens, the values in the cells are not compared.
+    ///
+    /// Under the 180 degrees rotation of a grid with an odd side the center cell is its own image, so it never breaks
+    /// the symmetry. A sudoku without givens is symmetric in every way.
+    pub fn clue_symmetry(&self) -> Vec<SymmetryKind> {
+        let geometry = self.geometry();
+        let side = self.side();
+        SymmetryKind::ALL
+            .iter()
+            .copied()
+            .filter(|kind| {
+                (0..geometry.size())
+                    .filter(|&pos| self.givens[pos])
+                    .all(|pos| {
+                        let (row, col) = geometry.cell(pos);
+                        let (row, col) = kind.image(row, col, side);
+                        self.givens[geometry.pos(row, col)]
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Returns a 9x9 sudoku with givens in the cells, the values differ so that only the positions can match.
+#[cfg(test)]
+fn with_givens(cells: &[(usize, usize)]) -> Sudoku {
+    let empty = Sudoku::empty();
+    let mut field = vec![0; empty.side() * empty.side()];
+    for (i, &(row, col)) in cells.iter().enumerate() {
+        field[empty.geometry().pos(row, col)] = (i % 9) as u8 + 1;
+    }
+    empty.with_field(field)
+}
+
+#[test]
+fn rotational_pattern() {
+    let cells = [(0, 0), (8, 8), (0, 3), (8, 5), (2, 7), (6, 1)];
+    assert_eq!(
+        vec![SymmetryKind::Rotational],
+        with_givens(&cells).clue_symmetry()
+    );
+    // The center cell is mapped to itself.
+    let mut centered = cells.to_vec();
+    centered.push((4, 4));
+    assert_eq!(
+        vec![SymmetryKind::Rotational],
+        with_givens(&centered).clue_symmetry()
+    );
+    // A cell next to the center needs its partner on the other side.
+    centered.push((4, 3));
+    assert!(with_givens(&centered).clue_symmetry().is_empty());
+    centered.push((4, 5));
+    assert_eq!(
+        vec![SymmetryKind::Rotational],
+        with_givens(&centered).clue_symmetry()
+    );
+    let easy: Sudoku = crate::examples::EASY.parse().unwrap();
+    assert_eq!(vec![SymmetryKind::Rotational], easy.clue_symmetry());
+    // Grids with an even side have no center cell.
+    let small = Sudoku::with_box_size(2)
+        .unwrap()
+        .with_field(vec![1, 0, 0, 0, 0, 2, 0, 0, 0, 0, 3, 0, 0, 0, 0, 4]);
+    assert_eq!(
+        vec![
+            SymmetryKind::Rotational,
+            SymmetryKind::Diagonal,
+            SymmetryKind::AntiDiagonal
+        ],
+        small.clue_symmetry()
+    );
+}
+
+#[test]
+fn mirror_patterns() {
+    let mirrored = with_givens(&[(0, 1), (0, 7), (3, 4), (5, 2), (5, 6)]);
+    assert_eq!(vec![SymmetryKind::Horizontal], mirrored.clue_symmetry());
+    assert_eq!(
+        vec![SymmetryKind::Vertical],
+        mirrored.transpose().unwrap().clue_symmetry()
+    );
+    let diagonal = with_givens(&[(0, 1), (1, 0), (4, 4), (2, 8), (8, 2)]);
+    assert_eq!(vec![SymmetryKind::Diagonal], diagonal.clue_symmetry());
+    let anti = with_givens(&[(0, 0), (8, 8), (0, 1), (7, 8)]);
+    assert_eq!(vec![SymmetryKind::AntiDiagonal], anti.clue_symmetry());
+    assert_eq!("horizontal mirror", SymmetryKind::Horizontal.to_string());
+}
+
+#[test]
+fn asymmetric_pattern() {
+    let hard: Sudoku = crate::examples::HARD.parse().unwrap();
+    assert!(hard.clue_symmetry().is_empty());
+    assert!(with_givens(&[(0, 0), (0, 1)]).clue_symmetry().is_empty());
+    assert_eq!(SymmetryKind::ALL.to_vec(), Sudoku::empty().clue_symmetry());
+}
@@ -0,0 +1,263 @@
+//! Thermometers, whose values strictly increase from the bulb to the tip.
+//!
+//! Thermometers are read from text with a thermometer per line: its cells from the bulb on, named like `r1c2` with
+//! 1-based rows and columns and separated by spaces or commas. Consecutive cells touch by a side or a corner. Lines
+//! starting with `#` or `//` are comments:
+//!
+//! ```text
+//! # The bulb is in the top-left corner
+//! r1c1 r2c2 r3c3
+//! r9c1, r9c2, r8c3
+//! ```
+use super::backtracking::{Sudoku, SudokuError, EMPTY};
+use super::cages::parse_cell_name;
+use super::constraint::{Constraint, Violation};
+use super::geometry::Geometry;
+use crate::formats::is_ignored_line;
+use crate::prelude::*;
+
+/// Cells whose values strictly increase from the first one, the bulb, to the last one, the tip.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Thermometer {
+    /// Positions of the cells from the bulb on.
+    pub cells: Vec<usize>,
+}
+
+impl Thermometer {
+    /// Returns the smallest and the largest value the cell at the index of the thermometer may hold.
+    ///
+    /// Every step along the thermometer adds at least 1, so the filled cells before and after the index bound the
+    /// value by their distance to it, as does the range of values of the grid.
+    fn bounds(&self, grid: &Sudoku, at: usize) -> (usize, usize) {
+        let len = self.cells.len();
+        let mut low = at + 1;
+        let mut high = (grid.side() + at + 1).saturating_sub(len);
+        for (i, &pos) in self.cells.iter().enumerate() {
+            let value = grid.field[pos] as usize;
+            if value == EMPTY as usize || i == at {
+                continue;
+            }
+            match i < at {
+                true => low = low.max(value + (at - i)),
+                false => high = high.min(value.saturating_sub(i - at)),
+            }
+        }
+        (low, high)
+    }
+
+    /// Returns the (row, col) of the bulb.
+    fn bulb(&self, grid: &Sudoku) -> (usize, usize) {
+        grid.geometry().cell(self.cells[0])
+    }
+}
+
+impl Constraint for Thermometer {
+    fn allows(&self, grid: &Sudoku, pos: usize, value: u8) -> bool {
+        match self.cells.iter().position(|&cell| cell == pos) {
+            Some(at) => {
+                let (low, high) = self.bounds(grid, at);
+                (low..=high).contains(&(value as usize))
+            }
+            None => true,
+        }
+    }
+
+    fn check_complete(&self, grid: &Sudoku) -> Result<(), Violation> {
+        let filled: Vec<(usize, u8)> = self
+            .cells
+            .iter()
+            .map(|&pos| (pos, grid.field[pos]))
+            .filter(|&(_, value)| value != EMPTY)
+            .collect();
+        match filled.windows(2).find(|pair| pair[0].1 >= pair[1].1) {
+            Some(pair) => {
+                let (bulb_row, bulb_col) = self.bulb(grid);
+                let (lower, higher) = (
+                    grid.geometry().cell(pair[0].0),
+                    grid.geometry().cell(pair[1].0),
+                );
+                Err(Violation {
+                    msg: format!(
+                        "the thermometer from r{}c{} does not increase: {} is followed by {}",
+                        bulb_row + 1,
+                        bulb_col + 1,
+                        pair[0].1,
+                        pair[1].1
+                    ),
+                    cells: vec![lower, higher],
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn reason(&self, grid: &Sudoku, pos: usize, value: u8) -> String {
+        let (bulb_row, bulb_col) = self.bulb(grid);
+        let at = self
+            .cells
+            .iter()
+            .position(|&cell| cell == pos)
+            .unwrap_or_default();
+        match self.bounds(grid, at) {
+            (low, high) if low > high => format!(
+                "no value fits the thermometer from r{}c{}",
+                bulb_row + 1,
+                bulb_col + 1
+            ),
+            (low, high) => format!(
+                "{} is outside of {}-{} on the thermometer from r{}c{}",
+                value,
+                low,
+                high,
+                bulb_row + 1,
+                bulb_col + 1
+            ),
+        }
+    }
+
+    fn peers(&self, _grid: &Sudoku, pos: usize) -> Option<Vec<usize>> {
+        match self.cells.contains(&pos) {
+            true => Some(self.cells.clone()),
+            false => Some(Vec::new()),
+        }
+    }
+}
+
+/// Parses thermometers in the format described in the module documentation for a grid of the given geometry.
+///
+/// A thermometer needs at least 2 different cells, at most as many as the side of the grid, each touching the previous
+/// one.
+pub fn parse_thermometers(text: &str, geometry: Geometry) -> Result<Vec<Thermometer>, SudokuError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !is_ignored_line(line))
+        .map(|(num, line)| {
+            parse_thermometer(line, geometry)
+                .map_err(|msg| SudokuError::ParseLine { line: num + 1, msg })
+        })
+        .collect()
+}
+
+/// Parses a single line with the cells of a thermometer.
+fn parse_thermometer(line: &str, geometry: Geometry) -> Result<Thermometer, String> {
+    let cells = line
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty())
+        .map(|cell| {
+            parse_cell_name(cell, geometry).ok_or_else(|| format!("invalid cell `{}`", cell))
+        })
+        .collect::<Result<Vec<usize>, String>>()?;
+    if !(2..=geometry.side()).contains(&cells.len()) {
+        return Err(format!(
+            "a thermometer has 2 to {} cells, found {}",
+            geometry.side(),
+            cells.len()
+        ));
+    }
+    let name = |pos: usize| {
+        let (row, col) = geometry.cell(pos);
+        format!("r{}c{}", row + 1, col + 1)
+    };
+    for (i, &pos) in cells.iter().enumerate().skip(1) {
+        if cells[..i].contains(&pos) {
+            return Err(format!("{} is repeated", name(pos)));
+        }
+        let ((row, col), (prev_row, prev_col)) = (geometry.cell(pos), geometry.cell(cells[i - 1]));
+        if row.abs_diff(prev_row) > 1 || col.abs_diff(prev_col) > 1 {
+            return Err(format!(
+                "{} does not touch {}",
+                name(pos),
+                name(cells[i - 1])
+            ));
+        }
+    }
+    Ok(Thermometer { cells })
+}
+
+#[cfg(test)]
+fn thermometer(cells: &[(usize, usize)]) -> Thermometer {
+    Thermometer {
+        cells: cells
+            .iter()
+            .map(|&(row, col)| Geometry::CLASSIC.pos(row, col))
+            .collect(),
+    }
+}
+
+#[test]
+fn parse_thermometers_in_both_styles() {
+    let thermometers = parse_thermometers(
+        "# comment\nr1c1 r2c2 R3C3\n\nr9c1, r9c2\n",
+        Geometry::CLASSIC,
+    )
+    .unwrap();
+    assert_eq!(
+        vec![
+            thermometer(&[(0, 0), (1, 1), (2, 2)]),
+            thermometer(&[(8, 0), (8, 1)])
+        ],
+        thermometers
+    );
+}
+
+#[test]
+fn parse_malformed_thermometers() {
+    let message = |text: &str| match parse_thermometers(text, Geometry::CLASSIC) {
+        Err(SudokuError::ParseLine { line, msg }) => format!("{}: {}", line, msg),
+        res => panic!("Unexpected result {:?}", res),
+    };
+    assert_eq!("2: invalid cell `r0c1`", message("r1c1 r1c2\nr0c1 r1c1"));
+    assert_eq!(
+        "1: a thermometer has 2 to 9 cells, found 1",
+        message("r1c1")
+    );
+    assert_eq!("1: r1c3 does not touch r1c1", message("r1c1 r1c3"));
+    assert_eq!("1: r1c1 is repeated", message("r1c1 r1c2 r1c1"));
+    let long: Vec<String> = (1..=10).map(|row| format!("r{}c1", row)).collect();
+    assert!(parse_thermometers(&long.join(" "), Geometry::CLASSIC).is_err());
+    assert!(parse_thermometers(&long[..9].join(" "), Geometry::CLASSIC).is_ok());
+}
+
+#[test]
+fn values_increase_along_thermometer() {
+    let mut s = Sudoku::empty().with_constraint(thermometer(&[(0, 0), (0, 1), (0, 2), (1, 3)]));
+    // The bulb leaves room for the three cells above it, the tip for the three below.
+    assert!(s.set(0, 0, 7).is_err());
+    assert!(s.set(1, 3, 3).is_err());
+    s.set(0, 1, 5).unwrap();
+    match s.set(0, 2, 4) {
+        Err(err) => assert_eq!("4 is outside of 6-8 on the thermometer from r1c1", err.msg),
+        Ok(_) => panic!("4 does not increase"),
+    }
+    assert!(s.set(1, 3, 6).is_err());
+    s.set(1, 3, 7).unwrap();
+    s.solve().unwrap();
+    assert_eq!(Some(6), s.value(0, 2));
+    assert!(s.value(0, 0).unwrap() < 5);
+    assert!(s.violations().is_empty());
+}
+
+#[test]
+fn decreasing_thermometer_is_a_violation() {
+    let mut field = vec![EMPTY; 81];
+    field[..3].copy_from_slice(&[1, 5, 3]);
+    let s = Sudoku::empty()
+        .with_constraint(thermometer(&[(0, 0), (0, 1), (0, 2)]))
+        .with_field(field);
+    assert_eq!(
+        vec![Violation::new(
+            "the thermometer from r1c1 does not increase: 5 is followed by 3",
+            vec![(0, 1), (0, 2)]
+        )],
+        s.violations()
+    );
+    // Empty cells are skipped, the values around them still increase.
+    let mut field = vec![EMPTY; 81];
+    field[0] = 1;
+    field[2] = 3;
+    assert!(Sudoku::empty()
+        .with_constraint(thermometer(&[(0, 0), (0, 1), (0, 2)]))
+        .with_field(field)
+        .violations()
+        .is_empty());
+}
@@ -0,0 +1,653 @@
+use super::backtracking::{Sudoku, SudokuError, EMPTY, SIDE};
+use super::cages::Cage;
+use super::geometry::Geometry;
+use super::regions::Regions;
+use super::rules::Rules;
+use crate::prelude::*;
+
+/// Key identifying a puzzle up to symmetry, see `Sudoku::canonical_form`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CanonicalKey(Vec<u8>);
+
+impl CanonicalKey {
+    /// Returns the cells of the canonical representation.
+    pub fn cells(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// How a transform moves the cells, which decides the rules of a variant it keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    /// Rotations and reflections of the whole grid, which keep the diagonals, the windows, the cells a knight's move
+    /// apart and the neighbours of every cell.
+    Symmetry,
+    /// Swaps of rows, columns, bands or stacks, which keep the rows, columns and boxes only.
+    Swap,
+}
+
+impl Sudoku {
+    /// Returns for every cell of a grid of the given geometry the position in `self` of the cell returned by `source`.
+    ///
+    /// `source` maps the (row, col) of a cell in the new grid to the (row, col) of the cell in `self`.
+    fn sources(
+        &self,
+        geometry: Geometry,
+        source: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Vec<usize> {
+        (0..geometry.size())
+            .map(|pos| {
+                let (row, col) = geometry.cell(pos);
+                let (row, col) = source(row, col);
+                self.geometry().pos(row, col)
+            })
+            .collect()
+    }
+
+    /// Builds a copy of the sudoku of the given geometry by taking every cell from the position returned by `source`.
+    ///
+    /// Givens, notes, parity marks, cages and the regions of a jigsaw sudoku move with their cells, the node limit, the
+    /// order of the search and the metadata stay. An error is returned for constraints added with `with_constraint`,
+    /// e.g. thermometers, which cannot be moved, and for the rules of a variant, which a `Motion::Swap` breaks.
+    fn remap(
+        &self,
+        geometry: Geometry,
+        motion: Motion,
+        source: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Result<Sudoku, SudokuError> {
+        if self.constraints().len() > self.rules().constraints().len() {
+            return Err(SudokuError::InvalidTransform(
+                "constraints added to the sudoku cannot be moved".to_string(),
+            ));
+        }
+        if motion == Motion::Swap && self.rules() != Rules::default() {
+            return Err(SudokuError::InvalidTransform(
+                "the rules of the variant do not hold once lines are swapped".to_string(),
+            ));
+        }
+        let sources = self.sources(geometry, source);
+        let mut s = self.with_geometry_field(
+            geometry,
+            sources.iter().map(|&pos| self.field[pos]).collect(),
+        );
+        s.givens = sources.iter().map(|&pos| self.givens[pos]).collect();
+        if !self.parity.is_empty() {
+            s.parity = sources.iter().map(|&pos| self.parity[pos]).collect();
+        }
+        if !self.notes.is_empty() {
+            s.notes = sources.iter().map(|&pos| self.notes[pos]).collect();
+        }
+        s.metadata = self.metadata.clone();
+        if *self.regions() != Regions::boxes(self.geometry()) {
+            let region_of = sources
+                .iter()
+                .map(|&pos| self.regions().region_of(pos))
+                .collect();
+            s = s.with_regions(Regions::from_map(self.side(), region_of))?;
+        }
+        if !self.cages().is_empty() {
+            let mut target = vec![0; sources.len()];
+            for (pos, &source) in sources.iter().enumerate() {
+                target[source] = pos;
+            }
+            let cages = self
+                .cages()
+                .iter()
+                .map(|cage| {
+                    let mut cells: Vec<usize> = cage.cells.iter().map(|&pos| target[pos]).collect();
+                    cells.sort_unstable();
+                    Cage {
+                        cells,
+                        sum: cage.sum,
+                    }
+                })
+                .collect();
+            s = s.with_cages(cages)?;
+        }
+        Ok(s)
+    }
+
+    /// Builds a sudoku of the given geometry from the cells of `self` like `remap`, without its rules, regions, cages
+    /// or marks, which the canonical form ignores.
+    fn remap_plain(
+        &self,
+        geometry: Geometry,
+        source: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Sudoku {
+        let field = self
+            .sources(geometry, source)
+            .into_iter()
+            .map(|pos| self.field[pos])
+            .collect();
+        Sudoku::from_geometry(geometry, field)
+    }
+
+    /// Returns a copy of the sudoku with digits replaced according to `mapping`.
+    ///
+    /// Digit `d` is replaced with `mapping[d - 1]`, empty cells stay empty. The mapping must be a permutation of
+    /// 1..=9 and the sudoku must be 9x9 without cages, parity marks, the non-consecutive rule or constraints added with
+    /// `with_constraint`, e.g. thermometers, which the new digits would break, otherwise an error is returned.
+    pub fn relabel(&self, mapping: [u8; SIDE]) -> Result<Sudoku, SudokuError> {
+        if !self.cages().is_empty() {
+            return Err(SudokuError::InvalidMapping(
+                "digits of a killer sudoku cannot be relabeled".to_string(),
+            ));
+        }
+        if !self.parity_marks().is_empty() {
+            return Err(SudokuError::InvalidMapping(
+                "digits of an even/odd sudoku cannot be relabeled".to_string(),
+            ));
+        }
+        if self.rules().non_consecutive {
+            return Err(SudokuError::InvalidMapping(
+                "digits of a non-consecutive sudoku cannot be relabeled".to_string(),
+            ));
+        }
+        if self.constraints().len() > self.rules().constraints().len() {
+            return Err(SudokuError::InvalidMapping(
+                "digits of a sudoku with added constraints cannot be relabeled".to_string(),
+            ));
+        }
+        if self.side() != SIDE {
+            return Err(SudokuError::InvalidMapping(format!(
+                "a mapping of {} values does not fit a {}x{} sudoku",
+                SIDE,
+                self.side(),
+                self.side()
+            )));
+        }
+        let mut seen = [false; SIDE + 1];
+        for &val in mapping.iter() {
+            if val == EMPTY || val > SIDE as u8 {
+                return Err(SudokuError::InvalidMapping(format!(
+                    "value {} is outside of 1..={}",
+                    val, SIDE
+                )));
+            }
+            if seen[val as usize] {
+                return Err(SudokuError::InvalidMapping(format!(
+                    "value {} is used more than once",
+                    val
+                )));
+            }
+            seen[val as usize] = true;
+        }
+        let mut field = self.field.clone();
+        for cell in field.iter_mut().filter(|cell| **cell != EMPTY) {
+            *cell = mapping[*cell as usize - 1];
+        }
+        Ok(self.with_field(field))
+    }
+
+    /// Returns a copy of the sudoku mirrored over its main (top-left to bottom-right) diagonal.
+    ///
+    /// Boxes of a rectangular shape are mirrored too, a grid of 2x3 boxes becomes a grid of 3x2 boxes. Like the other
+    /// rotations and reflections, it keeps the rules of a variant and fails for constraints added with
+    /// `with_constraint`.
+    pub fn transpose(&self) -> Result<Sudoku, SudokuError> {
+        self.remap(
+            self.geometry().transposed(),
+            Motion::Symmetry,
+            |row, col| (col, row),
+        )
+    }
+
+    /// Returns a copy of the sudoku rotated by 90 degrees clockwise.
+    pub fn rotate(&self) -> Result<Sudoku, SudokuError> {
+        let last = self.side() - 1;
+        self.remap(
+            self.geometry().transposed(),
+            Motion::Symmetry,
+            |row, col| (last - col, row),
+        )
+    }
+
+    /// Returns a copy of the sudoku with the order of columns reversed.
+    pub fn flip_horizontal(&self) -> Result<Sudoku, SudokuError> {
+        let last = self.side() - 1;
+        self.remap(self.geometry(), Motion::Symmetry, |row, col| {
+            (row, last - col)
+        })
+    }
+
+    /// Returns a copy of the sudoku with the order of rows reversed.
+    pub fn flip_vertical(&self) -> Result<Sudoku, SudokuError> {
+        let last = self.side() - 1;
+        self.remap(self.geometry(), Motion::Symmetry, |row, col| {
+            (last - row, col)
+        })
+    }
+
+    /// Returns a key which is equal for puzzles that are the same modulo symmetry.
+    ///
+    /// The key is the lexicographically minimal field over all rotations and reflections, band and stack
+    /// permutations and digit relabelings of the puzzle. Swaps of rows within a band and of columns within a stack
+    /// are not considered, so this is an approximation: equal keys always mean equivalent puzzles, but some
+    /// equivalent puzzles get different keys. Rotations and reflections which change the shape of the boxes are
+    /// skipped.
+    pub fn canonical_form(&self) -> CanonicalKey {
+        let geometry = self.geometry();
+        let (box_rows, box_cols) = (geometry.box_rows(), geometry.box_cols());
+        let band_orders = permutations(box_cols);
+        let stack_orders = permutations(box_rows);
+        let mut best: Option<Vec<u8>> = None;
+        let transposed =
+            |s: &Sudoku| s.remap_plain(s.geometry().transposed(), |row, col| (col, row));
+        let rotated = |s: &Sudoku| {
+            let last = s.side() - 1;
+            s.remap_plain(s.geometry().transposed(), |row, col| (last - col, row))
+        };
+        let mut geometric = Sudoku::from_geometry(geometry, self.field.clone());
+        for _ in 0..4 {
+            let reflections = [transposed(&geometric), geometric.clone()];
+            for variant in reflections
+                .iter()
+                .filter(|variant| variant.geometry() == geometry)
+            {
+                for bands in band_orders.iter() {
+                    for stacks in stack_orders.iter() {
+                        let candidate = variant
+                            .remap_plain(geometry, |row, col| {
+                                (
+                                    bands[row / box_rows] * box_rows + row % box_rows,
+                                    stacks[col / box_cols] * box_cols + col % box_cols,
+                                )
+                            })
+                            .normalized_labels();
+                        if best.as_ref().map_or(true, |best| candidate < *best) {
+                            best = Some(candidate);
+                        }
+                    }
+                }
+            }
+            geometric = rotated(&geometric);
+        }
+        CanonicalKey(best.expect("the puzzle itself is always a candidate"))
+    }
+
+    /// Returns the field relabeled so that digits are numbered in order of their first appearance.
+    ///
+    /// This is the lexicographically minimal relabeling of the field.
+    fn normalized_labels(&self) -> Vec<u8> {
+        let mut mapping = vec![EMPTY; self.side() + 1];
+        let mut next = 1;
+        let mut field = self.field.clone();
+        for cell in field.iter_mut().filter(|cell| **cell != EMPTY) {
+            if mapping[*cell as usize] == EMPTY {
+                mapping[*cell as usize] = next;
+                next += 1;
+            }
+            *cell = mapping[*cell as usize];
+        }
+        field
+    }
+
+    /// Returns a copy of the sudoku with rows `a` and `b` swapped.
+    ///
+    /// Both rows must belong to the same band, otherwise the result would not be a valid sudoku. The rules of a variant
+    /// and constraints added with `with_constraint` do not hold once lines are swapped, the swap fails for them too.
+    pub fn swap_rows_within_band(&self, a: usize, b: usize) -> Result<Sudoku, SudokuError> {
+        check_within_group("row", a, b, self.side(), self.geometry().box_rows())?;
+        self.remap(self.geometry(), Motion::Swap, |row, col| {
+            (swapped(row, a, b), col)
+        })
+    }
+
+    /// Returns a copy of the sudoku with columns `a` and `b` swapped.
+    ///
+    /// Both columns must belong to the same stack, otherwise the result would not be a valid sudoku. Fails for the
+    /// variants `swap_rows_within_band` fails for.
+    pub fn swap_cols_within_stack(&self, a: usize, b: usize) -> Result<Sudoku, SudokuError> {
+        check_within_group("column", a, b, self.side(), self.geometry().box_cols())?;
+        self.remap(self.geometry(), Motion::Swap, |row, col| {
+            (row, swapped(col, a, b))
+        })
+    }
+
+    /// Returns a copy of the sudoku with bands (horizontal groups of boxes) `a` and `b` swapped, fails for the variants
+    /// `swap_rows_within_band` fails for.
+    pub fn swap_bands(&self, a: usize, b: usize) -> Result<Sudoku, SudokuError> {
+        let box_rows = self.geometry().box_rows();
+        check_group("band", a, b, self.geometry().box_cols())?;
+        self.remap(self.geometry(), Motion::Swap, |row, col| {
+            (swapped_group(row, a, b, box_rows), col)
+        })
+    }
+
+    /// Returns a copy of the sudoku with stacks (vertical groups of boxes) `a` and `b` swapped, fails for the variants
+    /// `swap_rows_within_band` fails for.
+    pub fn swap_stacks(&self, a: usize, b: usize) -> Result<Sudoku, SudokuError> {
+        let box_cols = self.geometry().box_cols();
+        check_group("stack", a, b, self.geometry().box_rows())?;
+        self.remap(self.geometry(), Motion::Swap, |row, col| {
+            (row, swapped_group(col, a, b, box_cols))
+        })
+    }
+}
+
+/// Returns all orderings of `0..count`.
+fn permutations(count: usize) -> Vec<Vec<usize>> {
+    if count == 0 {
+        return vec![Vec::new()];
+    }
+    let mut result = Vec::new();
+    for shorter in permutations(count - 1) {
+        for at in 0..count {
+            let mut order = shorter.clone();
+            order.insert(at, count - 1);
+            result.push(order);
+        }
+    }
+    result
+}
+
+/// Returns `b` for `a`, `a` for `b` and `line` itself otherwise.
+fn swapped(line: usize, a: usize, b: usize) -> usize {
+    match line {
+        l if l == a => b,
+        l if l == b => a,
+        l => l,
+    }
+}
+
+/// Maps a row (or column) to its counterpart after groups of `width` lines `a` and `b` are swapped.
+fn swapped_group(line: usize, a: usize, b: usize, width: usize) -> usize {
+    swapped(line / width, a, b) * width + line % width
+}
+
+/// Makes sure both rows (or columns) exist and belong to the same band (or stack) of `width` lines.
+fn check_within_group(
+    kind: &str,
+    a: usize,
+    b: usize,
+    side: usize,
+    width: usize,
+) -> Result<(), SudokuError> {
+    if a >= side || b >= side {
+        return Err(SudokuError::InvalidSwap(format!(
+            "{} index must be less than {}",
+            kind, side
+        )));
+    }
+    if a / width != b / width {
+        return Err(SudokuError::InvalidSwap(format!(
+            "{}s {} and {} belong to different groups",
+            kind, a, b
+        )));
+    }
+    Ok(())
+}
+
+/// Makes sure both bands (or stacks) exist, there are `count` of them.
+fn check_group(kind: &str, a: usize, b: usize, count: usize) -> Result<(), SudokuError> {
+    if a >= count || b >= count {
+        return Err(SudokuError::InvalidSwap(format!(
+            "{} index must be less than {}",
+            kind, count
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+use super::backtracking::{solvable_field_helper, SIZE};
+#[cfg(test)]
+use super::limit::NodeLimit;
+#[cfg(test)]
+use super::parity::Parity;
+#[cfg(test)]
+use super::regions::JIGSAW_LAYOUT;
+#[cfg(test)]
+use super::thermometers::Thermometer;
+#[cfg(test)]
+use crate::formats::metadata::Metadata;
+
+#[cfg(test)]
+const MAPPING: [u8; SIDE] = [3, 1, 2, 9, 8, 7, 4, 5, 6];
+
+#[test]
+fn relabel() {
+    let s = solvable_field_helper().relabel(MAPPING).unwrap();
+    assert_eq!(8, s.field[0]);
+    assert_eq!(EMPTY, s.field[2]);
+}
+
+#[test]
+fn relabeled_solution_equals_solution_of_relabeled() {
+    let mut original = solvable_field_helper();
+    original.solve().unwrap();
+    let mut relabeled = solvable_field_helper().relabel(MAPPING).unwrap();
+    relabeled.solve().unwrap();
+    assert_eq!(original.relabel(MAPPING).unwrap(), relabeled);
+}
+
+#[test]
+fn relabel_with_repeated_value() {
+    let s = solvable_field_helper();
+    assert!(s.relabel([1, 2, 3, 4, 5, 6, 7, 8, 8]).is_err());
+}
+
+#[test]
+fn relabel_with_value_out_of_range() {
+    let s = solvable_field_helper();
+    assert!(s.relabel([0, 2, 3, 4, 5, 6, 7, 8, 9]).is_err());
+    assert!(s.relabel([10, 2, 3, 4, 5, 6, 7, 8, 9]).is_err());
+}
+
+#[test]
+fn swap_rows_within_band() {
+    let s = solvable_field_helper();
+    let swapped = s.swap_rows_within_band(3, 5).unwrap();
+    assert_eq!(&s.field[27..36], &swapped.field[45..54]);
+    assert_eq!(&s.field[45..54], &swapped.field[27..36]);
+    assert_eq!(&s.field[36..45], &swapped.field[36..45]);
+}
+
+#[test]
+fn swap_rows_across_bands() {
+    let s = solvable_field_helper();
+    assert!(s.swap_rows_within_band(2, 3).is_err());
+}
+
+#[test]
+fn swap_cols_across_stacks() {
+    let s = solvable_field_helper();
+    assert!(s.swap_cols_within_stack(0, 8).is_err());
+    assert!(s.swap_cols_within_stack(0, 9).is_err());
+}
+
+#[test]
+fn swap_bands_and_stacks_keep_sudoku_solvable() {
+    let s = solvable_field_helper();
+    let mut swapped = s
+        .swap_bands(0, 2)
+        .unwrap()
+        .swap_stacks(1, 2)
+        .unwrap()
+        .swap_cols_within_stack(3, 4)
+        .unwrap();
+    assert_eq!(s.field[0], swapped.field[6 * SIDE]);
+    assert!(swapped.solve().is_ok());
+}
+
+#[test]
+fn swap_bands_out_of_range() {
+    let s = solvable_field_helper();
+    assert!(s.swap_bands(0, 3).is_err());
+    assert!(s.swap_stacks(3, 1).is_err());
+}
+
+#[test]
+fn rotate() {
+    let s = solvable_field_helper();
+    let rotated = s.rotate().unwrap();
+    // Top-left corner moves to the top-right corner.
+    assert_eq!(s.field[0], rotated.field[SIDE - 1]);
+    assert_eq!(
+        s,
+        rotated
+            .rotate()
+            .unwrap()
+            .rotate()
+            .unwrap()
+            .rotate()
+            .unwrap()
+    );
+}
+
+#[test]
+fn flips() {
+    let s = solvable_field_helper();
+    assert_eq!(s.field[1], s.flip_horizontal().unwrap().field[SIDE - 2]);
+    assert_eq!(
+        s.field[1],
+        s.flip_vertical().unwrap().field[SIZE - SIDE + 1]
+    );
+    assert_eq!(s.field[1], s.transpose().unwrap().field[SIDE]);
+}
+
+#[test]
+fn canonical_form_of_symmetric_copies() {
+    let s = solvable_field_helper();
+    let key = s.canonical_form();
+    let copy = s
+        .rotate()
+        .unwrap()
+        .relabel(MAPPING)
+        .unwrap()
+        .swap_bands(0, 1)
+        .unwrap()
+        .flip_horizontal()
+        .unwrap();
+    assert_eq!(key, copy.canonical_form());
+    assert_eq!(key, s.transpose().unwrap().canonical_form());
+}
+
+#[test]
+fn canonical_form_of_different_puzzles() {
+    let s = solvable_field_helper();
+    let mut field = s.field.clone();
+    field[0] = EMPTY;
+    let other = Sudoku::from_field(field);
+    assert_ne!(s.canonical_form(), other.canonical_form());
+}
+
+#[test]
+fn transforms_keep_parity_marks() {
+    let mut marks = vec![None; SIZE];
+    marks[1] = Some(Parity::Even);
+    marks[SIDE] = Some(Parity::Odd);
+    let s = Sudoku::empty().with_parity(marks).unwrap();
+    let transposed = s.transpose().unwrap();
+    assert_eq!(Some(Parity::Odd), transposed.parity(0, 1));
+    assert_eq!(Some(Parity::Even), transposed.parity(1, 0));
+    assert_eq!(Some(Parity::Even), s.rotate().unwrap().parity(1, SIDE - 1));
+    assert_eq!(
+        Some(Parity::Even),
+        s.flip_horizontal().unwrap().parity(0, SIDE - 2)
+    );
+    assert_eq!(
+        Some(Parity::Odd),
+        s.flip_vertical().unwrap().parity(SIDE - 2, 0)
+    );
+    assert_eq!(
+        Some(Parity::Even),
+        s.swap_stacks(0, 2).unwrap().parity(0, 7)
+    );
+    assert!(Sudoku::empty().rotate().unwrap().parity_marks().is_empty());
+}
+
+#[test]
+fn transforms_keep_variants() {
+    let rules = Rules {
+        non_consecutive: true,
+        ..Rules::default()
+    };
+    let s = Sudoku::empty().with_rules(rules);
+    assert!(s.rotate().unwrap().rules().non_consecutive);
+    assert!(s.flip_vertical().unwrap().rules().non_consecutive);
+    // Swapped lines give cells other neighbours, as new digits make other digits consecutive.
+    assert_eq!(
+        "Invalid transform: the rules of the variant do not hold once lines are swapped",
+        s.swap_bands(0, 1).unwrap_err().to_string()
+    );
+    assert!(matches!(
+        s.relabel(MAPPING),
+        Err(SudokuError::InvalidMapping(_))
+    ));
+    // Entries stay entries, the notes, the node limit and the metadata come along.
+    let metadata = Metadata {
+        id: Some("42".to_string()),
+        ..Metadata::default()
+    };
+    let mut puzzle = solvable_field_helper()
+        .with_node_limit(NodeLimit::Max(5))
+        .with_metadata(metadata);
+    puzzle.set(0, 2, 4).unwrap();
+    puzzle.add_note(0, 3, 6).unwrap();
+    let rotated = puzzle.rotate().unwrap();
+    assert_eq!(puzzle.clue_count(), rotated.clue_count());
+    assert_eq!(Some(4), rotated.value(2, SIDE - 1));
+    assert!(!rotated.is_given(2, SIDE - 1));
+    assert_eq!(vec![6], rotated.notes(3, SIDE - 1).collect::<Vec<_>>());
+    assert_eq!(NodeLimit::Max(5), rotated.node_limit());
+    assert_eq!(puzzle.metadata(), rotated.metadata());
+    // Regions and cages move with their cells.
+    let layout = Regions::parse(JIGSAW_LAYOUT, Geometry::CLASSIC).unwrap();
+    let jigsaw = Sudoku::empty().with_regions(layout).unwrap();
+    let flipped = jigsaw.flip_horizontal().unwrap();
+    assert_eq!(
+        jigsaw.regions().region_of(5),
+        flipped.regions().region_of(3)
+    );
+    assert_eq!(jigsaw, flipped.flip_horizontal().unwrap());
+    let killer = Sudoku::empty()
+        .with_cages(vec![Cage {
+            cells: vec![0, 1],
+            sum: 3,
+        }])
+        .unwrap();
+    assert_eq!(vec![0, SIDE], killer.transpose().unwrap().cages()[0].cells);
+    // Added constraints cannot be moved or relabeled.
+    let thermometer = Sudoku::empty().with_constraint(Thermometer {
+        cells: vec![0, 1, 2],
+    });
+    assert!(matches!(
+        thermometer.transpose(),
+        Err(SudokuError::InvalidTransform(_))
+    ));
+    assert!(thermometer.relabel(MAPPING).is_err());
+}
+
+#[test]
+fn transforms_of_6x6() {
+    let s = Sudoku::parse_with_box_shape(".6.....4...35...2.4....1........1.6.", 2, 3).unwrap();
+    let transposed = s.transpose().unwrap();
+    assert_eq!(Geometry::new(3, 2), Some(transposed.geometry()));
+    assert_eq!(s, transposed.transpose().unwrap());
+    assert_eq!(
+        s,
+        s.rotate()
+            .unwrap()
+            .rotate()
+            .unwrap()
+            .rotate()
+            .unwrap()
+            .rotate()
+            .unwrap()
+    );
+    assert_eq!(s.field[1], s.swap_bands(0, 2).unwrap().field[4 * 6 + 1]);
+    assert!(s.swap_bands(0, 3).is_err());
+    assert!(s.swap_stacks(0, 1).is_ok());
+    assert!(s.swap_stacks(0, 2).is_err());
+    assert!(s.swap_rows_within_band(0, 2).is_err());
+    assert!(s.relabel(MAPPING).is_err());
+    assert_eq!(
+        s.canonical_form(),
+        s.flip_vertical()
+            .unwrap()
+            .swap_stacks(0, 1)
+            .unwrap()
+            .canonical_form()
+    );
+}
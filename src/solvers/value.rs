@@ -0,0 +1,109 @@
+//! Values of the cells as their own type, so a value cannot be passed where a row or a column is expected.
+//!
+//! `Sudoku::put` takes the cell as a `Pos` and the value as a `Value`, mixing them up doesn't compile:
+//!
+//! ```compile_fail
+//! use sudoku::solvers::backtracking::Sudoku;
+//! use sudoku::solvers::geometry::Pos;
+//! use sudoku::solvers::value::Value;
+//!
+//! let mut s = Sudoku::empty();
+//! s.put(Value::new(5).unwrap(), Pos::new(0, 2)).unwrap();
+//! ```
+//!
+//! The methods taking bare numbers, like `Sudoku::set`, stay for callers which already hold them.
+use super::backtracking::{value_char, MAX_SQUARE_SIDE, SIDE};
+use core::convert::TryFrom;
+use core::fmt;
+
+/// Value of a filled cell, from 1 to the side of the grid; empty cells have no value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
+pub struct Value(u8);
+
+impl Value {
+    /// Returns the value of a classic 9x9 sudoku, None unless it is from 1 to 9.
+    pub fn new(value: u8) -> Option<Value> {
+        Value::with_side(value, SIDE)
+    }
+
+    /// Returns the value of a grid with the side, e.g. 16 for the 16x16 sudoku, None unless it is from 1 to the side.
+    pub fn with_side(value: u8, side: usize) -> Option<Value> {
+        match value >= 1 && value as usize <= side {
+            true => Some(Value(value)),
+            false => None,
+        }
+    }
+
+    /// Returns the value as a number.
+    pub fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl From<Value> for u8 {
+    fn from(value: Value) -> u8 {
+        value.0
+    }
+}
+
+/// Accepts the values of the largest supported grid, from 1 to 16, `Value::new` limits them to a 9x9 sudoku.
+impl TryFrom<u8> for Value {
+    type Error = ValueOutOfRange;
+
+    fn try_from(value: u8) -> Result<Value, ValueOutOfRange> {
+        let max = MAX_SQUARE_SIDE * MAX_SQUARE_SIDE;
+        Value::with_side(value, max).ok_or(ValueOutOfRange { value, max })
+    }
+}
+
+/// Prints the value like in the grid: digits, then letters from `A` for 10 in 16x16 sudokus.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", value_char(self.0))
+    }
+}
+
+/// Number which is not a value of the grid, see `Value::try_from`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueOutOfRange {
+    pub value: u8,
+    /// Largest value of the grid.
+    pub max: usize,
+}
+
+/// Prints the range, e.g. `value must be between 1 and 9`.
+impl fmt::Display for ValueOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "value must be between 1 and {}", self.max)
+    }
+}
+
+#[test]
+fn checked_values() {
+    assert_eq!(Some(5), Value::new(5).map(Value::get));
+    assert_eq!(None, Value::new(0));
+    assert_eq!(None, Value::new(10));
+    assert_eq!(Some(Value(4)), Value::with_side(4, 4));
+    assert_eq!(None, Value::with_side(5, 4));
+    assert_eq!(Some(16), Value::with_side(16, 16).map(u8::from));
+    assert_eq!(Ok(Value(16)), Value::try_from(16));
+    let err = Value::try_from(17).unwrap_err();
+    assert_eq!("value must be between 1 and 16", err.to_string());
+    assert!(Value::try_from(0).is_err());
+    assert_eq!("7", Value(7).to_string());
+    assert_eq!("G", Value(16).to_string());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_values() {
+    assert_eq!("7", serde_json::to_string(&Value(7)).unwrap());
+    assert_eq!(Value(7), serde_json::from_str("7").unwrap());
+    assert!(serde_json::from_str::<Value>("0").is_err());
+    assert!(serde_json::from_str::<Value>("17").is_err());
+}
@@ -0,0 +1,257 @@
+//! Order in which the backtracking search tries the values of a cell.
+//!
+//! The order only changes how fast a solution is found and, for puzzles with several solutions, which one: a puzzle
+//! has no solution or a unique one whatever the order.
+use super::backtracking::{Sudoku, Unsolvable};
+use super::limit::{LimitReached, NodeLimit};
+use crate::prelude::*;
+
+/// Order in which `Sudoku::solve` tries the values of a cell, see `Sudoku::with_value_order`.
+///
+/// Which order solves a puzzle fastest depends on the puzzle, `LeastConstraining` trims the search of many hard
+/// puzzles by half or more but makes it longer for others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueOrder {
+    /// From 1 to the largest value.
+    Ascending,
+    /// The value allowed in the fewest empty cells of the row, the column and the box first, so the placement takes
+    /// the fewest options away from the other cells.
+    LeastConstraining,
+    /// Shuffled for every cell, the same way for the same seed, see `Sudoku::with_seed`.
+    Random,
+}
+
+/// Ascending order, the search is the same run after run.
+impl Default for ValueOrder {
+    fn default() -> ValueOrder {
+        ValueOrder::Ascending
+    }
+}
+
+/// How `Sudoku::solve_with_options`, `Sudoku::count_solutions_with` and the generator search, the default is the
+/// ascending order of `Sudoku::solve`.
+///
+/// With several solutions, which one is found depends on the order, whether a puzzle has none, one or several
+/// solutions doesn't, and neither does their count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SolveOptions {
+    pub value_order: ValueOrder,
+    /// Seed of the `Random` order, drawn from the system entropy when it is None (0 without the `std` feature).
+    pub seed: Option<u64>,
+    /// Most placements of the search, the default one depends on the size of the grid.
+    pub node_limit: NodeLimit,
+}
+
+impl SolveOptions {
+    /// Returns the options trying values in random order, shuffled by the seed.
+    pub fn random(seed: Option<u64>) -> SolveOptions {
+        SolveOptions {
+            value_order: ValueOrder::Random,
+            seed,
+            ..SolveOptions::default()
+        }
+    }
+
+    /// Returns the options stopping the search after `max` placements, or never with None.
+    pub fn max_nodes(mut self, max: Option<u64>) -> SolveOptions {
+        self.node_limit = max.map_or(NodeLimit::Unlimited, NodeLimit::Max);
+        self
+    }
+
+    /// Returns the seed of the `Random` order, the given one or one drawn from the system entropy.
+    pub fn seed(&self) -> u64 {
+        #[cfg(feature = "std")]
+        let entropy = || rand::random();
+        #[cfg(not(feature = "std"))]
+        let entropy = || 0;
+        self.seed.unwrap_or_else(entropy)
+    }
+}
+
+impl Sudoku {
+    /// Returns the sudoku searching with the options, a `Random` order without a seed gets one drawn now, so the
+    /// search stays the same when it is stopped and continued.
+    pub fn with_options(self, opts: &SolveOptions) -> Sudoku {
+        let seed = match opts.value_order {
+            ValueOrder::Random => opts.seed(),
+            _ => opts.seed.unwrap_or_default(),
+        };
+        self.with_value_order(opts.value_order)
+            .with_seed(seed)
+            .with_node_limit(opts.node_limit)
+    }
+
+    /// Solves the sudoku like `solve`, trying the values in the order of the options.
+    pub fn solve_with_options(&mut self, opts: &SolveOptions) -> Result<(), Unsolvable> {
+        *self = self.clone().with_options(opts);
+        self.solve()
+    }
+
+    /// Returns the number of solutions like `count_solutions`, trying the values in the order of the options.
+    ///
+    /// The count is the same for every order, only the solutions found before reaching the limit may differ.
+    pub fn count_solutions_with(
+        &self,
+        limit: usize,
+        opts: &SolveOptions,
+    ) -> Result<usize, LimitReached> {
+        self.clone().with_options(opts).count_solutions(limit)
+    }
+
+    /// Returns the sudoku which tries the values of a cell in the given order when it is solved.
+    ///
+    /// A stopped search, or its saved state, must continue with the order it started with.
+    pub fn with_value_order(mut self, order: ValueOrder) -> Sudoku {
+        self.value_order = order;
+        self
+    }
+
+    /// Returns the order in which the search tries the values of a cell.
+    pub fn value_order(&self) -> ValueOrder {
+        self.value_order
+    }
+
+    /// Returns the sudoku with the seed of the `Random` value order, 0 unless it is set.
+    pub fn with_seed(mut self, seed: u64) -> Sudoku {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns every value in the order the search tries them in the empty cell at the position.
+    ///
+    /// The order depends on the cell and the filled cells only, so it is the same when the search comes back to the
+    /// cell after a rollback.
+    pub(crate) fn ordered_values(&self, pos: usize) -> Vec<u8> {
+        let mut values: Vec<u8> = self.values().collect();
+        match self.value_order {
+            ValueOrder::Ascending => {}
+            ValueOrder::LeastConstraining => {
+                let peers = self.empty_peers(pos);
+                // Values which are not allowed go last, the sort is stable so ties stay ascending.
+                values.sort_by_key(|&val| match self.is_allowed(val, pos) {
+                    true => peers
+                        .iter()
+                        .filter(|&&peer| self.is_allowed(val, peer))
+                        .count(),
+                    false => usize::MAX,
+                });
+            }
+            ValueOrder::Random => {
+                let mut state = self.seed ^ (pos as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                for i in (1..values.len()).rev() {
+                    values.swap(i, (splitmix64(&mut state) % (i as u64 + 1)) as usize);
+                }
+            }
+        }
+        values
+    }
+}
+
+/// Returns the next number of the SplitMix64 generator, enough to shuffle the values of a cell without `rand`.
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+use super::backtracking::{solvable_field_helper, SolveStatus, EMPTY};
+
+/// Returns the solution of the puzzle found with the order, with the number of placements it took.
+#[cfg(test)]
+fn solve_with(puzzle: &Sudoku, order: ValueOrder) -> (Sudoku, u64) {
+    let mut s = puzzle.clone().with_value_order(order);
+    let mut placements = 0;
+    s.solve_resumable(|stats| {
+        placements = stats.placements;
+        false
+    })
+    .unwrap();
+    (s, placements)
+}
+
+#[test]
+fn orders_of_values() {
+    let s = solvable_field_helper();
+    assert_eq!(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], s.ordered_values(2));
+    // r1c3 allows 1, 2 and 4, 1 is a candidate of the fewest other cells of its row, column and box.
+    let lcv = s.clone().with_value_order(ValueOrder::LeastConstraining);
+    let order = lcv.ordered_values(2);
+    assert_eq!(vec![1, 4, 2], order[..3].to_vec());
+    assert!(order[3..].iter().all(|&val| !s.is_allowed(val, 2)));
+    let random = s.clone().with_value_order(ValueOrder::Random);
+    let mut shuffled = random.ordered_values(2);
+    assert_eq!(shuffled, random.clone().ordered_values(2));
+    assert_ne!(shuffled, random.clone().with_seed(1).ordered_values(2));
+    shuffled.sort_unstable();
+    assert_eq!(s.ordered_values(2), shuffled);
+}
+
+#[test]
+fn every_order_finds_the_unique_solution() {
+    let hard: Sudoku = include_str!("../../tasks/very_hard.sudoku")
+        .parse()
+        .unwrap();
+    let (expected, ascending) = solve_with(&hard, ValueOrder::Ascending);
+    let (solution, least_constraining) = solve_with(&hard, ValueOrder::LeastConstraining);
+    assert_eq!(expected, solution);
+    // The heuristic doesn't pay off on every puzzle: here it takes 1_744 placements instead of 542.
+    assert!(least_constraining > ascending);
+    for seed in 0..3 {
+        let random = hard.clone().with_seed(seed);
+        assert_eq!(expected, solve_with(&random, ValueOrder::Random).0);
+    }
+}
+
+#[test]
+fn least_constraining_value_trims_search() {
+    let puzzle: Sudoku =
+        "6.....8.3.4.7.................5.4.7.3..2.....1.6.......2.....5.....8.6......1...."
+            .parse()
+            .unwrap();
+    let (expected, ascending) = solve_with(&puzzle, ValueOrder::Ascending);
+    let (solution, least_constraining) = solve_with(&puzzle, ValueOrder::LeastConstraining);
+    assert_eq!(expected, solution);
+    assert_eq!((68_691, 38_208), (ascending, least_constraining));
+}
+
+#[test]
+fn resume_search_in_random_order() {
+    let mut expected = solvable_field_helper().with_value_order(ValueOrder::Random);
+    expected.solve().unwrap();
+    let mut s = solvable_field_helper().with_value_order(ValueOrder::Random);
+    while s.solve_resumable(|stats| stats.placements >= 3).unwrap() != SolveStatus::Solved {}
+    assert_eq!(expected, s);
+}
+
+/// Returns the events of the search solving the puzzle with the options.
+#[cfg(test)]
+fn trace_with(puzzle: &Sudoku, opts: &SolveOptions) -> Vec<super::observer::TraceEvent> {
+    let mut s = puzzle.clone().with_trace();
+    s.solve_with_options(opts).unwrap();
+    s.take_trace()
+}
+
+#[test]
+fn searches_with_options_are_reproducible() {
+    let puzzle = solvable_field_helper();
+    let ascending = SolveOptions::default();
+    let trace = trace_with(&puzzle, &ascending);
+    assert!(!trace.is_empty());
+    assert_eq!(trace, trace_with(&puzzle, &ascending));
+    let random = SolveOptions::random(Some(11));
+    let shuffled = trace_with(&puzzle, &random);
+    assert_eq!(shuffled, trace_with(&puzzle, &random));
+    assert_ne!(trace, shuffled);
+    // The order picks which of several solutions is found, never how many there are.
+    let mut open = puzzle.clone();
+    open.field[..9].iter_mut().for_each(|val| *val = EMPTY);
+    let counts: Vec<usize> = [ascending, random, SolveOptions::random(Some(12))]
+        .iter()
+        .map(|opts| open.count_solutions_with(100, opts).unwrap())
+        .collect();
+    assert_eq!(vec![counts[0]; 3], counts);
+    assert!(counts[0] > 1);
+}